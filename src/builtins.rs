@@ -5,7 +5,26 @@ use cranelift_entity::entity_impl;
 macro_rules! foreach_builtin_function {
     ($mac:ident) => {
         $mac! {
-            // no builtins yet
+            /// Returns an index for wasm's `memory.grow` builtin function.
+            memory32_grow(vmctx: vmctx, delta: i64, index: i32) -> pointer;
+            /// Returns an index for wasm's `memory.copy` builtin function.
+            memory_copy(vmctx: vmctx, dst_index: i32, dst: i64, src_index: i32, src: i64, len: i64);
+            /// Returns an index for wasm's `memory.fill` builtin function.
+            memory_fill(vmctx: vmctx, memory_index: i32, dst: i64, val: i32, len: i64);
+            /// Returns an index for wasm's `memory.init` builtin function.
+            memory_init(vmctx: vmctx, memory_index: i32, data_index: i32, dst: i64, src: i32, len: i32);
+            /// Returns an index for wasm's `data.drop` builtin function.
+            data_drop(vmctx: vmctx, data_index: i32);
+            /// Returns an index for wasm's `table.init` builtin function.
+            table_init(vmctx: vmctx, table_index: i32, elem_index: i32, dst: i64, src: i32, len: i32);
+            /// Returns an index for wasm's `elem.drop` builtin function.
+            elem_drop(vmctx: vmctx, elem_index: i32);
+            /// Returns an index for the builtin called when the epoch deadline is reached.
+            new_epoch(vmctx: vmctx) -> i64;
+            /// Returns an index for the builtin called when the fuel runs out.
+            out_of_gas(vmctx: vmctx);
+            /// Returns an index for the builtin allocating arrays of the GC proposal.
+            gc_alloc_array(vmctx: vmctx, elem_size: i32, len: i32) -> reference;
         }
     };
 }
@@ -1,5 +1,5 @@
 use crate::builtins::BuiltinFunctionIndex;
-use crate::indices::DefinedFuncIndex;
+use crate::indices::{DefinedFuncIndex, ModuleInternedTypeIndex};
 
 /// A sortable, comparable key for a compilation output.
 /// This is used to sort by compilation output kind and bucket results.
@@ -47,6 +47,14 @@ impl CompileKey {
         }
     }
 
+    pub fn wasm_to_array_trampoline(index: ModuleInternedTypeIndex) -> Self {
+        let module = 0; // TODO change this when we support multiple modules per compilation (components?)
+        Self {
+            namespace: Self::WASM_TO_ARRAY_TRAMPOLINE_KIND | module,
+            index: index.as_u32(),
+        }
+    }
+
     pub fn wasm_to_builtin_trampoline(index: BuiltinFunctionIndex) -> Self {
         Self {
             namespace: Self::WASM_TO_BUILTIN_TRAMPOLINE_KIND,
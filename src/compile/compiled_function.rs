@@ -1,7 +1,9 @@
 use crate::builtins::BuiltinFunctionIndex;
 use crate::compile::{FilePos, NS_BUILTIN, NS_WASM_FUNC};
 use crate::indices::FuncIndex;
+use crate::translate::WasmValType;
 use crate::trap::Trap;
+use alloc::boxed::Box;
 use cranelift_codegen::ir::{ExternalName, StackSlots, UserExternalName, UserExternalNameRef};
 use cranelift_codegen::{
     binemit, Final, FinalizedMachReloc, FinalizedRelocTarget, MachBufferFinalized,
@@ -77,16 +79,29 @@ pub struct CompiledFunctionMetadata {
     pub start_srcloc: FilePos,
     /// End source location.
     pub end_srcloc: FilePos,
-    // /// An array of data for the instructions in this function, indicating where
-    // /// each instruction maps back to in the original function.
-    // ///
-    // /// This array is sorted least-to-greatest by the `code_offset` field.
-    // /// Additionally the span of each `InstructionAddressMap` is implicitly the
-    // /// gap between it and the next item in the array.
-    // pub address_map: Box<[InstructionAddressMapping]>,
+    /// An array of data for the instructions in this function, indicating where
+    /// each instruction maps back to in the original function.
+    ///
+    /// This array is sorted least-to-greatest by the `code_offset` field.
+    /// Additionally the span of each `InstructionAddressMap` is implicitly the
+    /// gap between it and the next item in the array.
+    pub address_map: Box<[InstructionAddressMapping]>,
+    /// The types of the function's locals, starting with its parameters.
+    ///
+    /// Only recorded if [`Config::debug_info`][crate::Config::debug_info] is enabled.
+    pub local_types: Box<[WasmValType]>,
 }
 
-#[derive(Debug)]
+/// Maps a machine code offset back to the WebAssembly instruction it was generated for.
+#[derive(Debug, Clone, Copy)]
+pub struct InstructionAddressMapping {
+    /// The offset of the first machine code instruction, relative to the function start.
+    pub code_offset: u32,
+    /// The offset of the WebAssembly instruction in the original module.
+    pub srcloc: FilePos,
+}
+
+#[derive(Debug, Clone)]
 pub struct Relocation {
     pub kind: binemit::Reloc,
     pub target: RelocationTarget,
@@ -2,21 +2,27 @@ mod compile_key;
 mod compiled_function;
 
 use crate::builtins::BuiltinFunctionIndex;
-use crate::compile::compiled_function::{RelocationTarget, TrapInfo};
-use crate::indices::DefinedFuncIndex;
+use crate::compile::compiled_function::TrapInfo;
+use crate::indices::{DefinedFuncIndex, ModuleInternedTypeIndex};
 use crate::translate::{
-    FunctionBodyData, ModuleTranslation, ModuleTypes, TranslatedModule, WasmFuncType,
+    FunctionBodyData, ModuleTranslation, ModuleTypes, TranslatedModule, WasmFuncType, WasmValType,
 };
 use crate::trap::Trap;
-use crate::Engine;
+use crate::{Engine, Error};
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 use compile_key::CompileKey;
-pub use compiled_function::CompiledFunction;
+pub use compiled_function::{
+    CompiledFunction, InstructionAddressMapping, Relocation, RelocationTarget,
+};
+use core::mem;
+use core::ops::ControlFlow;
+use cranelift_codegen::binemit::Reloc;
 use cranelift_codegen::control::ControlPlane;
+use cranelift_codegen::ValueLabelsRanges;
 use cranelift_entity::{EntitySet, PrimaryMap};
 
 /// Namespace corresponding to wasm functions, the index is the index of the
@@ -100,6 +106,34 @@ pub struct CompiledFunctionInfo {
     /// A trampoline for host callers (e.g. `Func::wrap`) calling into this function (if needed).
     pub host_to_wasm_trampoline: Option<FunctionLoc>,
     pub start_srcloc: FilePos,
+    /// Information for inspecting the function's locals at a trap, only present if
+    /// [`Config::debug_info`][crate::Config::debug_info] is enabled.
+    pub debug: Option<FunctionDebugInfo>,
+    /// Information for annotating the disassembled machine code of the function.
+    #[cfg(feature = "disassemble")]
+    pub disas: FunctionDisasInfo,
+}
+
+/// Maps the machine code of a function back to its WebAssembly instructions and locals.
+#[derive(Debug)]
+pub struct FunctionDebugInfo {
+    /// Where each machine code instruction maps back to in the original module, sorted by code offset.
+    pub address_map: Box<[InstructionAddressMapping]>,
+    /// The locations of the function's locals, labelled by their local index.
+    pub value_labels_ranges: ValueLabelsRanges,
+    /// The types of the function's locals, starting with its parameters.
+    pub local_types: Box<[WasmValType]>,
+}
+
+/// The relocations and address map of a function, kept around for
+/// [`Module::disassemble`][crate::Module::disassemble].
+#[cfg(feature = "disassemble")]
+#[derive(Debug)]
+pub struct FunctionDisasInfo {
+    /// The relocations of the function, their offsets are relative to the function start.
+    pub relocations: Box<[Relocation]>,
+    /// Where each machine code instruction maps back to in the original module, sorted by code offset.
+    pub address_map: Box<[InstructionAddressMapping]>,
 }
 
 /// Description of where a function is located in the text section of a
@@ -113,6 +147,38 @@ pub struct FunctionLoc {
     pub length: u32,
 }
 
+/// An absolute address in the compiled code of a module that has to be patched once the address
+/// the code is loaded at is known, see [`Module::relocations`][crate::Module::relocations].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageRelocation {
+    /// The offset of the patched field from the start of the image.
+    pub offset: usize,
+    /// The size of the patched field.
+    pub kind: ImageRelocationKind,
+    /// The offset from the start of the image the field has to point to, i.e. the field is set to
+    /// the address the image is loaded at plus `target`.
+    pub target: usize,
+}
+
+/// The size of the field patched by an [`ImageRelocation`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageRelocationKind {
+    /// A 4 byte absolute address, in the byte order of the target.
+    Abs4,
+    /// An 8 byte absolute address, in the byte order of the target.
+    Abs8,
+}
+
+impl ImageRelocationKind {
+    /// Returns the size of the patched field in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            ImageRelocationKind::Abs4 => 4,
+            ImageRelocationKind::Abs8 => 8,
+        }
+    }
+}
+
 pub type CompileInput<'a> =
     Box<dyn FnOnce(&dyn Compiler) -> crate::Result<CompileOutput> + Send + 'a>;
 
@@ -170,20 +236,68 @@ impl<'a> CompileInputs<'a> {
             }
         }
 
-        // TODO collect wasm->native trampolines
+        // Compile a wasm->host trampoline for every distinct signature of imported functions, so
+        // host functions satisfying the imports can be called through the module's own code.
+        let mut signatures = EntitySet::new();
+        for (_, func) in translation
+            .module
+            .functions
+            .iter()
+            .take_while(|(index, _)| translation.module.is_imported_func(*index))
+        {
+            let signature = translation.module.types[func.signature];
+            if !signatures.insert(signature) {
+                continue;
+            }
+
+            inputs.push(Box::new(move |compiler| {
+                let symbol = format!("wasm[0]::wasm_to_array_trampoline[{}]", signature.as_u32());
+                tracing::debug!("compiling {symbol}...");
+
+                let ty = types.get_wasm_type(signature).unwrap().unwrap_func();
+                let function = compiler.compile_wasm_to_array_trampoline(ty)?;
+
+                Ok(CompileOutput {
+                    key: CompileKey::wasm_to_array_trampoline(signature),
+                    function,
+                    symbol,
+                })
+            }));
+        }
 
         Self(inputs)
     }
 
-    pub fn compile(self, compiler: &dyn Compiler) -> crate::Result<UnlinkedCompileOutputs> {
-        let mut outputs = self
-            .0
-            .into_iter()
-            .map(|f| f(compiler))
-            .collect::<Result<Vec<_>, _>>()?;
+    /// Runs all compile jobs.
+    ///
+    /// If `deterministic` is set, the outputs are sorted by their key so the layout of the text
+    /// section doesn't depend on the order the jobs were created or completed in.
+    ///
+    /// `progress` is called with the number of finished and total jobs after each job, returning
+    /// [`ControlFlow::Break`] aborts compilation with [`Error::Cancelled`].
+    pub fn compile(
+        self,
+        compiler: &dyn Compiler,
+        deterministic: bool,
+        progress: &mut dyn FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> crate::Result<UnlinkedCompileOutputs> {
+        // Jobs always run serially for now, should this ever change the `deterministic` flag
+        // needs to force serial compilation.
+        let total = self.0.len();
+        let mut outputs = Vec::with_capacity(total);
+        for (index, f) in self.0.into_iter().enumerate() {
+            outputs.push(f(compiler)?);
+            if progress(index + 1, total).is_break() {
+                return Err(Error::Cancelled);
+            }
+        }
 
         compile_required_builtin_trampolines(compiler, &mut outputs)?;
 
+        if deterministic {
+            outputs.sort_unstable_by_key(|output| output.key);
+        }
+
         let mut indices: BTreeMap<u32, BTreeMap<CompileKey, usize>> = BTreeMap::new();
         for (index, output) in outputs.iter().enumerate() {
             indices
@@ -194,6 +308,17 @@ impl<'a> CompileInputs<'a> {
 
         Ok(UnlinkedCompileOutputs { indices, outputs })
     }
+
+    /// Runs all compile jobs and returns every error encountered.
+    ///
+    /// Unlike [`CompileInputs::compile`] this doesn't stop at the first failing job and discards
+    /// the compiled code, it is meant for reporting problems with a module.
+    pub fn compile_and_report(self, compiler: &dyn Compiler) -> Vec<crate::Error> {
+        self.0
+            .into_iter()
+            .filter_map(|f| f(compiler).err())
+            .collect()
+    }
 }
 
 fn compile_required_builtin_trampolines(
@@ -264,12 +389,17 @@ impl UnlinkedCompileOutputs {
     ) -> (
         Vec<u8>,
         PrimaryMap<DefinedFuncIndex, CompiledFunctionInfo>,
+        BTreeMap<ModuleInternedTypeIndex, FunctionLoc>,
         (Vec<u32>, Vec<Trap>),
+        Vec<ImageRelocation>,
     ) {
         let mut text_builder = engine.compiler().text_section_builder(self.outputs.len());
         let mut ctrl_plane = ControlPlane::default();
         let mut locs = Vec::new(); // TODO get a capacity value for this
         let mut traps = TrapsBuilder::default();
+        // absolute relocations can only be applied once the code is loaded, their targets are
+        // resolved below since they may refer to functions that haven't been appended yet
+        let mut absolute_relocs = Vec::new();
 
         for output in &self.outputs {
             let body = output.function.buffer();
@@ -291,13 +421,26 @@ impl UnlinkedCompileOutputs {
                     }
                 };
 
+                let resolved =
+                    text_builder.resolve_reloc(off + u64::from(r.offset), r.kind, r.addend, target);
+
                 // Ensure that we actually resolved the relocation
-                debug_assert!(text_builder.resolve_reloc(
-                    off + u64::from(r.offset),
-                    r.kind,
-                    r.addend,
-                    target
-                ));
+                if engine.config().is_position_independent_code() {
+                    // absolute relocations would tie the code to the address it was linked at
+                    assert!(
+                        resolved && !matches!(r.kind, Reloc::Abs4 | Reloc::Abs8),
+                        "relocation {r:?} in {} is not position-independent",
+                        output.symbol
+                    );
+                } else if !resolved {
+                    let kind = match r.kind {
+                        Reloc::Abs4 => ImageRelocationKind::Abs4,
+                        Reloc::Abs8 => ImageRelocationKind::Abs8,
+                        _ => panic!("relocation {r:?} in {} was not resolved", output.symbol),
+                    };
+                    let offset = usize::try_from(off + u64::from(r.offset)).unwrap();
+                    absolute_relocs.push((offset, kind, r.addend, target));
+                }
             }
 
             let loc = FunctionLoc {
@@ -328,18 +471,92 @@ impl UnlinkedCompileOutputs {
                     .remove(&host_to_wasm_trampoline_key)
                     .map(|index| locs[index]);
 
+                #[cfg(feature = "disassemble")]
+                let disas = FunctionDisasInfo {
+                    relocations: self.outputs[index].function.relocations().collect(),
+                    address_map: self.outputs[index].function.metadata().address_map.clone(),
+                };
+
+                let metadata = self.outputs[index].function.metadata_mut();
+                let debug = engine.config().is_debug_info().then(|| FunctionDebugInfo {
+                    address_map: mem::take(&mut metadata.address_map),
+                    value_labels_ranges: mem::take(&mut metadata.value_labels_ranges),
+                    local_types: mem::take(&mut metadata.local_types),
+                });
+
                 CompiledFunctionInfo {
-                    start_srcloc: self.outputs[index].function.metadata().start_srcloc,
+                    start_srcloc: metadata.start_srcloc,
                     wasm_func_loc: locs[index],
                     host_to_wasm_trampoline,
+                    debug,
+                    #[cfg(feature = "disassemble")]
+                    disas,
+                }
+            })
+            .collect();
+
+        let wasm_to_array_trampolines = self
+            .indices
+            .remove(&CompileKey::WASM_TO_ARRAY_TRAMPOLINE_KIND)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, index)| {
+                let signature = ModuleInternedTypeIndex::from_u32(key.index);
+                (signature, locs[index])
+            })
+            .collect();
+
+        let relocations = absolute_relocs
+            .into_iter()
+            .map(|(offset, kind, addend, target)| {
+                let start = i64::from(locs[target].start);
+                ImageRelocation {
+                    offset,
+                    kind,
+                    target: usize::try_from(start + addend).unwrap(),
                 }
             })
             .collect();
 
-        (text_builder.finish(&mut ctrl_plane), funcs, traps.finish())
+        (
+            text_builder.finish(&mut ctrl_plane),
+            funcs,
+            wasm_to_array_trampolines,
+            traps.finish(),
+            relocations,
+        )
     }
 }
 
+/// Compiles a trampoline that lets WASM call a host function of type `ty` and places it in its
+/// own text section.
+///
+/// Returns the text section, the location of the trampoline within it and the trap information
+/// for the text section.
+pub fn compile_wasm_to_array_trampoline(
+    engine: &Engine,
+    ty: &WasmFuncType,
+) -> crate::Result<(Vec<u8>, FunctionLoc, (Vec<u32>, Vec<Trap>))> {
+    let function = engine.compiler().compile_wasm_to_array_trampoline(ty)?;
+
+    let mut text_builder = engine.compiler().text_section_builder(1);
+    let mut ctrl_plane = ControlPlane::default();
+    let mut traps = TrapsBuilder::default();
+
+    let body = function.buffer();
+    let off = text_builder.append(true, body, function.alignment(), &mut ctrl_plane);
+    // trampolines never call other functions so there are no relocations to resolve
+    debug_assert!(function.relocations().next().is_none());
+
+    let loc = FunctionLoc {
+        start: u32::try_from(off).unwrap(),
+        length: u32::try_from(body.len()).unwrap(),
+    };
+    traps.push_traps(loc, function.traps());
+
+    Ok((text_builder.finish(&mut ctrl_plane), loc, traps.finish()))
+}
+
 #[derive(Default)]
 struct TrapsBuilder {
     offsets: Vec<u32>,
@@ -0,0 +1,340 @@
+use crate::DEFAULT_OFFSET_GUARD_SIZE;
+use wasmparser::WasmFeatures;
+
+/// The default size of the stack async calls run on (1 MiB).
+const DEFAULT_ASYNC_STACK_SIZE: usize = 1 << 20;
+
+/// Global configuration options used to create an [`Engine`][crate::Engine].
+///
+/// Options left as `None` use their documented default.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    wasm_features: Option<WasmFeatures>,
+    position_independent_code: bool,
+    profiling_strategy: ProfilingStrategy,
+    epoch_interruption: bool,
+    consume_fuel: bool,
+    async_stack_size: Option<usize>,
+    module_cache_capacity: usize,
+    deterministic: bool,
+    debug_info: bool,
+    relaxed_simd_deterministic: bool,
+    canonicalize_nans: bool,
+    opt_level: OptLevel,
+    preserve_frame_pointers: Option<bool>,
+    probestack: Option<bool>,
+    dynamic_memories: bool,
+    memory_guard_size: Option<u64>,
+}
+
+/// Profilers the engine can make compiled code visible to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProfilingStrategy {
+    /// No profiling support.
+    #[default]
+    None,
+    /// Appends the address and name of every compiled function to `/tmp/perf-<pid>.map`, the
+    /// file `perf` reads to symbolize JIT-compiled code.
+    PerfMap,
+}
+
+/// How much effort the compiler puts into optimizing the generated code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OptLevel {
+    /// No optimizations, compiles the fastest.
+    None,
+    /// Optimizes for the speed of the generated code.
+    Speed,
+    /// Optimizes for both the speed and the size of the generated code.
+    #[default]
+    SpeedAndSize,
+}
+
+impl OptLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Speed => "speed",
+            Self::SpeedAndSize => "speed_and_size",
+        }
+    }
+}
+
+impl Config {
+    /// Creates a new configuration object with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configures the set of WebAssembly features modules are validated against.
+    ///
+    /// Modules using, or declaring through their `target_features` section, a feature that is
+    /// not part of this set are rejected.
+    ///
+    /// Defaults to the WebAssembly 2.0 feature set.
+    pub fn wasm_features(&mut self, features: WasmFeatures) -> &mut Self {
+        self.wasm_features = Some(features);
+        self
+    }
+
+    /// Configures whether compiled code must be fully position-independent.
+    ///
+    /// When enabled, the compiler makes no assumptions about the final load address of the code:
+    /// every relocation is resolved relative to the text section at compile time, so the code can
+    /// be copied to and executed at any address. This is required for loaders that place code at
+    /// a different address than it was compiled for.
+    ///
+    /// This is disabled by default.
+    pub fn position_independent_code(&mut self, enable: bool) -> &mut Self {
+        self.position_independent_code = enable;
+        self
+    }
+
+    /// Configures which profiler compiled code is made visible to.
+    ///
+    /// This is [`ProfilingStrategy::None`] by default.
+    pub fn profiling_strategy(&mut self, strategy: ProfilingStrategy) -> &mut Self {
+        self.profiling_strategy = strategy;
+        self
+    }
+
+    /// Configures whether WebAssembly code can be interrupted based on the engine's epoch.
+    ///
+    /// When enabled, compiled code compares the epoch of the engine against the deadline of the
+    /// store at every function entry and loop header. The epoch is advanced through
+    /// [`Engine::increment_epoch`][crate::Engine::increment_epoch], the deadline is set through
+    /// [`Store::set_epoch_deadline`][crate::Store::set_epoch_deadline]. This allows bounding the
+    /// execution time of WebAssembly code at the cost of slightly slower code.
+    ///
+    /// This is disabled by default.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.epoch_interruption = enable;
+        self
+    }
+
+    /// Configures whether WebAssembly code consumes fuel as it executes.
+    ///
+    /// When enabled, every executed operator consumes fuel from the store, which is provided
+    /// through [`Store::set_fuel`][crate::Store::set_fuel]. Unlike epochs this makes the amount of
+    /// work WebAssembly code does before being interrupted deterministic, but the accounting makes
+    /// compiled code noticeably slower.
+    ///
+    /// This is disabled by default.
+    pub fn consume_fuel(&mut self, enable: bool) -> &mut Self {
+        self.consume_fuel = enable;
+        self
+    }
+
+    /// Configures the size in bytes of the stack that calls made through
+    /// [`Func::call_async`][crate::Func::call_async] run on.
+    ///
+    /// The size is rounded up to the host page size. This is 1 MiB by default.
+    pub fn async_stack_size(&mut self, size: usize) -> &mut Self {
+        self.async_stack_size = Some(size);
+        self
+    }
+
+    /// Configures how many compiled modules the engine keeps in its in-memory module cache.
+    ///
+    /// When enabled, [`Module::from_bytes`][crate::Module::from_bytes] returns the cached module
+    /// if the same bytes were compiled before instead of translating and compiling them again.
+    /// Once the cache is full, the least recently used module is evicted, the whole cache can be
+    /// cleared through [`Engine::clear_module_cache`][crate::Engine::clear_module_cache].
+    ///
+    /// This is `0` by default, which disables the cache.
+    pub fn module_cache_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.module_cache_capacity = capacity;
+        self
+    }
+
+    /// Configures whether compiling the same module must always produce byte-identical code.
+    ///
+    /// When enabled, functions are compiled one after the other and placed in the text section in
+    /// a fixed order that doesn't depend on the order compilation jobs finish in, and Cranelift
+    /// never perturbs its decisions through its chaos-mode control plane. Together with the same
+    /// configuration and host architecture, this makes compiled artifacts reproducible across
+    /// runs.
+    ///
+    /// This also makes the compiled code itself behave the same on every host: it implies
+    /// [`Config::canonicalize_nans`] and [`Config::relaxed_simd_deterministic`].
+    ///
+    /// This is disabled by default.
+    pub fn deterministic(&mut self, enable: bool) -> &mut Self {
+        self.deterministic = enable;
+        self
+    }
+
+    /// Configures whether compiled modules keep the information needed to inspect the values of
+    /// WebAssembly locals at a trap.
+    ///
+    /// When enabled, modules retain their DWARF sections as well as the mapping from machine code
+    /// back to WebAssembly instructions and variable locations, which
+    /// [`Store::debug_variables`][crate::Store::debug_variables] uses to resolve named locals and
+    /// parameters.
+    ///
+    /// This is disabled by default.
+    pub fn debug_info(&mut self, enable: bool) -> &mut Self {
+        self.debug_info = enable;
+        self
+    }
+
+    /// Configures whether relaxed SIMD instructions are forced to use lowerings that produce the
+    /// same results on every host.
+    ///
+    /// The relaxed SIMD proposal allows instructions like `f32x4.relaxed_madd` to behave
+    /// differently depending on the host, e.g. to only be fused if the host supports fused
+    /// multiply-add natively. When enabled, the deterministic behavior is always picked (e.g.
+    /// `relaxed_madd` is always fused) regardless of the cost to performance, which makes results
+    /// reproducible across machines.
+    ///
+    /// This is disabled by default.
+    pub fn relaxed_simd_deterministic(&mut self, enable: bool) -> &mut Self {
+        self.relaxed_simd_deterministic = enable;
+        self
+    }
+
+    /// Configures whether floating point operations producing a NaN always produce the canonical
+    /// NaN.
+    ///
+    /// WebAssembly allows arithmetic NaN results to carry any payload, and hosts differ in how
+    /// they propagate the payloads of NaN operands. When enabled, every NaN produced by a float
+    /// operation is replaced with the canonical NaN (positive sign, only the most significant
+    /// mantissa bit set), which makes results reproducible across machines at a small cost.
+    ///
+    /// This is disabled by default.
+    pub fn canonicalize_nans(&mut self, enable: bool) -> &mut Self {
+        self.canonicalize_nans = enable;
+        self
+    }
+
+    /// Configures the optimization level of the compiler.
+    ///
+    /// Lower levels compile faster at the cost of slower code, which is a good trade-off for
+    /// modules that are short-lived or only run once.
+    ///
+    /// The default is [`OptLevel::SpeedAndSize`].
+    pub fn opt_level(&mut self, level: OptLevel) -> &mut Self {
+        self.opt_level = level;
+        self
+    }
+
+    /// Configures whether compiled code maintains a frame pointer chain.
+    ///
+    /// Backtraces of WebAssembly traps are collected by walking the frame pointers, so they are
+    /// not captured when this is disabled, see
+    /// [`Store::set_wasm_backtrace_details`][crate::Store::set_wasm_backtrace_details]. Disabling
+    /// it frees up a register for the generated code.
+    ///
+    /// This is enabled by default.
+    pub fn preserve_frame_pointers(&mut self, enable: bool) -> &mut Self {
+        self.preserve_frame_pointers = Some(enable);
+        self
+    }
+
+    /// Configures whether functions with large stack frames probe every page of their frame
+    /// before using it.
+    ///
+    /// Probing makes sure a frame larger than the guard page below the stack can't skip over it
+    /// and silently corrupt whatever memory lies beyond. Only disable this if the stacks
+    /// WebAssembly runs on are protected by other means.
+    ///
+    /// This is enabled by default.
+    pub fn probestack(&mut self, enable: bool) -> &mut Self {
+        self.probestack = Some(enable);
+        self
+    }
+
+    /// Configures whether linear memories are bounds-checked explicitly instead of relying on
+    /// guard pages.
+    ///
+    /// By default memories reserve address space for their largest possible size followed by a
+    /// 2 GiB guard region, which lets most accesses skip the bounds check entirely. Dynamic
+    /// memories reserve no guard region and compare every access against the current length of
+    /// the memory instead, trading some performance for a much smaller reservation. This is
+    /// useful when many memories are alive at the same time. Hosts that can't reserve the guard
+    /// region, e.g. 32-bit hosts, always use dynamic memories.
+    ///
+    /// This is disabled by default.
+    pub fn dynamic_memories(&mut self, enable: bool) -> &mut Self {
+        self.dynamic_memories = enable;
+        self
+    }
+
+    /// Configures the size in bytes of the guard region reserved after each linear memory.
+    ///
+    /// Accesses whose static offset falls within the guard region don't need an explicit bounds
+    /// check, so smaller guard regions reduce the address space each memory reserves at the cost
+    /// of more bounds checks. The size is rounded up to the host page size and has no effect on
+    /// [dynamic memories][Self::dynamic_memories], which never reserve a guard region. Memories
+    /// whose reservation doesn't fit the host address space fail to instantiate.
+    ///
+    /// This is 2 GiB by default.
+    pub fn memory_guard_size(&mut self, size: u64) -> &mut Self {
+        self.memory_guard_size = Some(size);
+        self
+    }
+
+    pub(crate) fn features(&self) -> WasmFeatures {
+        self.wasm_features.unwrap_or(WasmFeatures::WASM2)
+    }
+
+    pub(crate) fn is_position_independent_code(&self) -> bool {
+        self.position_independent_code
+    }
+
+    pub(crate) fn profiling(&self) -> ProfilingStrategy {
+        self.profiling_strategy
+    }
+
+    pub(crate) fn is_epoch_interruption(&self) -> bool {
+        self.epoch_interruption
+    }
+
+    pub(crate) fn is_consume_fuel(&self) -> bool {
+        self.consume_fuel
+    }
+
+    pub(crate) fn get_async_stack_size(&self) -> usize {
+        self.async_stack_size.unwrap_or(DEFAULT_ASYNC_STACK_SIZE)
+    }
+
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    pub(crate) fn is_relaxed_simd_deterministic(&self) -> bool {
+        self.relaxed_simd_deterministic || self.deterministic
+    }
+
+    pub(crate) fn is_canonicalize_nans(&self) -> bool {
+        self.canonicalize_nans || self.deterministic
+    }
+
+    pub(crate) fn is_dynamic_memories(&self) -> bool {
+        self.dynamic_memories || !cfg!(target_pointer_width = "64")
+    }
+
+    pub(crate) fn get_memory_guard_size(&self) -> u64 {
+        self.memory_guard_size.unwrap_or(DEFAULT_OFFSET_GUARD_SIZE)
+    }
+
+    pub(crate) fn is_debug_info(&self) -> bool {
+        self.debug_info
+    }
+
+    pub(crate) fn opt_level_str(&self) -> &'static str {
+        self.opt_level.as_str()
+    }
+
+    pub(crate) fn is_preserve_frame_pointers(&self) -> bool {
+        self.preserve_frame_pointers.unwrap_or(true)
+    }
+
+    pub(crate) fn is_probestack(&self) -> bool {
+        self.probestack.unwrap_or(true)
+    }
+
+    pub(crate) fn get_module_cache_capacity(&self) -> usize {
+        self.module_cache_capacity
+    }
+}
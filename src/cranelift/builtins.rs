@@ -80,6 +80,10 @@ impl BuiltinFunctionSignatures {
         AbiParam::special(self.pointer_type, ArgumentPurpose::VMContext)
     }
 
+    fn pointer(&self) -> AbiParam {
+        AbiParam::new(self.pointer_type)
+    }
+
     fn u8(&self) -> AbiParam {
         AbiParam::new(types::I8)
     }
@@ -89,8 +93,11 @@ impl BuiltinFunctionSignatures {
     fn i64(&self) -> AbiParam {
         AbiParam::new(types::I64)
     }
+    fn reference(&self) -> AbiParam {
+        // GC references are 32-bit offsets into the GC heap
+        AbiParam::new(types::I32)
+    }
 
-    #[expect(clippy::no_effect_underscore_binding, reason = "empty builtins")]
     pub(crate) fn signature(&self, builtin: BuiltinFunctionIndex) -> Signature {
         let mut _cur = 0usize;
         macro_rules! iter {
@@ -7,6 +7,7 @@ use crate::cranelift::CraneliftGlobal;
 use crate::indices::{
     DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, TableIndex, TypeIndex,
 };
+use crate::translate::WasmRefType;
 use crate::trap::{TRAP_NULL_REFERENCE, TRAP_UNREACHABLE};
 use crate::wasm_unsupported;
 use alloc::vec;
@@ -89,7 +90,10 @@ pub fn translate_operator(
         Operator::Drop => {
             state.pop1();
         }
-        Operator::Select => {
+        // The explicit type of `TypedSelect` is only needed for validation, which we require to
+        // have been performed before translation. References are plain pointers at this point,
+        // so they are selected just like numbers.
+        Operator::Select | Operator::TypedSelect { .. } => {
             let (mut arg1, mut arg2, cond) = state.pop3();
             if builder.func.dfg.value_type(arg1).is_vector() {
                 arg1 = optionally_bitcast_vector(arg1, I8X16, builder);
@@ -130,7 +134,7 @@ pub fn translate_operator(
                 .extend_from_slice(builder.block_params(loop_body));
 
             builder.switch_to_block(loop_body);
-            // env.before_loop_header(builder)?;
+            env.before_loop_header(builder)?;
         }
         Operator::If { blockty } => {
             let val = state.pop1();
@@ -587,9 +591,13 @@ pub fn translate_operator(
             let args = state.peekn_mut(num_args);
             bitcast_wasm_params(sigref, args, builder, env);
 
+            let table_index = TableIndex::from_u32(*table_index);
+            let table = state.get_table(builder.func, table_index, env).clone();
+
             env.translate_return_call_indirect(
                 builder,
-                TableIndex::from_u32(*table_index),
+                table_index,
+                &table,
                 type_index,
                 sigref,
                 callee,
@@ -1121,14 +1129,16 @@ pub fn translate_operator(
         }
         Operator::TableGet { table: index } => {
             let table_index = TableIndex::from_u32(*index);
+            let table = state.get_table(builder.func, table_index, env).clone();
             let index = state.pop1();
-            state.push1(env.translate_table_get(builder.cursor(), table_index, index)?);
+            state.push1(env.translate_table_get(builder, table_index, &table, index)?);
         }
         Operator::TableSet { table: index } => {
             let table_index = TableIndex::from_u32(*index);
+            let table = state.get_table(builder.func, table_index, env).clone();
             let value = state.pop1();
             let index = state.pop1();
-            env.translate_table_set(builder.cursor(), table_index, value, index)?;
+            env.translate_table_set(builder, table_index, &table, value, index)?;
         }
         Operator::TableGrow { table: index } => {
             let table_index = TableIndex::from_u32(*index);
@@ -1156,19 +1166,6 @@ pub fn translate_operator(
             let index = FuncIndex::from_u32(*function_index);
             state.push1(env.translate_ref_func(builder.cursor(), index)?);
         }
-        Operator::TypedSelect { ty: _ } => {
-            // We ignore the explicit type parameter as it is only needed for
-            // validation, which we require to have been performed before
-            // translation.
-            let (mut arg1, mut arg2, cond) = state.pop3();
-            if builder.func.dfg.value_type(arg1).is_vector() {
-                arg1 = optionally_bitcast_vector(arg1, I8X16, builder);
-            }
-            if builder.func.dfg.value_type(arg2).is_vector() {
-                arg2 = optionally_bitcast_vector(arg2, I8X16, builder);
-            }
-            state.push1(builder.ins().select(cond, arg1, arg2));
-        }
         //
         // // bulk memory operations
         // // https://github.com/WebAssembly/bulk-memory-operations
@@ -1584,7 +1581,10 @@ pub fn translate_operator(
             let loaded = builder.ins().uload32x2(flags, base, 0i32);
             state.push1(loaded);
         }
-        Operator::V128Store { .. } => todo!(),
+        Operator::V128Store { memarg } => {
+            // wasm stores never trap on misalignment, so the alignment hint is only a hint
+            translate_store(memarg, ir::Opcode::Store, builder, state, env)?;
+        }
         Operator::I8x16Splat | Operator::I16x8Splat => {
             let reduced = builder.ins().ireduce(type_of(op).lane_type(), state.pop1());
             let splatted = builder.ins().splat(type_of(op), reduced);
@@ -2465,11 +2465,25 @@ pub fn translate_operator(
                 state.get_indirect_sig(builder.func, TypeIndex::from_u32(*type_index), env);
             let callee = state.pop1();
 
+            // See `Operator::CallRef` above, non-nullable references don't need a null check.
+            let ty = validator.get_operand_type(0);
+            let needs_null_check = ty.expect("expected operand on stack").is_none_or(|ty| {
+                let ty = ty.as_reference_type().expect("expected reference type");
+
+                ty.is_nullable()
+            });
+
             // Bitcast any vector arguments to their default type, I8X16, before calling.
             let args = state.peekn_mut(num_args);
             bitcast_wasm_params(sigref, args, builder, env);
 
-            env.translate_return_call_ref(builder, sigref, callee, state.peekn(num_args))?;
+            env.translate_return_call_ref(
+                builder,
+                sigref,
+                callee,
+                state.peekn(num_args),
+                needs_null_check,
+            )?;
 
             state.popn(num_args);
             state.reachable = false;
@@ -2492,30 +2506,110 @@ pub fn translate_operator(
             let val = env.translate_i31_get_u(builder.cursor(), i31ref)?;
             state.push1(val);
         }
+        Operator::RefTestNonNull { hty } | Operator::RefTestNullable { hty } => {
+            let ref_ty = WasmRefType {
+                nullable: matches!(op, Operator::RefTestNullable { .. }),
+                heap_type: env.convert_heap_type(*hty),
+            };
+            let gc_ref = state.pop1();
+            let result = env.translate_ref_test(builder, ref_ty, gc_ref)?;
+            state.push1(result);
+        }
+        Operator::RefCastNonNull { hty } | Operator::RefCastNullable { hty } => {
+            let ref_ty = WasmRefType {
+                nullable: matches!(op, Operator::RefCastNullable { .. }),
+                heap_type: env.convert_heap_type(*hty),
+            };
+            let gc_ref = state.pop1();
+            let gc_ref = env.translate_ref_cast(builder, ref_ty, gc_ref)?;
+            state.push1(gc_ref);
+        }
+        Operator::ArrayNew { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let (elem, len) = state.pop2();
+            let array = env.translate_array_new(builder, array_type_index, elem, len)?;
+            state.push1(array);
+        }
+        Operator::ArrayNewDefault { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let len = state.pop1();
+            let array = env.translate_array_new_default(builder, array_type_index, len)?;
+            state.push1(array);
+        }
+        Operator::ArrayNewFixed {
+            array_type_index,
+            array_size,
+        } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let array_size = usize::try_from(*array_size).unwrap();
+            let elems = state.peekn(array_size);
+            let array = env.translate_array_new_fixed(builder, array_type_index, elems)?;
+            state.popn(array_size);
+            state.push1(array);
+        }
+        Operator::ArrayGet { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let (array, index) = state.pop2();
+            let val = env.translate_array_get(builder, array_type_index, array, index)?;
+            state.push1(val);
+        }
+        Operator::ArrayGetS { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let (array, index) = state.pop2();
+            let val = env.translate_array_get_s(builder, array_type_index, array, index)?;
+            state.push1(val);
+        }
+        Operator::ArrayGetU { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let (array, index) = state.pop2();
+            let val = env.translate_array_get_u(builder, array_type_index, array, index)?;
+            state.push1(val);
+        }
+        Operator::ArraySet { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let (array, index, val) = state.pop3();
+            env.translate_array_set(builder, array_type_index, array, index, val)?;
+        }
+        Operator::ArrayLen => {
+            let array = state.pop1();
+            let len = env.translate_array_len(builder, array)?;
+            state.push1(len);
+        }
+        Operator::ArrayFill { array_type_index } => {
+            let array_type_index = TypeIndex::from_u32(*array_type_index);
+            let len = state.pop1();
+            let (array, index, val) = state.pop3();
+            env.translate_array_fill(builder, array_type_index, array, index, val, len)?;
+        }
+        Operator::ArrayCopy {
+            array_type_index_dst,
+            array_type_index_src,
+        } => {
+            let dst_array_type_index = TypeIndex::from_u32(*array_type_index_dst);
+            let src_array_type_index = TypeIndex::from_u32(*array_type_index_src);
+            let (src_array, src_index, len) = state.pop3();
+            let (dst_array, dst_index) = state.pop2();
+            env.translate_array_copy(
+                builder,
+                dst_array_type_index,
+                dst_array,
+                dst_index,
+                src_array_type_index,
+                src_array,
+                src_index,
+                len,
+            )?;
+        }
         Operator::StructNew { .. }
         | Operator::StructNewDefault { .. }
         | Operator::StructGet { .. }
         | Operator::StructGetS { .. }
         | Operator::StructGetU { .. }
         | Operator::StructSet { .. }
-        | Operator::ArrayNew { .. }
-        | Operator::ArrayNewDefault { .. }
-        | Operator::ArrayNewFixed { .. }
         | Operator::ArrayNewData { .. }
         | Operator::ArrayNewElem { .. }
-        | Operator::ArrayGet { .. }
-        | Operator::ArrayGetS { .. }
-        | Operator::ArrayGetU { .. }
-        | Operator::ArraySet { .. }
-        | Operator::ArrayLen
-        | Operator::ArrayFill { .. }
-        | Operator::ArrayCopy { .. }
         | Operator::ArrayInitData { .. }
         | Operator::ArrayInitElem { .. }
-        | Operator::RefTestNonNull { .. }
-        | Operator::RefTestNullable { .. }
-        | Operator::RefCastNonNull { .. }
-        | Operator::RefCastNullable { .. }
         | Operator::BrOnCast { .. }
         | Operator::BrOnCastFail { .. }
         | Operator::AnyConvertExtern
@@ -2806,48 +2900,151 @@ fn translate_store(
     Ok(())
 }
 
+/// Prepares the address of an atomic access of `access_ty` popped from the stack.
+///
+/// Unlike regular accesses, atomic accesses trap if the address isn't naturally aligned. This
+/// check happens before the bounds check.
+fn prepare_atomic_access(
+    access_ty: Type,
+    memarg: &MemArg,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    env: &mut TranslationEnvironment,
+) -> crate::Result<Reachability<(MemFlags, Value)>> {
+    if !matches!(access_ty, I8 | I16 | I32 | I64) {
+        return Err(wasm_unsupported!(
+            "atomic access of unsupported type {access_ty:?}"
+        ));
+    }
+
+    let memory_index = MemoryIndex::from_u32(memarg.memory);
+    let index = state.pop1();
+    let access_size = u8::try_from(access_ty.bytes()).unwrap();
+
+    let mem = state.get_memory(builder.func, memory_index, env);
+    Ok(
+        match mem.prepare_atomic_addr(builder, index, access_size, memarg, env) {
+            Reachability::Unreachable => Reachability::Unreachable,
+            Reachability::Reachable((flags, _wasm_index, addr)) => {
+                Reachability::Reachable((flags, addr))
+            }
+        },
+    )
+}
+
+/// Narrows `val` to `access_ty` if it is wider.
+fn reduce_to_access_ty(builder: &mut FunctionBuilder, val: Value, access_ty: Type) -> Value {
+    let ty = builder.func.dfg.value_type(val);
+    assert!(ty.bytes() >= access_ty.bytes());
+    if ty.bytes() > access_ty.bytes() {
+        builder.ins().ireduce(access_ty, val)
+    } else {
+        val
+    }
+}
+
+/// Zero-extends `val` of `access_ty` to `widened_ty` if it is narrower.
+fn extend_to_widened_ty(
+    builder: &mut FunctionBuilder,
+    val: Value,
+    access_ty: Type,
+    widened_ty: Type,
+) -> Value {
+    assert!(matches!(widened_ty, I32 | I64) && widened_ty.bytes() >= access_ty.bytes());
+    if access_ty == widened_ty {
+        val
+    } else {
+        builder.ins().uextend(widened_ty, val)
+    }
+}
+
 fn translate_atomic_rmw(
-    _widened_ty: Type,
-    _access_ty: Type,
-    _op: AtomicRmwOp,
-    _memarg: &MemArg,
-    _builder: &mut FunctionBuilder,
-    _state: &mut FuncTranslationState,
-    _env: &mut TranslationEnvironment,
+    widened_ty: Type,
+    access_ty: Type,
+    op: AtomicRmwOp,
+    memarg: &MemArg,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    env: &mut TranslationEnvironment,
 ) -> crate::Result<()> {
-    todo!()
+    // The operation is performed at type `access_ty`, and the old value is zero-extended
+    // to type `widened_ty`.
+    let arg = state.pop1();
+    let arg = reduce_to_access_ty(builder, arg, access_ty);
+
+    let (flags, addr) = unwrap_or_return_unreachable_state!(
+        state,
+        prepare_atomic_access(access_ty, memarg, builder, state, env)?
+    );
+
+    let res = builder.ins().atomic_rmw(access_ty, flags, op, addr, arg);
+    state.push1(extend_to_widened_ty(builder, res, access_ty, widened_ty));
+    Ok(())
 }
 
 fn translate_atomic_cas(
-    _widened_ty: Type,
-    _access_ty: Type,
-    _memarg: &MemArg,
-    _builder: &mut FunctionBuilder,
-    _state: &mut FuncTranslationState,
-    _env: &mut TranslationEnvironment,
+    widened_ty: Type,
+    access_ty: Type,
+    memarg: &MemArg,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    env: &mut TranslationEnvironment,
 ) -> crate::Result<()> {
-    todo!()
+    // The compare-and-swap is performed at type `access_ty`, and the old value is zero-extended
+    // to type `widened_ty`.
+    let (expected, replacement) = state.pop2();
+    let expected = reduce_to_access_ty(builder, expected, access_ty);
+    let replacement = reduce_to_access_ty(builder, replacement, access_ty);
+
+    let (flags, addr) = unwrap_or_return_unreachable_state!(
+        state,
+        prepare_atomic_access(access_ty, memarg, builder, state, env)?
+    );
+
+    let res = builder.ins().atomic_cas(flags, addr, expected, replacement);
+    state.push1(extend_to_widened_ty(builder, res, access_ty, widened_ty));
+    Ok(())
 }
 
 fn translate_atomic_load(
-    _widened_ty: Type,
-    _access_ty: Type,
-    _memarg: &MemArg,
-    _builder: &mut FunctionBuilder,
-    _state: &mut FuncTranslationState,
-    _env: &mut TranslationEnvironment,
+    widened_ty: Type,
+    access_ty: Type,
+    memarg: &MemArg,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    env: &mut TranslationEnvironment,
 ) -> crate::Result<()> {
-    todo!()
+    // The load is performed at type `access_ty`, and the loaded value is zero extended
+    // to `widened_ty`.
+    let (flags, addr) = unwrap_or_return_unreachable_state!(
+        state,
+        prepare_atomic_access(access_ty, memarg, builder, state, env)?
+    );
+
+    let res = builder.ins().atomic_load(access_ty, flags, addr);
+    state.push1(extend_to_widened_ty(builder, res, access_ty, widened_ty));
+    Ok(())
 }
 
 fn translate_atomic_store(
-    _access_ty: Type,
-    _memarg: &MemArg,
-    _builder: &mut FunctionBuilder,
-    _state: &mut FuncTranslationState,
-    _env: &mut TranslationEnvironment,
+    access_ty: Type,
+    memarg: &MemArg,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    env: &mut TranslationEnvironment,
 ) -> crate::Result<()> {
-    todo!()
+    // The operation is performed at type `access_ty`, and the data to be stored may first
+    // need to be narrowed accordingly.
+    let data = state.pop1();
+    let data = reduce_to_access_ty(builder, data, access_ty);
+
+    let (flags, addr) = unwrap_or_return_unreachable_state!(
+        state,
+        prepare_atomic_access(access_ty, memarg, builder, state, env)?
+    );
+
+    builder.ins().atomic_store(flags, data, addr);
+    Ok(())
 }
 
 fn mem_op_size(opcode: ir::Opcode, ty: Type) -> u8 {
@@ -1,13 +1,20 @@
 use crate::builtins::BuiltinFunctionIndex;
-use crate::compile::{CompiledFunction, Compiler, FilePos, NS_WASM_FUNC};
+use crate::compile::{
+    CompiledFunction, Compiler, FilePos, InstructionAddressMapping, NS_WASM_FUNC,
+};
+use crate::config::Config;
 use crate::cranelift::builtins::BuiltinFunctionSignatures;
 use crate::cranelift::env::TranslationEnvironment;
 use crate::cranelift::func_translator::FuncTranslator;
 use crate::indices::DefinedFuncIndex;
 use crate::placeholder::arch;
-use crate::runtime::{StaticVMOffsets, VMCONTEXT_MAGIC};
+use crate::runtime::{
+    StaticVMOffsets, VMArrayCallHostFuncContext, VMFuncRef, VMCONTEXT_MAGIC,
+    VM_ARRAY_CALL_HOST_FUNC_MAGIC,
+};
 use crate::translate::{
     FunctionBodyData, ModuleTranslation, ModuleTypes, WasmFuncType, WasmValType,
+    WasmparserTypeConverter,
 };
 use crate::trap::TRAP_INTERNAL_ASSERT;
 use crate::utils::{array_call_signature, value_type, wasm_call_signature};
@@ -30,6 +37,10 @@ pub struct CraneliftCompiler {
     isa: OwnedTargetIsa,
     contexts: Mutex<Vec<CompilationContext>>,
     offsets: StaticVMOffsets,
+    epoch_interruption: bool,
+    consume_fuel: bool,
+    relaxed_simd_deterministic: bool,
+    debug_info: bool,
 }
 
 impl fmt::Debug for CraneliftCompiler {
@@ -39,9 +50,13 @@ impl fmt::Debug for CraneliftCompiler {
 }
 
 impl CraneliftCompiler {
-    pub(crate) fn new(isa: OwnedTargetIsa) -> CraneliftCompiler {
+    pub(crate) fn new(isa: OwnedTargetIsa, config: &Config) -> CraneliftCompiler {
         Self {
             offsets: StaticVMOffsets::new(isa.pointer_bytes()),
+            epoch_interruption: config.is_epoch_interruption(),
+            consume_fuel: config.is_consume_fuel(),
+            relaxed_simd_deterministic: config.is_relaxed_simd_deterministic(),
+            debug_info: config.is_debug_info(),
             isa,
             contexts: Mutex::new(Vec::new()), // TODO capacity should be equal to the number of harts
         }
@@ -101,9 +116,17 @@ impl Compiler for CraneliftCompiler {
 
         // set up stack limit
         let vmctx = context.func.create_global_value(GlobalValueData::VMContext);
-        let stack_limit = context.func.create_global_value(GlobalValueData::Load {
+        let runtime_limits = context.func.create_global_value(GlobalValueData::Load {
             base: vmctx,
-            offset: i32::from(self.offsets.vmctx_stack_limit()).into(),
+            offset: i32::from(self.offsets.vmctx_runtime_limits()).into(),
+            global_type: isa.pointer_type(),
+            flags: MemFlags::trusted().with_readonly(),
+        });
+        let stack_limit = context.func.create_global_value(GlobalValueData::Load {
+            base: runtime_limits,
+            offset: i32::try_from(self.offsets.vmruntime_limits_stack_limit())
+                .unwrap()
+                .into(),
             global_type: isa.pointer_type(),
             flags: MemFlags::trusted(),
         });
@@ -112,7 +135,14 @@ impl Compiler for CraneliftCompiler {
         // collect debug info
         context.func.collect_debug_info();
 
-        let mut env = TranslationEnvironment::new(isa, &translation.module, types);
+        let mut env = TranslationEnvironment::new(
+            isa,
+            &translation.module,
+            types,
+            self.epoch_interruption,
+            self.consume_fuel,
+            self.relaxed_simd_deterministic,
+        );
         let mut validator = data
             .validator
             .into_validator(mem::take(&mut compiler.ctx.validator_allocations));
@@ -123,7 +153,21 @@ impl Compiler for CraneliftCompiler {
             &mut env,
         )?;
 
-        compiler.finish(Some(&data.body))
+        let mut function = compiler.finish(Some(&data.body))?;
+
+        if self.debug_info {
+            let type_convert = WasmparserTypeConverter::new(types, &translation.module);
+            let mut local_types = func_ty.params.to_vec();
+            let mut locals = data.body.get_locals_reader()?;
+            for _ in 0..locals.get_count() {
+                let (count, ty) = locals.read()?;
+                let ty = type_convert.convert_val_type(ty);
+                local_types.extend((0..count).map(|_| ty.clone()));
+            }
+            function.metadata_mut().local_types = local_types.into_boxed_slice();
+        }
+
+        Ok(function)
     }
 
     fn compile_array_to_wasm_trampoline(
@@ -201,29 +245,66 @@ impl Compiler for CraneliftCompiler {
     ) -> crate::Result<CompiledFunction> {
         let pointer_type = self.isa.pointer_type();
         let wasm_call_sig = wasm_call_signature(self.target_isa(), wasm_func_ty);
-        let _array_call_sig = array_call_signature(self.target_isa());
+        let array_call_sig = array_call_signature(self.target_isa());
 
         let mut compiler = self.function_compiler();
         let func = ir::Function::with_name_signature(UserFuncName::default(), wasm_call_sig);
         let (mut builder, block0) = compiler.builder(func);
 
         let args = builder.func.dfg.block_params(block0).to_vec();
-        let _callee_vmctx = args[0];
-        let _caller_vmctx = args[1];
+        let callee_vmctx = args[0];
+        let caller_vmctx = args[1];
+
+        // Assert that we were really given a host function context and then perform the "routine
+        // of the exit trampoline" of saving fp/pc/etc. since we are about to leave wasm.
+        debug_assert_vmctx_kind(
+            self.target_isa(),
+            &mut builder,
+            callee_vmctx,
+            VM_ARRAY_CALL_HOST_FUNC_MAGIC,
+        );
+        save_last_wasm_exit_fp_and_pc(&mut builder, pointer_type, &self.offsets, caller_vmctx);
 
         // Spill all wasm arguments to the stack in `ValRaw` slots.
-        let (_args_base, args_len) = allocate_stack_array_and_spill_args(
+        let (args_base, args_len) = allocate_stack_array_and_spill_args(
             wasm_func_ty,
             &mut builder,
             &args[2..],
             pointer_type,
         );
-        let _args_len = builder.ins().iconst(pointer_type, i64::from(args_len));
+        let args_len = builder.ins().iconst(pointer_type, i64::from(args_len));
+
+        // Load the actual host function pointer out of the `VMFuncRef` embedded in the
+        // callee's `VMArrayCallHostFuncContext`.
+        let array_call_offset = mem::offset_of!(VMArrayCallHostFuncContext, func_ref)
+            + mem::offset_of!(VMFuncRef, array_call);
+        let callee = builder.ins().load(
+            pointer_type,
+            MemFlags::trusted().with_readonly(),
+            callee_vmctx,
+            i32::try_from(array_call_offset).unwrap(),
+        );
 
-        // TODO figure out address of host func (from host func vmctx)
-        // TODO call indirect with [callee_vmctx, caller_vmctx, args_base, args_len]
+        // Then call the host function with the array calling convention.
+        let array_call_sig = builder.func.import_signature(array_call_sig);
+        builder.ins().call_indirect(
+            array_call_sig,
+            callee,
+            &[callee_vmctx, caller_vmctx, args_base, args_len],
+        );
 
-        todo!()
+        // The host function placed its results in the same array, load them and return.
+        let results = load_values_from_array(
+            &wasm_func_ty.results,
+            &mut builder,
+            args_base,
+            args_len,
+            pointer_type,
+        );
+        builder.ins().return_(&results);
+        builder.finalize();
+
+        compiler.finish(None)
     }
 
     fn compile_wasm_to_builtin(
@@ -319,12 +400,32 @@ impl FunctionCompiler<'_> {
 
         let preferred_alignment = self.compiler.isa.function_alignment().preferred;
         let alignment = compiled_code.buffer.alignment.max(preferred_alignment);
+        // the address map is also used to annotate disassembled code
+        let keep_address_map = self.compiler.debug_info || cfg!(feature = "disassemble");
+        let debug_info = keep_address_map.then(|| {
+            let address_map = compiled_code
+                .buffer
+                .get_srclocs_sorted()
+                .iter()
+                .filter(|srcloc| !srcloc.loc.is_default())
+                .map(|srcloc| InstructionAddressMapping {
+                    code_offset: srcloc.start,
+                    srcloc: FilePos::new(srcloc.loc.bits()),
+                })
+                .collect();
+            (address_map, compiled_code.value_labels_ranges.clone())
+        });
         let mut compiled_function = CompiledFunction::new(
             compiled_code.buffer.clone(),
             context.func.params.user_named_funcs().clone(),
             alignment,
         );
 
+        if let Some((address_map, value_labels_ranges)) = debug_info {
+            compiled_function.metadata_mut().address_map = address_map;
+            compiled_function.metadata_mut().value_labels_ranges = value_labels_ranges;
+        }
+
         compiled_function.metadata_mut().sized_stack_slots =
             mem::take(&mut context.func.sized_stack_slots);
 
@@ -337,19 +438,6 @@ impl FunctionCompiler<'_> {
                 FilePos::new(u32::try_from(offset).unwrap());
             compiled_function.metadata_mut().end_srcloc =
                 FilePos::new(u32::try_from(offset + len).unwrap());
-
-            // TODO
-            // let srclocs = compiled_function
-            //     .buffer
-            //     .get_srclocs_sorted()
-            //     .into_iter()
-            //     .map(|&MachSrcLoc { start, end, loc }| (loc, start, end - start));
-
-            // compiled_function.metadata_mut().address_map = collect_address_map(
-            //     u32::try_from(compiled_function.buffer.data().len()).unwrap(),
-            //     srclocs,
-            // )
-            //     .into_boxed_slice();
         }
 
         self.ctx.codegen_context.clear();
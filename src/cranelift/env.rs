@@ -4,40 +4,53 @@ use crate::compile::NS_WASM_FUNC;
 use crate::cranelift::builtins::BuiltinFunctions;
 use crate::cranelift::code_translator::Reachability;
 use crate::cranelift::memory::CraneliftMemory;
-use crate::cranelift::{CraneliftGlobal, CraneliftTable};
+use crate::cranelift::state::FuncTranslationState;
+use crate::cranelift::{CraneliftGlobal, CraneliftTable, TableSize};
 use crate::indices::{
-    CanonicalizedTypeIndex, DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, TableIndex,
-    TypeIndex,
+    CanonicalizedTypeIndex, DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex,
+    ModuleInternedTypeIndex, TableIndex, TypeIndex,
+};
+use crate::runtime::{
+    VMFuncRef, VMMemoryDefinition, VMOffsets, VMTableDefinition, ARRAY_ELEMS_OFFSET,
+    ARRAY_LENGTH_OFFSET,
 };
-use crate::runtime::{VMFuncRef, VMMemoryDefinition, VMOffsets, VMTableDefinition};
 use crate::translate::{
     ModuleTypes, TranslatedModule, WasmFuncType, WasmHeapTopTypeInner, WasmHeapType,
-    WasmHeapTypeInner, WasmRefType, WasmparserTypeConverter,
+    WasmHeapTypeInner, WasmRefType, WasmStorageType, WasmparserTypeConverter,
+};
+use crate::trap::{
+    TRAP_ARRAY_OUT_OF_BOUNDS, TRAP_BAD_SIGNATURE, TRAP_CAST_FAILURE, TRAP_I31_NULL_REFERENCE,
+    TRAP_INDIRECT_CALL_TO_NULL, TRAP_NULL_REFERENCE,
 };
-use crate::trap::{TRAP_BAD_SIGNATURE, TRAP_INDIRECT_CALL_TO_NULL, TRAP_NULL_REFERENCE};
 use crate::utils::{reference_type, value_type, wasm_call_signature};
+use crate::{wasm_unsupported, MEMORY_MAX};
 use alloc::vec;
 use alloc::vec::Vec;
-use core::cmp;
 use core::mem::offset_of;
+use core::{cmp, mem};
 use cranelift_codegen::cursor::FuncCursor;
 use cranelift_codegen::ir;
 use cranelift_codegen::ir::condcodes::IntCC;
 use cranelift_codegen::ir::immediates::Offset32;
-use cranelift_codegen::ir::types::{I32, I64};
+use cranelift_codegen::ir::types::{I16, I32, I64, I8};
 use cranelift_codegen::ir::{
     ArgumentPurpose, ExtFuncData, ExternalName, FuncRef, GlobalValue, GlobalValueData, Inst,
     MemFlags, MemoryType, SigRef, Signature, TrapCode, Type, UserExternalName, Value,
 };
 use cranelift_codegen::ir::{Function, InstBuilder};
 use cranelift_codegen::isa::TargetIsa;
+use cranelift_entity::packed_option::ReservedValue;
 use cranelift_entity::SecondaryMap;
-use cranelift_frontend::FunctionBuilder;
+use cranelift_frontend::{FunctionBuilder, Variable};
 use smallvec::SmallVec;
+use wasmparser::Operator;
 
 /// A smallvec that holds the IR values for a struct's fields.
 pub type StructFieldsVec = SmallVec<[Value; 4]>;
 
+/// The tag bit that marks a reference as an unboxed `i31ref`.
+const I31_DISCRIMINANT: i64 = 1;
+
 #[expect(clippy::struct_excessive_bools, reason = "TODO replace with bitflags")]
 pub struct TranslationEnvironment<'module_env> {
     isa: &'module_env dyn TargetIsa,
@@ -61,6 +74,24 @@ pub struct TranslationEnvironment<'module_env> {
     table_access_spectre_mitigation: bool,
     /// Whether to use proof-carrying code to verify lowerings.
     proof_carrying_code: bool,
+
+    /// Whether to check the epoch of the engine against the deadline of the store at function
+    /// entries and loop headers.
+    epoch_interruption: bool,
+    /// The `*const VMRuntimeLimits` of the store, loaded once at the function entry.
+    vmruntime_limits_ptr: Value,
+    /// A function-local cache of the epoch deadline.
+    epoch_deadline_var: Variable,
+    /// A function-local cache of the pointer to the engine's epoch counter.
+    epoch_ptr_var: Variable,
+
+    /// Whether executed operators consume fuel from the store.
+    consume_fuel: bool,
+    /// A function-local cache of the store's `fuel_consumed`, written back to the store before
+    /// control leaves the function.
+    fuel_var: Variable,
+    /// The fuel consumed by operators translated since `fuel_var` was last updated.
+    fuel_consumed: i64,
 }
 
 impl<'module_env> TranslationEnvironment<'module_env> {
@@ -68,6 +99,9 @@ impl<'module_env> TranslationEnvironment<'module_env> {
         isa: &'module_env dyn TargetIsa,
         module: &'module_env TranslatedModule,
         types: &'module_env ModuleTypes,
+        epoch_interruption: bool,
+        consume_fuel: bool,
+        relaxed_simd_deterministic: bool,
     ) -> Self {
         let vmoffsets = VMOffsets::for_module(isa.pointer_bytes(), module);
         let builtin_functions = BuiltinFunctions::new(isa);
@@ -81,10 +115,20 @@ impl<'module_env> TranslationEnvironment<'module_env> {
             vmctx: None,
             pcc_vmctx_memtype: None,
 
-            relaxed_simd_deterministic: false,
+            relaxed_simd_deterministic,
             heap_access_spectre_mitigation: true,
             table_access_spectre_mitigation: true,
             proof_carrying_code: true,
+
+            epoch_interruption,
+            vmruntime_limits_ptr: Value::reserved_value(),
+            epoch_deadline_var: Variable::new(0),
+            epoch_ptr_var: Variable::new(0),
+
+            consume_fuel,
+            fuel_var: Variable::new(0),
+            // Even empty functions consume some fuel.
+            fuel_consumed: 1,
         }
     }
 
@@ -254,23 +298,30 @@ impl TranslationEnvironment<'_> {
         let vmctx = self.vmctx(func);
         let pointer_type = self.pointer_type();
 
-        let (base, base_offset) = if let Some(def_index) = self.module.defined_table_index(index) {
-            let base_offset = self.offsets.vmctx_vmtable_definition_base(def_index);
-            let base_offset = i32::try_from(base_offset).unwrap();
-
-            (vmctx, base_offset)
-        } else {
-            let from_offset = self.offsets.vmctx_vmtable_import_from(index);
-            let table = func.create_global_value(ir::GlobalValueData::Load {
-                base: vmctx,
-                offset: Offset32::new(i32::try_from(from_offset).unwrap()),
-                global_type: pointer_type,
-                flags: MemFlags::trusted().with_readonly(),
-            });
-            let base_offset = i32::try_from(offset_of!(VMTableDefinition, base)).unwrap();
+        let (base, base_offset, current_length_offset) =
+            if let Some(def_index) = self.module.defined_table_index(index) {
+                let base_offset = self.offsets.vmctx_vmtable_definition_base(def_index);
+                let base_offset = i32::try_from(base_offset).unwrap();
+                let current_length_offset = self
+                    .offsets
+                    .vmctx_vmtable_definition_current_length(def_index);
+                let current_length_offset = i32::try_from(current_length_offset).unwrap();
+
+                (vmctx, base_offset, current_length_offset)
+            } else {
+                let from_offset = self.offsets.vmctx_vmtable_import_from(index);
+                let table = func.create_global_value(ir::GlobalValueData::Load {
+                    base: vmctx,
+                    offset: Offset32::new(i32::try_from(from_offset).unwrap()),
+                    global_type: pointer_type,
+                    flags: MemFlags::trusted().with_readonly(),
+                });
+                let base_offset = i32::try_from(offset_of!(VMTableDefinition, base)).unwrap();
+                let current_length_offset =
+                    i32::try_from(offset_of!(VMTableDefinition, current_length)).unwrap();
 
-            (table, base_offset)
-        };
+                (table, base_offset, current_length_offset)
+            };
 
         let table_base = func.create_global_value(GlobalValueData::Load {
             base,
@@ -287,9 +338,18 @@ impl TranslationEnvironment<'_> {
         };
 
         let bound = if Some(table.minimum) == table.maximum {
-            table.minimum
+            TableSize::Static {
+                bound: table.minimum,
+            }
         } else {
-            todo!("resizable tables")
+            TableSize::Dynamic {
+                bound_gv: func.create_global_value(GlobalValueData::Load {
+                    base,
+                    offset: Offset32::from(current_length_offset),
+                    global_type: I64,
+                    flags: MemFlags::trusted(),
+                }),
+            }
         };
 
         CraneliftTable {
@@ -303,29 +363,47 @@ impl TranslationEnvironment<'_> {
         let plan = &self.module.memories[index];
         let vmctx = self.vmctx(func);
 
-        let (base, base_offset, ptr_memtype) = match self.module.defined_memory_index(index) {
-            Some(_) if plan.shared => todo!("shared memory"),
-            Some(def_index) => {
-                let base_offset = self.offsets.vmctx_vmmemory_definition_base(def_index);
-                let base_offset = i32::try_from(base_offset).unwrap();
+        let (base, base_offset, current_length_offset, ptr_memtype) =
+            match self.module.defined_memory_index(index) {
+                Some(_) if plan.shared => todo!("shared memory"),
+                Some(def_index) => {
+                    let base_offset = self.offsets.vmctx_vmmemory_definition_base(def_index);
+                    let base_offset = i32::try_from(base_offset).unwrap();
+                    let current_length_offset = self
+                        .offsets
+                        .vmctx_vmmemory_definition_current_length(def_index);
+                    let current_length_offset = i32::try_from(current_length_offset).unwrap();
+
+                    (
+                        vmctx,
+                        base_offset,
+                        current_length_offset,
+                        self.pcc_vmctx_memtype,
+                    )
+                }
+                None => {
+                    let from_offset = self.offsets.vmctx_vmmemory_import_from(index);
 
-                (vmctx, base_offset, self.pcc_vmctx_memtype)
-            }
-            None => {
-                let from_offset = self.offsets.vmctx_vmmemory_import_from(index);
+                    // load the pointer to the memory from our VMMemoryImport
+                    let (memory, def_mt) = self.load_pointer_with_memtypes(
+                        func,
+                        vmctx,
+                        from_offset,
+                        true,
+                        self.pcc_vmctx_memtype,
+                    );
+                    let base_offset = i32::try_from(offset_of!(VMMemoryDefinition, base)).unwrap();
+                    let current_length_offset =
+                        i32::try_from(offset_of!(VMMemoryDefinition, current_length)).unwrap();
+                    (memory, base_offset, current_length_offset, def_mt)
+                }
+            };
 
-                // load the pointer to the memory from our VMMemoryImport
-                let (memory, def_mt) = self.load_pointer_with_memtypes(
-                    func,
-                    vmctx,
-                    from_offset,
-                    true,
-                    self.pcc_vmctx_memtype,
-                );
-                let base_offset = i32::try_from(offset_of!(VMMemoryDefinition, base)).unwrap();
-                (memory, base_offset, def_mt)
-            }
-        };
+        // Memories with pages smaller than the host page size can't rely on guard pages and require
+        // explicit bounds checks against the current length. PCC can't reason about those (yet)
+        // so we opt these memories out of it.
+        let host_page_size_log2 = self.isa.page_size_align_log2();
+        let ptr_memtype = ptr_memtype.filter(|_| plan.page_size_log2 >= host_page_size_log2);
 
         let (base_fact, memory_type) = if let Some(ptr_memtype) = ptr_memtype {
             // Create a memtype representing the untyped memory region.
@@ -349,10 +427,9 @@ impl TranslationEnvironment<'_> {
                         offset,
                         ty: self.isa.pointer_type(),
                         // Read-only field from the PoV of PCC checks:
-                        // don't allow stores to this field. (Even if
-                        // it is a dynamic memory whose base can
-                        // change, that update happens inside the
-                        // runtime, not in generated code.)
+                        // don't allow stores to this field. Memories
+                        // never move when they grow, so the base
+                        // doesn't change either.
                         readonly: true,
                         fact: Some(base_fact.clone()),
                     });
@@ -371,6 +448,9 @@ impl TranslationEnvironment<'_> {
             (None, None)
         };
 
+        // Memories grow in place within their reservation, both static and dynamic ones, so the
+        // base never changes and the load can be marked readonly. This lets Cranelift keep the
+        // base in a register across calls to `memory.grow` and other functions.
         let heap_base = func.create_global_value(GlobalValueData::Load {
             base,
             offset: Offset32::new(base_offset),
@@ -379,6 +459,15 @@ impl TranslationEnvironment<'_> {
         });
         func.global_value_facts[heap_base] = base_fact;
 
+        // The current length is updated by the runtime when the memory grows so this load can't be
+        // marked readonly.
+        let current_length_gv = func.create_global_value(GlobalValueData::Load {
+            base,
+            offset: Offset32::new(current_length_offset),
+            global_type: self.pointer_type(),
+            flags: MemFlags::trusted(),
+        });
+
         let min_size = plan.minimum_byte_size().unwrap_or_else(|_| {
             // The only valid Wasm memory size that won't fit in a 64-bit
             // integer is the maximum memory64 size (2^64) which is one
@@ -392,12 +481,18 @@ impl TranslationEnvironment<'_> {
 
         CraneliftMemory {
             base_gv: heap_base,
+            current_length_gv,
             memory_type,
             min_size,
             max_size,
-            bound: plan.max_size_based_on_index_type(),
+            // memories can't grow past `MEMORY_MAX` so that's where the reservation ends, the
+            // index space of 64-bit memories is far larger than any reservation could be
+            bound: plan
+                .max_size_based_on_index_type()
+                .min(u64::try_from(MEMORY_MAX).unwrap()),
             index_type: if plan.memory64 { I64 } else { I32 },
             offset_guard_size: plan.offset_guard_size,
+            style: plan.style,
             page_size_log2: plan.page_size_log2,
         }
     }
@@ -408,7 +503,11 @@ impl TranslationEnvironment<'_> {
         index: GlobalIndex,
     ) -> CraneliftGlobal {
         let global = &self.module.globals[index];
-        debug_assert!(!global.shared);
+
+        // Shared globals can be changed by other threads and need atomic accesses.
+        if global.shared {
+            return CraneliftGlobal::Custom;
+        }
 
         let (gv, offset) = self.get_global_location(func, index);
 
@@ -523,7 +622,14 @@ impl TranslationEnvironment<'_> {
         builder: &mut FunctionBuilder,
         index: GlobalIndex,
     ) -> crate::Result<Value> {
-        todo!()
+        let (addr, ty, flags) = self.custom_global_addr(builder, index);
+        let int_ty = self.shared_global_int_type(index, ty)?;
+        let val = builder.ins().atomic_load(int_ty, flags, addr);
+        if int_ty == ty {
+            Ok(val)
+        } else {
+            Ok(builder.ins().bitcast(ty, MemFlags::new(), val))
+        }
     }
 
     /// Translate a WASM `global.set` instruction at the builder's current position
@@ -534,7 +640,66 @@ impl TranslationEnvironment<'_> {
         index: GlobalIndex,
         value: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let (addr, ty, flags) = self.custom_global_addr(builder, index);
+        debug_assert_eq!(ty, builder.func.dfg.value_type(value));
+        let int_ty = self.shared_global_int_type(index, ty)?;
+        let value = if int_ty == ty {
+            value
+        } else {
+            builder.ins().bitcast(int_ty, MemFlags::new(), value)
+        };
+        builder.ins().atomic_store(flags, value, addr);
+        Ok(())
+    }
+
+    /// Returns the integer type shared global `index` of type `ty` is atomically accessed as.
+    fn shared_global_int_type(&self, index: GlobalIndex, ty: Type) -> crate::Result<Type> {
+        match ty {
+            I32 | ir::types::F32 => Ok(I32),
+            I64 | ir::types::F64 => Ok(I64),
+            _ => Err(wasm_unsupported!(
+                "shared globals of type {}",
+                self.module.globals[index].content_type
+            )),
+        }
+    }
+
+    /// Returns the address, type and memory flags for accessing a custom global.
+    ///
+    /// Custom globals are shared globals, either defined by this module or imported, in which case
+    /// the address of the definition is read from the `VMGlobalImport`.
+    fn custom_global_addr(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        index: GlobalIndex,
+    ) -> (Value, Type, MemFlags) {
+        debug_assert!(self.module.globals[index].shared);
+
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let addr = if let Some(def_index) = self.module.defined_global_index(index) {
+            let offset = self.offsets.vmctx_vmglobal_definition(def_index);
+            builder.ins().iadd_imm(vmctx, i64::from(offset))
+        } else {
+            let from_offset =
+                i32::try_from(self.offsets.vmctx_vmglobal_import_from(index)).unwrap();
+            builder.ins().load(
+                self.pointer_type(),
+                MemFlags::trusted().with_readonly(),
+                vmctx,
+                from_offset,
+            )
+        };
+
+        let ty = value_type(
+            &self.module.globals[index].content_type,
+            self.pointer_type(),
+        );
+
+        let mut flags = MemFlags::trusted();
+        // Put globals in the "table" abstract mem category as well.
+        flags.set_alias_region(Some(ir::AliasRegion::Table));
+
+        (addr, ty, flags)
     }
 
     /// Translate a WASM `call` instruction at the builder's current
@@ -606,7 +771,7 @@ impl TranslationEnvironment<'_> {
         args: &[Value],
         may_be_null: bool,
     ) -> crate::Result<Inst> {
-        todo!()
+        Ok(CallBuilder::new(builder, self).call_ref(sig_ref, callee, args, may_be_null))
     }
 
     /// Translate a WASM `return_call` instruction at the builder's
@@ -625,7 +790,8 @@ impl TranslationEnvironment<'_> {
         callee: FuncRef,
         args: &[Value],
     ) -> crate::Result<()> {
-        todo!()
+        CallBuilder::new_tail(builder, self).direct_call(callee_index, callee, args);
+        Ok(())
     }
 
     /// Translate a WASM `return_call_indirect` instruction at the
@@ -640,12 +806,21 @@ impl TranslationEnvironment<'_> {
         &mut self,
         builder: &mut FunctionBuilder,
         table_index: TableIndex,
+        table: &CraneliftTable,
         type_index: TypeIndex,
         sig_ref: SigRef,
         callee: Value,
         args: &[Value],
     ) -> crate::Result<()> {
-        todo!()
+        CallBuilder::new_tail(builder, self).indirect_call(
+            table_index,
+            table,
+            type_index,
+            sig_ref,
+            callee,
+            args,
+        );
+        Ok(())
     }
 
     /// Translate a WASM `return_call_ref` instruction at the builder's
@@ -656,6 +831,8 @@ impl TranslationEnvironment<'_> {
     /// to be translated to a native function address depending on your implementation of
     /// this trait.
     ///
+    /// `may_be_null` indicates whether a null check is necessary, see `translate_call_ref`.
+    ///
     /// The signature `sig_ref` was previously created by `make_indirect_sig()`.
     pub fn translate_return_call_ref(
         &mut self,
@@ -663,8 +840,10 @@ impl TranslationEnvironment<'_> {
         sig_ref: SigRef,
         callee: Value,
         args: &[Value],
+        may_be_null: bool,
     ) -> crate::Result<()> {
-        todo!()
+        CallBuilder::new_tail(builder, self).call_ref(sig_ref, callee, args, may_be_null);
+        Ok(())
     }
 
     /// Translate a WASM `memory.grow` instruction at `pos`.
@@ -675,11 +854,22 @@ impl TranslationEnvironment<'_> {
     /// Returns the old size (in WASM pages) of the memory or `-1` to indicate failure.
     pub fn translate_memory_grow(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         memory_index: MemoryIndex,
         delta: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        let memory_grow = self.builtin_functions.memory32_grow(pos.func);
+        let index_arg = pos.ins().iconst(I32, i64::from(memory_index.as_u32()));
+        let vmctx = self.vmctx_val(&mut pos);
+
+        // the builtin always takes a 64-bit delta
+        let delta = uextend_to_i64(&mut pos, delta);
+
+        let call_inst = pos.ins().call(memory_grow, &[vmctx, delta, index_arg]);
+        let result = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+
+        let index_type = self.memory_index_type(memory_index);
+        Ok(self.convert_pointer_to_index_type(pos, result, index_type))
     }
 
     /// Translate a WASM `memory.size` instruction at `pos`.
@@ -689,10 +879,68 @@ impl TranslationEnvironment<'_> {
     /// Returns the current size (in WASM pages) of the memory.
     pub fn translate_memory_size(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         memory_index: MemoryIndex,
     ) -> crate::Result<Value> {
-        todo!()
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx_val(&mut pos);
+
+        let current_length_in_bytes =
+            if let Some(def_index) = self.module.defined_memory_index(memory_index) {
+                let offset = self
+                    .offsets
+                    .vmctx_vmmemory_definition_current_length(def_index);
+                let offset = i32::try_from(offset).unwrap();
+                pos.ins()
+                    .load(pointer_type, MemFlags::trusted(), vmctx, offset)
+            } else {
+                let from_offset = self.offsets.vmctx_vmmemory_import_from(memory_index);
+                let from_offset = i32::try_from(from_offset).unwrap();
+                let memory = pos.ins().load(
+                    pointer_type,
+                    MemFlags::trusted().with_readonly(),
+                    vmctx,
+                    from_offset,
+                );
+                let offset = i32::try_from(offset_of!(VMMemoryDefinition, current_length)).unwrap();
+                pos.ins()
+                    .load(pointer_type, MemFlags::trusted(), memory, offset)
+            };
+
+        let page_size_log2 = self.module.memories[memory_index].page_size_log2;
+        let current_length_in_pages = pos
+            .ins()
+            .ushr_imm(current_length_in_bytes, i64::from(page_size_log2));
+
+        let index_type = self.memory_index_type(memory_index);
+        Ok(self.convert_pointer_to_index_type(pos, current_length_in_pages, index_type))
+    }
+
+    fn memory_index_type(&self, index: MemoryIndex) -> Type {
+        if self.module.memories[index].memory64 {
+            I64
+        } else {
+            I32
+        }
+    }
+
+    /// Converts a pointer-sized value returned from the runtime to the index type of a memory.
+    fn convert_pointer_to_index_type(
+        &self,
+        mut pos: FuncCursor,
+        val: Value,
+        index_type: Type,
+    ) -> Value {
+        let pointer_type = self.pointer_type();
+        if pointer_type == index_type {
+            val
+        } else {
+            // memory64 is not supported on 32-bit hosts so we only ever need to narrow the value
+            // here. Note that a failed `memory.grow` returns `usize::MAX` which conveniently reduces
+            // to `-1` for every index type.
+            assert!(pointer_type.bits() > index_type.bits());
+            pos.ins().ireduce(index_type, val)
+        }
     }
 
     /// Translate a WASM `memory.copy` instruction.
@@ -701,14 +949,31 @@ impl TranslationEnvironment<'_> {
     /// `src_pos` and `dst_pos` are the source and destination offsets in bytes, and `len` is the number of bytes to copy.
     pub fn translate_memory_copy(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         src_index: MemoryIndex,
         dst_index: MemoryIndex,
         src_pos: Value,
         dst_pos: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let memory_copy = self.builtin_functions.memory_copy(pos.func);
+        let vmctx = self.vmctx_val(&mut pos);
+
+        let dst_index_arg = pos.ins().iconst(I32, i64::from(dst_index.as_u32()));
+        let src_index_arg = pos.ins().iconst(I32, i64::from(src_index.as_u32()));
+
+        // the builtin always takes 64-bit addresses and length, regardless of the index types of
+        // the involved memories
+        let dst_pos = uextend_to_i64(&mut pos, dst_pos);
+        let src_pos = uextend_to_i64(&mut pos, src_pos);
+        let len = uextend_to_i64(&mut pos, len);
+
+        pos.ins().call(
+            memory_copy,
+            &[vmctx, dst_index_arg, dst_pos, src_index_arg, src_pos, len],
+        );
+
+        Ok(())
     }
 
     /// Translate a WASM `memory.fill` instruction.
@@ -717,39 +982,69 @@ impl TranslationEnvironment<'_> {
     /// value to fill the memory with and `len` is the number of bytes to fill.
     pub fn translate_memory_fill(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         memory_index: MemoryIndex,
         dst: Value,
         value: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let memory_fill = self.builtin_functions.memory_fill(pos.func);
+        let vmctx = self.vmctx_val(&mut pos);
+        let memory_index_arg = pos.ins().iconst(I32, i64::from(memory_index.as_u32()));
+
+        let dst = uextend_to_i64(&mut pos, dst);
+        let len = uextend_to_i64(&mut pos, len);
+
+        pos.ins()
+            .call(memory_fill, &[vmctx, memory_index_arg, dst, value, len]);
+
+        Ok(())
     }
 
     /// Translate a WASM `memory.init` instruction.
     ///
     /// The `memory_index` identifies the linear memory amd `data_index` identifies the passive data segment.
-    /// The `dst` value is the destination offset into the linear memory, `_src` is the offset into the
+    /// The `dst` value is the destination offset into the linear memory, `src` is the offset into the
     /// data segment and `len` is the number of bytes to copy.
     pub fn translate_memory_init(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         memory_index: MemoryIndex,
         data_index: DataIndex,
         dst: Value,
         src: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let memory_init = self.builtin_functions.memory_init(pos.func);
+        let vmctx = self.vmctx_val(&mut pos);
+        let memory_index_arg = pos.ins().iconst(I32, i64::from(memory_index.as_u32()));
+        let data_index_arg = pos.ins().iconst(I32, i64::from(data_index.as_u32()));
+
+        // offsets into data segments and the length are always 32-bit, only the destination
+        // depends on the memory's index type
+        let dst = uextend_to_i64(&mut pos, dst);
+
+        pos.ins().call(
+            memory_init,
+            &[vmctx, memory_index_arg, data_index_arg, dst, src, len],
+        );
+
+        Ok(())
     }
 
     /// Translate a WASM `data.drop` instruction.
     pub fn translate_data_drop(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         data_index: DataIndex,
     ) -> crate::Result<()> {
-        todo!()
+        let data_drop = self.builtin_functions.data_drop(pos.func);
+        let vmctx = self.vmctx_val(&mut pos);
+        let data_index_arg = pos.ins().iconst(I32, i64::from(data_index.as_u32()));
+
+        pos.ins().call(data_drop, &[vmctx, data_index_arg]);
+
+        Ok(())
     }
 
     /// Translate a WASM `table.size` instruction.
@@ -788,11 +1083,28 @@ impl TranslationEnvironment<'_> {
     /// Returns the element at the given index.
     pub fn translate_table_get(
         &mut self,
-        pos: FuncCursor,
+        builder: &mut FunctionBuilder,
         table_index: TableIndex,
+        table: &CraneliftTable,
         index: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        let pointer_type = self.pointer_type();
+        let heap_type = &self.module.tables[table_index].element_type.heap_type;
+
+        match heap_type.top().inner {
+            // Function references are stored as plain `VMFuncRef` pointers, with null
+            // represented as a null pointer.
+            WasmHeapTopTypeInner::Func => {
+                let (elem_addr, flags) = table.prepare_addr(
+                    builder,
+                    index,
+                    pointer_type,
+                    self.table_access_spectre_mitigation(),
+                );
+                Ok(builder.ins().load(pointer_type, flags, elem_addr, 0))
+            }
+            _ => Err(wasm_unsupported!("`table.get` on {heap_type} tables")),
+        }
     }
 
     /// Translate a WASM `table.set` instruction.
@@ -800,12 +1112,28 @@ impl TranslationEnvironment<'_> {
     /// The `table_index` identifies the table, `value` is the value to set and `index` is the index of the element to set.
     pub fn translate_table_set(
         &mut self,
-        pos: FuncCursor,
+        builder: &mut FunctionBuilder,
         table_index: TableIndex,
+        table: &CraneliftTable,
         value: Value,
         index: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let pointer_type = self.pointer_type();
+        let heap_type = &self.module.tables[table_index].element_type.heap_type;
+
+        match heap_type.top().inner {
+            WasmHeapTopTypeInner::Func => {
+                let (elem_addr, flags) = table.prepare_addr(
+                    builder,
+                    index,
+                    pointer_type,
+                    self.table_access_spectre_mitigation(),
+                );
+                builder.ins().store(flags, value, elem_addr, 0);
+                Ok(())
+            }
+            _ => Err(wasm_unsupported!("`table.set` on {heap_type} tables")),
+        }
     }
 
     /// Translate a WASM `table.copy` instruction.
@@ -844,23 +1172,42 @@ impl TranslationEnvironment<'_> {
     /// `dst` is the destination offset, `_src` is the source offset and `len` is the number of elements to copy.
     pub fn translate_table_init(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         table_index: TableIndex,
         elem_index: ElemIndex,
         dst: Value,
         src: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let table_init = self.builtin_functions.table_init(pos.func);
+        let vmctx = self.vmctx_val(&mut pos);
+        let table_index_arg = pos.ins().iconst(I32, i64::from(table_index.as_u32()));
+        let elem_index_arg = pos.ins().iconst(I32, i64::from(elem_index.as_u32()));
+
+        // like `memory.init`, only the destination depends on the table's index type
+        let dst = uextend_to_i64(&mut pos, dst);
+
+        pos.ins().call(
+            table_init,
+            &[vmctx, table_index_arg, elem_index_arg, dst, src, len],
+        );
+
+        Ok(())
     }
 
     /// Translate a WASM `elem.drop` instruction.
     pub fn translate_elem_drop(
         &mut self,
-        pos: FuncCursor,
+        mut pos: FuncCursor,
         elem_index: ElemIndex,
     ) -> crate::Result<()> {
-        todo!()
+        let elem_drop = self.builtin_functions.elem_drop(pos.func);
+        let vmctx = self.vmctx_val(&mut pos);
+        let elem_index_arg = pos.ins().iconst(I32, i64::from(elem_index.as_u32()));
+
+        pos.ins().call(elem_drop, &[vmctx, elem_index_arg]);
+
+        Ok(())
     }
 
     /// Translate a WASM i32.atomic.wait` or `i64.atomic.wait` instruction.
@@ -927,19 +1274,34 @@ impl TranslationEnvironment<'_> {
     }
 
     /// Translate an `i32` value into an `i31ref`.
-    pub fn translate_ref_i31(&mut self, pos: FuncCursor, value: Value) -> crate::Result<Value> {
-        todo!()
+    ///
+    /// `i31ref`s are unboxed, tagged immediates: the value is shifted left by one and the lowest
+    /// bit is set to distinguish it from (null or heap) references.
+    pub fn translate_ref_i31(&mut self, mut pos: FuncCursor, value: Value) -> crate::Result<Value> {
+        let shifted = pos.ins().ishl_imm(value, 1);
+        Ok(pos.ins().bor_imm(shifted, I31_DISCRIMINANT))
     }
 
     /// Sign-extend an `i31ref` into an `i32`.
-    pub fn translate_i31_get_s(&mut self, pos: FuncCursor, value: Value) -> crate::Result<Value> {
-        todo!()
+    pub fn translate_i31_get_s(
+        &mut self,
+        mut pos: FuncCursor,
+        value: Value,
+    ) -> crate::Result<Value> {
+        pos.ins().trapz(value, TRAP_I31_NULL_REFERENCE);
+        Ok(pos.ins().sshr_imm(value, 1))
     }
 
     /// Zero-extend an `i31ref` into an `i32`.
-    pub fn translate_i31_get_u(&mut self, pos: FuncCursor, value: Value) -> crate::Result<Value> {
-        todo!()
+    pub fn translate_i31_get_u(
+        &mut self,
+        mut pos: FuncCursor,
+        value: Value,
+    ) -> crate::Result<Value> {
+        pos.ins().trapz(value, TRAP_I31_NULL_REFERENCE);
+        Ok(pos.ins().ushr_imm(value, 1))
     }
+
     // Translate a `struct.new` instruction.
     pub fn translate_struct_new(
         &mut self,
@@ -1012,7 +1374,14 @@ impl TranslationEnvironment<'_> {
         elem: Value,
         len: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+        let array = self.alloc_array(builder, elem_size, len);
+        let array_addr = self.array_addr(builder, array);
+        let start = builder
+            .ins()
+            .iadd_imm(array_addr, i64::from(ARRAY_ELEMS_OFFSET));
+        self.fill_array_elems(builder, elem_ty, start, elem, len);
+        Ok(array)
     }
 
     /// Translate an `array.new_default` instruction.
@@ -1022,7 +1391,9 @@ impl TranslationEnvironment<'_> {
         array_type_index: TypeIndex,
         len: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        // the elements of new arrays are zeroed, which is the default value of every storage type
+        let (elem_size, _) = self.array_element_layout(array_type_index);
+        Ok(self.alloc_array(builder, elem_size, len))
     }
 
     /// Translate an `array.new_fixed` instruction.
@@ -1032,7 +1403,21 @@ impl TranslationEnvironment<'_> {
         array_type_index: TypeIndex,
         elems: &[Value],
     ) -> crate::Result<Value> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+        let len = builder
+            .ins()
+            .iconst(I32, i64::try_from(elems.len()).unwrap());
+        let array = self.alloc_array(builder, elem_size, len);
+        let array_addr = self.array_addr(builder, array);
+
+        for (i, elem) in elems.iter().enumerate() {
+            let offset =
+                i64::from(ARRAY_ELEMS_OFFSET) + i64::try_from(i).unwrap() * i64::from(elem_size);
+            let addr = builder.ins().iadd_imm(array_addr, offset);
+            store_array_elem(builder, elem_ty, *elem, addr);
+        }
+
+        Ok(array)
     }
 
     /// Translate an `array.new_data` instruction.
@@ -1044,7 +1429,7 @@ impl TranslationEnvironment<'_> {
         data_offset: Value,
         len: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        Err(wasm_unsupported!("`array.new_data`"))
     }
 
     /// Translate an `array.new_elem` instruction.
@@ -1056,7 +1441,7 @@ impl TranslationEnvironment<'_> {
         elem_offset: Value,
         len: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        Err(wasm_unsupported!("`array.new_elem`"))
     }
 
     /// Translate an `array.copy` instruction.
@@ -1071,7 +1456,21 @@ impl TranslationEnvironment<'_> {
         src_index: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        // validation ensures the source elements can be stored in the destination, and storage
+        // types only match their own kind, so both arrays share the same element layout
+        let (elem_size, elem_ty) = self.array_element_layout(dst_array_type_index);
+
+        builder.ins().trapz(dst_array, TRAP_NULL_REFERENCE);
+        builder.ins().trapz(src_array, TRAP_NULL_REFERENCE);
+        let dst_addr = self.array_addr(builder, dst_array);
+        let src_addr = self.array_addr(builder, src_array);
+        self.array_bounds_check_range(builder, dst_addr, dst_index, len);
+        self.array_bounds_check_range(builder, src_addr, src_index, len);
+
+        let dst = self.array_elem_addr(builder, dst_addr, dst_index, elem_size);
+        let src = self.array_elem_addr(builder, src_addr, src_index, elem_size);
+        self.copy_array_elems(builder, elem_ty, dst, src, len);
+        Ok(())
     }
 
     /// Translate an `array.fill` instruction.
@@ -1084,7 +1483,15 @@ impl TranslationEnvironment<'_> {
         value: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+
+        builder.ins().trapz(array, TRAP_NULL_REFERENCE);
+        let array_addr = self.array_addr(builder, array);
+        self.array_bounds_check_range(builder, array_addr, index, len);
+
+        let start = self.array_elem_addr(builder, array_addr, index, elem_size);
+        self.fill_array_elems(builder, elem_ty, start, value, len);
+        Ok(())
     }
 
     /// Translate an `array.init_data` instruction.
@@ -1098,7 +1505,7 @@ impl TranslationEnvironment<'_> {
         data_offset: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        Err(wasm_unsupported!("`array.init_data`"))
     }
 
     /// Translate an `array.init_elem` instruction.
@@ -1112,7 +1519,7 @@ impl TranslationEnvironment<'_> {
         elem_offset: Value,
         len: Value,
     ) -> crate::Result<()> {
-        todo!()
+        Err(wasm_unsupported!("`array.init_elem`"))
     }
 
     /// Translate an `array.len` instruction.
@@ -1121,7 +1528,9 @@ impl TranslationEnvironment<'_> {
         builder: &mut FunctionBuilder,
         array: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        builder.ins().trapz(array, TRAP_NULL_REFERENCE);
+        let array_addr = self.array_addr(builder, array);
+        Ok(load_array_len(builder, array_addr))
     }
 
     /// Translate an `array.get` instruction.
@@ -1132,7 +1541,9 @@ impl TranslationEnvironment<'_> {
         array: Value,
         index: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+        let addr = self.array_access(builder, array, index, elem_size);
+        Ok(builder.ins().load(elem_ty, array_elem_flags(), addr, 0))
     }
 
     /// Translate an `array.get_s` instruction.
@@ -1143,7 +1554,11 @@ impl TranslationEnvironment<'_> {
         array: Value,
         index: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+        let addr = self.array_access(builder, array, index, elem_size);
+        // validation ensures this is only used with packed storage types
+        let value = builder.ins().load(elem_ty, array_elem_flags(), addr, 0);
+        Ok(builder.ins().sextend(I32, value))
     }
 
     /// Translate an `array.get_u` instruction.
@@ -1154,7 +1569,11 @@ impl TranslationEnvironment<'_> {
         array: Value,
         index: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+        let addr = self.array_access(builder, array, index, elem_size);
+        // validation ensures this is only used with packed storage types
+        let value = builder.ins().load(elem_ty, array_elem_flags(), addr, 0);
+        Ok(builder.ins().uextend(I32, value))
     }
 
     /// Translate an `array.set` instruction.
@@ -1166,17 +1585,687 @@ impl TranslationEnvironment<'_> {
         index: Value,
         value: Value,
     ) -> crate::Result<()> {
-        todo!()
+        let (elem_size, elem_ty) = self.array_element_layout(array_type_index);
+        let addr = self.array_access(builder, array, index, elem_size);
+        store_array_elem(builder, elem_ty, value, addr);
+        Ok(())
+    }
+
+    /// Returns the size in bytes and the IR type of the elements of the array type at
+    /// `array_type_index`.
+    ///
+    /// Packed elements are loaded and stored as `i8` and `i16`.
+    fn array_element_layout(&self, array_type_index: TypeIndex) -> (u32, Type) {
+        let array_ty = self
+            .types
+            .get_wasm_type(self.module.types[array_type_index])
+            .unwrap()
+            .unwrap_array();
+        let elem_ty = match &array_ty.0.element_type {
+            WasmStorageType::I8 => I8,
+            WasmStorageType::I16 => I16,
+            WasmStorageType::Val(ty) => value_type(ty, self.pointer_type()),
+        };
+        (elem_ty.bytes(), elem_ty)
+    }
+
+    /// Allocates an array of `len` zeroed elements of `elem_size` bytes, returning its reference.
+    fn alloc_array(&mut self, builder: &mut FunctionBuilder, elem_size: u32, len: Value) -> Value {
+        let gc_alloc_array = self.builtin_functions.gc_alloc_array(builder.func);
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let elem_size = builder.ins().iconst(I32, i64::from(elem_size));
+        let call = builder.ins().call(gc_alloc_array, &[vmctx, elem_size, len]);
+        builder.func.dfg.first_result(call)
+    }
+
+    /// Returns the address of the array referenced by the non-null `array`.
+    fn array_addr(&mut self, builder: &mut FunctionBuilder, array: Value) -> Value {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let limits = builder.ins().load(
+            pointer_type,
+            MemFlags::trusted().with_readonly(),
+            vmctx,
+            i32::from(self.offsets.static_.vmctx_runtime_limits()),
+        );
+        // The heap base isn't readonly, the heap is only created by the first allocation.
+        let heap_base = builder.ins().load(
+            pointer_type,
+            MemFlags::trusted(),
+            limits,
+            i32::try_from(self.offsets.static_.vmruntime_limits_gc_heap_base()).unwrap(),
+        );
+        let offset = builder.ins().uextend(pointer_type, array);
+        builder.ins().iadd(heap_base, offset)
+    }
+
+    /// Returns the address of element `index` of the array at `array_addr`.
+    fn array_elem_addr(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        array_addr: Value,
+        index: Value,
+        elem_size: u32,
+    ) -> Value {
+        let index = builder.ins().uextend(self.pointer_type(), index);
+        let offset = builder.ins().imul_imm(index, i64::from(elem_size));
+        let addr = builder.ins().iadd(array_addr, offset);
+        builder.ins().iadd_imm(addr, i64::from(ARRAY_ELEMS_OFFSET))
+    }
+
+    /// Returns the address of element `index` of `array`, trapping if `array` is null or `index`
+    /// is out of bounds.
+    fn array_access(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        array: Value,
+        index: Value,
+        elem_size: u32,
+    ) -> Value {
+        builder.ins().trapz(array, TRAP_NULL_REFERENCE);
+        let array_addr = self.array_addr(builder, array);
+        let len = load_array_len(builder, array_addr);
+        let oob = builder
+            .ins()
+            .icmp(IntCC::UnsignedGreaterThanOrEqual, index, len);
+        builder.ins().trapnz(oob, TRAP_ARRAY_OUT_OF_BOUNDS);
+        self.array_elem_addr(builder, array_addr, index, elem_size)
+    }
+
+    /// Traps unless the `len` elements starting at `index` lie within the array at `array_addr`.
+    fn array_bounds_check_range(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        array_addr: Value,
+        index: Value,
+        len: Value,
+    ) {
+        // the end is computed in 64 bits, so it can't overflow
+        let array_len = load_array_len(builder, array_addr);
+        let array_len = builder.ins().uextend(I64, array_len);
+        let index = builder.ins().uextend(I64, index);
+        let len = builder.ins().uextend(I64, len);
+        let end = builder.ins().iadd(index, len);
+        let oob = builder
+            .ins()
+            .icmp(IntCC::UnsignedGreaterThan, end, array_len);
+        builder.ins().trapnz(oob, TRAP_ARRAY_OUT_OF_BOUNDS);
+    }
+
+    /// Stores `value` into the `len` elements of type `elem_ty` starting at `start`.
+    fn fill_array_elems(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        elem_ty: Type,
+        start: Value,
+        value: Value,
+        len: Value,
+    ) {
+        let pointer_type = self.pointer_type();
+        let len = builder.ins().uextend(pointer_type, len);
+        let size = builder.ins().imul_imm(len, i64::from(elem_ty.bytes()));
+        let end = builder.ins().iadd(start, size);
+
+        let header_block = builder.create_block();
+        let addr = builder.append_block_param(header_block, pointer_type);
+        let body_block = builder.create_block();
+        let continue_block = builder.create_block();
+        builder.ins().jump(header_block, &[start]);
+
+        builder.switch_to_block(header_block);
+        let done = builder.ins().icmp(IntCC::Equal, addr, end);
+        builder
+            .ins()
+            .brif(done, continue_block, &[], body_block, &[]);
+
+        builder.switch_to_block(body_block);
+        builder.seal_block(body_block);
+        store_array_elem(builder, elem_ty, value, addr);
+        let next = builder.ins().iadd_imm(addr, i64::from(elem_ty.bytes()));
+        builder.ins().jump(header_block, &[next]);
+        builder.seal_block(header_block);
+
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
+    }
+
+    /// Copies the `len` elements of type `elem_ty` starting at `src` to `dst`.
+    ///
+    /// The ranges may overlap, as in `memmove`.
+    fn copy_array_elems(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        elem_ty: Type,
+        dst: Value,
+        src: Value,
+        len: Value,
+    ) {
+        let pointer_type = self.pointer_type();
+        let elem_size = i64::from(elem_ty.bytes());
+        let len = builder.ins().uextend(pointer_type, len);
+        let size = builder.ins().imul_imm(len, elem_size);
+
+        let forward_header_block = builder.create_block();
+        let forward_offset = builder.append_block_param(forward_header_block, pointer_type);
+        let forward_body_block = builder.create_block();
+        let backward_header_block = builder.create_block();
+        let backward_offset = builder.append_block_param(backward_header_block, pointer_type);
+        let backward_body_block = builder.create_block();
+        let continue_block = builder.create_block();
+
+        // copying towards the start has to go front to back, otherwise back to front, so no
+        // element is overwritten before it is copied
+        let forward = builder.ins().icmp(IntCC::UnsignedLessThanOrEqual, dst, src);
+        let zero = builder.ins().iconst(pointer_type, 0);
+        builder.ins().brif(
+            forward,
+            forward_header_block,
+            &[zero],
+            backward_header_block,
+            &[size],
+        );
+
+        builder.switch_to_block(forward_header_block);
+        let done = builder.ins().icmp(IntCC::Equal, forward_offset, size);
+        builder
+            .ins()
+            .brif(done, continue_block, &[], forward_body_block, &[]);
+
+        builder.switch_to_block(forward_body_block);
+        builder.seal_block(forward_body_block);
+        copy_array_elem(builder, elem_ty, dst, src, forward_offset);
+        let next = builder.ins().iadd_imm(forward_offset, elem_size);
+        builder.ins().jump(forward_header_block, &[next]);
+        builder.seal_block(forward_header_block);
+
+        builder.switch_to_block(backward_header_block);
+        let done = builder.ins().icmp_imm(IntCC::Equal, backward_offset, 0);
+        builder
+            .ins()
+            .brif(done, continue_block, &[], backward_body_block, &[]);
+
+        builder.switch_to_block(backward_body_block);
+        builder.seal_block(backward_body_block);
+        let next = builder.ins().iadd_imm(backward_offset, -elem_size);
+        copy_array_elem(builder, elem_ty, dst, src, next);
+        builder.ins().jump(backward_header_block, &[next]);
+        builder.seal_block(backward_header_block);
+
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
     }
 
     /// Translate a `ref.test` instruction.
+    ///
+    /// Returns an `i32` that is `1` if `gc_ref` is an instance of `ref_ty` and `0` otherwise.
     pub fn translate_ref_test(
         &mut self,
         builder: &mut FunctionBuilder<'_>,
         ref_ty: WasmRefType,
         gc_ref: Value,
     ) -> crate::Result<Value> {
-        todo!()
+        assert!(!ref_ty.heap_type.shared, "shared heap types not supported");
+
+        let non_null_block = builder.create_block();
+        let continue_block = builder.create_block();
+        let result = builder.append_block_param(continue_block, I32);
+
+        // null is only an instance of nullable types, everything else depends on the heap type
+        let is_null = builder.ins().icmp_imm(IntCC::Equal, gc_ref, 0);
+        let null_result = builder.ins().iconst(I32, i64::from(ref_ty.nullable));
+        builder
+            .ins()
+            .brif(is_null, continue_block, &[null_result], non_null_block, &[]);
+
+        builder.switch_to_block(non_null_block);
+        builder.seal_block(non_null_block);
+        let heap_type = &ref_ty.heap_type;
+        let non_null_result = match heap_type.ty {
+            // the top types are inhabited by every non-null reference of their hierarchy, and
+            // `eq` by everything in the internal hierarchy, since the only internal references
+            // that can exist are `i31ref`s and arrays
+            WasmHeapTypeInner::Func
+            | WasmHeapTypeInner::Extern
+            | WasmHeapTypeInner::Any
+            | WasmHeapTypeInner::Eq => builder.ins().iconst(I32, 1),
+            // the bottom types are only inhabited by null
+            WasmHeapTypeInner::NoFunc | WasmHeapTypeInner::NoExtern | WasmHeapTypeInner::None => {
+                builder.ins().iconst(I32, 0)
+            }
+            WasmHeapTypeInner::I31 => builder.ins().band_imm(gc_ref, I31_DISCRIMINANT),
+            // every other non-null internal reference points to an array in the GC heap
+            WasmHeapTypeInner::Array => {
+                let i31_bit = builder.ins().band_imm(gc_ref, I31_DISCRIMINANT);
+                let is_array = builder.ins().icmp_imm(IntCC::Equal, i31_bit, 0);
+                builder.ins().uextend(I32, is_array)
+            }
+            WasmHeapTypeInner::ConcreteFunc(CanonicalizedTypeIndex::Module(index)) => {
+                let matches = self.func_ref_is_subtype(builder, gc_ref, index);
+                builder.ins().uextend(I32, matches)
+            }
+            _ => {
+                return Err(wasm_unsupported!(
+                    "`ref.test` and `ref.cast` against {heap_type}"
+                ))
+            }
+        };
+        builder.ins().jump(continue_block, &[non_null_result]);
+
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
+        Ok(result)
+    }
+
+    /// Translate a `ref.cast` instruction.
+    ///
+    /// Returns `gc_ref` unchanged if it is an instance of `ref_ty` and traps otherwise.
+    pub fn translate_ref_cast(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        ref_ty: WasmRefType,
+        gc_ref: Value,
+    ) -> crate::Result<Value> {
+        let matches = self.translate_ref_test(builder, ref_ty, gc_ref)?;
+        builder.ins().trapz(matches, TRAP_CAST_FAILURE);
+        Ok(gc_ref)
+    }
+
+    /// Checks whether the function referenced by the non-null `func_ref` has the type at
+    /// `expected` or one of its subtypes.
+    ///
+    /// The set of subtypes is known at compile time, since function types can only be declared
+    /// as subtypes of types within the same module. Returns an `i8` that is `1` on a match.
+    fn func_ref_is_subtype(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        func_ref: Value,
+        expected: ModuleInternedTypeIndex,
+    ) -> Value {
+        let sig_id_size = self.offsets.static_.size_of_vmshared_type_index();
+        let sig_id_type = Type::int(u16::from(sig_id_size).wrapping_mul(8)).unwrap();
+        let mem_flags = MemFlags::trusted().with_readonly();
+
+        let actual_type_id = builder.ins().load(
+            sig_id_type,
+            mem_flags,
+            func_ref,
+            i32::try_from(offset_of!(VMFuncRef, type_index)).unwrap(),
+        );
+
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let type_ids = builder.ins().load(
+            self.pointer_type(),
+            mem_flags,
+            vmctx,
+            i32::from(self.offsets.static_.vmctx_type_ids()),
+        );
+
+        let mut matches = builder.ins().iconst(ir::types::I8, 0);
+        for (index, _) in self.types.wasm_types() {
+            if !self.is_module_subtype(index, expected) {
+                continue;
+            }
+
+            let offset =
+                i32::try_from(index.as_u32().checked_mul(sig_id_type.bytes()).unwrap()).unwrap();
+            let type_id = builder.ins().load(sig_id_type, mem_flags, type_ids, offset);
+            let eq = builder.ins().icmp(IntCC::Equal, actual_type_id, type_id);
+            matches = builder.ins().bor(matches, eq);
+        }
+
+        matches
+    }
+
+    /// Returns whether `ty` is `supertype` or one of its (transitive) declared subtypes.
+    fn is_module_subtype(
+        &self,
+        mut ty: ModuleInternedTypeIndex,
+        supertype: ModuleInternedTypeIndex,
+    ) -> bool {
+        loop {
+            if ty == supertype {
+                return true;
+            }
+
+            match self.types.get_wasm_type(ty).and_then(|ty| ty.supertype) {
+                Some(CanonicalizedTypeIndex::Module(next)) => ty = next,
+                _ => return false,
+            }
+        }
+    }
+
+    /// Called once all locals of the function are declared, `num_locals` is the total number of
+    /// locals including parameters.
+    ///
+    /// Variables used by the environment are allocated after the locals, so they don't collide.
+    pub fn after_locals(&mut self, num_locals: usize) {
+        self.epoch_deadline_var = Variable::new(num_locals);
+        self.epoch_ptr_var = Variable::new(num_locals + 1);
+        self.fuel_var = Variable::new(num_locals + 2);
+    }
+
+    /// Called after the locals are declared but before any of the function's operators are
+    /// translated.
+    pub fn before_translate_function(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> crate::Result<()> {
+        if self.epoch_interruption || self.consume_fuel {
+            self.declare_vmruntime_limits_ptr(builder);
+        }
+        if self.consume_fuel {
+            self.fuel_function_entry(builder);
+        }
+        if self.epoch_interruption {
+            self.epoch_function_entry(builder);
+        }
+
+        // Checking only at loop headers isn't enough to bound the execution time: a tree of
+        // functions each calling the next level multiple times runs for a time exponential in the
+        // size of the program without a single loop. Checking at every function entry as well
+        // bounds the time between two checks by the length of the longest straight-line function
+        // body.
+        self.interrupt_check(builder);
+
+        Ok(())
+    }
+
+    /// Called after the function's last operator was translated, before the final return is
+    /// inserted.
+    pub fn after_translate_function(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> crate::Result<()> {
+        if self.consume_fuel && state.reachable {
+            self.fuel_function_exit(builder);
+        }
+
+        Ok(())
+    }
+
+    /// Called before an operator is translated.
+    pub fn before_translate_operator(
+        &mut self,
+        op: &Operator,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> crate::Result<()> {
+        if self.consume_fuel {
+            self.fuel_before_op(op, builder, state.reachable);
+        }
+
+        Ok(())
+    }
+
+    /// Called after an operator was translated.
+    pub fn after_translate_operator(
+        &mut self,
+        op: &Operator,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> crate::Result<()> {
+        if self.consume_fuel && state.reachable {
+            self.fuel_after_op(op, builder);
+        }
+
+        Ok(())
+    }
+
+    /// Called at the header of every `loop`, after switching to the loop's body block.
+    pub fn before_loop_header(&mut self, builder: &mut FunctionBuilder) -> crate::Result<()> {
+        self.interrupt_check(builder);
+
+        Ok(())
+    }
+
+    /// Emits the checks that allow interrupting long-running code at function entries and loop
+    /// headers: whether the fuel ran out and/or the epoch deadline was reached, depending on what
+    /// is enabled. Both only branch to a cold block calling into the runtime in the slow case.
+    fn interrupt_check(&mut self, builder: &mut FunctionBuilder) {
+        if self.consume_fuel {
+            self.fuel_check(builder);
+        }
+        if self.epoch_interruption {
+            self.epoch_check(builder);
+        }
+    }
+
+    fn declare_vmruntime_limits_ptr(&mut self, builder: &mut FunctionBuilder) {
+        // The `*const VMRuntimeLimits` never changes during the execution of a function, so we
+        // load it once at the function entry and reuse it everywhere.
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let offset = i32::from(self.offsets.static_.vmctx_runtime_limits());
+        debug_assert!(self.vmruntime_limits_ptr.is_reserved_value());
+        self.vmruntime_limits_ptr = builder.ins().load(
+            pointer_type,
+            MemFlags::trusted().with_readonly(),
+            vmctx,
+            offset,
+        );
+    }
+
+    fn fuel_function_entry(&mut self, builder: &mut FunctionBuilder) {
+        // The fuel is cached in a variable while executing the function, it is written back to the
+        // store whenever control might leave the function.
+        builder.declare_var(self.fuel_var, I64);
+        self.fuel_load_into_var(builder);
+    }
+
+    fn fuel_function_exit(&mut self, builder: &mut FunctionBuilder) {
+        self.fuel_save_from_var(builder);
+    }
+
+    fn fuel_before_op(&mut self, op: &Operator, builder: &mut FunctionBuilder, reachable: bool) {
+        if !reachable {
+            // Whatever made the code unreachable already accounted for the fuel consumed so far.
+            debug_assert_eq!(self.fuel_consumed, 0);
+            return;
+        }
+
+        self.fuel_consumed += match op {
+            // These generate no code
+            Operator::Nop | Operator::Drop => 0,
+            // Control flow itself is cheap, note that `if` isn't part of this since it performs
+            // a conditional check.
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::Unreachable
+            | Operator::Return
+            | Operator::Else
+            | Operator::End => 0,
+            _ => 1,
+        };
+
+        match op {
+            // Control is about to leave this function, so the store must observe the fuel consumed
+            // so far.
+            Operator::Unreachable
+            | Operator::Return
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::CallRef { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::ReturnCallRef { .. } => {
+                self.fuel_increment_var(builder);
+                self.fuel_save_from_var(builder);
+            }
+            // These end the current basic block, the consumed fuel must be accounted for before
+            // control flow continues elsewhere. Code before a loop is only counted once this way.
+            // `block` is deliberately missing, entering it is unconditional.
+            Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::End
+            | Operator::Else => self.fuel_increment_var(builder),
+            // Operators that might trap don't update the fuel, it is only tracked approximately.
+            _ => {}
+        }
+    }
+
+    fn fuel_after_op(&mut self, op: &Operator, builder: &mut FunctionBuilder) {
+        // The callee consumed fuel as well
+        if let Operator::Call { .. } | Operator::CallIndirect { .. } | Operator::CallRef { .. } = op
+        {
+            self.fuel_load_into_var(builder);
+        }
+    }
+
+    /// Adds the fuel consumed since the last update to `fuel_var`.
+    fn fuel_increment_var(&mut self, builder: &mut FunctionBuilder) {
+        let consumption = mem::replace(&mut self.fuel_consumed, 0);
+        if consumption == 0 {
+            return;
+        }
+
+        let fuel = builder.use_var(self.fuel_var);
+        let fuel = builder.ins().iadd_imm(fuel, consumption);
+        builder.def_var(self.fuel_var, fuel);
+    }
+
+    fn fuel_load_into_var(&mut self, builder: &mut FunctionBuilder) {
+        let offset = i32::try_from(self.offsets.static_.vmruntime_limits_fuel_consumed()).unwrap();
+        let fuel = builder
+            .ins()
+            .load(I64, MemFlags::trusted(), self.vmruntime_limits_ptr, offset);
+        builder.def_var(self.fuel_var, fuel);
+    }
+
+    fn fuel_save_from_var(&mut self, builder: &mut FunctionBuilder) {
+        let offset = i32::try_from(self.offsets.static_.vmruntime_limits_fuel_consumed()).unwrap();
+        let fuel = builder.use_var(self.fuel_var);
+        builder
+            .ins()
+            .store(MemFlags::trusted(), fuel, self.vmruntime_limits_ptr, offset);
+    }
+
+    fn fuel_check(&mut self, builder: &mut FunctionBuilder) {
+        self.fuel_increment_var(builder);
+        let out_of_fuel_block = builder.create_block();
+        builder.set_cold_block(out_of_fuel_block);
+        let continuation_block = builder.create_block();
+
+        // The consumed fuel counts up from a negative number, once it is no longer negative we
+        // ran out of fuel.
+        let fuel = builder.use_var(self.fuel_var);
+        let cmp = builder
+            .ins()
+            .icmp_imm(IntCC::SignedGreaterThanOrEqual, fuel, 0);
+        builder
+            .ins()
+            .brif(cmp, out_of_fuel_block, &[], continuation_block, &[]);
+        builder.seal_block(out_of_fuel_block);
+
+        // Call into the runtime to trap or yield. It might add fuel, so we save and reload it
+        // around the call.
+        builder.switch_to_block(out_of_fuel_block);
+        self.fuel_save_from_var(builder);
+        let out_of_gas = self.builtin_functions.out_of_gas(builder.func);
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        builder.ins().call(out_of_gas, &[vmctx]);
+        self.fuel_load_into_var(builder);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(continuation_block);
+
+        builder.switch_to_block(continuation_block);
+    }
+
+    fn epoch_function_entry(&mut self, builder: &mut FunctionBuilder) {
+        builder.declare_var(self.epoch_deadline_var, I64);
+        self.epoch_load_deadline_into_var(builder);
+
+        builder.declare_var(self.epoch_ptr_var, self.pointer_type());
+        let epoch_ptr = self.epoch_ptr(builder);
+        builder.def_var(self.epoch_ptr_var, epoch_ptr);
+    }
+
+    fn epoch_load_deadline_into_var(&mut self, builder: &mut FunctionBuilder) {
+        let offset = i32::try_from(self.offsets.static_.vmruntime_limits_epoch_deadline()).unwrap();
+        let deadline =
+            builder
+                .ins()
+                .load(I64, MemFlags::trusted(), self.vmruntime_limits_ptr, offset);
+        builder.def_var(self.epoch_deadline_var, deadline);
+    }
+
+    fn epoch_ptr(&mut self, builder: &mut FunctionBuilder) -> Value {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let offset = i32::from(self.offsets.static_.vmctx_epoch_ptr());
+        builder.ins().load(
+            pointer_type,
+            MemFlags::trusted().with_readonly(),
+            vmctx,
+            offset,
+        )
+    }
+
+    fn epoch_load_current(&mut self, builder: &mut FunctionBuilder) -> Value {
+        let addr = builder.use_var(self.epoch_ptr_var);
+        builder.ins().load(I64, MemFlags::trusted(), addr, 0)
+    }
+
+    fn epoch_check(&mut self, builder: &mut FunctionBuilder) {
+        let continuation_block = builder.create_block();
+
+        // Load the current epoch and compare it against the cached deadline.
+        let cur_epoch_value = self.epoch_load_current(builder);
+        self.epoch_check_cached(builder, cur_epoch_value, continuation_block);
+
+        // The epoch reached the cached deadline, but the actual deadline might have been updated
+        // in the meantime (e.g. by a function we called yielding) so reload it and check again.
+        self.epoch_check_full(builder, cur_epoch_value, continuation_block);
+    }
+
+    fn epoch_check_cached(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        cur_epoch_value: Value,
+        continuation_block: ir::Block,
+    ) {
+        let new_epoch_block = builder.create_block();
+        builder.set_cold_block(new_epoch_block);
+
+        let epoch_deadline = builder.use_var(self.epoch_deadline_var);
+        let cmp = builder.ins().icmp(
+            IntCC::UnsignedGreaterThanOrEqual,
+            cur_epoch_value,
+            epoch_deadline,
+        );
+        builder
+            .ins()
+            .brif(cmp, new_epoch_block, &[], continuation_block, &[]);
+        builder.seal_block(new_epoch_block);
+
+        builder.switch_to_block(new_epoch_block);
+    }
+
+    fn epoch_check_full(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        cur_epoch_value: Value,
+        continuation_block: ir::Block,
+    ) {
+        // The deadline is kept in a variable to speed up the common case, but here we want a
+        // precise check so we reload it from the store first.
+        self.epoch_load_deadline_into_var(builder);
+        self.epoch_check_cached(builder, cur_epoch_value, continuation_block);
+
+        // The deadline was reached, call into the runtime to trap or yield. `new_epoch` returns
+        // the new deadline, so we don't have to reload it.
+        let new_epoch = self.builtin_functions.new_epoch(builder.func);
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let call = builder.ins().call(new_epoch, &[vmctx]);
+        let new_deadline = *builder.func.dfg.inst_results(call).first().unwrap();
+        builder.def_var(self.epoch_deadline_var, new_deadline);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(continuation_block);
+
+        builder.switch_to_block(continuation_block);
     }
 }
 
@@ -1233,7 +2322,7 @@ impl<'a, 'func, 'module_env> CallBuilder<'a, 'func, 'module_env> {
         callee: FuncRef,
         call_args: &[Value],
     ) -> Inst {
-        let mut real_call_args = Vec::with_capacity(call_args.len() +2);
+        let mut real_call_args = Vec::with_capacity(call_args.len() + 2);
         let caller_vmctx = self
             .builder
             .func
@@ -1354,6 +2443,26 @@ impl<'a, 'func, 'module_env> CallBuilder<'a, 'func, 'module_env> {
         Reachability::Reachable(inst)
     }
 
+    /// Call through a function reference used by [`call_ref`][call_ref] and
+    /// [`return_call_ref`][return_call_ref].
+    ///
+    /// The type of `callee` was already checked during validation, so the only thing left to check
+    /// is that the reference is non-null, which is only necessary if `may_be_null` is set.
+    ///
+    /// [call_ref]: https://webassembly.github.io/function-references/core/exec/instructions.html#xref-syntax-instructions-syntax-instr-control-mathsf-call-ref-x
+    /// [return_call_ref]: https://webassembly.github.io/function-references/core/exec/instructions.html#xref-syntax-instructions-syntax-instr-control-mathsf-return-call-ref-x
+    pub fn call_ref(
+        mut self,
+        sig_ref: SigRef,
+        callee: Value,
+        call_args: &[Value],
+        may_be_null: bool,
+    ) -> Inst {
+        let trap_code = may_be_null.then_some(TRAP_NULL_REFERENCE);
+        let (func_ptr, callee_vmctx) = self.load_func_and_vmctx(callee, trap_code);
+        self.unchecked_indirect_call(sig_ref, func_ptr, callee_vmctx, call_args)
+    }
+
     fn check_indirect_call_type_signature(
         &mut self,
         table_index: TableIndex,
@@ -1565,3 +2674,62 @@ impl<'a, 'func, 'module_env> CallBuilder<'a, 'func, 'module_env> {
         }
     }
 }
+
+/// Zero-extends `val` to 64 bits if it is a 32-bit value.
+///
+/// Builtins take all memory addresses and lengths as 64-bit values so they work for both 32- and
+/// 64-bit memories.
+fn uextend_to_i64(pos: &mut FuncCursor, val: Value) -> Value {
+    if pos.func.dfg.value_type(val) == I32 {
+        pos.ins().uextend(I64, val)
+    } else {
+        val
+    }
+}
+
+/// The flags for accessing array elements, which never trap since every access is bounds checked.
+///
+/// Elements are only aligned to 8 bytes, so `v128` elements might be misaligned.
+fn array_elem_flags() -> MemFlags {
+    MemFlags::new().with_notrap()
+}
+
+/// Loads the length of the array at `array_addr`.
+fn load_array_len(builder: &mut FunctionBuilder, array_addr: Value) -> Value {
+    builder.ins().load(
+        I32,
+        MemFlags::trusted(),
+        array_addr,
+        i32::try_from(ARRAY_LENGTH_OFFSET).unwrap(),
+    )
+}
+
+/// Stores `value` as an array element of type `elem_ty` at `addr`.
+///
+/// Values of packed elements are `i32`s that get truncated.
+fn store_array_elem(builder: &mut FunctionBuilder, elem_ty: Type, value: Value, addr: Value) {
+    let flags = array_elem_flags();
+    if builder.func.dfg.value_type(value) == elem_ty {
+        builder.ins().store(flags, value, addr, 0);
+    } else if elem_ty == I8 {
+        builder.ins().istore8(flags, value, addr, 0);
+    } else {
+        debug_assert_eq!(elem_ty, I16);
+        builder.ins().istore16(flags, value, addr, 0);
+    }
+}
+
+/// Copies the array element of type `elem_ty` at `offset` bytes from `src` to the same offset
+/// from `dst`.
+fn copy_array_elem(
+    builder: &mut FunctionBuilder,
+    elem_ty: Type,
+    dst: Value,
+    src: Value,
+    offset: Value,
+) {
+    let src = builder.ins().iadd(src, offset);
+    let value = builder.ins().load(elem_ty, array_elem_flags(), src, 0);
+    let dst = builder.ins().iadd(dst, offset);
+    builder.ins().store(array_elem_flags(), value, dst, 0);
+}
@@ -62,7 +62,11 @@ impl FuncTranslator {
         builder.append_block_params_for_function_returns(exit_block);
         self.state.initialize(&builder.func.signature, exit_block);
 
-        translate_local_decls(&mut reader, &mut builder, num_params, validator, env)?;
+        let num_locals =
+            translate_local_decls(&mut reader, &mut builder, num_params, validator, env)?;
+        env.after_locals(num_locals);
+        env.before_translate_function(&mut builder)?;
+
         translate_function_body(validator, reader, &mut builder, &mut self.state, env)?;
 
         builder.finalize();
@@ -86,11 +90,13 @@ fn declare_wasm_parameters(
             // This is a normal WebAssembly signature parameter, so create a local for it.
             let local = Variable::new(next_local);
             builder.declare_var(local, param_type.value_type);
-            // This is checked by validation to not overflow
-            next_local += 1;
 
             let param_value = builder.block_params(entry_block)[i];
             builder.def_var(local, param_value);
+            builder.set_val_label(param_value, ValueLabel::new(next_local));
+
+            // This is checked by validation to not overflow
+            next_local += 1;
         }
         if param_type.purpose == ir::ArgumentPurpose::VMContext {
             let param_value = builder.block_params(entry_block)[i];
@@ -101,13 +107,15 @@ fn declare_wasm_parameters(
     next_local
 }
 
+/// Declares the locals of the function, returning the total number of locals including the
+/// parameters.
 fn translate_local_decls(
     reader: &mut BinaryReader,
     builder: &mut FunctionBuilder,
     num_params: usize,
     validator: &mut FuncValidator<impl WasmModuleResources>,
     env: &mut TranslationEnvironment,
-) -> crate::Result<()> {
+) -> crate::Result<usize> {
     let mut next_local = num_params;
     let local_count = reader.read_var_u32()?;
 
@@ -120,7 +128,7 @@ fn translate_local_decls(
         declare_locals(builder, count, ty, &mut next_local, env);
     }
 
-    Ok(())
+    Ok(next_local)
 }
 
 fn declare_locals(
@@ -207,10 +215,12 @@ fn translate_function_body(
         let pos = reader.original_position();
         builder.set_srcloc(cur_srcloc(&reader));
         let op = reader.read_operator()?;
+        env.before_translate_operator(&op, builder, state)?;
         translate_operator(validator, &op, builder, state, env)?;
+        env.after_translate_operator(&op, builder, state)?;
         validator.op(pos, &op)?;
     }
-    // env.after_translate_function(builder, state)?;
+    env.after_translate_function(builder, state)?;
     let pos = reader.original_position();
     validator.finish(pos)?;
 
@@ -1,5 +1,6 @@
 use crate::cranelift::code_translator::Reachability;
 use crate::cranelift::env::TranslationEnvironment;
+use crate::translate::MemoryStyle;
 use crate::trap::TRAP_HEAP_MISALIGNED;
 use cranelift_codegen::cursor::{Cursor, FuncCursor};
 use cranelift_codegen::ir;
@@ -13,6 +14,8 @@ use wasmparser::MemArg;
 pub struct CraneliftMemory {
     /// The address of the start of the heap's storage.
     pub base_gv: ir::GlobalValue,
+    /// The current length of the heap's storage in bytes.
+    pub current_length_gv: ir::GlobalValue,
     /// The index type for the heap.
     pub index_type: ir::Type,
     /// The memory type for the pointed-to memory, if using proof-carrying code.
@@ -29,6 +32,8 @@ pub struct CraneliftMemory {
     pub max_size: Option<u64>,
     /// Size in bytes of the offset-guard pages following the heap.
     pub offset_guard_size: u64,
+    /// Whether accesses can rely on the guard pages or must be checked explicitly.
+    pub style: MemoryStyle,
     /// The log2 of this memory's page size.
     pub page_size_log2: u8,
 }
@@ -158,10 +163,6 @@ impl CraneliftMemory {
 
         let host_page_size_log2 = env.target_isa().page_size_align_log2();
         let can_use_virtual_memory = self.page_size_log2 >= host_page_size_log2;
-        assert!(
-            can_use_virtual_memory,
-            "k23's memories require the ability to use virtual memory"
-        );
 
         let make_compare =
             |builder: &mut FunctionBuilder, compare_kind: IntCC, lhs: Value, rhs: Value| {
@@ -209,7 +210,43 @@ impl CraneliftMemory {
                 result
             };
 
-        if offset_and_size > self.bound {
+        if !can_use_virtual_memory || self.style == MemoryStyle::Dynamic {
+            // 0. Memories with pages smaller than the host page size (see the custom-page-sizes
+            //    proposal) can't rely on unmapped memory to catch out-of-bounds accesses since
+            //    the memory's length need not be a multiple of the host page size. Dynamic
+            //    memories have no guard region to catch them in the first place.
+            //
+            //    Instead, we have to explicitly test whether
+            //
+            //        index + offset + access_size > current_length
+            //
+            //    and trap if so.
+            let offset_and_size_value = builder
+                .ins()
+                .iconst(env.pointer_type(), i64::try_from(offset_and_size).unwrap());
+            let adjusted_index = builder.ins().uadd_overflow_trap(
+                index,
+                offset_and_size_value,
+                TrapCode::HEAP_OUT_OF_BOUNDS,
+            );
+            let current_length = builder
+                .ins()
+                .global_value(env.pointer_type(), self.current_length_gv);
+            let oob =
+                builder
+                    .ins()
+                    .icmp(IntCC::UnsignedGreaterThan, adjusted_index, current_length);
+            Reachability::Reachable(self.explicit_check_oob_condition_and_compute_addr(
+                builder,
+                env.pointer_type(),
+                index,
+                offset,
+                access_size,
+                spectre_mitigations_enabled,
+                None,
+                oob,
+            ))
+        } else if offset_and_size > self.bound {
             // 1. First special case: trap immediately if `offset + access_size >
             //    bound`, since we will end up being out-of-bounds regardless of the
             //    given `index`.
@@ -220,7 +257,7 @@ impl CraneliftMemory {
                 <= self
                     .bound
                     .saturating_add(self.offset_guard_size)
-                    .saturating_add(offset_and_size)
+                    .saturating_sub(offset_and_size)
         {
             // 2. Second special case for when we can completely omit explicit
             //    bounds checks for 32-bit static memories.
@@ -33,12 +33,27 @@ pub(crate) enum CraneliftGlobal {
     Custom,
 }
 
+/// The size of a table.
+#[derive(Debug, Clone)]
+pub(crate) enum TableSize {
+    /// The table can't grow, so its size is known at compile time.
+    Static {
+        /// The size of the table, in elements.
+        bound: u64,
+    },
+    /// The table can grow, so its size has to be loaded at runtime.
+    Dynamic {
+        /// Global value giving the current size of the table, in elements.
+        bound_gv: ir::GlobalValue,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct CraneliftTable {
     /// Global value giving the address of the start of the table.
     pub base_gv: ir::GlobalValue,
     /// The size of the table, in elements.
-    pub bound: u64,
+    pub bound: TableSize,
     /// The size of a table element, in bytes.
     pub element_size: u32,
 }
@@ -54,12 +69,28 @@ impl CraneliftTable {
         let index_ty = builder.func.dfg.value_type(index);
 
         // Start with the bounds check. Trap if `index + 1 > bound`.
-        let bound = builder
-            .ins()
-            .iconst(index_ty, Imm64::new(i64::try_from(self.bound).unwrap()));
-        let oob = builder
-            .ins()
-            .icmp(IntCC::UnsignedGreaterThanOrEqual, index, bound);
+        let oob = match self.bound {
+            TableSize::Static { bound } => {
+                let bound = builder
+                    .ins()
+                    .iconst(index_ty, Imm64::new(i64::try_from(bound).unwrap()));
+                builder
+                    .ins()
+                    .icmp(IntCC::UnsignedGreaterThanOrEqual, index, bound)
+            }
+            TableSize::Dynamic { bound_gv } => {
+                // the current length is always stored as a 64-bit integer
+                let bound = builder.ins().global_value(ir::types::I64, bound_gv);
+                let index = if index_ty == ir::types::I64 {
+                    index
+                } else {
+                    builder.ins().uextend(ir::types::I64, index)
+                };
+                builder
+                    .ins()
+                    .icmp(IntCC::UnsignedGreaterThanOrEqual, index, bound)
+            }
+        };
 
         if !spectre_mitigations_enabled {
             builder.ins().trapnz(oob, TRAP_TABLE_OUT_OF_BOUNDS);
@@ -69,7 +69,10 @@ impl Iterator for BlockTypeParamsOrReturns<'_> {
         match self {
             BlockTypeParamsOrReturns::Empty => (0, Some(0)),
             BlockTypeParamsOrReturns::One(_) => (1, Some(1)),
-            BlockTypeParamsOrReturns::Many(slice, _) => (slice.len(), Some(slice.len())),
+            BlockTypeParamsOrReturns::Many(slice, offset) => {
+                let len = slice.len().saturating_sub(*offset);
+                (len, Some(len))
+            }
         }
     }
 }
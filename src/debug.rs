@@ -0,0 +1,443 @@
+//! Inspecting the locals of trapping WebAssembly functions through their DWARF debug info.
+//!
+//! When [`Config::debug_info`][crate::Config::debug_info] is enabled, compiled modules keep their
+//! DWARF sections together with two side tables for every function: a map from machine code
+//! offsets back to WebAssembly instructions and the locations (registers or stack slots) of each
+//! local across the function's machine code.
+//!
+//! Resolving the variables of a trapping frame then works like this:
+//! - Map the trapping pc to the WebAssembly instruction it was generated for, the DWARF address of
+//!   that instruction is its offset relative to the start of the code section.
+//! - Find the `DW_TAG_subprogram` covering that address and look at its parameters and variables.
+//! - Evaluate their `DW_AT_location`, which is either a single expression or a location list. The
+//!   only expressions we understand are the ones pointing at a WebAssembly local
+//!   (`DW_OP_WASM_location 0x0 <local>`), which is what LLVM emits for unoptimized code.
+//! - Look up where the local lives at the trapping pc and read it from the captured registers or
+//!   the copy of the frame's stack taken by the trap handler.
+//!
+//! Source locations are resolved the same way, except that the DWARF address of the instruction is
+//! looked up in the line number programs instead.
+
+use crate::compile::FunctionDebugInfo;
+use crate::runtime::VMVal;
+use crate::translate::{DebugInfo, WasmValType};
+use crate::{Module, Val};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::num::NonZeroU64;
+use cranelift_codegen::ir::ValueLabel;
+use cranelift_codegen::LabelValueLoc;
+use gimli::{EndianSlice, LittleEndian, Reader, Section, SectionId};
+
+type R<'a> = EndianSlice<'a, LittleEndian>;
+
+/// `DW_OP_WASM_location` location kind for WebAssembly locals.
+const WASM_LOCATION_LOCAL: u8 = 0x00;
+
+/// The machine state of a WebAssembly frame that raised a trap.
+///
+/// Obtained through [`Store::last_trap_frame`][crate::Store::last_trap_frame].
+#[derive(Debug, Clone)]
+pub struct TrapFrame {
+    pub(crate) pc: usize,
+    pub(crate) fp: usize,
+    /// The general purpose registers, indexed by their hardware encoding.
+    pub(crate) regs: [usize; 32],
+    /// A copy of the frame's stack, ending at the canonical frame address. Empty unless the
+    /// engine generates debug info.
+    pub(crate) stack: Box<[u8]>,
+}
+
+impl TrapFrame {
+    /// Returns the program counter of the trapping instruction.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Returns the frame pointer of the trapping function.
+    pub fn fp(&self) -> usize {
+        self.fp
+    }
+}
+
+/// A named local or parameter of a trapping WebAssembly function.
+#[derive(Debug, Clone)]
+pub struct DebugVariable {
+    /// The name of the variable as recorded in the DWARF debug info.
+    pub name: String,
+    /// The value of the variable at the trap, `None` if its location is unknown.
+    pub value: Option<Val>,
+}
+
+/// A location in the source code a WebAssembly module was compiled from.
+///
+/// Obtained through [`Module::source_location`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLoc {
+    /// The path of the source file, including its directory if recorded.
+    pub file: String,
+    /// The 1-based line number, `0` if the instruction can't be attributed to a line.
+    pub line: u64,
+    /// The 1-based column number, `0` if the instruction can't be attributed to a column.
+    pub column: u64,
+}
+
+/// The DWARF sections of a module, kept around after compilation.
+#[derive(Debug)]
+pub(crate) struct ModuleDwarf {
+    sections: Vec<(SectionId, Box<[u8]>)>,
+    /// The offset of the code section in the original wasm file, DWARF addresses are relative to it.
+    code_section_offset: u64,
+}
+
+impl ModuleDwarf {
+    pub fn new(info: &DebugInfo<'_>) -> Self {
+        let mut sections = Vec::new();
+        let mut push = |id: SectionId, data: &R<'_>| {
+            if !data.is_empty() {
+                sections.push((id, Box::from(data.slice())));
+            }
+        };
+
+        push(SectionId::DebugAbbrev, info.dwarf.debug_abbrev.reader());
+        push(SectionId::DebugAddr, info.dwarf.debug_addr.reader());
+        push(SectionId::DebugInfo, info.dwarf.debug_info.reader());
+        push(SectionId::DebugLine, info.dwarf.debug_line.reader());
+        push(SectionId::DebugLineStr, info.dwarf.debug_line_str.reader());
+        push(SectionId::DebugStr, info.dwarf.debug_str.reader());
+        push(
+            SectionId::DebugStrOffsets,
+            info.dwarf.debug_str_offsets.reader(),
+        );
+        push(SectionId::DebugLoc, info.debug_loc.reader());
+        push(SectionId::DebugLocLists, info.debug_loclists.reader());
+        push(SectionId::DebugRanges, info.debug_ranges.reader());
+        push(SectionId::DebugRngLists, info.debug_rnglists.reader());
+
+        Self {
+            sections,
+            code_section_offset: info.code_section_offset,
+        }
+    }
+
+    fn load(&self) -> crate::Result<gimli::Dwarf<R<'_>>> {
+        let dwarf = gimli::Dwarf::load(|id| {
+            let data = self
+                .sections
+                .iter()
+                .find(|(section, _)| *section == id)
+                .map_or(&[][..], |(_, data)| &**data);
+
+            Ok::<_, gimli::Error>(EndianSlice::new(data, LittleEndian))
+        })?;
+
+        Ok(dwarf)
+    }
+}
+
+/// Resolves the named variables of the function in `module` that trapped at `text_offset`.
+pub(crate) fn variables(
+    module: &Module,
+    text_offset: usize,
+    frame: &TrapFrame,
+) -> crate::Result<Vec<DebugVariable>> {
+    let Some(module_dwarf) = module.dwarf() else {
+        return Ok(Vec::new());
+    };
+    let Some((debug, func_offset, addr)) = lookup_addr(module, module_dwarf, text_offset) else {
+        return Ok(Vec::new());
+    };
+
+    let dwarf = module_dwarf.load()?;
+    let cx = FrameContext {
+        dwarf: &dwarf,
+        debug,
+        func_offset,
+        addr,
+        frame,
+    };
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut tree = unit.entries_tree(None)?;
+        if let Some(vars) = cx.find_subprogram(&unit, tree.root()?)? {
+            return Ok(vars);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Resolves the source location of the WebAssembly instruction `text_offset` in `module` was
+/// generated for.
+pub(crate) fn source_location(
+    module: &Module,
+    text_offset: usize,
+) -> crate::Result<Option<SourceLoc>> {
+    let Some(module_dwarf) = module.dwarf() else {
+        return Ok(None);
+    };
+    let Some((_, _, addr)) = lookup_addr(module, module_dwarf, text_offset) else {
+        return Ok(None);
+    };
+
+    let dwarf = module_dwarf.load()?;
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        if let Some(loc) = line_for_addr(&dwarf, &unit, addr)? {
+            return Ok(Some(loc));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Finds the function containing `text_offset` and returns its debug info, the offset relative to
+/// the function start and the DWARF address of the WebAssembly instruction the code at
+/// `text_offset` belongs to.
+fn lookup_addr<'m>(
+    module: &'m Module,
+    module_dwarf: &ModuleDwarf,
+    text_offset: usize,
+) -> Option<(&'m FunctionDebugInfo, u32, u64)> {
+    let (debug, func_offset) = module.function_info().values().find_map(|info| {
+        let start = usize::try_from(info.wasm_func_loc.start).unwrap();
+        let len = usize::try_from(info.wasm_func_loc.length).unwrap();
+        let offset = text_offset
+            .checked_sub(start)
+            .filter(|offset| *offset < len)?;
+        Some((info.debug.as_ref()?, u32::try_from(offset).unwrap()))
+    })?;
+
+    // the code belongs to the last WebAssembly instruction starting at or before it
+    let srcloc = debug
+        .address_map
+        .iter()
+        .take_while(|mapping| mapping.code_offset <= func_offset)
+        .last()
+        .and_then(|mapping| mapping.srcloc.file_offset())?;
+    let addr = u64::from(srcloc).checked_sub(module_dwarf.code_section_offset)?;
+
+    Some((debug, func_offset, addr))
+}
+
+/// Runs the line number program of `unit`, looking for the row that covers `addr`.
+fn line_for_addr(
+    dwarf: &gimli::Dwarf<R<'_>>,
+    unit: &gimli::Unit<R<'_>>,
+    addr: u64,
+) -> crate::Result<Option<SourceLoc>> {
+    let Some(program) = unit.line_program.clone() else {
+        return Ok(None);
+    };
+
+    // a row covers all addresses up to the next row of the same sequence
+    let mut rows = program.rows();
+    let mut prev: Option<gimli::LineRow> = None;
+    while let Some((header, row)) = rows.next_row()? {
+        if let Some(prev) = prev.filter(|prev| (prev.address()..row.address()).contains(&addr)) {
+            let file = match prev.file(header) {
+                Some(entry) => {
+                    let name = attr_string(dwarf, unit, entry.path_name())?;
+                    match entry.directory(header) {
+                        Some(dir) if !name.starts_with('/') => {
+                            let mut path = attr_string(dwarf, unit, dir)?;
+                            if !path.is_empty() && !path.ends_with('/') {
+                                path.push('/');
+                            }
+                            path.push_str(&name);
+                            path
+                        }
+                        _ => name,
+                    }
+                }
+                None => String::new(),
+            };
+            let column = match prev.column() {
+                gimli::ColumnType::LeftEdge => 0,
+                gimli::ColumnType::Column(column) => column.get(),
+            };
+
+            return Ok(Some(SourceLoc {
+                file,
+                line: prev.line().map_or(0, NonZeroU64::get),
+                column,
+            }));
+        }
+
+        prev = (!row.end_sequence()).then_some(*row);
+    }
+
+    Ok(None)
+}
+
+fn attr_string(
+    dwarf: &gimli::Dwarf<R<'_>>,
+    unit: &gimli::Unit<R<'_>>,
+    attr: gimli::AttributeValue<R<'_>>,
+) -> crate::Result<String> {
+    let str = dwarf.attr_string(unit, attr)?;
+    Ok(String::from_utf8_lossy(str.slice()).into_owned())
+}
+
+struct FrameContext<'a> {
+    dwarf: &'a gimli::Dwarf<R<'a>>,
+    debug: &'a FunctionDebugInfo,
+    /// The offset of the trapping instruction relative to the function start.
+    func_offset: u32,
+    /// The DWARF address of the trapping instruction.
+    addr: u64,
+    frame: &'a TrapFrame,
+}
+
+impl<'a> FrameContext<'a> {
+    /// Searches the tree below `node` for the subprogram covering the trapping address and
+    /// resolves its variables.
+    fn find_subprogram(
+        &self,
+        unit: &gimli::Unit<R<'a>>,
+        node: gimli::EntriesTreeNode<'_, '_, '_, R<'a>>,
+    ) -> crate::Result<Option<Vec<DebugVariable>>> {
+        let entry = node.entry();
+        if entry.tag() == gimli::DW_TAG_subprogram && self.covers_addr(unit, entry)? {
+            let mut vars = Vec::new();
+            let mut children = node.children();
+            while let Some(child) = children.next()? {
+                let entry = child.entry();
+                if matches!(
+                    entry.tag(),
+                    gimli::DW_TAG_formal_parameter | gimli::DW_TAG_variable
+                ) {
+                    vars.extend(self.variable(unit, entry)?);
+                }
+            }
+            return Ok(Some(vars));
+        }
+
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            if let Some(vars) = self.find_subprogram(unit, child)? {
+                return Ok(Some(vars));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn covers_addr(
+        &self,
+        unit: &gimli::Unit<R<'a>>,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, R<'a>>,
+    ) -> crate::Result<bool> {
+        let mut ranges = self.dwarf.die_ranges(unit, entry)?;
+        while let Some(range) = ranges.next()? {
+            if (range.begin..range.end).contains(&self.addr) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Resolves a single variable, returns `None` for unnamed variables.
+    fn variable(
+        &self,
+        unit: &gimli::Unit<R<'a>>,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, R<'a>>,
+    ) -> crate::Result<Option<DebugVariable>> {
+        let Some(name) = entry.attr_value(gimli::DW_AT_name)? else {
+            return Ok(None);
+        };
+        let name = self.dwarf.attr_string(unit, name)?;
+        let name = String::from_utf8_lossy(name.slice()).into_owned();
+
+        let expr = match entry.attr_value(gimli::DW_AT_location)? {
+            Some(gimli::AttributeValue::Exprloc(expr)) => Some(expr),
+            Some(attr) => match self.dwarf.attr_locations(unit, attr)? {
+                Some(mut locations) => {
+                    let mut expr = None;
+                    while let Some(location) = locations.next()? {
+                        if (location.range.begin..location.range.end).contains(&self.addr) {
+                            expr = Some(location.data);
+                            break;
+                        }
+                    }
+                    expr
+                }
+                None => None,
+            },
+            None => None,
+        };
+
+        let value = match expr {
+            Some(expr) => wasm_local(expr).and_then(|local| self.read_local(local)),
+            None => None,
+        };
+
+        Ok(Some(DebugVariable { name, value }))
+    }
+
+    /// Reads the value of the WebAssembly local `local` at the trapping instruction.
+    fn read_local(&self, local: u32) -> Option<Val> {
+        let ty = self.debug.local_types.get(usize::try_from(local).ok()?)?;
+        let ranges = self
+            .debug
+            .value_labels_ranges
+            .get(&ValueLabel::from_u32(local))?;
+        // ranges are recorded by instruction end offset, so the state right before the trapping
+        // instruction is described by the ranges covering its start offset
+        let range = ranges
+            .iter()
+            .find(|range| (range.start..range.end).contains(&self.func_offset))?;
+
+        let raw = match range.loc {
+            // only integer locals live in the general purpose registers we capture
+            LabelValueLoc::Reg(reg) if matches!(ty, WasmValType::I32 | WasmValType::I64) => {
+                let reg = reg.to_real_reg()?;
+                u64::try_from(self.frame.regs[usize::from(reg.hw_enc())]).ok()?
+            }
+            LabelValueLoc::Reg(_) => return None,
+            LabelValueLoc::CFAOffset(offset) => {
+                // the frame is gone by now, so read the slot from the copy taken when it trapped
+                let stack = &self.frame.stack;
+                let start = stack
+                    .len()
+                    .checked_add_signed(isize::try_from(offset).ok()?)?;
+                let bytes = stack.get(start..start.checked_add(size_of::<u64>())?)?;
+                u64::from_ne_bytes(bytes.try_into().ok()?)
+            }
+        };
+
+        let raw = VMVal::u64(raw);
+        match ty {
+            WasmValType::I32 => Some(Val::I32(raw.get_i32())),
+            WasmValType::I64 => Some(Val::I64(raw.get_i64())),
+            WasmValType::F32 => Some(Val::F32(raw.get_f32())),
+            WasmValType::F64 => Some(Val::F64(raw.get_f64())),
+            WasmValType::V128 | WasmValType::Ref(_) => None,
+        }
+    }
+}
+
+/// Returns the WebAssembly local a DWARF location expression points at.
+///
+/// Only the simple `DW_OP_WASM_location 0x0 <local>` expression, optionally followed by
+/// `DW_OP_stack_value`, is supported.
+fn wasm_local(expr: gimli::Expression<R<'_>>) -> Option<u32> {
+    let mut reader = expr.0;
+
+    if reader.read_u8().ok()? != gimli::DW_OP_WASM_location.0
+        || reader.read_u8().ok()? != WASM_LOCATION_LOCAL
+    {
+        return None;
+    }
+    let local = reader.read_uleb128_u32().ok()?;
+
+    if !reader.is_empty() && reader.read_u8().ok()? != gimli::DW_OP_stack_value.0 {
+        return None;
+    }
+
+    reader.is_empty().then_some(local)
+}
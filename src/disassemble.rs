@@ -0,0 +1,122 @@
+use crate::compile::{InstructionAddressMapping, Relocation, RelocationTarget};
+use crate::indices::DefinedFuncIndex;
+use crate::{Error, Module};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use capstone::arch::BuildsCapstone;
+use capstone::{arch, Capstone};
+use core::fmt::Write;
+use target_lexicon::{Architecture, Triple};
+
+/// Disassembles the machine code of `def_func_index`, annotating each instruction with the
+/// WebAssembly offset it was generated for and the relocations applied to it.
+pub(crate) fn disassemble_function(
+    module: &Module,
+    def_func_index: DefinedFuncIndex,
+) -> crate::Result<String> {
+    let info = &module.function_info()[def_func_index];
+    let loc = info.wasm_func_loc;
+    let start = usize::try_from(loc.start).unwrap();
+    let end = start + usize::try_from(loc.length).unwrap();
+    let body = &module.code().text()[start..end];
+
+    let cs = capstone_for(module.target())?;
+    let insns = cs.disasm_all(body, 0)?;
+
+    let func_index = module.translated().func_index(def_func_index);
+    let mut out = format!("{}:\n", module.func_symbol(func_index));
+    let mut last_srcloc = None;
+
+    for insn in insns.iter() {
+        let offset = u32::try_from(insn.address()).unwrap();
+        let len = u32::try_from(insn.bytes().len()).unwrap();
+
+        let bytes = insn
+            .bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(
+            out,
+            "  {offset:#06x}: {bytes:<24} {} {}",
+            insn.mnemonic().unwrap_or("<unknown>"),
+            insn.op_str().unwrap_or("")
+        )
+        .unwrap();
+
+        // only print the source position when it changes, one wasm instruction usually expands
+        // to a handful of machine instructions
+        let srcloc = srcloc_for_offset(&info.disas.address_map, offset);
+        if srcloc != last_srcloc {
+            if let Some(pos) = srcloc {
+                write!(out, " ; @{pos:#x}").unwrap();
+            }
+            last_srcloc = srcloc;
+        }
+        out.push('\n');
+
+        for reloc in info
+            .disas
+            .relocations
+            .iter()
+            .filter(|r| (offset..offset + len).contains(&r.offset))
+        {
+            writeln!(
+                out,
+                "          ; reloc {:?} -> {}{:+}",
+                reloc.kind,
+                reloc_target_symbol(module, reloc),
+                reloc.addend
+            )
+            .unwrap();
+        }
+    }
+
+    Ok(out)
+}
+
+/// Returns the offset of the WebAssembly instruction the machine code at `code_offset` was
+/// generated for.
+fn srcloc_for_offset(address_map: &[InstructionAddressMapping], code_offset: u32) -> Option<u32> {
+    let index = match address_map.binary_search_by_key(&code_offset, |m| m.code_offset) {
+        Ok(index) => index,
+        Err(0) => return None,
+        Err(index) => index - 1,
+    };
+    address_map[index].srcloc.file_offset()
+}
+
+fn reloc_target_symbol(module: &Module, reloc: &Relocation) -> String {
+    match reloc.target {
+        RelocationTarget::Wasm(func_index) => module.func_symbol(func_index),
+        RelocationTarget::Builtin(index) => format!("wasm_builtin_{}", index.name()),
+    }
+}
+
+/// Creates a disassembler for the architecture the module's code was compiled for.
+fn capstone_for(target: &Triple) -> crate::Result<Capstone> {
+    let cs = match target.architecture {
+        Architecture::X86_64 => Capstone::new()
+            .x86()
+            .mode(arch::x86::ArchMode::Mode64)
+            .build()?,
+        Architecture::Aarch64(_) => Capstone::new()
+            .arm64()
+            .mode(arch::arm64::ArchMode::Arm)
+            .build()?,
+        Architecture::Riscv64(_) => Capstone::new()
+            .riscv()
+            .mode(arch::riscv::ArchMode::RiscV64)
+            .extra_mode([arch::riscv::ArchExtraMode::RiscVC].into_iter())
+            .build()?,
+        _ => {
+            return Err(Error::UnsupportedTarget(format!(
+                "can't disassemble code for {target}"
+            )))
+        }
+    };
+
+    Ok(cs)
+}
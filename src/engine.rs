@@ -1,8 +1,27 @@
-use crate::compile::Compiler;
+use crate::compile::{compile_wasm_to_array_trampoline, Compiler, FunctionLoc};
+use crate::config::Config;
 use crate::cranelift::CraneliftCompiler;
-use crate::type_registry::TypeRegistry;
+use crate::func::FuncType;
+use crate::module_cache::ModuleCache;
+use crate::runtime::{CodeMemory, MmapVec, VMWasmCallFunction};
+use crate::translate::{
+    self, MemoryDesc, ModuleTypes, TranslatedModule, WasmCompositeType, WasmFuncType, WasmSubType,
+    WasmparserTypeConverter,
+};
+use crate::type_registry::{RegisteredType, TypeRegistry};
+use crate::{placeholder, Error};
+use alloc::format;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicU64, Ordering};
 use cranelift_codegen::settings::{Configurable, Flags};
+use cranelift_codegen::CodegenError;
+use hashbrown::HashMap;
+use spin::lock_api::Mutex;
+use target_lexicon::Triple;
+use wasmparser::{Validator, WasmFeatures};
 
 /// Global context for the runtime.
 ///
@@ -14,39 +33,288 @@ pub struct Engine(Arc<EngineInner>);
 
 #[derive(Debug)]
 pub struct EngineInner {
+    config: Config,
     compiler: CraneliftCompiler,
-    type_registry: TypeRegistry,
+    /// Shared with the types registered in it, see [`RuntimeTypeCollection`][crate::type_registry::RuntimeTypeCollection].
+    type_registry: Arc<TypeRegistry>,
+    epoch: AtomicU64,
+    module_cache: ModuleCache,
+    /// The wasm-to-array trampolines of host functions, shared by all host functions of a type.
+    host_trampolines: Mutex<HashMap<WasmFuncType, (Arc<CodeMemory>, FunctionLoc)>>,
 }
 
 impl Default for Engine {
     fn default() -> Self {
+        Self::new(&Config::default())
+    }
+}
+
+impl Engine {
+    /// Creates a new engine with the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the host architecture isn't supported by the compiler.
+    pub fn new(config: &Config) -> Self {
         let isa_builder = cranelift_codegen::isa::lookup(target_lexicon::HOST).unwrap();
+        Self::with_isa(config, isa_builder).unwrap()
+    }
+
+    /// Creates a new engine compiling code for the target `triple`, e.g. `aarch64-unknown-none`,
+    /// instead of the host.
+    ///
+    /// `cpu_features` is a comma-separated list of Cranelift ISA settings for the target, such as
+    /// `has_lse` or `has_avx2`, the code may use. Settings with values are written as
+    /// `name=value`.
+    ///
+    /// Modules compiled by an engine for a foreign target can be inspected, e.g. through
+    /// [`Module::text`][crate::Module::text], but not instantiated. Compiling for other
+    /// architectures than the host's requires the `cross-compile` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedTarget`] if `triple` is malformed or not supported by the
+    /// compiler, or if `cpu_features` contains unknown settings.
+    pub fn for_target(config: &Config, triple: &str, cpu_features: &str) -> crate::Result<Self> {
+        let triple = Triple::from_str(triple)
+            .map_err(|err| Error::UnsupportedTarget(format!("invalid triple {triple}: {err}")))?;
+        let mut isa_builder = cranelift_codegen::isa::lookup(triple.clone())
+            .map_err(|err| Error::UnsupportedTarget(format!("{triple}: {err}")))?;
+
+        for feature in cpu_features
+            .split(',')
+            .map(str::trim)
+            .filter(|feature| !feature.is_empty())
+        {
+            let res = match feature.split_once('=') {
+                Some((name, value)) => isa_builder.set(name, value),
+                None => isa_builder.enable(feature),
+            };
+            res.map_err(|err| {
+                Error::UnsupportedTarget(format!("CPU feature {feature} of {triple}: {err}"))
+            })?;
+        }
+
+        Self::with_isa(config, isa_builder)
+            .map_err(|err| Error::UnsupportedTarget(format!("{triple}: {err}")))
+    }
+
+    fn with_isa(
+        config: &Config,
+        isa_builder: cranelift_codegen::isa::Builder,
+    ) -> Result<Self, CodegenError> {
         let mut b = cranelift_codegen::settings::builder();
-        b.set("opt_level", "speed_and_size").unwrap();
+        b.set("opt_level", config.opt_level_str()).unwrap();
         b.set("libcall_call_conv", "isa_default").unwrap();
-        b.set("preserve_frame_pointers", "true").unwrap();
-        b.set("enable_probestack", "true").unwrap();
-        b.set("probestack_strategy", "inline").unwrap();
-        let target_isa = isa_builder.finish(Flags::new(b)).unwrap();
+        if config.is_preserve_frame_pointers() {
+            b.enable("preserve_frame_pointers").unwrap();
+        }
+        if config.is_probestack() {
+            b.enable("enable_probestack").unwrap();
+            b.set("probestack_strategy", "inline").unwrap();
+        }
+        if config.is_position_independent_code() {
+            b.enable("is_pic").unwrap();
+        }
+        if config.is_canonicalize_nans() {
+            b.enable("enable_nan_canonicalization").unwrap();
+        }
+        let target_isa = isa_builder.finish(Flags::new(b))?;
 
-        Self(Arc::new(EngineInner {
-            compiler: CraneliftCompiler::new(target_isa),
-            type_registry: TypeRegistry::default(),
-        }))
+        Ok(Self(Arc::new(EngineInner {
+            config: config.clone(),
+            compiler: CraneliftCompiler::new(target_isa, config),
+            type_registry: Arc::new(TypeRegistry::default()),
+            epoch: AtomicU64::new(0),
+            module_cache: ModuleCache::new(config.get_module_cache_capacity()),
+            host_trampolines: Mutex::new(HashMap::new()),
+        })))
+    }
+
+    /// Returns the target triple this engine compiles code for.
+    pub fn target(&self) -> &Triple {
+        self.compiler().triple()
+    }
+
+    /// Returns whether code compiled by this engine can run on the host.
+    pub(crate) fn targets_host(&self) -> bool {
+        let target = self.target();
+        target.architecture == target_lexicon::HOST.architecture
+            && target.default_calling_convention()
+                == target_lexicon::HOST.default_calling_convention()
+    }
+
+    /// Returns the configuration this engine was created with.
+    pub fn config(&self) -> &Config {
+        &self.0.config
+    }
+
+    /// Returns the set of WebAssembly features modules are validated against, see
+    /// [`Config::wasm_features`].
+    ///
+    /// [`Validator`]s that check modules for this engine should be created with these features.
+    pub fn features(&self) -> WasmFeatures {
+        self.0.config.features()
     }
-}
 
-impl Engine {
     pub(crate) fn compiler(&self) -> &dyn Compiler {
         &self.0.compiler
     }
 
     /// Returns the type registry of this engine, used to canonicalize types.
-    pub fn type_registry(&self) -> &TypeRegistry {
+    pub fn type_registry(&self) -> &Arc<TypeRegistry> {
         &self.0.type_registry
     }
 
-    pub(crate) fn same(lhs: &Engine, rhs: &Engine) -> bool {
-        Arc::ptr_eq(&lhs.0, &rhs.0)
+    /// Registers the function type `ty` with this engine's type registry.
+    ///
+    /// Types are deduplicated, so the returned type shares its
+    /// [`type_index`][FuncType::type_index] with all structurally equal function types of this
+    /// engine, whether they were registered through this method, by compiling a module or by
+    /// defining a host function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ty` references concrete types, these only have meaning within a module.
+    pub fn register_type(&self, ty: &wasmparser::FuncType) -> FuncType {
+        let types = ModuleTypes::default();
+        let module = TranslatedModule::default();
+        let ty = WasmparserTypeConverter::new(&types, &module).convert_func_type(ty);
+
+        FuncType::from_registered_type(RegisteredType::new(
+            self,
+            WasmSubType {
+                is_final: true,
+                supertype: None,
+                composite_type: WasmCompositeType::new_func(false, ty),
+            },
+        ))
+    }
+
+    /// Advances the epoch of this engine by one tick.
+    ///
+    /// WebAssembly code running in stores of this engine notices the new epoch at its next
+    /// function entry or loop header, see [`Config::epoch_interruption`]. This is cheap and may
+    /// be called from any thread, e.g. from a timer to bound the execution time of WebAssembly.
+    pub fn increment_epoch(&self) {
+        self.0.epoch.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the current epoch of this engine.
+    pub(crate) fn current_epoch(&self) -> u64 {
+        self.0.epoch.load(Ordering::Relaxed)
+    }
+
+    /// Returns a pointer to the epoch counter of this engine, for compiled code to read.
+    pub(crate) fn epoch_counter(&self) -> *const AtomicU64 {
+        &self.0.epoch
+    }
+
+    /// Returns the trampoline WebAssembly calls host functions of type `ty` through, compiling it
+    /// the first time a host function of that type is created.
+    ///
+    /// The returned code must be kept alive for as long as the trampoline is referenced.
+    pub(crate) fn host_trampoline(
+        &self,
+        ty: &WasmFuncType,
+    ) -> crate::Result<(Arc<CodeMemory>, NonNull<VMWasmCallFunction>)> {
+        let mut trampolines = self.0.host_trampolines.lock();
+        let (code, loc) = if let Some((code, loc)) = trampolines.get(ty) {
+            (code.clone(), *loc)
+        } else {
+            let (code, loc, (trap_offsets, traps)) = compile_wasm_to_array_trampoline(self, ty)?;
+
+            let mut code =
+                CodeMemory::new(MmapVec::from_slice(&code)?, trap_offsets, traps, Vec::new());
+            code.publish()?;
+            let code = Arc::new(code);
+            placeholder::code_registry::register_code(&code);
+
+            trampolines.insert(ty.clone(), (code.clone(), loc));
+            (code, loc)
+        };
+
+        let wasm_call =
+            NonNull::new(code.resolve_function_loc(loc) as *mut VMWasmCallFunction).unwrap();
+        Ok((code, wasm_call))
+    }
+
+    pub(crate) fn module_cache(&self) -> &ModuleCache {
+        &self.0.module_cache
+    }
+
+    /// Returns how many modules were served from the module cache so far, see
+    /// [`Config::module_cache_capacity`].
+    pub fn module_cache_hits(&self) -> u64 {
+        self.0.module_cache.hits()
+    }
+
+    /// Returns how many modules had to be compiled because they weren't in the module cache, see
+    /// [`Config::module_cache_capacity`].
+    ///
+    /// This is always `0` if the cache is disabled.
+    pub fn module_cache_misses(&self) -> u64 {
+        self.0.module_cache.misses()
+    }
+
+    /// Removes all modules from the module cache.
+    pub fn clear_module_cache(&self) {
+        self.0.module_cache.clear();
+    }
+
+    /// Checks whether the given bytes are a valid WebAssembly module.
+    ///
+    /// This only runs validation against the features enabled in this engine's [`Config`], the
+    /// module is neither translated nor compiled. Use this to cheaply reject malformed input
+    /// before committing to [`Module::from_bytes`][crate::Module::from_bytes].
+    ///
+    /// # Errors
+    ///
+    /// Returns the same error [`Module::from_bytes`][crate::Module::from_bytes] would if the
+    /// module is malformed or invalid.
+    pub fn validate_module(&self, bytes: &[u8]) -> crate::Result<()> {
+        let mut validator = Validator::new_with_features(self.features());
+        validator.validate_all(bytes)?;
+        Ok(())
+    }
+
+    /// Scans `bytes` for the WebAssembly proposals the module uses, e.g. to pick the features of
+    /// the [`Validator`] before compiling it.
+    ///
+    /// Unlike the features the `target_features` custom section declares, which are checked when
+    /// compiling a module, this also looks at the module's types, definitions and every operator of
+    /// its functions. The result may still include features the engine has disabled, compiling the
+    /// module then fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module is malformed. The module isn't validated.
+    pub fn detect_features(&self, bytes: &[u8]) -> crate::Result<WasmFeatures> {
+        translate::detect_features(bytes)
+    }
+
+    /// Applies the engine's bounds checking strategy and guard size to `memory`.
+    pub(crate) fn configure_memory(&self, memory: &mut MemoryDesc) {
+        if self.config().is_dynamic_memories() {
+            memory.make_dynamic();
+        } else {
+            memory.offset_guard_size = self.config().get_memory_guard_size();
+        }
+    }
+
+    /// Checks that all of the given `required` features are enabled in this engine.
+    pub(crate) fn check_features(&self, required: WasmFeatures) -> crate::Result<()> {
+        let disabled = required.difference(self.config().features());
+        if disabled.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::DisabledFeatures {
+                features: disabled
+                    .iter_names()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+        }
     }
 }
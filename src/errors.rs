@@ -1,5 +1,6 @@
 use crate::translate::EntityType;
 use crate::trap::Trap;
+use crate::ExternKind;
 use alloc::format;
 use alloc::string::{String, ToString};
 use core::fmt;
@@ -30,8 +31,24 @@ pub enum Error {
         /// The type of the import.
         type_: EntityType,
     },
+    /// A definition was provided for an import, but its type doesn't match the import's type.
+    IncompatibleImport {
+        /// The module name of the import.
+        module: String,
+        /// The field name of the import.
+        field: String,
+        /// A human-readable description of the mismatch.
+        message: String,
+    },
     /// The WebAssembly code used an unsupported feature.
     Unsupported(String),
+    /// The module requires WebAssembly features that are disabled in the engine.
+    DisabledFeatures {
+        /// The names of the required but disabled features.
+        features: String,
+    },
+    /// The compilation target isn't supported, or code compiled for it can't run on the host.
+    UnsupportedTarget(String),
     /// Failed to compile a function.
     Cranelift {
         /// The name of the function that failed to compile.
@@ -43,15 +60,26 @@ pub enum Error {
     Gimli(gimli::Error),
     /// Failed to parse a wat file.
     Wat(wat::Error),
+    /// Failed to disassemble compiled code.
+    #[cfg(feature = "disassemble")]
+    Capstone(capstone::Error),
     /// A WebAssembly trap occurred.
     Trap {
         /// The trap that occurred.
         trap: Trap,
         /// A human-readable description of the trap.
         message: String,
+        /// The address of the inaccessible data for memory-related traps raised by JIT code.
+        faulting_addr: Option<usize>,
+        /// The offset of `faulting_addr` relative to the base of the linear memory it falls into.
+        wasm_offset: Option<u64>,
     },
     /// Memory mapping failed
     MmapFailed,
+    /// Compilation was cancelled through a progress callback.
+    Cancelled,
+    /// An instance allocator ran out of resources or can't satisfy the requirements of a module.
+    AllocationFailed(String),
     /// The name is already defined.
     AlreadyDefined {
         /// The defined module name.
@@ -59,6 +87,75 @@ pub enum Error {
         /// The defined field name.
         field: String,
     },
+    /// The name is not defined.
+    Undefined {
+        /// The module name.
+        module: String,
+        /// The field name.
+        field: String,
+    },
+    /// The instance has no export of the requested name.
+    MissingExport {
+        /// The name of the export.
+        name: String,
+    },
+    /// The instance has an export of the requested name, but of a different kind.
+    WrongExportKind {
+        /// The name of the export.
+        name: String,
+        /// The kind of export that was requested.
+        expected: ExternKind,
+        /// The kind of the export found.
+        found: ExternKind,
+    },
+    /// A function was called with a signature that doesn't match its type.
+    FuncTypeMismatch {
+        /// The function type the caller expected.
+        expected: String,
+        /// The actual type of the function.
+        actual: String,
+    },
+    /// A function was called with the wrong number of arguments.
+    WrongArgCount {
+        /// The number of parameters of the function.
+        expected: usize,
+        /// The number of arguments passed.
+        actual: usize,
+    },
+    /// A function was called with a results buffer of the wrong size.
+    WrongResultCount {
+        /// The number of results of the function.
+        expected: usize,
+        /// The size of the results buffer.
+        actual: usize,
+    },
+    /// Attempted to set the value of an immutable global.
+    ImmutableGlobal,
+    /// A global was set to a value that doesn't match its type.
+    GlobalTypeMismatch {
+        /// The type of the global.
+        expected: String,
+        /// The type of the value.
+        actual: String,
+    },
+    /// A value didn't have the type it was converted to.
+    ValTypeMismatch {
+        /// The type that was expected.
+        expected: String,
+        /// The type of the value.
+        actual: String,
+    },
+    /// A value passed to a function belongs to a different store than the function.
+    CrossStore,
+    /// The stack region provided by the embedder is invalid.
+    InvalidStack(String),
+    /// Growing a memory failed.
+    MemoryGrow {
+        /// The number of pages the memory was requested to grow by.
+        delta: u64,
+    },
+    /// A host function panicked, the panic was caught before it could unwind into WebAssembly.
+    HostPanic(String),
 }
 
 impl fmt::Display for Error {
@@ -82,9 +179,22 @@ impl fmt::Display for Error {
                     "Missing required import {module}::{field} ({type_})"
                 ))
             }
+            Self::IncompatibleImport {
+                module,
+                field,
+                message,
+            } => f.write_fmt(format_args!(
+                "incompatible import type for {module}::{field}: {message}"
+            )),
             Self::Unsupported(feature) => f.write_fmt(format_args!(
                 "Feature used by the WebAssembly code is not supported: {feature}"
             )),
+            Self::DisabledFeatures { features } => f.write_fmt(format_args!(
+                "Module requires WebAssembly features that are disabled in the engine: {features}"
+            )),
+            Self::UnsupportedTarget(message) => {
+                f.write_fmt(format_args!("unsupported target: {message}"))
+            }
             Self::Cranelift { func_name, message } => f.write_fmt(format_args!(
                 "failed to compile function {func_name}: {message}"
             )),
@@ -92,14 +202,91 @@ impl fmt::Display for Error {
                 f.write_fmt(format_args!("Failed to parse DWARF debug information: {e}"))
             }
             Self::Wat(e) => f.write_fmt(format_args!("Failed to parse wat: {e}")),
+            #[cfg(feature = "disassemble")]
+            Self::Capstone(e) => f.write_fmt(format_args!("Failed to disassemble code: {e}")),
             Self::Trap { trap, message, .. } => {
                 f.write_fmt(format_args!("{message}. Reason {trap}"))?;
                 Ok(())
             }
             Self::MmapFailed => f.write_str("Memory mapping failed"),
+            Self::Cancelled => f.write_str("compilation was cancelled"),
+            Self::AllocationFailed(message) => {
+                f.write_fmt(format_args!("instance allocation failed: {message}"))
+            }
             Self::AlreadyDefined { module, field } => {
                 f.write_fmt(format_args!("Name {module}::{field} is already defined"))
             }
+            Self::Undefined { module, field } => {
+                f.write_fmt(format_args!("Name {module}::{field} is not defined"))
+            }
+            Self::MissingExport { name } => f.write_fmt(format_args!("missing export {name}")),
+            Self::WrongExportKind {
+                name,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "export {name} is a {found}, expected a {expected}"
+            )),
+            Self::FuncTypeMismatch { expected, actual } => f.write_fmt(format_args!(
+                "function type mismatch: expected {expected}, found {actual}"
+            )),
+            Self::WrongArgCount { expected, actual } => {
+                f.write_fmt(format_args!("expected {expected} arguments, got {actual}"))
+            }
+            Self::WrongResultCount { expected, actual } => f.write_fmt(format_args!(
+                "expected space for {expected} results, got {actual}"
+            )),
+            Self::ImmutableGlobal => f.write_str("cannot set the value of an immutable global"),
+            Self::GlobalTypeMismatch { expected, actual } => f.write_fmt(format_args!(
+                "global type mismatch: expected {expected}, found {actual}"
+            )),
+            Self::ValTypeMismatch { expected, actual } => f.write_fmt(format_args!(
+                "value type mismatch: expected {expected}, found {actual}"
+            )),
+            Self::CrossStore => f.write_str("value used with a store it doesn't belong to"),
+            Self::InvalidStack(message) => {
+                f.write_fmt(format_args!("invalid stack region: {message}"))
+            }
+            Self::MemoryGrow { delta } => {
+                f.write_fmt(format_args!("failed to grow memory by {delta} pages"))
+            }
+            Self::HostPanic(message) => {
+                f.write_fmt(format_args!("host function panicked: {message}"))
+            }
+        }
+    }
+}
+
+impl Error {
+    /// Returns the trap code if this error was caused by a WebAssembly trap.
+    pub fn trap_code(&self) -> Option<Trap> {
+        match self {
+            Self::Trap { trap, .. } => Some(*trap),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the inaccessible data if this error was caused by a memory-related
+    /// trap in JIT-compiled code, such as an out-of-bounds load or store.
+    ///
+    /// This is only available for accesses caught by guard pages. Memories that are bounds-checked
+    /// explicitly trap before accessing any memory and therefore never provide an address, neither
+    /// do traps raised by host functions like [`Memory::read`][crate::Memory::read].
+    pub fn faulting_addr(&self) -> Option<usize> {
+        match self {
+            Self::Trap { faulting_addr, .. } => *faulting_addr,
+            _ => None,
+        }
+    }
+
+    /// Returns the offset of the [faulting address][Self::faulting_addr] relative to the base of
+    /// the linear memory it falls into, i.e. the effective address of the WebAssembly access.
+    ///
+    /// Returns `None` if there is no faulting address or it isn't part of any memory of the store.
+    pub fn wasm_offset(&self) -> Option<u64> {
+        match self {
+            Self::Trap { wasm_offset, .. } => *wasm_offset,
+            _ => None,
         }
     }
 }
@@ -142,6 +329,13 @@ impl From<wat::Error> for Error {
     }
 }
 
+#[cfg(feature = "disassemble")]
+impl From<capstone::Error> for Error {
+    fn from(value: capstone::Error) -> Self {
+        Self::Capstone(value)
+    }
+}
+
 impl core::error::Error for Error {}
 
 #[derive(Copy, Clone, Debug)]
@@ -1,50 +1,204 @@
-use crate::indices::VMSharedTypeIndex;
-use crate::placeholder::trap_handling::TrapReason;
-use crate::runtime::{StaticVMOffsets, VMContext, VMFunctionImport, VMVal};
-use crate::store::Stored;
-use crate::translate::WasmFuncType;
+use crate::debug::TrapFrame;
+use crate::indices::{CanonicalizedTypeIndex, VMSharedTypeIndex};
+use crate::placeholder::fiber::{Fiber, FiberStack};
+use crate::placeholder::trap_handling::{defer_post_return, raise_trap, TrapReason};
+use crate::runtime::{
+    CodeMemory, StatsEvent, VMArrayCallFunction, VMArrayCallHostFuncContext, VMContext, VMFuncRef,
+    VMFunctionImport, VMOpaqueContext, VMRuntimeLimits, VMVal, VM_ARRAY_CALL_HOST_FUNC_MAGIC,
+};
+use crate::store::{CallHook, Stored};
+use crate::translate::{
+    WasmCompositeType, WasmFuncType, WasmHeapTopTypeInner, WasmHeapTypeInner, WasmRefType,
+    WasmSubType, WasmValType,
+};
 use crate::type_registry::RegisteredType;
-use crate::values::Val;
-use crate::{placeholder, runtime, Store, MAX_WASM_STACK};
+use crate::values::{AnyRef, ExternRef, Val};
+use crate::{placeholder, runtime, Engine, StackRegion, Store};
+use alloc::boxed::Box;
 use alloc::string::ToString;
+use alloc::sync::Arc;
+use core::any::{Any, TypeId};
 use core::ffi::c_void;
-use core::mem;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::ptr;
+use core::ptr::NonNull;
+use core::task::{Context, Poll};
+
+/// Checks that `val` can be passed as a parameter of type `ty`, which has to be canonicalized for
+/// the engine.
+fn val_matches<T>(store: &Store<T>, val: &Val, ty: &WasmValType) -> crate::Result<()> {
+    let matches = match (val, ty) {
+        (Val::I32(_), WasmValType::I32)
+        | (Val::I64(_), WasmValType::I64)
+        | (Val::F32(_), WasmValType::F32)
+        | (Val::F64(_), WasmValType::F64)
+        | (Val::V128(_), WasmValType::V128) => true,
+        (Val::FuncRef(None), WasmValType::Ref(ty)) => {
+            ty.nullable && ty.heap_type.top().inner == WasmHeapTopTypeInner::Func
+        }
+        (Val::FuncRef(Some(func)), WasmValType::Ref(ty)) => {
+            if !func.comes_from_same_store(store) {
+                return Err(crate::Error::CrossStore);
+            }
+            match &ty.heap_type.ty {
+                WasmHeapTypeInner::Func => true,
+                WasmHeapTypeInner::ConcreteFunc(CanonicalizedTypeIndex::Shared(index)) => {
+                    func.ty(store).type_index() == *index
+                }
+                _ => false,
+            }
+        }
+        (Val::ExternRef(e), WasmValType::Ref(ty)) => {
+            ty.heap_type.top().inner == WasmHeapTopTypeInner::Extern
+                && match e {
+                    Some(_) => ty.heap_type.ty == WasmHeapTypeInner::Extern,
+                    None => ty.nullable,
+                }
+        }
+        (Val::AnyRef(r), WasmValType::Ref(ty)) => {
+            ty.heap_type.top().inner == WasmHeapTopTypeInner::Any
+                && match r {
+                    Some(r) => r.matches(&ty.heap_type),
+                    None => ty.nullable,
+                }
+        }
+        _ => false,
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(crate::Error::ValTypeMismatch {
+            expected: ty.to_string(),
+            actual: val.ty().to_string(),
+        })
+    }
+}
 
 /// A WebAssembly function.
-#[derive(Debug, Clone, Copy)]
+///
+/// Two `Func`s of the same store compare equal if they refer to the same function, e.g. when both
+/// were produced by `ref.func` of the same function in the same instance. Comparing functions of
+/// different stores is meaningless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Func(Stored<runtime::ExportedFunction>);
 
 impl Func {
-    /// Returns the type of this function.
+    /// Creates a new `Func` that calls the given Rust closure when invoked.
+    ///
+    /// The WebAssembly type of the function is inferred from the closure's parameter and return
+    /// types. The resulting function can be passed to WebAssembly through imports or tables and
+    /// can be called from WebAssembly like any other function.
+    ///
+    /// If the closure's first parameter is a [`Caller`] it can access the store's data through it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trampoline used by WebAssembly to call the closure fails to compile.
+    pub fn wrap<T: 'static, Params, Results>(
+        store: &mut Store<T>,
+        func: impl IntoFunc<T, Params, Results>,
+    ) -> crate::Result<Self> {
+        let host_func = func.into_func(&store.engine, None)?;
+        Ok(Self(store.push_host_func(host_func)))
+    }
+
+    /// Creates a new `Func` like [`Func::wrap`] that additionally runs `cleanup` after each call.
+    ///
+    /// When WebAssembly calls the function, `cleanup` runs once the enclosing call into
+    /// WebAssembly has returned or trapped, i.e. once the guest is done with whatever the call
+    /// handed out, e.g. buffers it returned. Calls made directly from the host have no enclosing
+    /// call, so `cleanup` runs right after the closure returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trampoline used by WebAssembly to call the closure fails to compile.
+    pub fn wrap_with_cleanup<T: 'static, Params, Results>(
+        store: &mut Store<T>,
+        func: impl IntoFunc<T, Params, Results>,
+        cleanup: impl Fn(Caller<'_, T>) + Send + Sync + 'static,
+    ) -> crate::Result<Self> {
+        let host_func = func.into_func(&store.engine, Some(Arc::new(cleanup)))?;
+        Ok(Self(store.push_host_func(host_func)))
+    }
+
+    /// Returns the type of this function, resolved from the engine's type registry.
+    ///
+    /// This works the same for functions defined by WebAssembly and by the host, e.g. to size the
+    /// results buffer before calling it.
     ///
     /// # Panics
     ///
-    /// TODO
-    pub fn ty(&self, store: &Store) -> FuncType {
+    /// Panics if this function doesn't belong to `store`.
+    pub fn ty<T>(&self, store: &Store<T>) -> FuncType {
         // Safety: at this point `VMContext` is initialized, so accessing its fields is safe
         let func_ref = unsafe { store[self.0].func_ref.as_ref() };
         let ty = store
             .engine
             .type_registry()
-            .get_type(&store.engine, func_ref.type_index)
+            .get_type(func_ref.type_index)
             .unwrap();
         FuncType(ty)
     }
 
+    /// Checks that the type of this function matches `Params` and `Results`, returning a
+    /// [`TypedFunc`] that can be called without boxing parameters and results in [`Val`]s.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FuncTypeMismatch`][crate::Error::FuncTypeMismatch] if the types don't
+    /// match.
+    pub fn typed<Params, Results>(
+        &self,
+        store: &Store<impl Sized>,
+    ) -> crate::Result<TypedFunc<Params, Results>>
+    where
+        Params: WasmParams,
+        Results: WasmResults,
+    {
+        let ty = self.ty(store);
+        let ty = ty.as_wasm_func_type();
+        let expected = WasmFuncType {
+            params: Params::valtypes(),
+            results: Results::valtypes(),
+        };
+        if *ty != expected {
+            return Err(crate::Error::FuncTypeMismatch {
+                expected: expected.to_string(),
+                actual: ty.to_string(),
+            });
+        }
+
+        Ok(TypedFunc {
+            func: *self,
+            values_vec_size: expected.params.len().max(expected.results.len()),
+            _marker: PhantomData,
+        })
+    }
+
     /// Calls the given function with the provided arguments and places the results in the provided
     /// results slice.
     ///
     /// # Errors
     ///
-    /// TODO
+    /// Returns [`Error::Trap`][crate::Error::Trap] if the function traps, its `trap` field says
+    /// why, e.g. [`Trap::UnreachableCodeReached`][crate::Trap::UnreachableCodeReached]. Use
+    /// [`Error::trap_code`][crate::Error::trap_code] to get it without matching on the error.
+    ///
+    /// If a host function called along the way returns an error, that error is returned unchanged
+    /// (a panicking host function returns [`Error::HostPanic`][crate::Error::HostPanic]).
     ///
     /// # Safety
     ///
     /// It is up to the caller to ensure the provided arguments are of the correct types and that
     /// the `results` slice has enough space to hold the results of the function.
-    pub unsafe fn call_unchecked(
+    pub unsafe fn call_unchecked<T>(
         &self,
-        store: &mut Store,
+        store: &mut Store<T>,
         params: &[Val],
         results: &mut [Val],
     ) -> crate::Result<()> {
@@ -78,53 +232,314 @@ impl Func {
         Ok(())
     }
 
-    unsafe fn call_unchecked_raw(
+    /// Calls the given function with the provided arguments and places the results in the provided
+    /// results slice.
+    ///
+    /// Unlike [`Func::call_unchecked`] this checks `params` and the size of `results` against the
+    /// type of the function first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongArgCount`][crate::Error::WrongArgCount] or
+    /// [`Error::WrongResultCount`][crate::Error::WrongResultCount] if `params` or `results` don't
+    /// have the length the function's type requires,
+    /// [`Error::ValTypeMismatch`][crate::Error::ValTypeMismatch] if an argument doesn't match the
+    /// type of its parameter, [`Error::CrossStore`][crate::Error::CrossStore] if a function passed
+    /// in `params` belongs to a different store, or an error if the function traps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this function doesn't belong to `store`.
+    pub fn call<T>(
         &self,
-        store: &mut Store,
+        store: &mut Store<T>,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> crate::Result<()> {
+        self.check_call(store, params, results)?;
+
+        // Safety: the arguments and the size of the results buffer were checked above
+        unsafe { self.call_unchecked(store, params, results) }
+    }
+
+    /// Checks `params` and the size of `results` against the type of this function.
+    fn check_call<T>(
+        &self,
+        store: &Store<T>,
+        params: &[Val],
+        results: &[Val],
+    ) -> crate::Result<()> {
+        let ty = self.ty(store);
+        let ty = ty.as_wasm_func_type();
+        if params.len() != ty.params.len() {
+            return Err(crate::Error::WrongArgCount {
+                expected: ty.params.len(),
+                actual: params.len(),
+            });
+        }
+        if results.len() != ty.results.len() {
+            return Err(crate::Error::WrongResultCount {
+                expected: ty.results.len(),
+                actual: results.len(),
+            });
+        }
+        for (param, ty) in params.iter().zip(ty.params.iter()) {
+            val_matches(store, param, ty)?;
+        }
+        Ok(())
+    }
+
+    /// Calls the given function on a separate stack, returning a future that completes once the
+    /// call returns.
+    ///
+    /// The call runs on the store's async stack (see [`Config::async_stack_size`]) while the
+    /// future is polled. If the store is configured to yield at its epoch deadline (see
+    /// [`Store::epoch_deadline_async_yield_and_update`]) or when running out of fuel (see
+    /// [`Store::out_of_fuel_async_yield`]) the WebAssembly code is suspended at that point and
+    /// the future returns [`Poll::Pending`], so long computations don't block the async runtime.
+    /// Polling the future again resumes the call where it left off.
+    ///
+    /// Dropping the future before it completed interrupts the call, it then completes with a
+    /// [`Trap::Interrupt`][crate::Trap::Interrupt] internally.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the async stack can't be allocated or if the function traps.
+    ///
+    /// # Safety
+    ///
+    /// It is up to the caller to ensure the provided arguments are of the correct types and that
+    /// the `results` slice has enough space to hold the results of the function.
+    ///
+    /// [`Config::async_stack_size`]: crate::Config::async_stack_size
+    pub async unsafe fn call_async_unchecked<T>(
+        &self,
+        store: &mut Store<T>,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> crate::Result<()> {
+        let stack = store.take_async_stack()?;
+        let store: *mut Store<T> = store;
+        // Declared before the fiber so it is dropped after it, i.e. once the call is done with the
+        // stack, even if the future is dropped early.
+        let stack = AsyncStackGuard {
+            store,
+            stack: ManuallyDrop::new(stack),
+        };
+
+        let mut res = Ok(());
+        {
+            let func = *self;
+            let fiber = Fiber::new(&stack.stack, || {
+                // Safety: the store is only accessed through this pointer while the fiber runs,
+                // which only happens while `FiberFuture` is polled. The caller ensures the
+                // arguments match the function's type.
+                res = unsafe { func.call_unchecked(&mut *store, params, results) };
+            });
+
+            FiberFuture { fiber }.await;
+        }
+
+        res
+    }
+
+    /// Calls the given function on a separate stack, returning a future that completes once the
+    /// call returns.
+    ///
+    /// Unlike [`Func::call_async_unchecked`] this checks `params` and the size of `results` against
+    /// the type of the function first, see [`Func::call`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Func::call`] and an error if the async stack can't be
+    /// allocated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this function doesn't belong to `store`.
+    pub async fn call_async<T>(
+        &self,
+        store: &mut Store<T>,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> crate::Result<()> {
+        self.check_call(store, params, results)?;
+
+        // Safety: the arguments and the size of the results buffer were checked above
+        unsafe { self.call_async_unchecked(store, params, results).await }
+    }
+
+    /// Calls a function that takes no parameters and returns no results.
+    ///
+    /// This is a fast path for e.g. `init` or `main` style functions, since there are no values to
+    /// pass it skips setting up the `VMVal` array that [`Func::call_unchecked`] requires.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function's type isn't `() -> ()` or if the function traps.
+    pub fn call0<T>(&self, store: &mut Store<T>) -> crate::Result<()> {
+        let ty = self.ty(store);
+        let ty = ty.as_wasm_func_type();
+        if !ty.params.is_empty() || !ty.results.is_empty() {
+            return Err(crate::Error::FuncTypeMismatch {
+                expected: "(func)".to_string(),
+                actual: ty.to_string(),
+            });
+        }
+
+        // Safety: the function neither reads parameters nor writes results, so an empty values
+        // array is sufficient.
+        unsafe { self.call_unchecked_raw(store, NonNull::dangling().as_ptr(), 0) }
+    }
+
+    /// Calls this function with the raw arguments stored at `args_results_ptr`, writing the
+    /// results in place over the arguments.
+    ///
+    /// This is the array-call ABI the trampolines use: the array holds the parameters on entry and
+    /// the results once the call returns. Unlike [`Func::call_unchecked`] neither the types nor
+    /// the number of values are looked at, which makes this the cheapest way to call a function
+    /// from trusted hot loops.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function traps.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that
+    /// - `args_results_ptr` points to `args_results_len` initialized and writable [`VMVal`]s,
+    /// - `args_results_len` is at least the larger of the function's number of parameters and
+    ///   results,
+    /// - the leading values match the function's parameters in number and type, and
+    /// - function references among them belong to `store`.
+    pub unsafe fn call_unchecked_raw<T>(
+        &self,
+        store: &mut Store<T>,
         args_results_ptr: *mut VMVal,
         args_results_len: usize,
     ) -> crate::Result<()> {
         let func_ref = store[self.0].func_ref.as_ref();
+
+        // Host functions have no instance to enter, their closure can be invoked directly.
+        if (*func_ref.vmctx).magic == VM_ARRAY_CALL_HOST_FUNC_MAGIC {
+            let ctx = VMArrayCallHostFuncContext::from_opaque(func_ref.vmctx);
+            assert!(
+                ptr::eq((*ctx).runtime_limits(), store.runtime_limits_ptr()),
+                "host function called through a store it doesn't belong to"
+            );
+            {
+                let _guard = enter_store(store);
+                (func_ref.array_call)(
+                    func_ref.vmctx.cast(),
+                    ptr::null_mut(),
+                    args_results_ptr,
+                    args_results_len,
+                );
+            }
+            return store.take_host_error().map_or(Ok(()), Err);
+        }
+
+        store.call_hook(CallHook::CallingWasm)?;
+
         let vmctx = VMContext::from_opaque(func_ref.vmctx);
-        let module = store[store.get_instance_from_vmctx(vmctx)].module();
+        let module = store[store.get_instance_from_vmctx(vmctx)].module().clone();
+        // Calls made on a fiber are already running on the stack they should use
+        let fiber_stack = placeholder::fiber::current_stack();
+        let on_fiber = fiber_stack.is_some();
+        let stack = fiber_stack.or_else(|| store.stack().cloned());
 
-        let _guard = enter_wasm(vmctx, &module.offsets().static_);
+        let guard = enter_wasm(
+            store.stack_limit_ptr(),
+            store.max_wasm_stack(),
+            stack.as_ref(),
+        );
+        // Only the outermost call switches stacks, calls from host functions back into WebAssembly
+        // have to continue on the stack they were called on.
+        let stack = stack.filter(|_| guard.is_outermost() && !on_fiber);
 
-        // Safety: this does syscalls
-        unsafe { placeholder::signals::ensure_signal_handlers_are_registered() }
+        placeholder::trap_handling::trap_handler().ensure_registered();
 
+        let capture_backtrace = store.capture_backtrace();
+        let stack_buffer = store.trap_stack_buffer();
+        // From here on host functions access the store through the published pointer, `store`
+        // isn't touched until the call returns.
+        let store_guard = enter_store(store);
+        // Safety: the stack buffer is owned by the store, which doesn't access it during the call
         let res = placeholder::trap_handling::catch_traps(
             vmctx,
             module.offsets().static_.clone(),
+            capture_backtrace,
+            stack_buffer,
             |caller| {
-                (func_ref.array_call)(vmctx, caller, args_results_ptr, args_results_len);
+                let call = || {
+                    (func_ref.array_call)(vmctx, caller, args_results_ptr, args_results_len);
+                };
+
+                match &stack {
+                    // Safety: `StackRegion`s are validated on construction and the embedder
+                    // guarantees the memory is valid and unused while we run on it.
+                    Some(stack) => unsafe { placeholder::arch::on_stack(stack.top(), call) },
+                    None => call(),
+                }
             },
         );
+        drop(store_guard);
+
+        let hook_res = store.call_hook(CallHook::ReturningFromWasm);
 
         if let Err(trap) = res {
-            let (_pc, trap_code, message) = match trap.reason {
-                TrapReason::Wasm(trap_code) => (None, trap_code, "k23 builtin produced a trap"),
+            store.count(StatsEvent::Trap);
+            let (frame, faulting_addr, trap_code, message) = match trap.reason {
+                TrapReason::User(err) => {
+                    store.set_last_trap_frame(None);
+                    store.set_last_trap_backtrace(trap.backtrace);
+                    return Err(err);
+                }
+                TrapReason::Wasm(trap_code) => {
+                    (None, None, trap_code, "k23 builtin produced a trap")
+                }
                 TrapReason::Jit {
                     pc,
-                    faulting_addr: _, // TODO make use of this
+                    fp,
+                    regs,
+                    stack_len,
+                    faulting_addr,
                     trap: trap_code,
-                } => (Some(pc), trap_code, "JIT-compiled WASM produced a trap"),
+                } => (
+                    Some(TrapFrame {
+                        pc,
+                        fp,
+                        regs,
+                        // Safety: the trap handler copied `stack_len` bytes into the buffer and
+                        // nothing wrote to it since
+                        stack: stack_buffer.map_or_else(Box::default, |buffer| unsafe {
+                            Box::from(&buffer.as_ref()[..stack_len])
+                        }),
+                    }),
+                    faulting_addr,
+                    trap_code,
+                    "JIT-compiled WASM produced a trap",
+                ),
             };
+            store.set_last_trap_frame(frame);
+            store.set_last_trap_backtrace(trap.backtrace);
 
             return Err(crate::Error::Trap {
                 trap: trap_code,
                 message: message.to_string(),
+                faulting_addr,
+                wasm_offset: faulting_addr.and_then(|addr| store.memory_offset_of(addr)),
             });
         }
 
-        Ok(())
+        hook_res
     }
 
-    pub(crate) unsafe fn as_raw(&self, store: &mut Store) -> *mut c_void {
+    pub(crate) unsafe fn as_raw<T>(&self, store: &mut Store<T>) -> *mut c_void {
         store[self.0].func_ref.as_ptr().cast()
     }
 
-    pub(crate) fn as_vmfunction_import(&self, store: &Store) -> VMFunctionImport {
+    pub(crate) fn as_vmfunction_import<T>(&self, store: &Store<T>) -> VMFunctionImport {
         // Safety: at this point `VMContext` is initialized, so accessing its fields is safe
         let func_ref = unsafe { store[self.0].func_ref.as_ref() };
         VMFunctionImport {
@@ -134,26 +549,177 @@ impl Func {
         }
     }
 
-    pub(crate) fn from_vm_export(store: &mut Store, export: runtime::ExportedFunction) -> Self {
+    /// Returns whether this function is defined by the host rather than by a WebAssembly instance.
+    pub(crate) fn is_host<T>(&self, store: &Store<T>) -> bool {
+        // Safety: at this point `VMContext` is initialized, so accessing its fields is safe
+        unsafe {
+            let func_ref = store[self.0].func_ref.as_ref();
+            (*func_ref.vmctx).magic == VM_ARRAY_CALL_HOST_FUNC_MAGIC
+        }
+    }
+
+    pub(crate) fn comes_from_same_store<T>(self, store: &Store<T>) -> bool {
+        store.has_function(self.0)
+    }
+
+    pub(crate) fn from_vm_export<T>(
+        store: &mut Store<T>,
+        export: runtime::ExportedFunction,
+    ) -> Self {
         Self(store.push_function(export))
     }
+
+    /// Creates a new `Func` from a raw `VMFuncRef` pointer, returning `None` if the pointer is null.
+    pub(crate) fn from_vm_func_ref<T>(store: &mut Store<T>, func_ref: *mut c_void) -> Option<Self> {
+        let func_ref = NonNull::new(func_ref.cast())?;
+        Some(Self::from_vm_export(
+            store,
+            runtime::ExportedFunction { func_ref },
+        ))
+    }
+}
+
+/// Returns the message a panic was started with, if it was started with one.
+#[cfg(not(feature = "no_std"))]
+fn panic_message(payload: &(dyn Any + Send)) -> alloc::string::String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<alloc::string::String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
 }
 
-fn enter_wasm(vmctx: *mut VMContext, offsets: &StaticVMOffsets) -> WasmExecutionGuard {
-    let stack_pointer = placeholder::arch::get_stack_pointer();
-    let wasm_stack_limit = stack_pointer.checked_sub(MAX_WASM_STACK).unwrap();
+/// Drives a [`Fiber`] running a call into WebAssembly to completion.
+///
+/// The call on the fiber publishes the store to its host functions, which stays in place while
+/// the fiber is suspended since the future borrows the store until it completes.
+struct FiberFuture<'a> {
+    fiber: Fiber<'a>,
+}
+
+impl Future for FiberFuture<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.fiber.resume() {
+            Poll::Ready(())
+        } else {
+            // The fiber suspended to give the async runtime a chance to run other tasks, but it is
+            // ready to continue right away.
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for FiberFuture<'_> {
+    fn drop(&mut self) {
+        self.fiber.cancel();
+    }
+}
+
+/// Returns the async stack to the store when dropped, so it is reused by the next async call even
+/// if the future of the previous one was dropped before it completed.
+struct AsyncStackGuard<T> {
+    store: *mut Store<T>,
+    stack: ManuallyDrop<FiberStack>,
+}
+
+impl<T> Drop for AsyncStackGuard<T> {
+    fn drop(&mut self) {
+        // Safety: the stack isn't accessed after this, and the pointer was derived from the store
+        // the future borrows for as long as it lives
+        unsafe {
+            let stack = ManuallyDrop::take(&mut self.stack);
+            (*self.store).return_async_stack(stack);
+        }
+    }
+}
+
+/// Sets up the stack limit for a call into WebAssembly.
+///
+/// The stack limit is shared by all instances in a store, since WebAssembly code calls freely
+/// between them. Only the outermost call sets the limit, nested calls (e.g. from host functions
+/// back into WebAssembly) keep it so the total stack usage stays bounded. The exception are nested
+/// calls running on a stack the limit doesn't lie on, e.g. the fiber of a nested async call,
+/// which are bounded by their own stack instead.
+fn enter_wasm(
+    stack_limit_ptr: *mut usize,
+    max_wasm_stack: usize,
+    stack: Option<&StackRegion>,
+) -> WasmExecutionGuard {
+    // Safety: the pointer is obtained from the store which outlives the call
+    let prev_stack = unsafe { *stack_limit_ptr };
+    let on_other_stack = stack.is_some_and(|stack| {
+        prev_stack != 0 && !(stack.limit()..stack.top()).contains(&prev_stack)
+    });
+
+    if prev_stack == 0 || on_other_stack {
+        let wasm_stack_limit = if let Some(stack) = stack {
+            // the budget applies to embedder-provided stacks too, but never beyond their end
+            stack
+                .limit()
+                .max(stack.top().saturating_sub(max_wasm_stack))
+        } else {
+            let stack_pointer = placeholder::arch::get_stack_pointer();
+            stack_pointer.checked_sub(max_wasm_stack).unwrap()
+        };
 
-    // Safety: at this point the `VMContext` is initialized and accessing its fields is safe.
-    unsafe {
-        let stack_limit_ptr = vmctx
-            .byte_add(offsets.vmctx_stack_limit() as usize)
-            .cast::<usize>();
-        let prev_stack = mem::replace(&mut *stack_limit_ptr, wasm_stack_limit);
-        WasmExecutionGuard {
-            stack_limit_ptr,
-            prev_stack,
+        // Safety: see above
+        unsafe {
+            *stack_limit_ptr = wasm_stack_limit;
         }
     }
+
+    WasmExecutionGuard {
+        stack_limit_ptr,
+        prev_stack,
+    }
+}
+
+/// Publishes `store` to the host functions called until the returned guard is dropped.
+///
+/// Neither host functions nor the WebAssembly code calling them have a reference to their store,
+/// so calls publish it in the store's runtime limits, which every host function's
+/// `VMArrayCallHostFuncContext` points to. The caller must not use `store` while the guard is
+/// alive, host functions access it through the published pointer.
+fn enter_store<T>(store: &mut Store<T>) -> StoreGuard {
+    let runtime_limits = store.runtime_limits_ptr();
+    // Safety: the runtime limits are boxed and live as long as the store
+    let prev = unsafe { (*runtime_limits).store.replace(ptr::from_mut(store).cast()) };
+    StoreGuard {
+        runtime_limits,
+        prev,
+    }
+}
+
+/// Returns the store published through `runtime_limits`, or `None` if the store isn't calling
+/// into WebAssembly right now or its data isn't a `T`.
+///
+/// # Safety
+///
+/// `runtime_limits` must point to the runtime limits of a live store.
+unsafe fn current_store<T: 'static>(
+    runtime_limits: *const VMRuntimeLimits,
+) -> Option<*mut Store<T>> {
+    let runtime_limits = &*runtime_limits;
+    let store = runtime_limits.store.get();
+    (!store.is_null() && runtime_limits.store_type.get() == Some(TypeId::of::<T>()))
+        .then(|| store.cast())
+}
+
+struct StoreGuard {
+    runtime_limits: *const VMRuntimeLimits,
+    prev: *mut (),
+}
+
+impl Drop for StoreGuard {
+    fn drop(&mut self) {
+        // Safety: the store, and with it its runtime limits, outlives the call
+        unsafe { (*self.runtime_limits).store.set(self.prev) };
+    }
 }
 
 struct WasmExecutionGuard {
@@ -161,9 +727,16 @@ struct WasmExecutionGuard {
     prev_stack: usize,
 }
 
+impl WasmExecutionGuard {
+    /// Whether this guard belongs to the outermost call into WebAssembly.
+    fn is_outermost(&self) -> bool {
+        self.prev_stack == 0
+    }
+}
+
 impl Drop for WasmExecutionGuard {
     fn drop(&mut self) {
-        // Safety: this relies on `enter_wasm` correctly calculating the stack limit pointer.
+        // Safety: the pointer is obtained from the store which outlives the call
         unsafe {
             *self.stack_limit_ptr = self.prev_stack;
         }
@@ -172,19 +745,654 @@ impl Drop for WasmExecutionGuard {
 
 /// A WebAssembly function type.
 ///
-/// This is essentially a reference counted index into the engine's type registry.
+/// This is essentially a reference counted index into the engine's type registry. Types are
+/// deduplicated by the registry, so two function types compare equal if and only if they are
+/// structurally equal and were registered with the same [`Engine`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FuncType(RegisteredType);
 
 impl FuncType {
-    pub(crate) fn type_index(&self) -> VMSharedTypeIndex {
+    pub(crate) fn from_registered_type(ty: RegisteredType) -> Self {
+        Self(ty)
+    }
+
+    /// Returns the engine-unique index of this type.
+    ///
+    /// Functions with the same signature share the same index, even if they come from
+    /// separately compiled modules or are defined by the host.
+    pub fn type_index(&self) -> VMSharedTypeIndex {
         self.0.index()
     }
 
+    /// Returns the parameter and result types of this function type.
     pub fn as_wasm_func_type(&self) -> &WasmFuncType {
         self.0.unwrap_func()
     }
 
+    /// Returns the types of the parameters of this function type.
+    pub fn params(&self) -> impl ExactSizeIterator<Item = &WasmValType> {
+        self.as_wasm_func_type().params.iter()
+    }
+
+    /// Returns the types of the results of this function type.
+    pub fn results(&self) -> impl ExactSizeIterator<Item = &WasmValType> {
+        self.as_wasm_func_type().results.iter()
+    }
+
     pub(crate) fn into_registered_type(self) -> RegisteredType {
         self.0
     }
 }
+
+/// Prints the signature as `(params) -> results`, e.g. `(i32, i32) -> i64`.
+///
+/// A single result is printed without parentheses, no or multiple results are parenthesized.
+impl fmt::Display for FuncType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_list<'a>(
+            f: &mut fmt::Formatter<'_>,
+            types: impl Iterator<Item = &'a WasmValType>,
+        ) -> fmt::Result {
+            f.write_str("(")?;
+            for (i, ty) in types.enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                fmt::Display::fmt(ty, f)?;
+            }
+            f.write_str(")")
+        }
+
+        write_list(f, self.params())?;
+        f.write_str(" -> ")?;
+        match self.as_wasm_func_type().results.as_ref() {
+            [result] => fmt::Display::fmt(result, f),
+            results => write_list(f, results.iter()),
+        }
+    }
+}
+
+/// A host-defined function.
+///
+/// This owns everything that is needed to call the host function from WebAssembly: the
+/// `VMArrayCallHostFuncContext` holding its `VMFuncRef`, the wasm-to-array trampoline and the
+/// registration of its type.
+#[derive(Debug)]
+pub struct HostFunc {
+    ctx: Box<VMArrayCallHostFuncContext>,
+    // The trampoline referenced by `ctx.func_ref.wasm_call`, shared with all host functions of the
+    // same type.
+    _code: Arc<CodeMemory>,
+    // Keeps `ctx.func_ref.type_index` registered for as long as this function lives.
+    _ty: RegisteredType,
+}
+
+impl HostFunc {
+    /// Creates a new host function of type `ty` that calls `array_call` with `host_state`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `array_call` correctly reads the parameters and writes the
+    /// results described by `ty` and is able to handle `host_state`.
+    pub(crate) unsafe fn new(
+        engine: &Engine,
+        ty: WasmFuncType,
+        array_call: VMArrayCallFunction,
+        host_state: Box<dyn Any + Send + Sync>,
+    ) -> crate::Result<Self> {
+        let (code, wasm_call) = engine.host_trampoline(&ty)?;
+
+        let ty = RegisteredType::new(
+            engine,
+            WasmSubType {
+                is_final: true,
+                supertype: None,
+                composite_type: WasmCompositeType::new_func(false, ty),
+            },
+        );
+
+        // Safety: the trampoline was compiled for `ty` and `array_call` is ensured by the caller
+        let ctx = unsafe {
+            VMArrayCallHostFuncContext::new(array_call, wasm_call, ty.index(), host_state)
+        };
+
+        Ok(Self {
+            ctx,
+            _code: code,
+            _ty: ty,
+        })
+    }
+
+    /// Returns a pointer to the `VMFuncRef` of this function.
+    pub(crate) fn func_ref(&mut self) -> NonNull<VMFuncRef> {
+        self.ctx.func_ref()
+    }
+
+    /// Ties this function to the store owning `runtime_limits`, the closure reaches the store
+    /// through them when called.
+    pub(crate) fn set_runtime_limits(&mut self, runtime_limits: *const VMRuntimeLimits) {
+        self.ctx.set_runtime_limits(runtime_limits);
+    }
+}
+
+/// A Rust type that corresponds to a WebAssembly value type.
+///
+/// This is what allows host functions created through [`Func::wrap`] and [`TypedFunc`]s to pass
+/// values to and from WebAssembly without boxing them in [`Val`]s. It is implemented for the
+/// numeric types (`i32`, `u32`, `i64`, `u64`, `f32` and `f64`), `u128` for `v128`,
+/// `Option<Func>`/`Func` for nullable and non-nullable function references and
+/// `Option<ExternRef>`/`Option<AnyRef>` for `externref` and `anyref`.
+pub trait WasmTy: Send + Sync + 'static {
+    /// Returns the WebAssembly type of this type.
+    fn valtype() -> WasmValType;
+    /// Converts a raw WebAssembly value into this type.
+    ///
+    /// # Safety
+    ///
+    /// `vmval` must hold a value of [`WasmTy::valtype`], references must belong to `store`.
+    #[doc(hidden)]
+    unsafe fn from_vmval<T>(store: &mut Store<T>, vmval: VMVal) -> Self;
+    /// Converts this value into a raw WebAssembly value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a reference that doesn't belong to `store`.
+    #[doc(hidden)]
+    fn to_vmval<T>(self, store: &mut Store<T>) -> VMVal;
+}
+
+macro_rules! impl_wasm_ty {
+    ($($ty:ty => $valtype:ident, |$from_arg:ident| $from:expr, |$to_arg:ident| $to:expr;)*) => {
+        $(
+            impl WasmTy for $ty {
+                fn valtype() -> WasmValType {
+                    WasmValType::$valtype
+                }
+
+                unsafe fn from_vmval<T>(_store: &mut Store<T>, $from_arg: VMVal) -> Self {
+                    $from
+                }
+
+                fn to_vmval<T>(self, _store: &mut Store<T>) -> VMVal {
+                    let $to_arg = self;
+                    $to
+                }
+            }
+        )*
+    };
+}
+
+impl_wasm_ty! {
+    i32 => I32, |v| v.get_i32(), |v| VMVal::i32(v);
+    u32 => I32, |v| v.get_u32(), |v| VMVal::u32(v);
+    i64 => I64, |v| v.get_i64(), |v| VMVal::i64(v);
+    u64 => I64, |v| v.get_u64(), |v| VMVal::u64(v);
+    f32 => F32, |v| f32::from_bits(v.get_f32()), |v| VMVal::f32(v.to_bits());
+    f64 => F64, |v| f64::from_bits(v.get_f64()), |v| VMVal::f64(v.to_bits());
+    u128 => V128, |v| v.get_v128(), |v| VMVal::v128(v);
+}
+
+impl WasmTy for Option<Func> {
+    fn valtype() -> WasmValType {
+        WasmValType::Ref(WasmRefType::FUNCREF)
+    }
+
+    unsafe fn from_vmval<T>(store: &mut Store<T>, vmval: VMVal) -> Self {
+        Func::from_vm_func_ref(store, vmval.get_funcref())
+    }
+
+    fn to_vmval<T>(self, store: &mut Store<T>) -> VMVal {
+        match self {
+            Some(func) => func.to_vmval(store),
+            None => VMVal::funcref(ptr::null_mut()),
+        }
+    }
+}
+
+impl WasmTy for Func {
+    fn valtype() -> WasmValType {
+        WasmValType::Ref(WasmRefType {
+            nullable: false,
+            ..WasmRefType::FUNCREF
+        })
+    }
+
+    unsafe fn from_vmval<T>(store: &mut Store<T>, vmval: VMVal) -> Self {
+        Func::from_vm_func_ref(store, vmval.get_funcref())
+            .expect("non-nullable function reference was null")
+    }
+
+    fn to_vmval<T>(self, store: &mut Store<T>) -> VMVal {
+        assert!(
+            self.comes_from_same_store(store),
+            "function used with the wrong store"
+        );
+        // Safety: we checked that the function belongs to the store
+        VMVal::funcref(unsafe { self.as_raw(store) })
+    }
+}
+
+impl WasmTy for Option<ExternRef> {
+    fn valtype() -> WasmValType {
+        WasmValType::Ref(WasmRefType::EXTERNREF)
+    }
+
+    unsafe fn from_vmval<T>(store: &mut Store<T>, vmval: VMVal) -> Self {
+        store.wrap_gc_ref(vmval.get_externref()).map(ExternRef)
+    }
+
+    fn to_vmval<T>(self, store: &mut Store<T>) -> VMVal {
+        VMVal::externref(self.map_or(0, |e| store.unwrap_gc_ref(e.0)))
+    }
+}
+
+impl WasmTy for Option<AnyRef> {
+    fn valtype() -> WasmValType {
+        WasmValType::Ref(WasmRefType::ANYREF)
+    }
+
+    unsafe fn from_vmval<T>(store: &mut Store<T>, vmval: VMVal) -> Self {
+        store.wrap_gc_ref(vmval.get_anyref()).map(AnyRef)
+    }
+
+    fn to_vmval<T>(self, store: &mut Store<T>) -> VMVal {
+        VMVal::anyref(self.map_or(0, |r| store.unwrap_gc_ref(r.0)))
+    }
+}
+
+/// A type that can be returned from host functions created through [`Func::wrap`].
+///
+/// This is implemented for `()` (no results) and all [`WasmTy`] types (a single result).
+pub trait WasmRet {
+    #[doc(hidden)]
+    fn valtypes() -> Box<[WasmValType]>;
+    /// Writes the results to the values array at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes of all results.
+    #[doc(hidden)]
+    unsafe fn store<T>(self, store: &mut Store<T>, ptr: *mut VMVal);
+}
+
+impl WasmRet for () {
+    fn valtypes() -> Box<[WasmValType]> {
+        Box::new([])
+    }
+
+    unsafe fn store<T>(self, _store: &mut Store<T>, _ptr: *mut VMVal) {}
+}
+
+impl<W: WasmTy> WasmRet for W {
+    fn valtypes() -> Box<[WasmValType]> {
+        Box::new([W::valtype()])
+    }
+
+    unsafe fn store<T>(self, store: &mut Store<T>, ptr: *mut VMVal) {
+        // Safety: ensured by the caller
+        unsafe {
+            *ptr = self.to_vmval(store);
+        }
+    }
+}
+
+/// The parameters of a [`TypedFunc`].
+///
+/// This is implemented for `()` (no parameters), all [`WasmTy`] types (a single parameter) and
+/// tuples of [`WasmTy`] types (multiple parameters).
+pub trait WasmParams {
+    #[doc(hidden)]
+    fn valtypes() -> Box<[WasmValType]>;
+    /// Writes the parameters to the values array at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes of all parameters.
+    #[doc(hidden)]
+    unsafe fn store<T>(self, store: &mut Store<T>, ptr: *mut VMVal);
+}
+
+/// The results of a [`TypedFunc`].
+///
+/// This is implemented for `()` (no results), all [`WasmTy`] types (a single result) and tuples
+/// of [`WasmTy`] types (multiple results).
+pub trait WasmResults {
+    #[doc(hidden)]
+    fn valtypes() -> Box<[WasmValType]>;
+    /// Reads the results from the values array at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads of all results, which must be of the types returned by
+    /// [`WasmResults::valtypes`].
+    #[doc(hidden)]
+    unsafe fn load<T>(store: &mut Store<T>, ptr: *const VMVal) -> Self;
+}
+
+impl WasmParams for () {
+    fn valtypes() -> Box<[WasmValType]> {
+        Box::new([])
+    }
+
+    unsafe fn store<T>(self, _store: &mut Store<T>, _ptr: *mut VMVal) {}
+}
+
+impl WasmResults for () {
+    fn valtypes() -> Box<[WasmValType]> {
+        Box::new([])
+    }
+
+    unsafe fn load<T>(_store: &mut Store<T>, _ptr: *const VMVal) -> Self {}
+}
+
+impl<W: WasmTy> WasmParams for W {
+    fn valtypes() -> Box<[WasmValType]> {
+        Box::new([W::valtype()])
+    }
+
+    unsafe fn store<T>(self, store: &mut Store<T>, ptr: *mut VMVal) {
+        // Safety: ensured by the caller
+        unsafe {
+            *ptr = self.to_vmval(store);
+        }
+    }
+}
+
+impl<W: WasmTy> WasmResults for W {
+    fn valtypes() -> Box<[WasmValType]> {
+        Box::new([W::valtype()])
+    }
+
+    unsafe fn load<T>(store: &mut Store<T>, ptr: *const VMVal) -> Self {
+        // Safety: ensured by the caller
+        unsafe { W::from_vmval(store, *ptr) }
+    }
+}
+
+macro_rules! impl_wasm_params_results {
+    ($($idx:tt $ty:ident),*) => {
+        impl<$($ty: WasmTy),*> WasmParams for ($($ty,)*) {
+            fn valtypes() -> Box<[WasmValType]> {
+                Box::new([$($ty::valtype()),*])
+            }
+
+            unsafe fn store<T>(self, store: &mut Store<T>, ptr: *mut VMVal) {
+                // Safety: ensured by the caller
+                unsafe {
+                    $(
+                        *ptr.add($idx) = self.$idx.to_vmval(store);
+                    )*
+                }
+            }
+        }
+
+        impl<$($ty: WasmTy),*> WasmResults for ($($ty,)*) {
+            fn valtypes() -> Box<[WasmValType]> {
+                Box::new([$($ty::valtype()),*])
+            }
+
+            unsafe fn load<T>(store: &mut Store<T>, ptr: *const VMVal) -> Self {
+                // Safety: ensured by the caller
+                unsafe { ($($ty::from_vmval(store, *ptr.add($idx)),)*) }
+            }
+        }
+    };
+}
+
+impl_wasm_params_results!(0 A1);
+impl_wasm_params_results!(0 A1, 1 A2);
+impl_wasm_params_results!(0 A1, 1 A2, 2 A3);
+impl_wasm_params_results!(0 A1, 1 A2, 2 A3, 3 A4);
+impl_wasm_params_results!(0 A1, 1 A2, 2 A3, 3 A4, 4 A5);
+impl_wasm_params_results!(0 A1, 1 A2, 2 A3, 3 A4, 4 A5, 5 A6);
+
+/// A [`Func`] whose type was checked to match `Params` and `Results`.
+///
+/// Unlike [`Func::call_unchecked`], calling a `TypedFunc` is safe and passes parameters and
+/// results as plain Rust values instead of [`Val`]s. Typed functions are created through
+/// [`Func::typed`] or [`Instance::get_typed_func`][crate::Instance::get_typed_func].
+pub struct TypedFunc<Params, Results> {
+    func: Func,
+    /// The length of the values array passed to the function, fits both params and results.
+    values_vec_size: usize,
+    _marker: PhantomData<fn(Params) -> Results>,
+}
+
+impl<Params, Results> Clone for TypedFunc<Params, Results> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Params, Results> Copy for TypedFunc<Params, Results> {}
+
+impl<Params, Results> fmt::Debug for TypedFunc<Params, Results> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedFunc")
+            .field("func", &self.func)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Params, Results> TypedFunc<Params, Results>
+where
+    Params: WasmParams,
+    Results: WasmResults,
+{
+    /// Returns the underlying untyped function.
+    pub fn func(&self) -> &Func {
+        &self.func
+    }
+
+    /// Calls the function with the given parameters, returning its results.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function traps.
+    pub fn call<T>(&self, store: &mut Store<T>, params: Params) -> crate::Result<Results> {
+        // take out the argument storage from the store
+        let mut values_vec = store.take_wasm_vmval_storage();
+        debug_assert!(values_vec.is_empty());
+        values_vec.resize_with(self.values_vec_size, || VMVal::v128(0));
+
+        // Safety: the type of the function was checked against `Params` and `Results` when this
+        // `TypedFunc` was created, so the values array fits both.
+        let res = unsafe {
+            params.store(store, values_vec.as_mut_ptr());
+            self.func
+                .call_unchecked_raw(store, values_vec.as_mut_ptr(), self.values_vec_size)
+                .map(|()| Results::load(store, values_vec.as_ptr()))
+        };
+
+        // clean up and return the argument storage
+        values_vec.truncate(0);
+        store.return_wasm_vmval_storage(values_vec);
+
+        res
+    }
+}
+
+/// The context a host function created through [`Func::wrap`] is called in.
+///
+/// Host functions that take a `Caller` as their first parameter can use it to access the
+/// [`Store`] WebAssembly code called them through, e.g. to read or update its data.
+pub struct Caller<'a, T> {
+    store: &'a mut Store<T>,
+}
+
+impl<T> Caller<'_, T> {
+    /// Returns a shared reference to the embedder-defined data of the store.
+    pub fn data(&self) -> &T {
+        self.store.data()
+    }
+
+    /// Returns a mutable reference to the embedder-defined data of the store.
+    pub fn data_mut(&mut self) -> &mut T {
+        self.store.data_mut()
+    }
+
+    /// Returns the engine the store belongs to.
+    pub fn engine(&self) -> &Engine {
+        &self.store.engine
+    }
+}
+
+/// A callback run after a host function created through [`Func::wrap_with_cleanup`] was called.
+#[doc(hidden)]
+pub type PostReturn<T> = Arc<dyn Fn(Caller<'_, T>) + Send + Sync>;
+
+/// The state of a host function: the closure to call and its optional cleanup callback.
+struct HostState<T, F> {
+    func: F,
+    post_return: Option<PostReturn<T>>,
+}
+
+/// A Rust closure that can be turned into a host function through [`Func::wrap`].
+///
+/// This is implemented for all `Fn` closures whose parameters implement [`WasmTy`] and whose
+/// return type implements [`WasmRet`]. Closures may additionally take a [`Caller`] as their first
+/// parameter to access the store they are called through.
+pub trait IntoFunc<T, Params, Results>: Send + Sync + 'static {
+    #[doc(hidden)]
+    fn into_func(
+        self,
+        engine: &Engine,
+        post_return: Option<PostReturn<T>>,
+    ) -> crate::Result<HostFunc>;
+}
+
+macro_rules! impl_into_func {
+    ($($idx:literal $arg:ident $ty:ident),*) => {
+        impl<T, F, $($ty,)* R> IntoFunc<T, ($($ty,)*), R> for F
+        where
+            T: 'static,
+            F: Fn($($ty),*) -> R + Send + Sync + 'static,
+            $($ty: WasmTy,)*
+            R: WasmRet,
+        {
+            fn into_func(
+                self,
+                engine: &Engine,
+                post_return: Option<PostReturn<T>>,
+            ) -> crate::Result<HostFunc> {
+                let f = move |_: Caller<'_, T>, $($arg: $ty),*| self($($arg),*);
+                IntoFunc::<T, (Caller<'_, T>, $($ty,)*), R>::into_func(f, engine, post_return)
+            }
+        }
+
+        impl<T, F, $($ty,)* R> IntoFunc<T, (Caller<'_, T>, $($ty,)*), R> for F
+        where
+            T: 'static,
+            F: Fn(Caller<'_, T>, $($ty),*) -> R + Send + Sync + 'static,
+            $($ty: WasmTy,)*
+            R: WasmRet,
+        {
+            fn into_func(
+                self,
+                engine: &Engine,
+                post_return: Option<PostReturn<T>>,
+            ) -> crate::Result<HostFunc> {
+                unsafe extern "C" fn array_call_trampoline<T, F, $($ty,)* R>(
+                    callee_vmctx: *mut VMContext,
+                    caller_vmctx: *mut VMContext,
+                    values_vec: *mut VMVal,
+                    _values_vec_len: usize,
+                ) where
+                    T: 'static,
+                    F: Fn(Caller<'_, T>, $($ty),*) -> R + Send + Sync + 'static,
+                    $($ty: WasmTy,)*
+                    R: WasmRet,
+                {
+                    // Safety: host functions are only ever called with their own
+                    // `VMArrayCallHostFuncContext` and a values array that fits their signature.
+                    // The context points to the runtime limits of the store the function was
+                    // added to, which outlives the function.
+                    unsafe {
+                        let ctx = VMArrayCallHostFuncContext::from_opaque(
+                            VMOpaqueContext::from_vmcontext(callee_vmctx),
+                        );
+                        let state = (*ctx).host_state().downcast_ref::<HostState<T, F>>().unwrap();
+                        let runtime_limits = (*ctx).runtime_limits();
+                        // direct calls from the host have no caller and aren't a transition
+                        let from_wasm = !caller_vmctx.is_null();
+                        // Direct calls check the store up front, so only WebAssembly of another
+                        // store calling this function, e.g. through a shared table, gets here.
+                        let Some(store) = current_store::<T>(runtime_limits) else {
+                            raise_trap(TrapReason::User(crate::wasm_unsupported!(
+                                "calling host functions of another store"
+                            )));
+                        };
+                        let call = || -> crate::Result<()> {
+                            if from_wasm {
+                                (*store).call_hook(CallHook::CallingHost)?;
+                            }
+                            $(
+                                let $arg = $ty::from_vmval(&mut *store, *values_vec.add($idx));
+                            )*
+                            let caller = Caller { store: &mut *store };
+                            (state.func)(caller, $($arg),*).store(&mut *store, values_vec);
+                            if let Some(post_return) = &state.post_return {
+                                if from_wasm {
+                                    let post_return = post_return.clone();
+                                    defer_post_return(Box::new(move || {
+                                        // the enclosing call is still publishing the store
+                                        if let Some(store) = current_store::<T>(runtime_limits) {
+                                            post_return(Caller { store: &mut *store });
+                                        }
+                                    }));
+                                } else {
+                                    post_return(Caller { store: &mut *store });
+                                }
+                            }
+                            if from_wasm {
+                                (*store).call_hook(CallHook::ReturningFromHost)?;
+                            }
+                            Ok(())
+                        };
+
+                        // Unwinding through the WebAssembly frames (or the `extern "C"` boundary)
+                        // is undefined behaviour, so panics are turned into errors right here.
+                        #[cfg(not(feature = "no_std"))]
+                        let res = std::panic::catch_unwind(core::panic::AssertUnwindSafe(call))
+                            .unwrap_or_else(|payload| {
+                                Err(crate::Error::HostPanic(panic_message(&*payload)))
+                            });
+                        #[cfg(feature = "no_std")]
+                        let res = call();
+
+                        if let Err(err) = res {
+                            if from_wasm {
+                                raise_trap(TrapReason::User(err));
+                            } else {
+                                (*store).set_host_error(err);
+                            }
+                        }
+                    }
+                }
+
+                let ty = WasmFuncType {
+                    params: Box::new([$($ty::valtype()),*]),
+                    results: R::valtypes(),
+                };
+
+                // Safety: `array_call_trampoline` is generic over the same types `ty` is derived from
+                unsafe {
+                    HostFunc::new(
+                        engine,
+                        ty,
+                        array_call_trampoline::<T, F, $($ty,)* R>,
+                        Box::new(HostState { func: self, post_return }),
+                    )
+                }
+            }
+        }
+    };
+}
+
+impl_into_func!();
+impl_into_func!(0 a1 A1);
+impl_into_func!(0 a1 A1, 1 a2 A2);
+impl_into_func!(0 a1 A1, 1 a2 A2, 2 a3 A3);
+impl_into_func!(0 a1 A1, 1 a2 A2, 2 a3 A3, 3 a4 A4);
+impl_into_func!(0 a1 A1, 1 a2 A2, 2 a3 A3, 3 a4 A4, 4 a5 A5);
+impl_into_func!(0 a1 A1, 1 a2 A2, 2 a3 A3, 3 a4 A4, 4 a5 A5, 5 a6 A6);
@@ -1,36 +1,237 @@
-use crate::runtime::VMGlobalImport;
+use crate::runtime::{VMGlobalDefinition, VMGlobalImport, VMVal};
 use crate::store::Stored;
-use crate::{runtime, Store, Val};
+use crate::translate::{GlobalDesc, WasmHeapTopTypeInner, WasmHeapTypeInner, WasmValType};
+use crate::{runtime, wasm_unsupported, Error, Store, Val};
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::ptr;
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 /// A WebAssembly global instance.
 #[derive(Debug, Clone, Copy)]
 pub struct Global(Stored<runtime::ExportedGlobal>);
 
 impl Global {
-    // pub fn new(store: &mut Store, ty: GlobalType) -> crate::Result<Self> {
-    //     todo!()
-    // }
-    // pub fn ty(&self, _store: &Store) -> &GlobalType {
-    //     todo!()
-    // }
+    /// Creates a new host-defined global with the initial value `val`.
+    ///
+    /// The type of the global is inferred from `val`. Host-defined globals can be passed to
+    /// WebAssembly through imports, e.g. using [`Linker::define`][crate::Linker::define].
+    pub fn new<T>(store: &mut Store<T>, val: Val, mutable: bool) -> Self {
+        let ty = GlobalDesc {
+            content_type: val.ty(),
+            mutable,
+            shared: false,
+        };
+
+        // Safety: `as_vmval` produces a value of the type inferred above
+        let definition = unsafe { VMGlobalDefinition::from_vmval(val.as_vmval(store)) };
+        let definition = store.push_host_global(definition);
+
+        Self(store.push_global(runtime::ExportedGlobal {
+            definition,
+            vmctx: ptr::null_mut(),
+            ty,
+        }))
+    }
+
     /// Get the current value of the global.
-    pub fn get(&self, _store: &Store) -> Val {
-        todo!()
+    pub fn get<T>(&self, store: &mut Store<T>) -> Val {
+        let definition = store[self.0].definition;
+        let ty = store[self.0].ty.clone();
+        // Safety: the definition is kept alive by its owner for as long as the store lives and
+        // holds a value of the global's type
+        unsafe {
+            let vmval = if ty.shared {
+                load_shared(definition, &ty.content_type)
+            } else {
+                (*definition).to_vmval(&ty.content_type)
+            };
+            Val::from_vmval(store, vmval, &ty.content_type)
+        }
+    }
+
+    /// Set the value of the global.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the global is immutable or if `val` doesn't match the type of the
+    /// global.
+    pub fn set<T>(&self, store: &mut Store<T>, val: Val) -> crate::Result<()> {
+        let ty = store[self.0].ty.clone();
+        if !ty.mutable {
+            return Err(Error::ImmutableGlobal);
+        }
+        if !val_matches(&val, &ty.content_type) {
+            return Err(Error::GlobalTypeMismatch {
+                expected: ty.content_type.to_string(),
+                actual: val.ty().to_string(),
+            });
+        }
+
+        // Safety: the type of `val` was checked above and the definition is kept alive by its
+        // owner for as long as the store lives
+        unsafe {
+            let vmval = val.as_vmval(store);
+            if ty.shared {
+                store_shared(store[self.0].definition, vmval, &ty.content_type);
+            } else {
+                *store[self.0].definition = VMGlobalDefinition::from_vmval(vmval);
+            }
+        }
+
+        Ok(())
     }
-    // pub fn set(&self, store: &mut Store, val: Val) {
-    //     todo!()
-    // }
-    pub(crate) fn as_vmglobal_import(&self, store: &Store) -> VMGlobalImport {
+
+    pub(crate) fn ty<'a, T>(&self, store: &'a Store<T>) -> &'a GlobalDesc {
+        &store[self.0].ty
+    }
+
+    pub(crate) fn as_vmglobal_import<T>(&self, store: &Store<T>) -> VMGlobalImport {
         VMGlobalImport {
             from: store[self.0].definition,
             vmctx: store[self.0].vmctx,
         }
     }
-    pub(crate) fn from_vm_export(store: &mut Store, export: runtime::ExportedGlobal) -> Self {
+    pub(crate) fn from_vm_export<T>(store: &mut Store<T>, export: runtime::ExportedGlobal) -> Self {
         Self(store.push_global(export))
     }
 
-    pub(crate) fn comes_from_same_store(self, store: &Store) -> bool {
+    pub(crate) fn comes_from_same_store<T>(self, store: &Store<T>) -> bool {
         store.has_global(self.0)
     }
 }
+
+/// A global that can be shared between stores, e.g. stores running on different threads.
+///
+/// Shared globals are created independently of any store and made available to a store through
+/// [`SharedGlobal::attach`], all globals attached this way refer to the same value. They are always
+/// mutable and every access is atomic, which limits them to `i32`, `i64`, `f32` and `f64` values.
+#[derive(Debug, Clone)]
+pub struct SharedGlobal {
+    definition: Arc<SharedGlobalDefinition>,
+    ty: GlobalDesc,
+}
+
+impl SharedGlobal {
+    /// Creates a new shared global with the initial value `val`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `val` isn't an `i32`, `i64`, `f32` or `f64`.
+    pub fn new(val: Val) -> crate::Result<Self> {
+        let vmval = match val {
+            Val::I32(i) => VMVal::i32(i),
+            Val::I64(i) => VMVal::i64(i),
+            Val::F32(bits) => VMVal::f32(bits),
+            Val::F64(bits) => VMVal::f64(bits),
+            Val::V128(_) | Val::FuncRef(_) | Val::ExternRef(_) | Val::AnyRef(_) => {
+                return Err(wasm_unsupported!("shared globals of type {}", val.ty()))
+            }
+        };
+
+        Ok(Self {
+            // Safety: `vmval` holds a value of the type inferred below
+            definition: Arc::new(SharedGlobalDefinition(UnsafeCell::new(unsafe {
+                VMGlobalDefinition::from_vmval(vmval)
+            }))),
+            ty: GlobalDesc {
+                content_type: val.ty(),
+                mutable: true,
+                shared: true,
+            },
+        })
+    }
+
+    /// Makes this global available to `store`, returning a handle that can be passed to
+    /// WebAssembly through imports or accessed through [`Global::get`] and [`Global::set`].
+    pub fn attach<T>(&self, store: &mut Store<T>) -> Global {
+        let definition = store.push_shared_global(self.definition.clone());
+
+        Global(store.push_global(runtime::ExportedGlobal {
+            definition,
+            vmctx: ptr::null_mut(),
+            ty: self.ty.clone(),
+        }))
+    }
+}
+
+/// The storage of a [`SharedGlobal`], kept alive by every store it is attached to.
+#[derive(Debug)]
+pub(crate) struct SharedGlobalDefinition(UnsafeCell<VMGlobalDefinition>);
+
+// Safety: shared globals are only ever accessed atomically, see `load_shared` and `store_shared`
+unsafe impl Send for SharedGlobalDefinition {}
+// Safety: see above
+unsafe impl Sync for SharedGlobalDefinition {}
+
+impl SharedGlobalDefinition {
+    pub(crate) fn get(&self) -> *mut VMGlobalDefinition {
+        self.0.get()
+    }
+}
+
+/// Atomically reads the value of a shared global of type `ty`.
+///
+/// # Safety
+///
+/// `definition` must point to a live definition of a shared global of type `ty`.
+unsafe fn load_shared(definition: *mut VMGlobalDefinition, ty: &WasmValType) -> VMVal {
+    match ty {
+        WasmValType::I32 | WasmValType::F32 => {
+            VMVal::u32(AtomicU32::from_ptr(definition.cast()).load(Ordering::SeqCst))
+        }
+        WasmValType::I64 | WasmValType::F64 => {
+            VMVal::u64(AtomicU64::from_ptr(definition.cast()).load(Ordering::SeqCst))
+        }
+        _ => unreachable!("shared globals of type {ty} are rejected at compile time"),
+    }
+}
+
+/// Atomically writes `val` to a shared global of type `ty`.
+///
+/// # Safety
+///
+/// `definition` must point to a live definition of a shared global of type `ty` and `val` must
+/// hold a value of type `ty`.
+unsafe fn store_shared(definition: *mut VMGlobalDefinition, val: VMVal, ty: &WasmValType) {
+    match ty {
+        WasmValType::I32 | WasmValType::F32 => {
+            AtomicU32::from_ptr(definition.cast()).store(val.get_u32(), Ordering::SeqCst);
+        }
+        WasmValType::I64 | WasmValType::F64 => {
+            AtomicU64::from_ptr(definition.cast()).store(val.get_u64(), Ordering::SeqCst);
+        }
+        _ => unreachable!("shared globals of type {ty} are rejected at compile time"),
+    }
+}
+
+/// Returns whether `val` can be stored in a global of type `ty`.
+fn val_matches(val: &Val, ty: &WasmValType) -> bool {
+    match (val, ty) {
+        (Val::I32(_), WasmValType::I32)
+        | (Val::I64(_), WasmValType::I64)
+        | (Val::F32(_), WasmValType::F32)
+        | (Val::F64(_), WasmValType::F64)
+        | (Val::V128(_), WasmValType::V128) => true,
+        (Val::FuncRef(func), WasmValType::Ref(ty)) => {
+            ty.heap_type.top().inner == WasmHeapTopTypeInner::Func
+                && (func.is_some() || ty.nullable)
+        }
+        (Val::ExternRef(e), WasmValType::Ref(ty)) => {
+            ty.heap_type.top().inner == WasmHeapTopTypeInner::Extern
+                && match e {
+                    Some(_) => ty.heap_type.ty == WasmHeapTypeInner::Extern,
+                    None => ty.nullable,
+                }
+        }
+        (Val::AnyRef(r), WasmValType::Ref(ty)) => {
+            ty.heap_type.top().inner == WasmHeapTopTypeInner::Any
+                && match r {
+                    Some(r) => r.matches(&ty.heap_type),
+                    None => ty.nullable,
+                }
+        }
+        _ => false,
+    }
+}
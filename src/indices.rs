@@ -5,6 +5,7 @@ use cranelift_entity::entity_impl;
 pub struct TypeIndex(u32);
 entity_impl!(TypeIndex);
 
+/// Index type of a function (imported or defined) inside a WebAssembly module.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct FuncIndex(u32);
 entity_impl!(FuncIndex);
@@ -29,6 +30,7 @@ entity_impl!(MemoryIndex);
 pub struct DefinedMemoryIndex(u32);
 entity_impl!(DefinedMemoryIndex);
 
+/// Index type of a global (imported or defined) inside a WebAssembly module.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct GlobalIndex(u32);
 entity_impl!(GlobalIndex);
@@ -107,6 +109,7 @@ entity_impl!(ModuleInternedTypeIndex);
 pub struct ModuleInternedRecGroupIndex(u32);
 entity_impl!(ModuleInternedRecGroupIndex);
 
+/// Index type of a type canonicalized in an engine's type registry, unique within that engine.
 #[repr(transparent)] // Used directly by JIT code.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct VMSharedTypeIndex(u32);
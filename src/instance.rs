@@ -1,11 +1,15 @@
-use crate::func::Func;
+use crate::func::{Func, TypedFunc, WasmParams, WasmResults};
 use crate::global::Global;
 use crate::indices::EntityIndex;
 use crate::memory::Memory;
 use crate::runtime::{ConstExprEvaluator, Imports, InstanceAllocator};
 use crate::store::Stored;
 use crate::table::Table;
-use crate::{runtime, Export, Extern, Module, Store};
+use crate::translate::TranslatedModule;
+use crate::{runtime, Error, Export, Extern, ExternKind, Module, Store};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::sync::Arc;
 
 /// An instantiated WebAssembly module.
 ///
@@ -22,29 +26,83 @@ pub struct Instance(Stored<runtime::Instance>);
 impl Instance {
     /// Instantiates a new `Instance`.
     ///
+    /// Active element segments are applied first, then active data segments and finally the
+    /// module's start function is called. A trap raised by the start function is returned as the
+    /// instantiation error.
+    ///
     /// # Safety
     ///
     /// This functions assumes the provided `imports` have already been validated and typechecked for
     /// compatibility with the `module` being instantiated.
-    pub(crate) unsafe fn new_unchecked(
-        store: &mut Store,
-        alloc: &dyn InstanceAllocator,
+    pub(crate) unsafe fn new_unchecked<T>(
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
         const_eval: &mut ConstExprEvaluator,
         module: Module,
         imports: Imports,
     ) -> crate::Result<Self> {
-        let instance = runtime::Instance::new_unchecked(alloc, const_eval, module, imports)?;
+        if !store.engine.targets_host() {
+            return Err(Error::UnsupportedTarget(format!(
+                "code compiled for {} can't run on the host",
+                store.engine.target()
+            )));
+        }
+
+        let start = module.start_func();
+        // this initializes the tables and then the memories
+        let instance = runtime::Instance::new_unchecked(store, alloc, const_eval, module, imports)?;
         let handle = store.push_instance(instance);
+
+        // the start function runs last, so it observes all initializers
+        if let Some(start) = start {
+            let export = store[handle].get_exported_func(start);
+            let func = Func::from_vm_export(store, export);
+            // validation ensures the start function has type `[] -> []`
+            func.call_unchecked(store, &[], &mut [])?;
+        }
+
         Ok(Self(handle))
     }
 
+    /// Instantiates `translated`, a module consisting solely of memory and table definitions.
+    ///
+    /// This backs host-defined memories and tables: nothing is compiled, the instance just owns
+    /// their resources for as long as the store lives and gives them a `VMContext`, so growing
+    /// them works just like for definitions of regular instances.
+    pub(crate) fn new_host<T>(
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
+        translated: TranslatedModule,
+    ) -> crate::Result<Self> {
+        debug_assert!(translated.imports.is_empty());
+        let module = Module::without_code(&store.engine, translated)?;
+
+        // Safety: the module has no imports
+        unsafe {
+            Self::new_unchecked(
+                store,
+                alloc,
+                &mut ConstExprEvaluator::default(),
+                module,
+                Imports::default(),
+            )
+        }
+    }
+
     /// Returns the module this instance was instantiated from.
-    pub fn module<'s>(&self, store: &'s Store) -> &'s Module {
+    pub fn module<'s, T>(&self, store: &'s Store<T>) -> &'s Module {
         store[self.0].module()
     }
 
     /// Returns an iterator over the exports of this instance.
-    pub(crate) fn exports(self, store: &mut Store) -> impl ExactSizeIterator<Item = Export> {
+    ///
+    /// Each item is a pair of the export's name and the exported [`Extern`], in the order the
+    /// module declares them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an export couldn't be resolved, which indicates a bug in instantiation.
+    pub fn exports<T>(self, store: &mut Store<T>) -> impl ExactSizeIterator<Item = Export<'_>> {
         let exports = &store[self.0].exports;
         if exports.iter().any(Option::is_none) {
             let module = store[self.0].module().clone();
@@ -72,40 +130,81 @@ impl Instance {
     }
 
     /// Attempts to get an export from this instance.
-    pub fn get_export(&self, store: &mut Store, name: &str) -> Option<Extern> {
+    pub fn get_export<T>(&self, store: &mut Store<T>, name: &str) -> Option<Extern> {
         let (export_name_index, _, index) =
             self.module(store).translated().exports.get_full(name)?;
         Some(self.get_export_inner(store, *index, export_name_index))
     }
 
-    /// Attempts to get an exported `Func` from this instance.
-    pub fn get_func(&self, store: &mut Store, name: &str) -> Option<Func> {
-        self.get_export(store, name)?.into_func()
+    /// Gets an exported `Func` from this instance.
+    ///
+    /// Use [`Instance::get_export`] to look up an export that may or may not be a function.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingExport`][crate::Error::MissingExport] if there is no export of the
+    /// given name and [`Error::WrongExportKind`][crate::Error::WrongExportKind] if the export
+    /// isn't a function.
+    pub fn get_func<T>(&self, store: &mut Store<T>, name: &str) -> crate::Result<Func> {
+        let export = self
+            .get_export(store, name)
+            .ok_or_else(|| crate::Error::MissingExport {
+                name: name.to_string(),
+            })?;
+
+        match export {
+            Extern::Func(func) => Ok(func),
+            export => Err(crate::Error::WrongExportKind {
+                name: name.to_string(),
+                expected: ExternKind::Func,
+                found: export.kind(),
+            }),
+        }
+    }
+
+    /// Attempts to get an exported `Func` from this instance and checks that its type matches
+    /// `Params` and `Results`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Instance::get_func`] and
+    /// [`Error::FuncTypeMismatch`][crate::Error::FuncTypeMismatch] if the function's type doesn't
+    /// match.
+    pub fn get_typed_func<Params, Results>(
+        &self,
+        store: &mut Store<impl Sized>,
+        name: &str,
+    ) -> crate::Result<TypedFunc<Params, Results>>
+    where
+        Params: WasmParams,
+        Results: WasmResults,
+    {
+        self.get_func(store, name)?.typed(store)
     }
 
     /// Attempts to get an exported `Table` from this instance.
-    pub fn get_table(&self, store: &mut Store, name: &str) -> Option<Table> {
+    pub fn get_table<T>(&self, store: &mut Store<T>, name: &str) -> Option<Table> {
         self.get_export(store, name)?.into_table()
     }
 
     /// Attempts to get an exported `Memory` from this instance.
-    pub fn get_memory(&self, store: &mut Store, name: &str) -> Option<Memory> {
+    pub fn get_memory<T>(&self, store: &mut Store<T>, name: &str) -> Option<Memory> {
         self.get_export(store, name)?.into_memory()
     }
 
     /// Attempts to get an exported `Global` from this instance.
-    pub fn get_global(&self, store: &mut Store, name: &str) -> Option<Global> {
+    pub fn get_global<T>(&self, store: &mut Store<T>, name: &str) -> Option<Global> {
         self.get_export(store, name)?.into_global()
     }
 
     /// Print a debug representation of this instances `VMContext` to the logger.
-    pub fn debug_vmctx(&self, store: &Store) {
+    pub fn debug_vmctx<T>(&self, store: &Store<T>) {
         store[self.0].debug_vmctx();
     }
 
-    fn get_export_inner(
+    fn get_export_inner<T>(
         self,
-        store: &mut Store,
+        store: &mut Store<T>,
         entity: EntityIndex,
         export_name_index: usize,
     ) -> Extern {
@@ -124,7 +223,7 @@ impl Instance {
         item
     }
 
-    pub(crate) fn comes_from_same_store(self, store: &Store) -> bool {
+    pub(crate) fn comes_from_same_store<T>(self, store: &Store<T>) -> bool {
         store.has_instance(self.0)
     }
 }
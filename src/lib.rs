@@ -10,7 +10,11 @@ extern crate core;
 
 mod builtins;
 mod compile;
+mod config;
 mod cranelift;
+mod debug;
+#[cfg(feature = "disassemble")]
+mod disassemble;
 mod engine;
 mod errors;
 mod func;
@@ -20,31 +24,53 @@ mod instance;
 mod linker;
 mod memory;
 mod module;
+mod module_cache;
 mod placeholder;
 mod runtime;
+mod stack;
 mod store;
 mod table;
 mod translate;
 mod trap;
 mod type_registry;
+mod types;
 mod utils;
 mod values;
 
 pub use errors::Error;
 pub(crate) type Result<T> = core::result::Result<T, Error>;
+pub use compile::{ImageRelocation, ImageRelocationKind};
+pub use config::{Config, OptLevel, ProfilingStrategy};
+pub use debug::{DebugVariable, SourceLoc, TrapFrame};
 pub use engine::Engine;
-pub use func::Func;
-pub use global::Global;
+pub use func::{
+    Caller, Func, FuncType, IntoFunc, TypedFunc, WasmParams, WasmResults, WasmRet, WasmTy,
+};
+pub use global::{Global, SharedGlobal};
+pub use indices::{FuncIndex, GlobalIndex, VMSharedTypeIndex};
 pub use instance::Instance;
-pub use linker::Linker;
+pub use linker::{InstancePre, Linker};
 pub use memory::Memory;
-pub use module::Module;
+pub use module::{ImageLayout, Module};
 pub use placeholder::instance_allocator::PlaceholderAllocatorDontUse;
-pub use runtime::{ConstExprEvaluator, InstanceAllocator};
-pub use store::Store;
+pub use placeholder::trap_handling::{
+    handle_fault, set_trap_handler, Backtrace, BacktraceFrame, TrapHandler,
+};
+pub use runtime::{
+    ConstEvalContext, ConstExprEvaluator, InstanceAllocator, OnDemandAllocator, PoolingAllocator,
+    PoolingConfig, VMVal,
+};
+pub use stack::StackRegion;
+#[cfg(feature = "stats")]
+pub use store::StoreStats;
+pub use store::{CallHook, ResourceLimiter, Store, WasmBacktraceDetails};
 pub use table::Table;
-pub use translate::ModuleTranslator;
-pub use values::{Ref, Val};
+pub use translate::{ConstExpr, ConstOp, DylinkInfo, ModuleTranslator, WasmValType};
+pub use trap::Trap;
+pub use types::{ExternType, GlobalType, MemoryType, TableType, ValType};
+pub use values::{AnyRef, ExternRef, Ref, Val};
+
+use core::fmt;
 
 /// The number of pages (for 32-bit modules) we can have before we run out of
 /// byte index space.
@@ -54,12 +80,13 @@ pub const WASM32_MAX_PAGES: u64 = 1 << 16;
 pub const WASM64_MAX_PAGES: u64 = 1 << 48;
 /// Maximum size, in bytes, of 32-bit memories (4G).
 pub const WASM32_MAX_SIZE: u64 = 1 << 32;
-/// Maximum size, in bytes of WebAssembly stacks.
+/// Default maximum size, in bytes, of WebAssembly stacks, see [`Store::set_stack_limit`].
 pub const MAX_WASM_STACK: usize = 512 * 1024;
 
 /***************** Settings *******************************************/
 /// Whether lowerings for relaxed simd instructions are forced to
 /// be deterministic.
+#[deprecated(note = "use `Config::relaxed_simd_deterministic` instead")]
 pub const RELAXED_SIMD_DETERMINISTIC: bool = false;
 /// 2 GiB of guard pages
 /// TODO why does this help to eliminate bounds checks?
@@ -91,8 +118,42 @@ pub enum Extern {
     Global(Global),
 }
 
+/// The kind of an [`Extern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternKind {
+    /// A function.
+    Func,
+    /// A table.
+    Table,
+    /// A linear memory.
+    Memory,
+    /// A global.
+    Global,
+}
+
+impl fmt::Display for ExternKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternKind::Func => f.write_str("function"),
+            ExternKind::Table => f.write_str("table"),
+            ExternKind::Memory => f.write_str("memory"),
+            ExternKind::Global => f.write_str("global"),
+        }
+    }
+}
+
 impl Extern {
-    pub(crate) fn from_export(export: runtime::Export, store: &mut Store) -> Self {
+    /// Returns the kind of this external value.
+    pub fn kind(&self) -> ExternKind {
+        match self {
+            Extern::Func(_) => ExternKind::Func,
+            Extern::Table(_) => ExternKind::Table,
+            Extern::Memory(_) => ExternKind::Memory,
+            Extern::Global(_) => ExternKind::Global,
+        }
+    }
+
+    pub(crate) fn from_export<T>(export: runtime::Export, store: &mut Store<T>) -> Self {
         use runtime::Export;
         match export {
             Export::Function(e) => Extern::Func(Func::from_vm_export(store, e)),
@@ -118,3 +179,27 @@ impl Extern {
         (Global(Global) into_global e)
     }
 }
+
+impl From<Func> for Extern {
+    fn from(func: Func) -> Self {
+        Extern::Func(func)
+    }
+}
+
+impl From<Table> for Extern {
+    fn from(table: Table) -> Self {
+        Extern::Table(table)
+    }
+}
+
+impl From<Memory> for Extern {
+    fn from(memory: Memory) -> Self {
+        Extern::Memory(memory)
+    }
+}
+
+impl From<Global> for Extern {
+    fn from(global: Global) -> Self {
+        Extern::Global(global)
+    }
+}
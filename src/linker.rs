@@ -1,7 +1,10 @@
 use crate::runtime::{ConstExprEvaluator, Imports, InstanceAllocator};
-use crate::translate::EntityType;
-use crate::{Engine, Error, Extern, Instance, Module, Store};
-use alloc::string::ToString;
+use crate::translate::{EntityType, GlobalDesc, MemoryDesc, TableDesc, WasmValType};
+use crate::{
+    Engine, Error, Extern, ExternKind, Func, Instance, MemoryType, Module, Store, TableType,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use hashbrown::hash_map::Entry;
@@ -44,11 +47,81 @@ impl Linker {
         self.map.get(&key)
     }
 
+    /// Returns the entry point of the command-style module defined under `module`.
+    ///
+    /// This is its `_start` function if defined, otherwise the function defined under the empty
+    /// name which is the default export by convention. Either way the function must take no
+    /// parameters and return no results.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `module` defines neither of them, if the definition isn't a function or
+    /// if the function's type isn't `() -> ()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the definition belongs to a different store.
+    pub fn get_default<T>(&self, store: &mut Store<T>, module: &str) -> crate::Result<Func> {
+        let (name, def) = ["_start", ""]
+            .into_iter()
+            .find_map(|name| Some((name, self.get(module, name)?)))
+            .ok_or_else(|| Error::Undefined {
+                module: module.to_string(),
+                field: "_start".to_string(),
+            })?;
+
+        let Extern::Func(func) = def else {
+            return Err(Error::WrongExportKind {
+                name: name.to_string(),
+                expected: ExternKind::Func,
+                found: def.kind(),
+            });
+        };
+
+        let ty = func.ty(store);
+        let ty = ty.as_wasm_func_type();
+        if !ty.params.is_empty() || !ty.results.is_empty() {
+            return Err(Error::FuncTypeMismatch {
+                expected: "(func)".to_string(),
+                actual: ty.to_string(),
+            });
+        }
+
+        Ok(*func)
+    }
+
+    /// Alias the definition `module`/`name` under `as_module`/`as_name`.
+    ///
+    /// Both names resolve to the same definition afterward, the item itself is not duplicated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `module`/`name` is not defined or if `as_module`/`as_name` is already
+    /// defined.
+    pub fn alias(
+        &mut self,
+        module: &str,
+        name: &str,
+        as_module: &str,
+        as_name: &str,
+    ) -> crate::Result<&mut Self> {
+        let item = self
+            .get(module, name)
+            .cloned()
+            .ok_or_else(|| Error::Undefined {
+                module: module.to_string(),
+                field: name.to_string(),
+            })?;
+        let key = self.import_key(as_module, Some(as_name));
+        self.insert(key, item)?;
+        Ok(self)
+    }
+
     /// Alias all exports of `module` under the name `as_module`.
     ///
     /// # Errors
     ///
-    /// TODO
+    /// Returns an error if any of the aliased names is already defined under `as_module`.
     pub fn alias_module(&mut self, module: &str, as_module: &str) -> crate::Result<&mut Self> {
         let module = self.intern_str(module);
         let as_module = self.intern_str(as_module);
@@ -70,14 +143,31 @@ impl Linker {
         Ok(self)
     }
 
+    /// Define `item` under the given `module` and `name`, making it available to modules importing
+    /// that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `module` and `name` are already defined.
+    pub fn define(
+        &mut self,
+        module: &str,
+        name: &str,
+        item: impl Into<Extern>,
+    ) -> crate::Result<&mut Self> {
+        let key = self.import_key(module, Some(name));
+        self.insert(key, item.into())?;
+        Ok(self)
+    }
+
     /// Define all exports of the provided `instance` under the module name `module_name`.
     ///
     /// # Errors
     ///
     /// TODO
-    pub fn define_instance(
+    pub fn define_instance<T>(
         &mut self,
-        store: &mut Store,
+        store: &mut Store<T>,
         module_name: &str,
         instance: Instance,
     ) -> crate::Result<&mut Self> {
@@ -99,50 +189,71 @@ impl Linker {
     /// on to `Instance::new_unchecked` for instantiation.
     ///
     /// Each import of module will be looked up in this Linker and must have previously been defined.
+    /// This is a shorthand for [`Linker::instantiate_pre`] followed by [`InstancePre::instantiate`].
     ///
     /// # Errors
     ///
-    /// TODO
+    /// Returns an error if an import is missing or has an incompatible type, or if instantiation
+    /// fails.
     ///
     /// # Panics
     ///
-    /// TODO
-    pub fn instantiate(
+    /// Panics if a definition used to satisfy an import belongs to a different store.
+    pub fn instantiate<T>(
         &self,
-        store: &mut Store,
-        alloc: &dyn InstanceAllocator,
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
         const_eval: &mut ConstExprEvaluator,
         module: &Module,
     ) -> crate::Result<Instance> {
-        let mut imports = Imports::with_capacity_for(module.translated());
-        for import in module.imports() {
-            let def =
-                self.get(&import.module, &import.name)
-                    .ok_or_else(|| Error::MissingImport {
+        self.instantiate_pre(module)?
+            .instantiate(store, alloc, const_eval)
+    }
+
+    /// Resolves the imports of the provided `module` once, so it can be instantiated repeatedly
+    /// without looking them up again.
+    ///
+    /// Each import of module will be looked up in this Linker and must have previously been defined.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an import is missing or refers to a definition of a different kind. The
+    /// types of the definitions are checked by [`InstancePre::instantiate`], since they live in the
+    /// store.
+    pub fn instantiate_pre(&self, module: &Module) -> crate::Result<InstancePre> {
+        let items = module
+            .imports()
+            .map(|import| {
+                let def =
+                    self.get(&import.module, &import.name)
+                        .ok_or_else(|| Error::MissingImport {
+                            module: import.module.to_string(),
+                            field: import.name.to_string(),
+                            type_: import.ty.clone(),
+                        })?;
+
+                match (def, &import.ty) {
+                    (Extern::Func(_), EntityType::Function(_))
+                    | (Extern::Table(_), EntityType::Table(_))
+                    | (Extern::Memory(_), EntityType::Memory(_))
+                    | (Extern::Global(_), EntityType::Global(_)) => Ok(def.clone()),
+                    (def, ty) => Err(Error::IncompatibleImport {
                         module: import.module.to_string(),
                         field: import.name.to_string(),
-                        type_: import.ty.clone(),
-                    })?;
-
-            match (def, &import.ty) {
-                (Extern::Func(func), EntityType::Function(_ty)) => {
-                    imports.functions.push(func.as_vmfunction_import(store));
-                }
-                (Extern::Table(table), EntityType::Table(_ty)) => {
-                    imports.tables.push(table.as_vmtable_import(store));
-                }
-                (Extern::Memory(memory), EntityType::Memory(_ty)) => {
-                    imports.memories.push(memory.as_vmmemory_import(store));
-                }
-                (Extern::Global(global), EntityType::Global(_ty)) => {
-                    imports.globals.push(global.as_vmglobal_import(store));
+                        message: format!(
+                            "expected {}, found {}",
+                            entity_kind(ty),
+                            extern_kind(def)
+                        ),
+                    }),
                 }
-                _ => panic!("mismatched import type"),
-            }
-        }
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
 
-        // Safety: we have typechecked the imports above.
-        unsafe { Instance::new_unchecked(store, alloc, const_eval, module.clone(), imports) }
+        Ok(InstancePre {
+            module: module.clone(),
+            items: items.into(),
+        })
     }
 
     fn insert(&mut self, key: ImportKey, item: Extern) -> crate::Result<()> {
@@ -179,3 +290,215 @@ impl Linker {
         idx
     }
 }
+
+/// A module whose imports have been resolved by a [`Linker`], ready to be instantiated.
+///
+/// Created through [`Linker::instantiate_pre`], this allows instantiating the same module many
+/// times without looking up its imports again.
+#[derive(Debug, Clone)]
+pub struct InstancePre {
+    module: Module,
+    items: Arc<[Extern]>,
+}
+
+impl InstancePre {
+    /// Returns the module this will instantiate.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Instantiates the module with the resolved imports.
+    ///
+    /// The memories, tables and `VMContext` of the new instance are allocated through `alloc` and
+    /// returned to it once the instance is torn down, i.e. when the store is dropped or
+    /// [`Store::reset`] is called. The instance keeps `alloc` alive until then.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an import has an incompatible type, or if instantiation fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a definition used to satisfy an import belongs to a different store.
+    pub fn instantiate<T>(
+        &self,
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
+        const_eval: &mut ConstExprEvaluator,
+    ) -> crate::Result<Instance> {
+        let mut imports = Imports::with_capacity_for(self.module.translated());
+        for (import, item) in self.module.imports().zip(self.items.iter()) {
+            // the types of definitions live in the store, so they can only be checked here
+            let incompatible = |message| Error::IncompatibleImport {
+                module: import.module.to_string(),
+                field: import.name.to_string(),
+                message,
+            };
+
+            match (item, &import.ty) {
+                (Extern::Func(func), EntityType::Function(ty)) => {
+                    assert!(func.comes_from_same_store(store));
+                    let ty = ty.unwrap_module_type_index();
+                    let expected = self
+                        .module
+                        .type_collection()
+                        .lookup_shared_type(ty)
+                        .unwrap();
+                    let actual = func.ty(store);
+                    if actual.type_index() != expected {
+                        let expected = store.engine.type_registry().get_type(expected).unwrap();
+                        return Err(incompatible(format!(
+                            "expected function {}, found function {}",
+                            expected.unwrap_func(),
+                            actual.as_wasm_func_type()
+                        )));
+                    }
+
+                    let mut import = func.as_vmfunction_import(store);
+                    // host functions are entered through the trampoline compiled alongside the
+                    // module for this import's signature, which is the function's signature too
+                    if func.is_host(store) {
+                        if let Some(wasm_call) = self.module.wasm_to_array_trampoline(ty) {
+                            import.wasm_call = wasm_call;
+                        }
+                    }
+                    imports.functions.push(import);
+                }
+                (Extern::Table(table), EntityType::Table(ty)) => {
+                    assert!(table.comes_from_same_store(store));
+                    table_ty_matches(ty, table.ty(store), table.size(store))
+                        .map_err(incompatible)?;
+                    imports.tables.push(table.as_vmtable_import(store));
+                }
+                (Extern::Memory(memory), EntityType::Memory(ty)) => {
+                    assert!(memory.comes_from_same_store(store));
+                    memory_ty_matches(ty, memory.ty(store), memory.size(store))
+                        .map_err(incompatible)?;
+                    imports.memories.push(memory.as_vmmemory_import(store));
+                }
+                (Extern::Global(global), EntityType::Global(ty)) => {
+                    assert!(global.comes_from_same_store(store));
+                    global_ty_matches(ty, global.ty(store)).map_err(incompatible)?;
+                    imports.globals.push(global.as_vmglobal_import(store));
+                }
+                _ => unreachable!("import kinds are checked by `Linker::instantiate_pre`"),
+            }
+        }
+
+        // Safety: we have typechecked the imports above.
+        unsafe { Instance::new_unchecked(store, alloc, const_eval, self.module.clone(), imports) }
+    }
+}
+
+/// Checks whether a global of type `actual` can be used to satisfy an import of type `expected`.
+///
+/// Mutable globals must match exactly, while immutable globals may be a subtype of the expected
+/// type since they can only be read.
+fn global_ty_matches(expected: &GlobalDesc, actual: &GlobalDesc) -> Result<(), String> {
+    let content_matches = match (&expected.content_type, &actual.content_type) {
+        (WasmValType::Ref(expected_ref), WasmValType::Ref(actual_ref)) if !expected.mutable => {
+            expected_ref.heap_type == actual_ref.heap_type
+                && (expected_ref.nullable || !actual_ref.nullable)
+        }
+        (expected, actual) => expected == actual,
+    };
+
+    if expected.mutable != actual.mutable || expected.shared != actual.shared || !content_matches {
+        return Err(format!(
+            "expected global {}, found global {}",
+            global_ty_to_string(expected),
+            global_ty_to_string(actual)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks whether a table of type `actual` with `size` elements can be used to satisfy an import
+/// of type `expected`.
+///
+/// Tables can be written to, so their element types must match exactly.
+fn table_ty_matches(expected: &TableDesc, actual: &TableDesc, size: u64) -> Result<(), String> {
+    if expected.element_type != actual.element_type
+        || expected.table64 != actual.table64
+        || expected.shared != actual.shared
+        || !limits_match(expected.minimum, expected.maximum, size, actual.maximum)
+    {
+        let actual = TableDesc {
+            minimum: size,
+            ..actual.clone()
+        };
+        return Err(format!(
+            "expected {}, found {}",
+            TableType::from_desc(expected.clone()),
+            TableType::from_desc(actual)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks whether a memory of type `actual` with `size` pages can be used to satisfy an import of
+/// type `expected`.
+fn memory_ty_matches(expected: &MemoryDesc, actual: &MemoryDesc, size: u64) -> Result<(), String> {
+    if expected.memory64 != actual.memory64
+        || expected.shared != actual.shared
+        || expected.page_size_log2 != actual.page_size_log2
+        || !limits_match(expected.minimum, expected.maximum, size, actual.maximum)
+    {
+        let actual = MemoryDesc {
+            minimum: size,
+            ..actual.clone()
+        };
+        return Err(format!(
+            "expected {}, found {}",
+            MemoryType::from_desc(expected.clone()),
+            MemoryType::from_desc(actual)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that the actual limits lie within the expected ones, where the minimum of a definition
+/// is its current size.
+fn limits_match(
+    expected_min: u64,
+    expected_max: Option<u64>,
+    actual_min: u64,
+    actual_max: Option<u64>,
+) -> bool {
+    actual_min >= expected_min
+        && match (expected_max, actual_max) {
+            (None, _) => true,
+            (Some(expected), Some(actual)) => actual <= expected,
+            (Some(_), None) => false,
+        }
+}
+
+fn global_ty_to_string(ty: &GlobalDesc) -> String {
+    match (ty.shared, ty.mutable) {
+        (true, true) => format!("(shared mut {})", ty.content_type),
+        (true, false) => format!("(shared {})", ty.content_type),
+        (false, true) => format!("(mut {})", ty.content_type),
+        (false, false) => ty.content_type.to_string(),
+    }
+}
+
+fn entity_kind(ty: &EntityType) -> &'static str {
+    match ty {
+        EntityType::Function(_) => "function",
+        EntityType::Table(_) => "table",
+        EntityType::Memory(_) => "memory",
+        EntityType::Global(_) => "global",
+    }
+}
+
+fn extern_kind(ext: &Extern) -> &'static str {
+    match ext {
+        Extern::Func(_) => "function",
+        Extern::Table(_) => "table",
+        Extern::Memory(_) => "memory",
+        Extern::Global(_) => "global",
+    }
+}
@@ -1,29 +1,197 @@
-use crate::runtime::VMMemoryImport;
+use crate::indices::EntityIndex;
+use crate::runtime::{InstanceAllocator, VMMemoryImport};
 use crate::store::Stored;
-use crate::{runtime, Store};
+use crate::translate::{MemoryDesc, TranslatedModule};
+use crate::trap::Trap;
+use crate::{runtime, Error, Instance, Store};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::ptr;
+use core::sync::atomic::Ordering;
+use wasmparser::{MemoryType, WasmFeatures};
 
 /// A WebAssembly linear memory instance.
 #[derive(Debug, Clone, Copy)]
 pub struct Memory(Stored<runtime::ExportedMemory>);
 
 impl Memory {
-    // pub fn new(store: &mut Store, ty: MemoryType) -> crate::Result<Self> {
-    //     todo!()
-    // }
+    /// Creates a new host-defined memory of type `ty`, allocating it through `alloc`.
+    ///
+    /// Host-defined memories can be passed to WebAssembly through imports, e.g. using
+    /// [`Linker::define`][crate::Linker::define]. They live as long as the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ty` is invalid, e.g. because its minimum exceeds its maximum, requires
+    /// WebAssembly features that are disabled in the engine, or if the allocation fails.
+    pub fn new<T>(
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
+        ty: MemoryType,
+    ) -> crate::Result<Self> {
+        let mut required = WasmFeatures::empty();
+        required.set(WasmFeatures::MEMORY64, ty.memory64);
+        required.set(WasmFeatures::THREADS, ty.shared);
+        required.set(WasmFeatures::CUSTOM_PAGE_SIZES, ty.page_size_log2.is_some());
+        store.engine.check_features(required)?;
+
+        let mut desc = MemoryDesc::from_wasmparser(ty, 0)?;
+        // the same limits validation enforces for the memories of modules
+        let index_bits = if desc.memory64 { 64 } else { 32 };
+        let max_pages = (1_u128 << index_bits) >> desc.page_size_log2;
+        let invalid = |message: &str| Error::InvalidWebAssembly {
+            message: message.to_string(),
+            offset: 0,
+        };
+        if u128::from(desc.minimum) > max_pages
+            || desc.maximum.is_some_and(|max| u128::from(max) > max_pages)
+        {
+            return Err(invalid(
+                "memory size must be at most the size of the index space",
+            ));
+        }
+        if desc.maximum.is_some_and(|max| desc.minimum > max) {
+            return Err(invalid("size minimum must not be greater than maximum"));
+        }
+        if desc.shared && desc.maximum.is_none() {
+            return Err(invalid("shared memory must have maximum size"));
+        }
+        store.engine.configure_memory(&mut desc);
+
+        let mut translated = TranslatedModule::default();
+        let index = translated.memories.push(desc);
+        translated
+            .exports
+            .insert(String::new(), EntityIndex::Memory(index));
+
+        let instance = Instance::new_host(store, alloc, translated)?;
+        Ok(instance.get_memory(store, "").unwrap())
+    }
+
     // pub fn ty(&self, _store: &Store) -> &MemoryType {
     //     todo!()
     // }
-    pub(crate) fn as_vmmemory_import(&self, store: &Store) -> VMMemoryImport {
+    /// Returns the current size of this memory in pages.
+    ///
+    /// The page size is determined by the memory's type, see the `custom-page-sizes` proposal.
+    pub fn size<T>(&self, store: &Store<T>) -> u64 {
+        let export = &store[self.0];
+        // Safety: the definition is kept alive by the instance that owns it
+        let byte_size = unsafe { (*export.definition).current_length.load(Ordering::Relaxed) };
+        u64::try_from(byte_size).unwrap() >> export.memory.page_size_log2
+    }
+
+    /// Grows this memory by `delta` pages, returning the old size in pages.
+    ///
+    /// The new pages are zeroed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory could not be grown, e.g. because it would exceed its maximum size.
+    pub fn grow<T>(&self, store: &mut Store<T>, delta: u64) -> crate::Result<u64> {
+        let export = &store[self.0];
+        let (definition, page_size_log2) = (export.definition, export.memory.page_size_log2);
+        let instance = store.get_instance_from_vmctx(export.vmctx);
+
+        let instance = &mut store[instance];
+        let index = instance.memory_index(definition);
+        let old_byte_size = instance
+            .defined_memory_grow(index, delta)
+            .map_err(|_| Error::MemoryGrow { delta })?;
+
+        Ok(u64::try_from(old_byte_size).unwrap() >> page_size_log2)
+    }
+
+    /// Copies `buf.len()` bytes starting at `offset` in this memory into `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Trap::MemoryOutOfBounds`] error if the range lies outside the current size of
+    /// the memory, in which case nothing is copied.
+    pub fn read<T>(&self, store: &Store<T>, offset: usize, buf: &mut [u8]) -> crate::Result<()> {
+        let base = self.checked_base(store, offset, buf.len())?;
+        // Safety: `checked_base` verified the range lies within the memory
+        unsafe {
+            ptr::copy_nonoverlapping(base.add(offset), buf.as_mut_ptr(), buf.len());
+        }
+        Ok(())
+    }
+
+    /// Copies `data` into this memory starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Trap::MemoryOutOfBounds`] error if the range lies outside the current size of
+    /// the memory, in which case nothing is written.
+    pub fn write<T>(&self, store: &mut Store<T>, offset: usize, data: &[u8]) -> crate::Result<()> {
+        let base = self.checked_base(store, offset, data.len())?;
+        // Safety: `checked_base` verified the range lies within the memory
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), base.add(offset), data.len());
+        }
+        Ok(())
+    }
+
+    /// Reads a little-endian `u32` at `offset` in this memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Trap::MemoryOutOfBounds`] error if the value lies outside the current size of
+    /// the memory.
+    pub fn read_u32<T>(&self, store: &Store<T>, offset: usize) -> crate::Result<u32> {
+        let mut buf = [0; 4];
+        self.read(store, offset, &mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Writes `val` as a little-endian `u32` at `offset` in this memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Trap::MemoryOutOfBounds`] error if the value lies outside the current size of
+    /// the memory.
+    pub fn write_u32<T>(&self, store: &mut Store<T>, offset: usize, val: u32) -> crate::Result<()> {
+        self.write(store, offset, &val.to_le_bytes())
+    }
+
+    /// Returns the base pointer of this memory after checking that the `len` bytes starting at
+    /// `offset` lie within its current size.
+    fn checked_base<T>(
+        &self,
+        store: &Store<T>,
+        offset: usize,
+        len: usize,
+    ) -> crate::Result<*mut u8> {
+        // Safety: the definition is kept alive by the instance that owns it
+        let definition = unsafe { &*store[self.0].definition };
+        let current_length = definition.current_length.load(Ordering::Relaxed);
+
+        match offset.checked_add(len) {
+            Some(end) if end <= current_length => Ok(definition.base),
+            _ => Err(Error::Trap {
+                trap: Trap::MemoryOutOfBounds,
+                message: "out of bounds memory access".to_string(),
+                faulting_addr: None,
+                wasm_offset: None,
+            }),
+        }
+    }
+
+    pub(crate) fn ty<'a, T>(&self, store: &'a Store<T>) -> &'a MemoryDesc {
+        &store[self.0].memory
+    }
+
+    pub(crate) fn as_vmmemory_import<T>(&self, store: &Store<T>) -> VMMemoryImport {
         VMMemoryImport {
             from: store[self.0].definition,
             vmctx: store[self.0].vmctx,
         }
     }
-    pub(crate) fn from_vm_export(store: &mut Store, export: runtime::ExportedMemory) -> Self {
+    pub(crate) fn from_vm_export<T>(store: &mut Store<T>, export: runtime::ExportedMemory) -> Self {
         Self(store.push_memory(export))
     }
 
-    pub(crate) fn comes_from_same_store(self, store: &Store) -> bool {
+    pub(crate) fn comes_from_same_store<T>(self, store: &Store<T>) -> bool {
         store.has_memory(self.0)
     }
 }
@@ -1,14 +1,28 @@
-use crate::compile::{CompileInputs, CompiledFunctionInfo};
-use crate::indices::{DefinedFuncIndex, EntityIndex, VMSharedTypeIndex};
-use crate::runtime::CodeMemory;
+use crate::compile::{CompileInputs, CompiledFunctionInfo, FunctionLoc, ImageRelocation};
+use crate::debug::{ModuleDwarf, SourceLoc};
+use crate::indices::{
+    DefinedFuncIndex, EntityIndex, FuncIndex, ModuleInternedTypeIndex, VMSharedTypeIndex,
+};
+use crate::runtime::{CodeMemory, VMWasmCallFunction};
 use crate::runtime::{MmapVec, VMOffsets};
-use crate::translate::{Import, TranslatedModule};
+use crate::translate::{
+    DylinkInfo, EntityType, Import, ModuleTranslation, ModuleTypes, TranslatedModule,
+};
 use crate::type_registry::RuntimeTypeCollection;
-use crate::{Engine, ModuleTranslator};
-use alloc::sync::Arc;
+use crate::types::{ExternType, GlobalType, MemoryType, TableType};
+use crate::{wasm_unsupported, Engine, FuncType, ModuleTranslator, ProfilingStrategy};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
 use core::mem;
+use core::ops::{ControlFlow, Range};
+use core::ptr::NonNull;
 use cranelift_entity::PrimaryMap;
-use wasmparser::Validator;
+use target_lexicon::Triple;
+use wasmparser::{Chunk, Parser, ValidPayload, Validator};
 
 /// A compiled WebAssembly module, ready to be instantiated.
 ///
@@ -27,6 +41,16 @@ struct ModuleInner {
     code: Arc<CodeMemory>,
     type_collection: RuntimeTypeCollection,
     function_info: PrimaryMap<DefinedFuncIndex, CompiledFunctionInfo>,
+    /// Trampolines for calling host functions with the signatures of the module's function
+    /// imports.
+    wasm_to_array_trampolines: BTreeMap<ModuleInternedTypeIndex, FunctionLoc>,
+    /// The module's DWARF sections, only kept if [`Config::debug_info`][crate::Config::debug_info]
+    /// is enabled.
+    dwarf: Option<ModuleDwarf>,
+    /// The function names from the module's name section.
+    func_names: BTreeMap<FuncIndex, String>,
+    /// The target the module's code was compiled for.
+    target: Triple,
 }
 
 impl Module {
@@ -46,6 +70,10 @@ impl Module {
     ///
     /// This will parse, translate and compile the module and is the first step in Wasm execution.
     ///
+    /// If the engine's module cache is enabled (see
+    /// [`Config::module_cache_capacity`][crate::Config::module_cache_capacity]) and the same bytes
+    /// were compiled before, the cached module is returned instead.
+    ///
     /// # Errors
     ///
     /// Returns an error if the WebAssembly module is malformed, or compilation fails.
@@ -58,47 +86,257 @@ impl Module {
         validator: &mut Validator,
         bytes: &[u8],
     ) -> crate::Result<Self> {
-        let (mut translation, types) = ModuleTranslator::new(validator).translate(bytes)?;
+        engine
+            .module_cache()
+            .get_or_insert_with(*validator.features(), bytes, || {
+                Self::compile(engine, validator, bytes, &mut |_, _| {
+                    ControlFlow::Continue(())
+                })
+            })
+    }
+
+    /// Creates a new module from the given WebAssembly bytes, reporting the progress of
+    /// compilation through `progress`.
+    ///
+    /// `progress` is called with the number of finished and the total number of compile jobs after
+    /// each job, where a job compiles a single function or trampoline. Returning
+    /// [`ControlFlow::Break`] aborts compilation. Modules served from the engine's module cache
+    /// (see [`Config::module_cache_capacity`][crate::Config::module_cache_capacity]) aren't
+    /// compiled, so `progress` isn't called for them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WebAssembly module is malformed, or compilation fails. Returns
+    /// [`Error::Cancelled`][crate::Error::Cancelled] if `progress` aborted compilation.
+    pub fn from_bytes_with_progress(
+        engine: &Engine,
+        validator: &mut Validator,
+        bytes: &[u8],
+        mut progress: impl FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> crate::Result<Self> {
+        engine
+            .module_cache()
+            .get_or_insert_with(*validator.features(), bytes, || {
+                Self::compile(engine, validator, bytes, &mut progress)
+            })
+    }
+
+    /// Creates a new module from WebAssembly bytes that arrive in `chunks`, e.g. while the module
+    /// is downloaded over the network.
+    ///
+    /// Every section is parsed and validated as soon as its bytes have arrived, so that parsing
+    /// overlaps with receiving the rest of the module and malformed modules are rejected without
+    /// waiting for the remaining chunks. Function bodies are validated while they are compiled,
+    /// which starts once the stream ended. The result is identical to calling
+    /// [`Module::from_bytes`] with all chunks concatenated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WebAssembly module is malformed, or compilation fails.
+    pub fn from_stream<I>(
+        engine: &Engine,
+        validator: &mut Validator,
+        chunks: I,
+    ) -> crate::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let bytes = Self::receive_stream(validator, chunks);
+        validator.reset();
+        Self::from_bytes(engine, validator, &bytes?)
+    }
+
+    /// Collects the bytes of `chunks`, parsing and validating each section once it arrived.
+    fn receive_stream<I>(validator: &mut Validator, chunks: I) -> crate::Result<Vec<u8>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut chunks = chunks.into_iter();
+        let mut bytes = Vec::new();
+        let mut parsed = 0;
+        let mut eof = false;
+
+        let mut parser = Parser::new(0);
+        parser.set_features(*validator.features());
+
+        loop {
+            match parser.parse(&bytes[parsed..], eof)? {
+                Chunk::NeedMoreData(_) => match chunks.next() {
+                    Some(chunk) => bytes.extend_from_slice(chunk.as_ref()),
+                    None => eof = true,
+                },
+                Chunk::Parsed { consumed, payload } => {
+                    parsed += consumed;
+                    match validator.payload(&payload)? {
+                        ValidPayload::End(_) => break,
+                        ValidPayload::Parser(_) => {
+                            return Err(wasm_unsupported!("component model is unsupported"));
+                        }
+                        ValidPayload::Ok | ValidPayload::Func(..) => {}
+                    }
+                }
+            }
+        }
+
+        // keep anything following the end of the module, so the result matches the one of
+        // `from_bytes` for the concatenated chunks
+        for chunk in chunks {
+            bytes.extend_from_slice(chunk.as_ref());
+        }
+
+        Ok(bytes)
+    }
+
+    fn compile(
+        engine: &Engine,
+        validator: &mut Validator,
+        bytes: &[u8],
+        progress: &mut dyn FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> crate::Result<Self> {
+        let (mut translation, types) = Self::translate(engine, validator, bytes)?;
 
         tracing::debug!("Gathering compile inputs...");
         let function_body_data = mem::take(&mut translation.function_bodies);
         let inputs = CompileInputs::from_module(&translation, &types, function_body_data);
 
         tracing::debug!("Compiling inputs...");
-        let unlinked_outputs = inputs.compile(engine.compiler())?;
+        let unlinked_outputs = inputs.compile(
+            engine.compiler(),
+            engine.config().is_deterministic(),
+            progress,
+        )?;
 
         tracing::debug!("Applying static relocations...");
-        let (code, function_info, (trap_offsets, traps)) =
+        let (code, function_info, wasm_to_array_trampolines, (trap_offsets, traps), relocations) =
             unlinked_outputs.link_and_finish(engine, &translation.module);
 
-        let type_collection = engine.type_registry().register_module_types(engine, types);
+        let type_collection = engine.type_registry().register_module_types(types);
+        let dwarf = engine
+            .config()
+            .is_debug_info()
+            .then(|| ModuleDwarf::new(&translation.debug_info));
 
         tracing::debug!("Allocating new memory map...");
         let vec = MmapVec::from_slice(&code)?;
-        let mut code = CodeMemory::new(vec, trap_offsets, traps);
+        let mut code = CodeMemory::new(vec, trap_offsets, traps, relocations);
         code.publish()?;
         let code = Arc::new(code);
 
-        crate::placeholder::code_registry::register_code(&code);
+        if engine.config().profiling() == ProfilingStrategy::PerfMap {
+            register_perf_map(&code, &translation, &function_info);
+        }
 
-        Ok(Self(Arc::new(ModuleInner {
+        let func_names = translation
+            .debug_info
+            .names
+            .funcs
+            .iter()
+            .map(|(index, name)| (*index, String::from(*name)))
+            .collect();
+
+        let module = Self(Arc::new(ModuleInner {
             offsets: VMOffsets::for_module(
                 engine.compiler().triple().pointer_width().unwrap().bytes(),
                 &translation.module,
             ),
             translated: translation.module,
             function_info,
+            wasm_to_array_trampolines,
             code,
             type_collection,
+            dwarf,
+            func_names,
+            target: engine.target().clone(),
+        }));
+        crate::placeholder::code_registry::register_module(&module, &module.0.code);
+
+        Ok(module)
+    }
+
+    /// Creates a module from `translated`, which must not define or import any functions, e.g. the
+    /// memories and tables defined by the host.
+    ///
+    /// Such modules have no code, so nothing needs to be compiled.
+    pub(crate) fn without_code(
+        engine: &Engine,
+        translated: TranslatedModule,
+    ) -> crate::Result<Self> {
+        debug_assert!(translated.functions.is_empty());
+
+        let mut code = CodeMemory::new(MmapVec::new(), Vec::new(), Vec::new(), Vec::new());
+        code.publish()?;
+
+        Ok(Self(Arc::new(ModuleInner {
+            offsets: VMOffsets::for_module(
+                engine.compiler().triple().pointer_width().unwrap().bytes(),
+                &translated,
+            ),
+            translated,
+            function_info: PrimaryMap::new(),
+            wasm_to_array_trampolines: BTreeMap::new(),
+            code: Arc::new(code),
+            type_collection: engine
+                .type_registry()
+                .register_module_types(ModuleTypes::default()),
+            dwarf: None,
+            func_names: BTreeMap::new(),
+            target: engine.target().clone(),
         })))
     }
 
+    /// Validates and compiles the given WebAssembly bytes, reporting all problems found.
+    ///
+    /// Unlike [`Module::from_bytes`] this doesn't stop at the first error, every function is
+    /// compiled even if a previous one failed so that e.g. all uses of unsupported features are
+    /// reported. Problems in the module structure itself still end translation early since
+    /// later sections can't be interpreted without the earlier ones.
+    ///
+    /// Returns an empty `Vec` if the module is valid and all of its features are supported.
+    pub fn validate_and_report(
+        engine: &Engine,
+        validator: &mut Validator,
+        bytes: &[u8],
+    ) -> Vec<crate::Error> {
+        let (mut translation, types) = match Self::translate(engine, validator, bytes) {
+            Ok(res) => res,
+            Err(err) => return vec![err],
+        };
+
+        let function_body_data = mem::take(&mut translation.function_bodies);
+        CompileInputs::from_module(&translation, &types, function_body_data)
+            .compile_and_report(engine.compiler())
+    }
+
+    /// Translates the module, making sure it doesn't use features that are disabled in the engine.
+    fn translate<'data>(
+        engine: &Engine,
+        validator: &mut Validator,
+        bytes: &'data [u8],
+    ) -> crate::Result<(ModuleTranslation<'data>, ModuleTypes)> {
+        // If `validator` enables features the engine doesn't, validating with it alone would
+        // accept modules that use them, so also validate against the engine's feature set.
+        if !engine.config().features().contains(*validator.features()) {
+            engine.validate_module(bytes)?;
+        }
+
+        let (mut translation, types) = ModuleTranslator::new(validator).translate(bytes)?;
+        engine.check_features(translation.required_features)?;
+
+        for memory in translation.module.memories.values_mut() {
+            engine.configure_memory(memory);
+        }
+
+        Ok((translation, types))
+    }
+
     /// Returns the modules imports.
     pub fn imports(&self) -> impl ExactSizeIterator<Item = &Import> {
         self.0.translated.imports.iter()
     }
 
-    /// Returns the modules exports.
+    /// Returns the modules exports in the order the module declares them.
     pub fn exports(&self) -> impl ExactSizeIterator<Item = (&str, EntityIndex)> + '_ {
         self.0
             .translated
@@ -107,11 +345,241 @@ impl Module {
             .map(|(name, index)| (name.as_str(), *index))
     }
 
+    /// Returns the modules imports as `(module, name, type)` triples.
+    pub fn import_types(&self) -> impl ExactSizeIterator<Item = (&str, &str, ExternType)> + '_ {
+        self.0.translated.imports.iter().map(|import| {
+            let ty = match &import.ty {
+                EntityType::Function(ty) => {
+                    ExternType::Func(self.func_type(ty.unwrap_module_type_index()))
+                }
+                EntityType::Table(desc) => ExternType::Table(TableType::from_desc(desc.clone())),
+                EntityType::Memory(desc) => ExternType::Memory(MemoryType::from_desc(desc.clone())),
+                EntityType::Global(desc) => ExternType::Global(GlobalType::from_desc(desc.clone())),
+            };
+            (import.module.as_str(), import.name.as_str(), ty)
+        })
+    }
+
+    /// Returns the modules exports along with their types, in the order the module declares them.
+    pub fn export_types(&self) -> impl ExactSizeIterator<Item = (&str, ExternType)> + '_ {
+        let translated = &self.0.translated;
+        self.exports().map(move |(name, index)| {
+            let ty = match index {
+                EntityIndex::Function(index) => {
+                    let signature = translated.functions[index].signature;
+                    ExternType::Func(self.func_type(translated.types[signature]))
+                }
+                EntityIndex::Table(index) => {
+                    ExternType::Table(TableType::from_desc(translated.tables[index].clone()))
+                }
+                EntityIndex::Memory(index) => {
+                    ExternType::Memory(MemoryType::from_desc(translated.memories[index].clone()))
+                }
+                EntityIndex::Global(index) => {
+                    ExternType::Global(GlobalType::from_desc(translated.globals[index].clone()))
+                }
+                EntityIndex::Tag(_) => unreachable!("exception handling is not supported"),
+            };
+            (name, ty)
+        })
+    }
+
+    /// Returns the function type at `index` in this module's types.
+    fn func_type(&self, index: ModuleInternedTypeIndex) -> FuncType {
+        let type_collection = &self.0.type_collection;
+        let shared_index = type_collection.lookup_shared_type(index).unwrap();
+        let ty = type_collection.registry().get_type(shared_index).unwrap();
+        FuncType::from_registered_type(ty)
+    }
+
     /// Returns the modules name if present.
     pub fn name(&self) -> Option<&str> {
         self.0.translated.name.as_deref()
     }
 
+    /// Returns the name of the function at `func_index` from the module's name section, if
+    /// present.
+    pub fn function_name(&self, func_index: FuncIndex) -> Option<&str> {
+        self.0.func_names.get(&func_index).map(String::as_str)
+    }
+
+    /// Returns the names of all functions named in the module's name section, ordered by their
+    /// index.
+    pub fn function_names(&self) -> impl ExactSizeIterator<Item = (FuncIndex, &str)> + '_ {
+        self.0
+            .func_names
+            .iter()
+            .map(|(index, name)| (*index, name.as_str()))
+    }
+
+    /// Returns the features the producer of the module declared in its `target_features` custom
+    /// section.
+    ///
+    /// These are the raw feature names (e.g. `simd128` or `bulk-memory`) as they appear in the
+    /// section, including features this crate doesn't recognize.
+    pub fn target_feature_strings(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.0.translated.target_features.iter().map(String::as_str)
+    }
+
+    /// Returns the target the module's code was compiled for, i.e. the target of the engine
+    /// that compiled it.
+    pub fn target(&self) -> &Triple {
+        &self.0.target
+    }
+
+    /// Returns the dynamic linking information of the module if it is a side module, i.e. if it
+    /// has a `dylink.0` custom section. A malformed section is ignored and logged.
+    pub fn dylink_info(&self) -> Option<&DylinkInfo> {
+        self.0.translated.dylink_info.as_ref()
+    }
+
+    /// Returns the index of the module's start function if present.
+    ///
+    /// The start function is called automatically when the module is instantiated.
+    pub fn start_func(&self) -> Option<FuncIndex> {
+        self.0.translated.start
+    }
+
+    /// Returns the offset of `pc` relative to the start of this module's compiled code, or `None`
+    /// if `pc` doesn't point into it.
+    pub fn text_offset(&self, pc: usize) -> Option<usize> {
+        let text = self.0.code.text();
+        pc.checked_sub(text.as_ptr() as usize)
+            .filter(|offset| *offset < text.len())
+    }
+
+    /// Returns the function whose compiled code contains `text_offset`, along with the offset
+    /// relative to the start of that function, or `None` if `text_offset` doesn't point into any
+    /// function, e.g. because it points into a trampoline.
+    pub fn text_offset_to_func(&self, text_offset: usize) -> Option<(FuncIndex, usize)> {
+        self.0
+            .function_info
+            .iter()
+            .find_map(|(def_func_index, info)| {
+                let start = usize::try_from(info.wasm_func_loc.start).unwrap();
+                let len = usize::try_from(info.wasm_func_loc.length).unwrap();
+                let offset = text_offset
+                    .checked_sub(start)
+                    .filter(|offset| *offset < len)?;
+                Some((self.0.translated.func_index(def_func_index), offset))
+            })
+    }
+
+    /// Returns the address range the compiled code of this module is loaded at.
+    ///
+    /// See [`Module::image_layout`] for the sections making up the image and
+    /// [`Module::relocations`] for the fixups a loader has to apply when placing a copy of it
+    /// at a different base address.
+    pub fn image_range(&self) -> Range<usize> {
+        let layout = self.image_layout();
+        layout.text.start..layout.rodata.end
+    }
+
+    /// Returns the address ranges of the sections making up the image of this module.
+    ///
+    /// Constants are emitted alongside the code of each function, so the read-only data section
+    /// is currently always empty and placed right after the text section.
+    pub fn image_layout(&self) -> ImageLayout {
+        let text = self.0.code.text();
+        let start = text.as_ptr() as usize;
+        let end = start + text.len();
+        ImageLayout {
+            text: start..end,
+            rodata: end..end,
+        }
+    }
+
+    /// Returns the absolute relocations that have to be applied when the image of this module is
+    /// loaded at a different base address, see [`Module::image_range`].
+    ///
+    /// Calls between functions and into builtins are resolved relative to each other when the
+    /// module is compiled and builtins, memories and globals are reached through the `VMContext`,
+    /// so only absolute addresses remain. The returned relocations have already been applied for
+    /// the address the image currently lives at. With
+    /// [`Config::position_independent_code`][crate::Config::position_independent_code] enabled
+    /// there are none, so the image can be copied to any base address without load-time fixups.
+    pub fn relocations(&self) -> &[ImageRelocation] {
+        self.0.code.relocations()
+    }
+
+    /// Returns the compiled machine code of this module.
+    ///
+    /// Offsets returned by [`Module::text_offset`] and [`Module::address_map`] are relative to the
+    /// start of this slice.
+    pub fn text(&self) -> &[u8] {
+        self.0.code.text()
+    }
+
+    /// Returns which WebAssembly instruction the compiled code was generated for, e.g. to
+    /// correlate program counters sampled by an external profiler with the original module.
+    ///
+    /// Each item is a pair of an offset into [`Module::text`] and the offset of the WebAssembly
+    /// instruction in the original module. An item covers all code up to the next item, the items
+    /// are sorted by their text offset.
+    ///
+    /// This requires [`Config::debug_info`][crate::Config::debug_info] to be enabled, the map
+    /// is empty otherwise.
+    pub fn address_map(&self) -> impl ExactSizeIterator<Item = (usize, u32)> {
+        let mut map = self
+            .0
+            .function_info
+            .values()
+            .filter_map(|info| Some((info.wasm_func_loc.start, info.debug.as_ref()?)))
+            .flat_map(|(start, debug)| {
+                debug.address_map.iter().filter_map(move |mapping| {
+                    let text_offset = usize::try_from(start + mapping.code_offset).ok()?;
+                    Some((text_offset, mapping.srcloc.file_offset()?))
+                })
+            })
+            .collect::<Vec<_>>();
+        map.sort_unstable_by_key(|(text_offset, _)| *text_offset);
+        map.into_iter()
+    }
+
+    /// Returns the source location of the WebAssembly instruction the compiled code at
+    /// `text_offset` was generated for, see [`Module::text_offset`].
+    ///
+    /// This requires [`Config::debug_info`][crate::Config::debug_info] to be enabled and the
+    /// module to carry a DWARF line number program (`.debug_line`). Returns `None` if either is
+    /// missing, the offset isn't part of a WebAssembly function or the debug info is malformed.
+    pub fn source_location(&self, text_offset: usize) -> Option<SourceLoc> {
+        crate::debug::source_location(self, text_offset)
+            .inspect_err(|err| tracing::debug!("failed to resolve source location: {err}"))
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the disassembled machine code of the function at `func_index`.
+    ///
+    /// Every instruction is annotated with the offset of the WebAssembly instruction it was
+    /// generated for and the relocations that were applied to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no disassembler for the architecture the module was compiled
+    /// for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `func_index` refers to an imported function.
+    #[cfg(feature = "disassemble")]
+    pub fn disassemble(&self, func_index: FuncIndex) -> crate::Result<String> {
+        let def_func_index = self
+            .0
+            .translated
+            .defined_func_index(func_index)
+            .expect("imported functions have no code to disassemble");
+
+        crate::disassemble::disassemble_function(self, def_func_index)
+    }
+
+    /// Returns the symbol name of the function at `func_index`, including its name from the
+    /// module's name section if present.
+    #[cfg(feature = "disassemble")]
+    pub(crate) fn func_symbol(&self, func_index: FuncIndex) -> String {
+        func_symbol(func_index, self.function_name(func_index))
+    }
+
     pub(crate) fn get_export(&self, name: &str) -> Option<EntityIndex> {
         self.0.translated.exports.get(name).copied()
     }
@@ -134,4 +602,384 @@ impl Module {
     pub(crate) fn function_info(&self) -> &PrimaryMap<DefinedFuncIndex, CompiledFunctionInfo> {
         &self.0.function_info
     }
+    /// Returns the trampoline for calling a host function of type `ty` from WebAssembly, if `ty`
+    /// is the signature of one of the module's function imports.
+    pub(crate) fn wasm_to_array_trampoline(
+        &self,
+        ty: ModuleInternedTypeIndex,
+    ) -> Option<NonNull<VMWasmCallFunction>> {
+        let loc = self.0.wasm_to_array_trampolines.get(&ty)?;
+        NonNull::new(self.code().resolve_function_loc(*loc) as *mut VMWasmCallFunction)
+    }
+
+    pub(crate) fn downgrade(&self) -> WeakModule {
+        WeakModule(Arc::downgrade(&self.0))
+    }
+
+    pub(crate) fn dwarf(&self) -> Option<&ModuleDwarf> {
+        self.0.dwarf.as_ref()
+    }
+}
+
+/// The address ranges of the sections making up the image of a [`Module`], see
+/// [`Module::image_layout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageLayout {
+    /// The executable code.
+    pub text: Range<usize>,
+    /// The read-only data referenced by the code.
+    pub rodata: Range<usize>,
+}
+
+/// Returns the symbol name used for the function at `func_index` in profiles and disassembly.
+impl Drop for ModuleInner {
+    fn drop(&mut self) {
+        crate::placeholder::code_registry::unregister_code(&self.code);
+    }
+}
+
+/// A reference to a [`Module`] that doesn't keep it alive.
+#[derive(Debug, Clone)]
+pub(crate) struct WeakModule(Weak<ModuleInner>);
+
+impl WeakModule {
+    pub(crate) fn upgrade(&self) -> Option<Module> {
+        self.0.upgrade().map(Module)
+    }
+}
+
+fn func_symbol(func_index: FuncIndex, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("wasm[0]::function[{}]::{name}", func_index.as_u32()),
+        None => format!("wasm[0]::function[{}]", func_index.as_u32()),
+    }
+}
+
+/// Makes the functions of a freshly loaded module visible to `perf`.
+fn register_perf_map(
+    code: &CodeMemory,
+    translation: &ModuleTranslation,
+    function_info: &PrimaryMap<DefinedFuncIndex, CompiledFunctionInfo>,
+) {
+    let funcs = function_info.iter().flat_map(|(def_func_index, info)| {
+        let func_index = translation.module.func_index(def_func_index);
+        let name = func_symbol(
+            func_index,
+            translation.debug_info.names.funcs.get(&func_index).copied(),
+        );
+        let trampoline = info.host_to_wasm_trampoline.map(|loc| {
+            let name = format!("wasm[0]::array_to_wasm_trampoline[{}]", func_index.as_u32());
+            (name, loc)
+        });
+
+        core::iter::once((name, info.wasm_func_loc)).chain(trampoline)
+    });
+
+    crate::placeholder::perf_map::register_functions(code, funcs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::ImageRelocationKind;
+    use crate::{Config, ConstExprEvaluator, Linker, PlaceholderAllocatorDontUse, Store, Val};
+
+    /// Moves the module's code to a new address, like a loader that relocates code at load time.
+    fn move_code(module: &mut Module) {
+        // the registry refers to the module, it has to be the only reference for us to modify it
+        crate::placeholder::code_registry::unregister_code(&module.0.code);
+        let inner = Arc::get_mut(&mut module.0).unwrap();
+
+        let mut code = inner.code.try_clone().unwrap();
+        code.publish().unwrap();
+        let code = Arc::new(code);
+
+        assert_ne!(code.text().as_ptr(), inner.code.text().as_ptr());
+        inner.code = code.clone();
+        crate::placeholder::code_registry::register_module(module, &code);
+    }
+
+    #[test_log::test]
+    fn position_independent_code() {
+        let str = r#"
+        (module
+            (memory 1)
+            (func $fib (param i32) (result i32)
+                (if (result i32) (i32.lt_u (local.get 0) (i32.const 2))
+                    (then (local.get 0))
+                    (else
+                        (i32.add
+                            (call $fib (i32.sub (local.get 0) (i32.const 1)))
+                            (call $fib (i32.sub (local.get 0) (i32.const 2)))
+                        )
+                    )
+                )
+            )
+            (func (export "run") (param i32) (result i32)
+                (drop (memory.grow (i32.const 1)))
+                (i32.add (call $fib (local.get 0)) (memory.size))
+            )
+        )"#;
+
+        let mut config = Config::new();
+        config.position_independent_code(true);
+        let engine = Engine::new(&config);
+        let mut validator = Validator::new();
+        let linker = Linker::new(&engine);
+        let mut const_eval = ConstExprEvaluator::default();
+        let mut run = |module: &Module| {
+            let mut store = Store::new(&engine, ());
+            let instance = linker
+                .instantiate(
+                    &mut store,
+                    Arc::new(PlaceholderAllocatorDontUse),
+                    &mut const_eval,
+                    module,
+                )
+                .unwrap();
+
+            let func = instance.get_func(&mut store, "run").unwrap();
+            let mut results = [Val::I32(0)];
+            func.call(&mut store, &[Val::I32(10)], &mut results)
+                .unwrap();
+            results[0].unwrap_i32()
+        };
+
+        let mut module = Module::from_str(&engine, &mut validator, str).unwrap();
+        let image = module.image_range();
+        assert!(module.relocations().is_empty());
+        assert_eq!(run(&module), 55 + 2);
+
+        // the same image runs unmodified at a different base address
+        move_code(&mut module);
+        let moved = module.image_range();
+        assert_ne!(moved.start, image.start);
+        assert_eq!(moved.len(), image.len());
+        assert_eq!(run(&module), 55 + 2);
+    }
+
+    #[test_log::test]
+    fn absolute_relocations() {
+        let str = r#"
+        (module
+            (table 2 funcref)
+            (elem (i32.const 0) $double $triple)
+            (func $double (param i32) (result i32)
+                (i32.mul (local.get 0) (i32.const 2))
+            )
+            (func $triple (param i32) (result i32)
+                (i32.mul (local.get 0) (i32.const 3))
+            )
+            (func (export "run") (param i32) (result i32)
+                (i32.add
+                    (call $double (local.get 0))
+                    (call_indirect (param i32) (result i32) (local.get 0) (i32.const 1))
+                )
+            )
+        )"#;
+
+        let engine = Engine::new(Config::new().position_independent_code(false));
+        let mut validator = Validator::new();
+        let mut module = Module::from_str(&engine, &mut validator, str).unwrap();
+
+        let check = |module: &Module| {
+            let layout = module.image_layout();
+            assert_eq!(module.image_range(), layout.text.start..layout.rodata.end);
+            assert!(layout.rodata.is_empty());
+
+            let image = module.image_range();
+            for reloc in module.relocations() {
+                assert!(reloc.offset + reloc.kind.size() <= image.len());
+                assert!(reloc.target < image.len());
+
+                // the relocations have been applied for the current base address
+                let field = &module.text()[reloc.offset..reloc.offset + reloc.kind.size()];
+                let value = match reloc.kind {
+                    ImageRelocationKind::Abs4 => {
+                        usize::try_from(u32::from_ne_bytes(field.try_into().unwrap())).unwrap()
+                    }
+                    ImageRelocationKind::Abs8 => {
+                        usize::try_from(u64::from_ne_bytes(field.try_into().unwrap())).unwrap()
+                    }
+                };
+                assert_eq!(value, image.start + reloc.target);
+            }
+
+            let mut store = Store::new(&engine, ());
+            let instance = Linker::new(&engine)
+                .instantiate(
+                    &mut store,
+                    Arc::new(PlaceholderAllocatorDontUse),
+                    &mut ConstExprEvaluator::default(),
+                    module,
+                )
+                .unwrap();
+            let func = instance.get_func(&mut store, "run").unwrap();
+            let mut results = [Val::I32(0)];
+            func.call(&mut store, &[Val::I32(7)], &mut results).unwrap();
+            assert_eq!(results[0].unwrap_i32(), 14 + 21);
+        };
+
+        check(&module);
+        let relocations = module.relocations().to_vec();
+
+        // moving the image re-applies the same relocations for the new base address
+        move_code(&mut module);
+        assert_eq!(module.relocations(), relocations.as_slice());
+        check(&module);
+    }
+
+    #[test_log::test]
+    fn deterministic() {
+        let str = r#"
+        (module
+            (memory 1)
+            (func $fib (export "fib") (param i32) (result i32)
+                (if (result i32) (i32.lt_u (local.get 0) (i32.const 2))
+                    (then (local.get 0))
+                    (else
+                        (i32.add
+                            (call $fib (i32.sub (local.get 0) (i32.const 1)))
+                            (call $fib (i32.sub (local.get 0) (i32.const 2)))
+                        )
+                    )
+                )
+            )
+            (func (export "grow") (result i32)
+                (memory.grow (i32.const 1))
+            )
+            (func (export "size") (result i32)
+                (memory.size)
+            )
+        )"#;
+
+        let mut config = Config::new();
+        config.deterministic(true);
+
+        // compile in separate engines, so nothing but the configuration is shared
+        let compile = || {
+            let engine = Engine::new(&config);
+            let module = Module::from_str(&engine, &mut Validator::new(), str).unwrap();
+            module.code().text().to_vec()
+        };
+
+        let first = compile();
+        let second = compile();
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test_log::test]
+    fn perf_map() {
+        let str = r#"
+        (module
+            (func $add (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1))
+            )
+            (func (export "sub") (param i32 i32) (result i32)
+                (i32.sub (local.get 0) (local.get 1))
+            )
+        )"#;
+
+        let mut config = Config::new();
+        config.profiling_strategy(ProfilingStrategy::PerfMap);
+        let engine = Engine::new(&config);
+        let mut validator = Validator::new();
+
+        let module = Module::from_str(&engine, &mut validator, str).unwrap();
+
+        // other tests may be writing to the same file concurrently, so only look for our entries
+        let path = crate::placeholder::perf_map::perf_map_path();
+        let perf_map = std::fs::read_to_string(path).unwrap();
+
+        for (def_func_index, info) in module.function_info() {
+            let addr = module.code().resolve_function_loc(info.wasm_func_loc);
+            let name = if def_func_index.as_u32() == 0 {
+                "wasm[0]::function[0]::add"
+            } else {
+                "wasm[0]::function[1]"
+            };
+
+            // the entry records the final address of the function, not its offset in the object
+            let entry = format!("{addr:x} {:x} {name}", info.wasm_func_loc.length);
+            assert!(
+                perf_map.lines().any(|line| line == entry),
+                "missing perf map entry {entry:?}"
+            );
+        }
+    }
+
+    #[test_log::test]
+    fn code_registry_lookup() {
+        let engine = Engine::default();
+        let mut validator = Validator::new();
+
+        let a = Module::from_str(
+            &engine,
+            &mut validator,
+            r#"
+            (module
+                (import "env" "f" (func))
+                (func (result i32) (i32.const 1))
+                (func (result i32) (i32.const 2))
+            )"#,
+        )
+        .unwrap();
+        let b = Module::from_str(
+            &engine,
+            &mut validator,
+            r#"
+            (module
+                (func (result i64) (i64.const 3))
+            )"#,
+        )
+        .unwrap();
+
+        for module in [&a, &b] {
+            for (def_func_index, info) in module.function_info() {
+                let func_index = module.translated().func_index(def_func_index);
+                let addr = module.code().resolve_function_loc(info.wasm_func_loc);
+
+                for offset in [0, usize::try_from(info.wasm_func_loc.length).unwrap() - 1] {
+                    let (found, text_offset) =
+                        crate::placeholder::code_registry::lookup_module(addr + offset).unwrap();
+                    assert!(Arc::ptr_eq(&found.0, &module.0));
+                    assert_eq!(
+                        found.text_offset_to_func(text_offset),
+                        Some((func_index, offset))
+                    );
+                }
+            }
+        }
+        assert_eq!(a.function_info().len(), 2);
+
+        // pointers outside of any code don't resolve
+        let local = 0_u8;
+        assert!(crate::placeholder::code_registry::lookup_module(
+            core::ptr::from_ref(&local) as usize
+        )
+        .is_none());
+    }
+
+    #[test_log::test]
+    fn cached_modules_dont_keep_the_engine_alive() {
+        let mut config = Config::new();
+        config.module_cache_capacity(4);
+        let engine = Engine::new(&config);
+        let mut validator = Validator::new();
+
+        let module = Module::from_str(
+            &engine,
+            &mut validator,
+            "(module (func (export \"f\") (param i32) (result i32) (local.get 0)))",
+        )
+        .unwrap();
+        assert_eq!(module.exports().count(), 1);
+        let registry = Arc::downgrade(engine.type_registry());
+
+        // the module stays in the cache, but the engine and with it its registry go away
+        drop(module);
+        drop(engine);
+        assert!(registry.upgrade().is_none());
+    }
 }
@@ -0,0 +1,122 @@
+use crate::Module;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::sync::atomic::{AtomicU64, Ordering};
+use hashbrown::DefaultHashBuilder;
+use spin::lock_api::Mutex;
+use wasmparser::WasmFeatures;
+
+/// An in-memory least-recently-used cache of compiled modules, keyed by their bytes.
+///
+/// All compiler settings come from the engine owning the cache, so only the features of the
+/// validator a module was created with need to be part of the key in addition to its bytes.
+pub(crate) struct ModuleCache {
+    capacity: usize,
+    hasher: DefaultHashBuilder,
+    /// The cached modules, the most recently used one last.
+    entries: Mutex<Vec<Entry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct Entry {
+    hash: u64,
+    features: WasmFeatures,
+    // The full bytes are kept so that hash collisions can't return the wrong module.
+    bytes: Box<[u8]>,
+    module: Module,
+}
+
+impl fmt::Debug for ModuleCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModuleCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.entries.lock().len())
+            .field("hits", &self.hits)
+            .field("misses", &self.misses)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ModuleCache {
+    /// Creates a new cache holding up to `capacity` modules, a capacity of `0` disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hasher: DefaultHashBuilder::default(),
+            entries: Mutex::new(Vec::with_capacity(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the cached module for `bytes` validated with `features`, or creates it through
+    /// `create` and caches it.
+    pub fn get_or_insert_with(
+        &self,
+        features: WasmFeatures,
+        bytes: &[u8],
+        create: impl FnOnce() -> crate::Result<Module>,
+    ) -> crate::Result<Module> {
+        if self.capacity == 0 {
+            return create();
+        }
+
+        let hash = self.hasher.hash_one((features.bits(), bytes));
+        {
+            let mut entries = self.entries.lock();
+            if let Some(pos) = entries
+                .iter()
+                .position(|e| e.hash == hash && e.features == features && *e.bytes == *bytes)
+            {
+                // move the entry to the back, marking it as the most recently used
+                let entry = entries.remove(pos);
+                let module = entry.module.clone();
+                entries.push(entry);
+
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(module);
+            }
+        }
+
+        // Compile without holding the lock, so other threads can still use the cache. Two threads
+        // compiling the same module at the same time both miss, which is harmless.
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let module = create()?;
+
+        let mut entries = self.entries.lock();
+        if !entries
+            .iter()
+            .any(|e| e.hash == hash && e.features == features && *e.bytes == *bytes)
+        {
+            if entries.len() >= self.capacity {
+                entries.remove(0);
+            }
+            entries.push(Entry {
+                hash,
+                features,
+                bytes: bytes.into(),
+                module: module.clone(),
+            });
+        }
+
+        Ok(module)
+    }
+
+    /// Removes all modules from the cache.
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+
+    /// Returns the number of lookups that were served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of lookups that had to compile the module.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
@@ -1,5 +1,7 @@
 //! Architecture specific functionality. Only uses are obtaining the host stack pointer before entering
-//! WASM and frame traversal utilities for backtracing.
+//! WASM, switching to embedder-provided stacks and frame traversal utilities for backtracing.
+
+use core::ptr;
 
 cfg_if::cfg_if! {
     if #[cfg(target_arch = "aarch64")] {
@@ -18,6 +20,22 @@ cfg_if::cfg_if! {
             stack_pointer
         }
 
+        /// Calls `f` with `data` as its argument on the stack whose highest address is `stack_top`.
+        unsafe fn switch_stack(stack_top: usize, data: *mut u8, f: unsafe extern "C" fn(*mut u8)) {
+            core::arch::asm!(
+                // x20 is callee-saved, so it survives the call
+                "mov x20, sp",
+                "mov sp, {stack_top}",
+                "blr {f}",
+                "mov sp, x20",
+                stack_top = in(reg) stack_top,
+                f = in(reg) f,
+                in("x0") data,
+                out("x20") _,
+                clobber_abi("C"),
+            );
+        }
+
         /// Retrieves the next older program counter and stack pointer from the current frame pointer.
         /// The aarch64 calling conventions save the return PC one i64 above the FP and
         /// the previous FP is pointed to by the current FP:
@@ -55,6 +73,9 @@ cfg_if::cfg_if! {
         /// The current frame pointer points to the next older frame pointer.
         pub const NEXT_OLDER_FP_FROM_FP_OFFSET: usize = 0;
 
+        /// The hardware encoding of the stack pointer register.
+        pub const STACK_POINTER_REG: usize = 31;
+
         /// Asserts that the frame pointer is sufficiently aligned for the platform.
         pub fn assert_fp_is_aligned(_fp: usize) {
             // From AAPCS64, section 6.2.3 The Frame Pointer[0]:
@@ -82,6 +103,22 @@ cfg_if::cfg_if! {
             stack_pointer
         }
 
+        /// Calls `f` with `data` as its argument on the stack whose highest address is `stack_top`.
+        unsafe fn switch_stack(stack_top: usize, data: *mut u8, f: unsafe extern "C" fn(*mut u8)) {
+            core::arch::asm!(
+                // r12 is callee-saved, so it survives the call
+                "mov r12, rsp",
+                "mov rsp, {stack_top}",
+                "call {f}",
+                "mov rsp, r12",
+                stack_top = in(reg) stack_top,
+                f = in(reg) f,
+                in("rdi") data,
+                out("r12") _,
+                clobber_abi("C"),
+            );
+        }
+
         /// Retrieves the next older program counter and stack pointer from the current frame pointer.
         pub unsafe fn get_next_older_pc_from_fp(fp: usize) -> usize {
             // The calling convention always pushes the return pointer (aka the PC of
@@ -92,6 +129,9 @@ cfg_if::cfg_if! {
         /// The current frame pointer points to the next older frame pointer.
         pub const NEXT_OLDER_FP_FROM_FP_OFFSET: usize = 0;
 
+        /// The hardware encoding of the stack pointer register.
+        pub const STACK_POINTER_REG: usize = 4;
+
         /// Asserts that the frame pointer is sufficiently aligned for the platform.
         pub fn assert_fp_is_aligned(fp: usize) {
             assert_eq!(fp % 16, 0, "stack should always be aligned to 16");
@@ -111,6 +151,22 @@ cfg_if::cfg_if! {
             stack_pointer
         }
 
+        /// Calls `f` with `data` as its argument on the stack whose highest address is `stack_top`.
+        unsafe fn switch_stack(stack_top: usize, data: *mut u8, f: unsafe extern "C" fn(*mut u8)) {
+            core::arch::asm!(
+                // s2 is callee-saved, so it survives the call
+                "mv s2, sp",
+                "mv sp, {stack_top}",
+                "jalr {f}",
+                "mv sp, s2",
+                stack_top = in(reg) stack_top,
+                f = in(reg) f,
+                in("a0") data,
+                out("s2") _,
+                clobber_abi("C"),
+            );
+        }
+
         /// Retrieves the next older program counter and stack pointer from the current frame pointer.
         pub unsafe fn get_next_older_pc_from_fp(fp: usize) -> usize {
             *(fp as *mut usize).offset(1)
@@ -119,6 +175,9 @@ cfg_if::cfg_if! {
         // The current frame pointer points to the next older frame pointer.
         pub const NEXT_OLDER_FP_FROM_FP_OFFSET: usize = 0;
 
+        /// The hardware encoding of the stack pointer register.
+        pub const STACK_POINTER_REG: usize = 2;
+
         /// Asserts that the frame pointer is sufficiently aligned for the platform.
         pub fn assert_fp_is_aligned(fp: usize) {
             assert_eq!(fp % 16, 0, "stack should always be aligned to 16");
@@ -130,6 +189,11 @@ cfg_if::cfg_if! {
             psm::stack_pointer() as usize
         }
 
+        /// Calls `f` with `data` as its argument on the stack whose highest address is `stack_top`.
+        unsafe fn switch_stack(_stack_top: usize, _data: *mut u8, _f: unsafe extern "C" fn(*mut u8)) {
+            unreachable!("callers check `SUPPORTS_STACK_SWITCHING` before switching stacks")
+        }
+
         /// Retrieves the next older program counter and stack pointer from the current frame pointer.
         pub unsafe fn get_next_older_pc_from_fp(fp: usize) -> usize {
             // The next older PC can be found in register %r14 at function entry, which
@@ -142,6 +206,9 @@ cfg_if::cfg_if! {
         /// by the current "FP".
         pub const NEXT_OLDER_FP_FROM_FP_OFFSET: usize = 0;
 
+        /// The hardware encoding of the stack pointer register.
+        pub const STACK_POINTER_REG: usize = 15;
+
         /// Asserts that the frame pointer is sufficiently aligned for the platform.
         pub fn assert_fp_is_aligned(fp: usize) {
             assert_eq!(fp % 8, 0, "stack should always be aligned to 8");
@@ -150,3 +217,44 @@ cfg_if::cfg_if! {
         compile_error!("unsupported target architecture");
     }
 }
+
+/// Whether [`on_stack`] can switch stacks on the host architecture.
+pub const SUPPORTS_STACK_SWITCHING: bool = !cfg!(target_arch = "s390x");
+
+/// Returns an error if [`on_stack`] can't switch stacks on the host architecture.
+///
+/// # Errors
+///
+/// Returns [`Error::Unsupported`][crate::Error::Unsupported] on architectures without stack
+/// switching support.
+pub fn ensure_stack_switching() -> crate::Result<()> {
+    if SUPPORTS_STACK_SWITCHING {
+        Ok(())
+    } else {
+        Err(crate::wasm_unsupported!(
+            "switching stacks is not supported on {}",
+            target_lexicon::HOST.architecture
+        ))
+    }
+}
+
+/// Runs `f` on the stack whose highest address is `stack_top`, returning once it completes.
+///
+/// Must only be called if [`SUPPORTS_STACK_SWITCHING`] is set.
+///
+/// # Safety
+///
+/// `stack_top` must be 16-byte aligned and point to the end of a region of memory that is valid
+/// for reads and writes, unused by anything else and large enough for everything `f` does.
+pub unsafe fn on_stack<F: FnOnce()>(stack_top: usize, f: F) {
+    unsafe extern "C" fn trampoline<F: FnOnce()>(data: *mut u8) {
+        // Safety: `data` points to the `Option<F>` in `on_stack` below, which outlives this call.
+        let f = unsafe { (*data.cast::<Option<F>>()).take().unwrap() };
+        f();
+    }
+
+    debug_assert_eq!(stack_top % 16, 0, "stack should always be aligned to 16");
+
+    let mut f = Some(f);
+    switch_stack(stack_top, ptr::from_mut(&mut f).cast(), trampoline::<F>);
+}
@@ -4,7 +4,9 @@
 //! faulting pc belongs to and by extension be able to retrieve trap and debugging information related
 //! to it.
 
+use crate::module::WeakModule;
 use crate::runtime::CodeMemory;
+use crate::Module;
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use spin::lock_api::RwLock;
@@ -15,16 +17,37 @@ fn global_code() -> &'static RwLock<GlobalRegistry> {
     GLOBAL_CODE.call_once(Default::default)
 }
 
-type GlobalRegistry = BTreeMap<usize, (usize, Arc<CodeMemory>)>;
+/// Registered regions of code, keyed by their last byte.
+type GlobalRegistry = BTreeMap<usize, RegisteredCode>;
+
+struct RegisteredCode {
+    start: usize,
+    code: Arc<CodeMemory>,
+    /// The module the code belongs to, if any. Code such as host trampolines isn't owned by a module.
+    module: Option<WeakModule>,
+}
 
 /// Find which registered region of code contains the given program counter, and
 /// what offset that PC is within that module's code.
 pub fn lookup_code(pc: usize) -> Option<(Arc<CodeMemory>, usize)> {
     let all_modules = global_code().read();
 
-    let (_end, (start, module)) = all_modules.range(pc..).next()?;
-    let text_offset = pc.checked_sub(*start)?;
-    Some((module.clone(), text_offset))
+    let (_end, registered) = all_modules.range(pc..).next()?;
+    let text_offset = pc.checked_sub(registered.start)?;
+    Some((registered.code.clone(), text_offset))
+}
+
+/// Find which module's code contains the given program counter, and what offset that PC is within
+/// that module's code.
+///
+/// Use [`Module::text_offset_to_func`] to further resolve the offset to a function.
+pub fn lookup_module(pc: usize) -> Option<(Module, usize)> {
+    let all_modules = global_code().read();
+
+    let (_end, registered) = all_modules.range(pc..).next()?;
+    let text_offset = pc.checked_sub(registered.start)?;
+    let module = registered.module.as_ref()?.upgrade()?;
+    Some((module, text_offset))
 }
 
 /// Registers a new region of code.
@@ -35,12 +58,43 @@ pub fn lookup_code(pc: usize) -> Option<(Arc<CodeMemory>, usize)> {
 /// This is used by trap handling to determine which region of code a faulting
 /// address.
 pub fn register_code(code: &Arc<CodeMemory>) {
+    register(code, None);
+}
+
+/// Registers the code of `module`, so it can be found through [`lookup_module`].
+///
+/// The same rules as for [`register_code`] apply, the registry doesn't keep the module itself
+/// alive though.
+pub fn register_module(module: &Module, code: &Arc<CodeMemory>) {
+    register(code, Some(module.downgrade()));
+}
+
+fn register(code: &Arc<CodeMemory>, module: Option<WeakModule>) {
     let text = code.text();
     if text.is_empty() {
         return;
     }
     let start = text.as_ptr() as usize;
     let end = start + text.len() - 1;
-    let prev = global_code().write().insert(end, (start, code.clone()));
+    let prev = global_code().write().insert(
+        end,
+        RegisteredCode {
+            start,
+            code: code.clone(),
+            module,
+        },
+    );
     assert!(prev.is_none());
 }
+
+/// Unregisters a region of code previously registered through [`register_code`] or
+/// [`register_module`].
+pub fn unregister_code(code: &Arc<CodeMemory>) {
+    let text = code.text();
+    if text.is_empty() {
+        return;
+    }
+    let end = text.as_ptr() as usize + text.len() - 1;
+    let registered = global_code().write().remove(&end);
+    assert!(registered.is_some_and(|registered| Arc::ptr_eq(&registered.code, code)));
+}
@@ -0,0 +1,200 @@
+//! Minimal stackful coroutines ("fibers") built on top of `setjmp`/`longjmp`.
+//!
+//! A fiber runs a closure on its own stack and can suspend itself at any point, handing control
+//! back to whoever resumed it. This is what allows async calls to yield in the middle of
+//! WebAssembly execution and continue later.
+
+use crate::placeholder::mmap::Mmap;
+use crate::placeholder::setjmp::{jmp_buf, longjmp, setjmp};
+use crate::placeholder::trap_handling::{CallThreadState, TLS};
+use crate::placeholder::{arch, host_page_size};
+use crate::trap::Trap;
+use crate::StackRegion;
+use alloc::boxed::Box;
+use core::cell::Cell;
+use core::{mem, ptr};
+
+/// The stack a [`Fiber`] runs on, with an inaccessible guard page below it.
+#[derive(Debug)]
+pub struct FiberStack {
+    region: StackRegion,
+    _mmap: Mmap,
+}
+
+impl FiberStack {
+    /// Allocates a new stack of at least `size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host architecture can't switch stacks or the stack can't be
+    /// mapped.
+    pub fn new(size: usize) -> crate::Result<Self> {
+        arch::ensure_stack_switching()?;
+
+        let page_size = host_page_size().get();
+        let size = size.next_multiple_of(page_size);
+
+        let mut mmap = Mmap::with_reserve(page_size + size)?;
+        mmap.make_accessible(page_size, size)?;
+
+        let base = mmap.as_mut_ptr();
+        // Safety: both ranges lie within the mapping, which lives as long as the region
+        let region = unsafe {
+            StackRegion::new(
+                base.add(page_size)..base.add(page_size + size),
+                base..base.add(page_size),
+            )?
+        };
+
+        Ok(Self {
+            region,
+            _mmap: mmap,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    NotStarted,
+    Running,
+    Suspended,
+    Done,
+}
+
+/// The parts of a fiber that are accessed from code running on it.
+struct FiberState {
+    stack: StackRegion,
+    state: Cell<State>,
+    cancelled: Cell<bool>,
+    /// Where the fiber jumps to when it suspends or completes.
+    resume_buf: Cell<jmp_buf>,
+    /// Where `resume` jumps to when continuing a suspended fiber.
+    suspend_buf: Cell<jmp_buf>,
+    /// The trap handling state of the fiber while it isn't running.
+    trap_state: Cell<Option<*const CallThreadState>>,
+}
+
+/// The fiber currently running on this thread, if any.
+#[thread_local]
+static CURRENT_FIBER: Cell<*const FiberState> = Cell::new(ptr::null());
+
+/// A closure running on its own stack that can suspend itself through [`suspend`].
+pub struct Fiber<'a> {
+    state: Box<FiberState>,
+    func: Option<Box<dyn FnOnce() + 'a>>,
+}
+
+impl<'a> Fiber<'a> {
+    /// Creates a new fiber that runs `func` on `stack` once resumed.
+    pub fn new(stack: &'a FiberStack, func: impl FnOnce() + 'a) -> Self {
+        Self {
+            state: Box::new(FiberState {
+                stack: stack.region.clone(),
+                state: Cell::new(State::NotStarted),
+                cancelled: Cell::new(false),
+                // Safety: `jmp_buf` is plain data, it is always written by `setjmp` before use
+                resume_buf: Cell::new(unsafe { mem::zeroed() }),
+                // Safety: see above
+                suspend_buf: Cell::new(unsafe { mem::zeroed() }),
+                trap_state: Cell::new(None),
+            }),
+            func: Some(Box::new(func)),
+        }
+    }
+
+    /// Runs the fiber until it either suspends or completes.
+    ///
+    /// Returns `true` if the fiber completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fiber already completed.
+    pub fn resume(&mut self) -> bool {
+        let state = &*self.state;
+        assert_ne!(state.state.get(), State::Done, "fiber already completed");
+
+        let prev_fiber = CURRENT_FIBER.replace(ptr::from_ref(state));
+        let host_trap_state = TLS.replace(state.trap_state.get());
+
+        // Safety: `setjmp` returns a second time once the fiber suspends or completes by jumping
+        // to `resume_buf`. Both buffers only ever refer to frames that are still live: ours until
+        // this function returns and the fiber's while it is suspended.
+        unsafe {
+            if setjmp(state.resume_buf.as_ptr().cast()) == 0 {
+                match state.state.replace(State::Running) {
+                    State::NotStarted => {
+                        let func = self.func.take().unwrap();
+                        arch::on_stack(state.stack.top(), move || {
+                            func();
+                            state.state.set(State::Done);
+                            longjmp(state.resume_buf.as_ptr().cast(), 1);
+                        });
+                        unreachable!("fibers complete by jumping to `resume_buf`");
+                    }
+                    State::Suspended => longjmp(state.suspend_buf.as_ptr().cast(), 1),
+                    State::Running | State::Done => unreachable!(),
+                }
+            }
+        }
+
+        state.trap_state.set(TLS.replace(host_trap_state));
+        CURRENT_FIBER.set(prev_fiber);
+
+        state.state.get() == State::Done
+    }
+
+    /// Makes a suspended fiber run to completion, with [`suspend`] returning an error on it.
+    pub fn cancel(&mut self) {
+        if self.state.state.get() == State::Suspended {
+            self.state.cancelled.set(true);
+            let done = self.resume();
+            debug_assert!(done, "fiber suspended again after being cancelled");
+        }
+    }
+}
+
+impl Drop for Fiber<'_> {
+    fn drop(&mut self) {
+        // The stack of a suspended fiber might hold references into the closure, so we can only
+        // free it once the fiber completed.
+        self.cancel();
+    }
+}
+
+/// Returns the stack of the fiber currently running on this thread, if any.
+pub fn current_stack() -> Option<StackRegion> {
+    // Safety: `CURRENT_FIBER` is only ever set to a live fiber while it is running
+    let state = unsafe { CURRENT_FIBER.get().as_ref()? };
+    Some(state.stack.clone())
+}
+
+/// Suspends the fiber currently running on this thread, returning once it is resumed.
+///
+/// # Errors
+///
+/// Returns [`Trap::Interrupt`] if called outside of a fiber or if the fiber is cancelled, in
+/// which case it should wind down as quickly as possible.
+pub fn suspend() -> Result<(), Trap> {
+    // Safety: `CURRENT_FIBER` is only ever set to a live fiber while it is running
+    let Some(state) = (unsafe { CURRENT_FIBER.get().as_ref() }) else {
+        return Err(Trap::Interrupt);
+    };
+    if state.cancelled.get() {
+        return Err(Trap::Interrupt);
+    }
+
+    // Safety: `setjmp` returns a second time once `resume` jumps to `suspend_buf`, at which point
+    // this frame is still live since the fiber's stack was left untouched while suspended.
+    unsafe {
+        if setjmp(state.suspend_buf.as_ptr().cast()) == 0 {
+            state.state.set(State::Suspended);
+            longjmp(state.resume_buf.as_ptr().cast(), 1);
+        }
+    }
+
+    if state.cancelled.get() {
+        Err(Trap::Interrupt)
+    } else {
+        Ok(())
+    }
+}
@@ -66,6 +66,28 @@ impl Mmap {
         Ok(Mmap { memory })
     }
 
+    /// Discards the contents of the whole region and makes it inaccessible again, leaving it in the
+    /// same state as a fresh [`Mmap::with_reserve`] reservation without giving up the address range.
+    pub fn decommit(&mut self) -> crate::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        // Safety: we replace our own mapping with a fresh one of the same size, any references
+        // into the old mapping are invalidated by the `&mut self` borrow.
+        unsafe {
+            rustix::mm::mmap_anonymous(
+                self.as_mut_ptr().cast(),
+                self.len(),
+                rustix::mm::ProtFlags::empty(),
+                rustix::mm::MapFlags::PRIVATE | rustix::mm::MapFlags::FIXED,
+            )
+            .map_err(|_| Error::MmapFailed)?;
+        }
+
+        Ok(())
+    }
+
     #[inline]
     pub unsafe fn slice(&self, range: Range<usize>) -> &[u8] {
         assert!(range.end <= self.len());
@@ -4,8 +4,10 @@
 
 pub mod arch;
 pub mod code_registry;
+pub mod fiber;
 pub mod instance_allocator;
 pub mod mmap;
+pub mod perf_map;
 mod setjmp;
 pub(crate) mod signals;
 pub mod trap_handling;
@@ -0,0 +1,71 @@
+//! Support for `perf` map files.
+//!
+//! `perf` can't symbolize JIT-compiled code on its own, but it looks for a `/tmp/perf-<pid>.map`
+//! file that lists one `START SIZE NAME` entry per function, with `START` and `SIZE` in hex.
+
+use crate::compile::FunctionLoc;
+use crate::runtime::CodeMemory;
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+use spin::Mutex;
+
+/// Serializes writers so entries of concurrently compiled modules don't interleave.
+static PERF_MAP_LOCK: Mutex<()> = Mutex::new(());
+
+/// Returns the path of the perf map file for the current process.
+pub fn perf_map_path() -> String {
+    // Safety: syscall
+    let pid = unsafe { libc::getpid() };
+    format!("/tmp/perf-{pid}.map")
+}
+
+/// Appends an entry for each of the given functions to the perf map file.
+///
+/// The entries record absolute addresses, so `code` has to be at its final address already.
+/// Failing to write the file only affects profiling, so errors are logged instead of returned.
+pub fn register_functions(
+    code: &CodeMemory,
+    funcs: impl IntoIterator<Item = (String, FunctionLoc)>,
+) {
+    let mut entries = String::new();
+    for (name, loc) in funcs {
+        let addr = code.resolve_function_loc(loc);
+        // names come from the wasm name section and must not break the line-based format
+        let name = name.replace(['\n', '\r'], " ");
+        writeln!(entries, "{addr:x} {:x} {name}", loc.length).unwrap();
+    }
+
+    if entries.is_empty() {
+        return;
+    }
+
+    let path = CString::new(perf_map_path()).unwrap();
+    let _guard = PERF_MAP_LOCK.lock();
+
+    // Safety: syscalls, `path` is a valid C string and `entries` is valid for reads of its length
+    unsafe {
+        let fd = libc::open(
+            path.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND | libc::O_CLOEXEC,
+            0o644,
+        );
+        if fd < 0 {
+            tracing::warn!("failed to open perf map file {path:?}");
+            return;
+        }
+
+        let mut bytes = entries.as_bytes();
+        while !bytes.is_empty() {
+            let written = libc::write(fd, bytes.as_ptr().cast(), bytes.len());
+            let Ok(written) = usize::try_from(written) else {
+                tracing::warn!("failed to write perf map file {path:?}");
+                break;
+            };
+            bytes = &bytes[written..];
+        }
+
+        libc::close(fd);
+    }
+}
@@ -21,7 +21,7 @@
 
 #![expect(static_mut_refs, reason = "signal handlers are static mut")]
 
-use crate::placeholder::code_registry;
+use crate::placeholder::trap_handling::trap_handler;
 use core::ffi::c_void;
 use core::mem::MaybeUninit;
 use core::{mem, ptr};
@@ -123,19 +123,26 @@ unsafe extern "C" fn trap_handler(
         };
 
         let cx = &*(context.cast::<libc::ucontext_t>());
-        let pc = usize::try_from((*cx.uc_mcontext).__ss.__pc).unwrap();
-        let fp = usize::try_from((*cx.uc_mcontext).__ss.__fp).unwrap();
+        let ss = &(*cx.uc_mcontext).__ss;
+        let pc = usize::try_from(ss.__pc).unwrap();
+        let fp = usize::try_from(ss.__fp).unwrap();
 
         // If this fault wasn't in wasm code, then it's not our problem
-        let Some((code, text_offset)) = code_registry::lookup_code(pc) else {
+        let Some(trap) = trap_handler().lookup_trap(pc, faulting_addr) else {
             return false;
         };
 
-        let Some(trap) = code.lookup_trap_code(text_offset) else {
-            return false;
-        };
+        // x0-x28 followed by fp (x29), lr (x30) and sp so registers can be looked up by their
+        // hardware encoding when inspecting the trapping frame.
+        let mut regs = [0; 32];
+        for (reg, value) in regs
+            .iter_mut()
+            .zip(ss.__x.iter().chain([&ss.__fp, &ss.__lr, &ss.__sp]))
+        {
+            *reg = usize::try_from(*value).unwrap();
+        }
 
-        info.set_jit_trap(pc, fp, faulting_addr, trap);
+        info.set_jit_trap(pc, fp, regs, faulting_addr, trap);
 
         // On macOS this is a bit special, unfortunately. If we were to
         // `siglongjmp` out of the signal handler that notably does
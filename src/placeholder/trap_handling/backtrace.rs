@@ -4,8 +4,11 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::ops::ControlFlow;
 
+/// The WebAssembly frames on the stack at the time of a trap, innermost frame first.
+///
+/// Obtained through [`Store::last_trap_backtrace`][crate::Store::last_trap_backtrace].
 #[derive(Debug)]
-pub struct Backtrace(Vec<Frame>);
+pub struct Backtrace(Vec<BacktraceFrame>);
 
 impl Backtrace {
     pub(crate) unsafe fn new_with_trap_state(
@@ -24,7 +27,7 @@ impl Backtrace {
     pub(crate) unsafe fn trace_with_trap_state(
         state: &CallThreadState,
         trap_pc_and_fp: Option<(usize, usize)>,
-        mut f: impl FnMut(Frame) -> ControlFlow<()>,
+        mut f: impl FnMut(BacktraceFrame) -> ControlFlow<()>,
     ) {
         tracing::trace!("====== Capturing Backtrace ======");
 
@@ -83,7 +86,7 @@ impl Backtrace {
         mut pc: usize,
         mut fp: usize,
         trampoline_fp: usize,
-        mut f: impl FnMut(Frame) -> ControlFlow<()>,
+        mut f: impl FnMut(BacktraceFrame) -> ControlFlow<()>,
     ) -> ControlFlow<()> {
         tracing::trace!("=== Tracing through contiguous sequence of Wasm frames ===");
         tracing::trace!("trampoline_fp = 0x{:016x}", trampoline_fp);
@@ -154,7 +157,7 @@ impl Backtrace {
             tracing::trace!("pc = {:p}", pc as *const ());
             tracing::trace!("fp = {:p}", fp as *const ());
 
-            f(Frame { pc, fp })?;
+            f(BacktraceFrame { pc, fp })?;
 
             pc = arch::get_next_older_pc_from_fp(fp);
 
@@ -179,14 +182,16 @@ impl Backtrace {
     }
 
     /// Iterate over the frames inside this backtrace.
-    pub fn frames(&self) -> impl ExactSizeIterator<Item = &Frame> + DoubleEndedIterator {
+    pub fn frames(&self) -> impl ExactSizeIterator<Item = &BacktraceFrame> + DoubleEndedIterator {
         self.0.iter()
     }
 }
 
 /// A stack frame within a Wasm stack trace.
 #[derive(Debug)]
-pub struct Frame {
+pub struct BacktraceFrame {
+    /// The program counter of the frame, for all but the innermost frame this is a return address.
     pub pc: usize,
+    /// The frame pointer of the frame.
     pub fp: usize,
 }
@@ -0,0 +1,106 @@
+use crate::placeholder::trap_handling::TLS;
+use crate::placeholder::{code_registry, signals};
+use crate::trap::Trap;
+use alloc::boxed::Box;
+use spin::once::Once;
+
+/// Translates hardware faults raised by JIT-compiled WebAssembly code into traps.
+///
+/// Compiled code relies on faults to detect traps: out-of-bounds accesses hit guard pages and
+/// explicit traps execute invalid instructions. By default, Unix signal handlers for `SIGSEGV`,
+/// `SIGILL`, `SIGFPE` and `SIGBUS` catch these faults. Embedders that handle faults themselves,
+/// e.g. a kernel, install their own handler through [`set_trap_handler`] and forward faults
+/// through [`handle_fault`].
+pub trait TrapHandler: Send + Sync {
+    /// Makes sure faults are delivered to this handler.
+    ///
+    /// This is called before every outermost call into WebAssembly, so it should return quickly
+    /// once the handler is registered.
+    fn ensure_registered(&self);
+
+    /// Returns the trap the fault at `pc` corresponds to, or `None` if the fault wasn't raised by
+    /// WebAssembly code and has to be handled elsewhere.
+    ///
+    /// The default implementation looks `pc` up in the trap tables of all compiled modules.
+    ///
+    /// This runs in fault context: the code that faulted is suspended halfway through, so the
+    /// handler must neither block nor allocate, nor take locks that code may be holding.
+    ///
+    /// The default implementation is the one exception, it takes a read lock on the registry of
+    /// compiled code. The faults it translates are raised by WebAssembly code, which never holds
+    /// that lock, so this can at worst wait for another thread that is registering a module. A
+    /// fault in host code that registers or unregisters modules, e.g. a host function compiling
+    /// one, would deadlock instead of crashing though.
+    fn lookup_trap(&self, pc: usize, faulting_addr: Option<usize>) -> Option<Trap> {
+        let _ = faulting_addr;
+        let (code, text_offset) = code_registry::lookup_code(pc)?;
+        code.lookup_trap_code(text_offset)
+    }
+}
+
+/// The default handler, based on Unix signals.
+struct SignalTrapHandler;
+
+impl TrapHandler for SignalTrapHandler {
+    fn ensure_registered(&self) {
+        // Safety: installing the signal handlers is done once, before any fault can be raised
+        unsafe { signals::ensure_signal_handlers_are_registered() }
+    }
+}
+
+static TRAP_HANDLER: Once<Box<dyn TrapHandler>> = Once::new();
+
+/// Replaces the default signal-based trap handler with `handler`.
+///
+/// # Panics
+///
+/// Panics if a trap handler is already in use, i.e. if this was called before or WebAssembly
+/// code has been called already.
+pub fn set_trap_handler(handler: impl TrapHandler + 'static) {
+    let mut handler = Some(handler);
+    TRAP_HANDLER.call_once(|| Box::new(handler.take().unwrap()));
+    assert!(handler.is_none(), "a trap handler is already in use");
+}
+
+/// Returns the trap handler in use, the signal-based default unless one was set.
+pub(crate) fn trap_handler() -> &'static dyn TrapHandler {
+    TRAP_HANDLER
+        .call_once(|| Box::new(SignalTrapHandler))
+        .as_ref()
+}
+
+/// Turns a fault raised at `pc` into a trap of the innermost call into WebAssembly.
+///
+/// This is meant to be called by the fault handler of embedders that installed their own
+/// [`TrapHandler`]. If the fault corresponds to a trap, this unwinds to the call into WebAssembly
+/// and never returns. Otherwise, e.g. because no WebAssembly code is running on this thread or the
+/// fault didn't happen in WebAssembly code, it returns `false` and the fault has to be handled
+/// elsewhere.
+///
+/// # Safety
+///
+/// This must be called from the fault handler, on the thread that faulted, before the faulting
+/// code is resumed. `pc`, `fp` and `regs` must be the state of the faulting code, `regs` indexed
+/// by the registers' hardware encoding.
+pub unsafe fn handle_fault(
+    pc: usize,
+    fp: usize,
+    regs: [usize; 32],
+    faulting_addr: Option<usize>,
+) -> bool {
+    let Some(state) = TLS.get() else {
+        return false;
+    };
+    let Some(trap) = trap_handler().lookup_trap(pc, faulting_addr) else {
+        return false;
+    };
+
+    // Safety: `TLS` only ever points to `CallThreadState`s that are still on the stack, the
+    // caller ensures we are on the faulting thread.
+    let state = unsafe { &*state };
+    // Safety: ensured by the caller
+    unsafe {
+        state.set_jit_trap(pc, fp, regs, faulting_addr, trap);
+        crate::placeholder::setjmp::longjmp(state.jmp_buf.as_ptr().cast(), 1)
+    }
+}
@@ -1,26 +1,59 @@
+use crate::placeholder::arch;
 use crate::runtime::{StaticVMOffsets, VMContext};
-pub use backtrace::Backtrace;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+pub use backtrace::{Backtrace, BacktraceFrame};
 use core::cell::{Cell, UnsafeCell};
 use core::mem::MaybeUninit;
-use core::ptr;
+use core::ptr::{self, NonNull};
 
 mod backtrace;
+mod handler;
 
-pub fn raise_trap(reason: TrapReason) {
+pub(crate) use handler::trap_handler;
+pub use handler::{handle_fault, set_trap_handler, TrapHandler};
+
+pub fn raise_trap(reason: TrapReason) -> ! {
     // Safety: TLS storage is always initialized
     let state = unsafe { &*TLS.get().unwrap() };
     state.unwind_with(UnwindReason::Trap(reason))
 }
 
-pub fn catch_traps<F>(
+/// Registers `f` to run once the innermost call into WebAssembly on this thread has returned,
+/// regardless of whether it returned normally or trapped.
+///
+/// # Panics
+///
+/// Panics if there is no call into WebAssembly on this thread.
+pub fn defer_post_return(f: Box<dyn FnOnce()>) {
+    // Safety: `TLS` only ever points to `CallThreadState`s that are still on the stack
+    let state = unsafe { &*TLS.get().unwrap() };
+    let mut post_return = state.post_return.take();
+    post_return.push(f);
+    state.post_return.set(post_return);
+}
+
+/// Calls `closure`, catching any trap it raises.
+///
+/// A backtrace of the trapping WebAssembly frames is only captured if `capture_backtrace` is set.
+/// The stack of a trapping frame is only copied if `stack_buffer` is provided, it has to be
+/// allocated up front since the trap handler must not allocate.
+///
+/// # Safety
+///
+/// `stack_buffer` must be valid for writes and not be accessed otherwise until this returns.
+pub unsafe fn catch_traps<F>(
     caller: *mut VMContext,
     vmctx_plan: StaticVMOffsets,
+    capture_backtrace: bool,
+    stack_buffer: Option<NonNull<[u8]>>,
     mut closure: F,
 ) -> Result<(), Trap>
 where
     F: FnMut(*mut VMContext),
 {
-    let result = CallThreadState::new(caller, vmctx_plan).with(|state| {
+    let state = CallThreadState::new(caller, vmctx_plan, capture_backtrace, stack_buffer);
+    let result = state.with(|state| {
         // Safety: call to extern
         let r = unsafe { crate::placeholder::setjmp::setjmp(state.jmp_buf.as_ptr().cast()) };
         if r == 0i32 {
@@ -50,6 +83,8 @@ pub struct Trap {
 pub enum TrapReason {
     /// A trap raised from a wasm builtin
     Wasm(crate::trap::Trap),
+    /// An error returned by the embedder while WebAssembly was on the stack, e.g. from a call hook.
+    User(crate::Error),
     /// A trap raised from Cranelift-generated code.
     Jit {
         /// The program counter where this trap originated.
@@ -57,6 +92,15 @@ pub enum TrapReason {
         /// This is later used with side tables from compilation to translate
         /// the trapping address to a trap code.
         pc: usize,
+        /// The frame pointer of the trapping function.
+        fp: usize,
+        /// The general purpose registers at the time of the trap, indexed by their hardware
+        /// encoding.
+        regs: [usize; 32],
+        /// The number of bytes of the trapping frame's stack, from the stack pointer up to the
+        /// canonical frame address, copied into the stack buffer passed to [`catch_traps`]. Zero
+        /// if there is no buffer or the stack can't be read, e.g. because it overflowed.
+        stack_len: usize,
         /// If the trap was a memory-related trap such as SIGSEGV then this
         /// field will contain the address of the inaccessible data.
         ///
@@ -84,7 +128,13 @@ pub struct CallThreadState {
     pub jmp_buf: Cell<crate::placeholder::setjmp::jmp_buf>,
     offsets: StaticVMOffsets,
     vmctx: *mut VMContext,
+    /// Whether traps should capture a backtrace, walking the stack isn't free.
+    capture_backtrace: bool,
+    /// Preallocated buffer the stack of a trapping frame is copied into, if it should be copied.
+    stack_buffer: Option<NonNull<[u8]>>,
     prev: Cell<*const CallThreadState>,
+    /// Callbacks registered through [`defer_post_return`], run once this call has returned.
+    post_return: Cell<Vec<Box<dyn FnOnce()>>>,
     /// The values of `VMRuntimeLimits::last_wasm_{exit_{pc,fp},entry_sp}`
     /// for the *previous* `CallThreadState` for this same store/limits. Our
     /// *current* last wasm PC/FP/SP are saved in `self.limits`. We save a
@@ -99,7 +149,12 @@ pub struct CallThreadState {
 }
 
 impl CallThreadState {
-    pub fn new(vmctx: *mut VMContext, vmoffsets: StaticVMOffsets) -> Self {
+    pub fn new(
+        vmctx: *mut VMContext,
+        vmoffsets: StaticVMOffsets,
+        capture_backtrace: bool,
+        stack_buffer: Option<NonNull<[u8]>>,
+    ) -> Self {
         // Safety: the offsets below are small so the code *should* not overflow
         // TODO this is horrific
         unsafe {
@@ -107,7 +162,10 @@ impl CallThreadState {
                 unwind: UnsafeCell::new(MaybeUninit::uninit()),
                 jmp_buf: Cell::new(crate::placeholder::setjmp::jmp_buf::from([0; 48])),
                 vmctx,
+                capture_backtrace,
+                stack_buffer,
                 prev: Cell::new(ptr::null()),
+                post_return: Cell::new(Vec::new()),
                 old_last_wasm_exit_fp: Cell::new(
                     *vmctx
                         .byte_add(vmoffsets.vmctx_last_wasm_exit_fp() as usize)
@@ -149,6 +207,12 @@ impl CallThreadState {
             closure(reset.state)
         };
 
+        // Run the callbacks after popping this state, so calls they make into WebAssembly don't
+        // appear nested in a call that has already returned.
+        for f in self.post_return.take() {
+            f();
+        }
+
         if ret == 0 {
             Ok(())
         } else {
@@ -166,7 +230,10 @@ impl CallThreadState {
         let backtrace = match reason {
             // Safety: since we pass None to `new_with_trap_state`, pc and fp will be read from the
             // `VMContext` instead. We have to trust that those are valid.
-            UnwindReason::Trap(_) => unsafe { Some(Backtrace::new_with_trap_state(self, None)) },
+            UnwindReason::Trap(_) if self.capture_backtrace => unsafe {
+                Some(Backtrace::new_with_trap_state(self, None))
+            },
+            UnwindReason::Trap(_) => None,
             // UnwindReason::Panic(_) => None,
         };
 
@@ -185,18 +252,29 @@ impl CallThreadState {
         &self,
         pc: usize,
         fp: usize,
+        regs: [usize; 32],
         faulting_addr: Option<usize>,
         trap: crate::trap::Trap,
     ) {
-        let backtrace = Backtrace::new_with_trap_state(self, Some((pc, fp)));
+        let backtrace = self
+            .capture_backtrace
+            .then(|| Backtrace::new_with_trap_state(self, Some((pc, fp))));
+        // The frame is abandoned once we longjmp out of it, so its stack slots have to be copied
+        // now while they are still intact.
+        let stack_len = self
+            .stack_buffer
+            .map_or(0, |buffer| copy_frame_stack(fp, &regs, trap, buffer));
         // Safety: `MaybeUninit` ensures proper alignment.
         (*self.unwind.get()).as_mut_ptr().write((
             UnwindReason::Trap(TrapReason::Jit {
                 pc,
+                fp,
+                regs,
+                stack_len,
                 faulting_addr,
                 trap,
             }),
-            Some(backtrace),
+            backtrace,
         ));
     }
 
@@ -233,6 +311,45 @@ impl CallThreadState {
     }
 }
 
+/// The largest trapping frame whose stack we copy, larger frames are most likely bogus.
+pub const MAX_FRAME_COPY_SIZE: usize = 64 * 1024;
+
+/// Copies the stack of the trapping frame described by `fp` and `regs`, from the stack pointer
+/// up to the canonical frame address, into `buffer`, returning the number of bytes copied.
+///
+/// # Safety
+///
+/// `fp` and `regs` must be the state of a frame that is still on the stack and `buffer` must be
+/// valid for writes.
+unsafe fn copy_frame_stack(
+    fp: usize,
+    regs: &[usize; 32],
+    trap: crate::trap::Trap,
+    buffer: NonNull<[u8]>,
+) -> usize {
+    // the stack pointer of an overflowed stack points into the guard region
+    if trap == crate::trap::Trap::StackOverflow {
+        return 0;
+    }
+
+    let sp = regs[arch::STACK_POINTER_REG];
+    // The canonical frame address is the stack pointer of the caller, which sits just above the
+    // frame record (saved frame pointer and return address) `fp` points to.
+    let Some(cfa) = fp.checked_add(2 * size_of::<usize>()) else {
+        return 0;
+    };
+    match cfa.checked_sub(sp) {
+        Some(len) if len <= buffer.len() => {
+            // Safety: everything between the stack pointer and the caller's stack pointer belongs
+            // to the trapping frame, which the caller ensures is still on the stack. The buffer is
+            // large enough and can't overlap with the frame since it was allocated beforehand.
+            ptr::copy_nonoverlapping(sp as *const u8, buffer.as_ptr().cast::<u8>(), len);
+            len
+        }
+        _ => 0,
+    }
+}
+
 impl Drop for CallThreadState {
     fn drop(&mut self) {
         // Safety: offsets are small so the code below *should* overflow
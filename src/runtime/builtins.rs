@@ -1,4 +1,13 @@
 use crate::builtins::BuiltinFunctionIndex;
+use crate::indices::{DataIndex, ElemIndex, MemoryIndex, TableIndex};
+use crate::placeholder::fiber;
+use crate::placeholder::trap_handling::{raise_trap, TrapReason};
+use crate::runtime::{
+    EpochDeadline, GcHeap, GrowFailure, Instance, OutOfFuel, VMContext, ARRAY_ELEMS_OFFSET,
+    ARRAY_LENGTH_OFFSET,
+};
+use crate::trap::Trap;
+use core::sync::atomic::Ordering;
 
 macro_rules! define_builtin_array {
     (
@@ -23,7 +32,7 @@ macro_rules! define_builtin_array {
             /// the value for the `CMContext::builtin_functions` field.
             pub const INIT: VMBuiltinFunctionsArray = VMBuiltinFunctionsArray {
                 $(
-                    $name: crate::vm::builtins::$name,
+                    $name: raw::$name,
                 )*
             };
         }
@@ -46,3 +55,249 @@ const _: () = {
                 * (BuiltinFunctionIndex::builtin_functions_total_number() as usize)
     );
 };
+
+/// The `extern "C"` entry points that are stored in the `VMBuiltinFunctionsArray`.
+///
+/// These recover the calling `Instance` from the `vmctx` and then forward to the actual
+/// implementations below.
+mod raw {
+    use crate::runtime::{Instance, VMContext};
+
+    macro_rules! define_builtin_shims {
+        (
+            $(
+                $( #[$attr:meta] )*
+                $name:ident( vmctx: vmctx $(, $pname:ident: $param:ident )* ) $( -> $result:ident )?;
+            )*
+        ) => {
+            $(
+                $( #[$attr] )*
+                pub unsafe extern "C" fn $name(
+                    vmctx: *mut VMContext,
+                    $( $pname: define_builtin_array!(@ty $param), )*
+                ) $( -> define_builtin_array!(@ty $result))? {
+                    // Safety: builtins are only ever called from JIT code which passes its own,
+                    // valid `vmctx` as the first argument.
+                    unsafe {
+                        Instance::from_vmctx(vmctx, |instance| {
+                            super::$name(instance, $( $pname ),*)
+                        })
+                    }
+                }
+            )*
+        };
+    }
+
+    crate::foreach_builtin_function!(define_builtin_shims);
+}
+
+/// Implementation of `memory.grow` for 32-bit memories.
+///
+/// Returns the old size of the memory in pages or `usize::MAX` if the memory could not be grown.
+/// Traps instead if the host failed to provide the memory and the store is configured to trap on
+/// grow failures.
+fn memory32_grow(instance: &mut Instance, delta: u64, memory_index: u32) -> *mut u8 {
+    let memory_index = MemoryIndex::from_u32(memory_index);
+    let page_size_log2 = instance.module().translated().memories[memory_index].page_size_log2;
+
+    // Safety: the `VMContext` is initialized, so the pointer is valid
+    let trap_on_memory_grow_failure = unsafe {
+        (*instance.vmctx_runtime_limits())
+            .trap_on_memory_grow_failure
+            .get()
+    };
+
+    let result = match instance.memory_grow(memory_index, delta) {
+        Ok(size_in_bytes) => size_in_bytes >> page_size_log2,
+        Err(GrowFailure::HostAllocation) if trap_on_memory_grow_failure => {
+            raise_trap(TrapReason::Wasm(Trap::HostAllocationFailed))
+        }
+        Err(_) => usize::MAX,
+    };
+
+    result as *mut u8
+}
+
+/// Implementation of `memory.copy`.
+fn memory_copy(
+    instance: &mut Instance,
+    dst_index: u32,
+    dst: u64,
+    src_index: u32,
+    src: u64,
+    len: u64,
+) {
+    let result = instance.memory_copy(
+        MemoryIndex::from_u32(dst_index),
+        dst,
+        MemoryIndex::from_u32(src_index),
+        src,
+        len,
+    );
+
+    if let Err(trap) = result {
+        raise_trap(TrapReason::Wasm(trap));
+    }
+}
+
+/// Implementation of `memory.fill`.
+fn memory_fill(instance: &mut Instance, memory_index: u32, dst: u64, val: u32, len: u64) {
+    // `memory.fill` only uses the lowest byte of the value
+    let val = val.to_le_bytes()[0];
+
+    if let Err(trap) = instance.memory_fill(MemoryIndex::from_u32(memory_index), dst, val, len) {
+        raise_trap(TrapReason::Wasm(trap));
+    }
+}
+
+/// Implementation of `memory.init`.
+fn memory_init(
+    instance: &mut Instance,
+    memory_index: u32,
+    data_index: u32,
+    dst: u64,
+    src: u32,
+    len: u32,
+) {
+    let result = instance.memory_init(
+        MemoryIndex::from_u32(memory_index),
+        DataIndex::from_u32(data_index),
+        dst,
+        src,
+        len,
+    );
+
+    if let Err(trap) = result {
+        raise_trap(TrapReason::Wasm(trap));
+    }
+}
+
+/// Implementation of `data.drop`.
+fn data_drop(instance: &mut Instance, data_index: u32) {
+    instance.data_drop(DataIndex::from_u32(data_index));
+}
+
+/// Implementation of `table.init`.
+fn table_init(
+    instance: &mut Instance,
+    table_index: u32,
+    elem_index: u32,
+    dst: u64,
+    src: u32,
+    len: u32,
+) {
+    let result = instance.table_init(
+        TableIndex::from_u32(table_index),
+        ElemIndex::from_u32(elem_index),
+        dst,
+        src,
+        len,
+    );
+
+    if let Err(trap) = result {
+        raise_trap(TrapReason::Wasm(trap));
+    }
+}
+
+/// Implementation of `elem.drop`.
+fn elem_drop(instance: &mut Instance, elem_index: u32) {
+    instance.elem_drop(ElemIndex::from_u32(elem_index));
+}
+
+/// Implementation of the epoch deadline check, called once the epoch reached the store's deadline.
+///
+/// Depending on the store's configuration this either traps or yields back to the async runtime.
+/// Returns the new deadline.
+fn new_epoch(instance: &mut Instance) -> u64 {
+    // Safety: the `VMContext` is initialized, so both pointers are valid
+    let (limits, epoch) = unsafe {
+        (
+            &*instance.vmctx_runtime_limits(),
+            &*instance.vmctx_epoch_ptr(),
+        )
+    };
+
+    match limits.epoch_deadline_behavior.get() {
+        EpochDeadline::Trap => raise_trap(TrapReason::Wasm(Trap::Interrupt)),
+        EpochDeadline::YieldAndUpdate(delta) => {
+            if let Err(trap) = fiber::suspend() {
+                raise_trap(TrapReason::Wasm(trap));
+            }
+
+            // the epoch might have advanced while we were suspended
+            let deadline = epoch.load(Ordering::Relaxed).saturating_add(delta);
+            limits.epoch_deadline.set(deadline);
+            deadline
+        }
+    }
+}
+
+/// Implementation of the builtin called when the store ran out of fuel.
+///
+/// Depending on the store's configuration this either traps or yields back to the async runtime.
+fn out_of_gas(instance: &mut Instance) {
+    // Safety: the `VMContext` is initialized, so the pointer is valid
+    let limits = unsafe { &*instance.vmctx_runtime_limits() };
+
+    match limits.out_of_fuel_behavior.get() {
+        OutOfFuel::Trap => raise_trap(TrapReason::Wasm(Trap::OutOfFuel)),
+        OutOfFuel::YieldAndRefuel(fuel) => {
+            if let Err(trap) = fiber::suspend() {
+                raise_trap(TrapReason::Wasm(trap));
+            }
+
+            let fuel = i64::try_from(fuel).unwrap_or(i64::MAX);
+            limits
+                .fuel_consumed
+                .set(limits.fuel_consumed.get().saturating_sub(fuel));
+        }
+    }
+}
+
+/// Implementation of the array allocation of `array.new` and friends.
+///
+/// Allocates an array of `len` zeroed elements of `elem_size` bytes each in the store's GC heap,
+/// which is created on first use, and returns the reference to it.
+fn gc_alloc_array(instance: &mut Instance, elem_size: u32, len: u32) -> u32 {
+    // Safety: the `VMContext` is initialized, so the pointer is valid
+    let limits = unsafe { &*instance.vmctx_runtime_limits() };
+
+    // The borrow of the heap must end before trapping, since that doesn't run destructors.
+    let result = {
+        let mut heap = limits.gc_heap.borrow_mut();
+        if heap.is_none() {
+            *heap = GcHeap::new().ok();
+        }
+
+        match heap.as_mut() {
+            None => Err(Trap::HostAllocationFailed),
+            Some(heap) => {
+                let size = u64::from(elem_size)
+                    .checked_mul(u64::from(len))
+                    .and_then(|size| size.checked_add(u64::from(ARRAY_ELEMS_OFFSET)))
+                    .and_then(|size| usize::try_from(size).ok());
+                match size.and_then(|size| heap.alloc(size)) {
+                    None => Err(Trap::AllocationTooLarge),
+                    Some(gc_ref) => {
+                        let base = heap.base();
+                        limits.gc_heap_base.set(base);
+                        Ok((base, gc_ref))
+                    }
+                }
+            }
+        }
+    };
+
+    match result {
+        Ok((base, gc_ref)) => {
+            // Safety: the object was just allocated, so its header lies within the heap
+            unsafe {
+                base.add(usize::try_from(gc_ref + ARRAY_LENGTH_OFFSET).unwrap())
+                    .cast::<u32>()
+                    .write(len);
+            }
+            gc_ref
+        }
+        Err(trap) => raise_trap(TrapReason::Wasm(trap)),
+    }
+}
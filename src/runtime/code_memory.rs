@@ -1,4 +1,4 @@
-use crate::compile::FunctionLoc;
+use crate::compile::{FunctionLoc, ImageRelocation, ImageRelocationKind};
 use crate::placeholder::mmap::Mmap;
 use crate::runtime::MmapVec;
 use crate::trap::Trap;
@@ -12,10 +12,16 @@ pub struct CodeMemory {
 
     trap_offsets: Vec<u32>,
     traps: Vec<Trap>,
+    relocations: Vec<ImageRelocation>,
 }
 
 impl CodeMemory {
-    pub fn new(mmap_vec: MmapVec<u8>, trap_offsets: Vec<u32>, traps: Vec<Trap>) -> Self {
+    pub fn new(
+        mmap_vec: MmapVec<u8>,
+        trap_offsets: Vec<u32>,
+        traps: Vec<Trap>,
+        relocations: Vec<ImageRelocation>,
+    ) -> Self {
         let (mmap, size) = mmap_vec.into_parts();
         Self {
             mmap,
@@ -23,33 +29,93 @@ impl CodeMemory {
             published: false,
             trap_offsets,
             traps,
+            relocations,
         }
     }
 
+    /// Copies the code into a new, not yet published, memory mapping at a different address.
+    #[cfg(test)]
+    pub fn try_clone(&self) -> crate::Result<Self> {
+        let mmap_vec = MmapVec::from_slice(self.text())?;
+        Ok(Self::new(
+            mmap_vec,
+            self.trap_offsets.clone(),
+            self.traps.clone(),
+            self.relocations.clone(),
+        ))
+    }
+
+    /// Applies the absolute relocations and makes the code executable, after this the code can
+    /// never be written to again.
+    ///
+    /// The code is written and relocated while the mapping is read/write, publishing first makes
+    /// it readonly and then read/execute so the mapping is never writable and executable at the
+    /// same time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if changing the protection of the mapping fails, the code must not be
+    /// executed in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the target of an [`ImageRelocationKind::Abs4`] relocation doesn't fit into 32
+    /// bits at the address the code is mapped at.
     pub fn publish(&mut self) -> crate::Result<()> {
         debug_assert!(!self.published);
-        self.published = true;
 
         if self.mmap.is_empty() {
             tracing::warn!("Compiled module has no code to publish");
+            self.published = true;
             return Ok(());
         }
 
+        self.apply_relocations();
         self.mmap.make_readonly(0..self.len)?;
 
         // Switch the executable portion from readonly to read/execute.
         self.mmap.make_executable(0..self.len, true)?;
 
+        self.published = true;
         Ok(())
     }
 
+    fn apply_relocations(&mut self) {
+        let base = self.mmap.as_ptr() as usize;
+        for reloc in &self.relocations {
+            let value = base + reloc.target;
+            let range = reloc.offset..reloc.offset + reloc.kind.size();
+            // Safety: relocations lie within the code, which is still writable
+            let field = unsafe { self.mmap.slice_mut(range) };
+            match reloc.kind {
+                ImageRelocationKind::Abs4 => {
+                    let value = u32::try_from(value).expect("relocation target out of range");
+                    field.copy_from_slice(&value.to_ne_bytes());
+                }
+                ImageRelocationKind::Abs8 => {
+                    field.copy_from_slice(&(value as u64).to_ne_bytes());
+                }
+            }
+        }
+    }
+
     #[inline]
     pub fn text(&self) -> &[u8] {
         // Safety: The constructor has to ensure that `self.len` is valid.
         unsafe { self.mmap.slice(0..self.len) }
     }
 
+    /// Returns the address of the function at `func_loc`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code hasn't been published, it's not executable yet.
     pub fn resolve_function_loc(&self, func_loc: FunctionLoc) -> usize {
+        assert!(
+            self.published,
+            "code must be published before it is executed"
+        );
+
         let text_range = {
             let r = self.text().as_ptr_range();
             r.start as usize..r.end as usize
@@ -72,6 +138,11 @@ impl CodeMemory {
         addr
     }
 
+    /// Returns the absolute relocations that were applied to the code when it was published.
+    pub fn relocations(&self) -> &[ImageRelocation] {
+        &self.relocations
+    }
+
     pub fn lookup_trap_code(&self, text_offset: usize) -> Option<Trap> {
         let text_offset = u32::try_from(text_offset).unwrap();
 
@@ -83,3 +154,49 @@ impl CodeMemory {
         Some(self.traps[index])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+
+    /// Returns the permissions of the mapping containing `addr` as listed in `/proc/self/maps`.
+    #[cfg(target_os = "linux")]
+    fn mapping_permissions(addr: usize) -> String {
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+        maps.lines()
+            .find_map(|line| {
+                let (range, rest) = line.split_once(' ')?;
+                let (start, end) = range.split_once('-')?;
+                let start = usize::from_str_radix(start, 16).ok()?;
+                let end = usize::from_str_radix(end, 16).ok()?;
+                (start..end)
+                    .contains(&addr)
+                    .then(|| rest.split(' ').next().unwrap().to_string())
+            })
+            .unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test_log::test]
+    fn published_code_is_not_writable() {
+        let mut code = CodeMemory::new(
+            MmapVec::from_slice(&[0xcc; 64]).unwrap(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let addr = code.text().as_ptr() as usize;
+        assert_eq!(mapping_permissions(addr), "rw-p");
+
+        code.publish().unwrap();
+        assert_eq!(mapping_permissions(addr), "r-xp");
+        assert_eq!(
+            code.resolve_function_loc(FunctionLoc {
+                start: 0,
+                length: 64
+            }),
+            addr
+        );
+    }
+}
@@ -1,11 +1,44 @@
-use crate::runtime::vmcontext::VMVal;
+use crate::indices::{FuncIndex, GlobalIndex};
 use crate::translate::{ConstExpr, ConstOp};
+use crate::{wasm_unsupported, Error, Val};
+use alloc::format;
 use smallvec::SmallVec;
 
+/// The context a constant expression is evaluated in.
+///
+/// Constant expressions may refer to globals (through `global.get`) and functions
+/// (through `ref.func`) of the module they appear in, an implementor of this trait is responsible
+/// for resolving these references.
+pub trait ConstEvalContext {
+    /// Returns the current value of the global at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the global can't be accessed in this context.
+    fn global_get(&mut self, index: GlobalIndex) -> crate::Result<Val>;
+
+    /// Returns a reference to the function at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function can't be accessed in this context.
+    fn ref_func(&mut self, index: FuncIndex) -> crate::Result<Val>;
+
+    /// Returns an `i31ref` holding the low 31 bits of `value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `i31ref`s can't be created in this context, which is the default.
+    fn ref_i31(&mut self, value: i32) -> crate::Result<Val> {
+        let _ = value;
+        Err(wasm_unsupported!("i31ref in constant expressions"))
+    }
+}
+
 /// Simple interpreter for constant expressions.
 #[derive(Debug, Default)]
 pub struct ConstExprEvaluator {
-    stack: SmallVec<[VMVal; 2]>,
+    stack: SmallVec<[Val; 2]>,
 }
 
 impl ConstExprEvaluator {
@@ -17,68 +50,189 @@ impl ConstExprEvaluator {
     ///
     /// # Errors
     ///
-    /// TODO
-    ///
-    /// # Panics
-    ///
-    /// The only uses of const expressions require them to evaluate to exactly one result.
-    /// This method will panic if there is not exactly one result.
-    pub fn eval(&mut self, expr: &ConstExpr) -> crate::Result<VMVal> {
+    /// Returns an error if the expression contains unsupported operators or if resolving a
+    /// `global.get` or `ref.func` operator through `ctx` fails. Returns
+    /// [`Error::InvalidWebAssembly`] if the expression doesn't evaluate to exactly one result or
+    /// an operator doesn't find operands of the right type, e.g. because `ctx` returned a global of
+    /// a different type.
+    pub fn eval(&mut self, expr: &ConstExpr, ctx: &mut dyn ConstEvalContext) -> crate::Result<Val> {
+        // make sure a previous failed evaluation doesn't leave stale values around
+        self.stack.clear();
+
         for op in expr.ops() {
             match op {
-                ConstOp::I32Const(value) => self.push(VMVal::i32(value)),
-                ConstOp::I64Const(value) => self.push(VMVal::i64(value)),
-                ConstOp::F32Const(value) => self.push(VMVal::f32(value)),
-                ConstOp::F64Const(value) => self.push(VMVal::f64(value)),
-                ConstOp::V128Const(value) => self.push(VMVal::v128(value)),
-                ConstOp::GlobalGet(_) => todo!(),
-                ConstOp::RefI31 => todo!(),
-                ConstOp::RefNull => todo!(),
-                ConstOp::RefFunc(_) => todo!(),
+                ConstOp::I32Const(value) => self.push(Val::I32(value)),
+                ConstOp::I64Const(value) => self.push(Val::I64(value)),
+                ConstOp::F32Const(value) => self.push(Val::F32(value)),
+                ConstOp::F64Const(value) => self.push(Val::F64(value)),
+                ConstOp::V128Const(value) => self.push(Val::V128(value)),
+                ConstOp::GlobalGet(index) => {
+                    let val = ctx.global_get(index)?;
+                    self.push(val);
+                }
+                ConstOp::RefI31 => {
+                    let value = self.pop_i32()?;
+                    let val = ctx.ref_i31(value)?;
+                    self.push(val);
+                }
+                ConstOp::RefNull(heap_type) => self.push(Val::null_ref(&heap_type)),
+                ConstOp::RefFunc(index) => {
+                    let val = ctx.ref_func(index)?;
+                    self.push(val);
+                }
                 ConstOp::I32Add => {
-                    let (arg1, arg2) = self.pop2();
+                    let arg2 = self.pop_i32()?;
+                    let arg1 = self.pop_i32()?;
 
-                    self.push(VMVal::i32(arg1.get_i32().wrapping_add(arg2.get_i32())));
+                    self.push(Val::I32(arg1.wrapping_add(arg2)));
                 }
                 ConstOp::I32Sub => {
-                    let (arg1, arg2) = self.pop2();
+                    let arg2 = self.pop_i32()?;
+                    let arg1 = self.pop_i32()?;
 
-                    self.push(VMVal::i32(arg1.get_i32().wrapping_sub(arg2.get_i32())));
+                    self.push(Val::I32(arg1.wrapping_sub(arg2)));
                 }
                 ConstOp::I32Mul => {
-                    let (arg1, arg2) = self.pop2();
+                    let arg2 = self.pop_i32()?;
+                    let arg1 = self.pop_i32()?;
 
-                    self.push(VMVal::i32(arg1.get_i32().wrapping_mul(arg2.get_i32())));
+                    self.push(Val::I32(arg1.wrapping_mul(arg2)));
                 }
                 ConstOp::I64Add => {
-                    let (arg1, arg2) = self.pop2();
+                    let arg2 = self.pop_i64()?;
+                    let arg1 = self.pop_i64()?;
 
-                    self.push(VMVal::i64(arg1.get_i64().wrapping_add(arg2.get_i64())));
+                    self.push(Val::I64(arg1.wrapping_add(arg2)));
                 }
                 ConstOp::I64Sub => {
-                    let (arg1, arg2) = self.pop2();
+                    let arg2 = self.pop_i64()?;
+                    let arg1 = self.pop_i64()?;
 
-                    self.push(VMVal::i64(arg1.get_i64().wrapping_sub(arg2.get_i64())));
+                    self.push(Val::I64(arg1.wrapping_sub(arg2)));
                 }
                 ConstOp::I64Mul => {
-                    let (arg1, arg2) = self.pop2();
+                    let arg2 = self.pop_i64()?;
+                    let arg1 = self.pop_i64()?;
 
-                    self.push(VMVal::i64(arg1.get_i64().wrapping_mul(arg2.get_i64())));
+                    self.push(Val::I64(arg1.wrapping_mul(arg2)));
                 }
             }
         }
 
-        assert_eq!(self.stack.len(), 1);
-        Ok(self.stack.pop().expect("empty stack"))
+        if self.stack.len() != 1 {
+            return Err(invalid_const_expr(&format!(
+                "expected 1 result, found {}",
+                self.stack.len()
+            )));
+        }
+        self.pop()
     }
 
-    fn push(&mut self, val: VMVal) {
+    fn push(&mut self, val: Val) {
         self.stack.push(val);
     }
 
-    fn pop2(&mut self) -> (VMVal, VMVal) {
-        let v2 = self.stack.pop().unwrap();
-        let v1 = self.stack.pop().unwrap();
-        (v1, v2)
+    fn pop(&mut self) -> crate::Result<Val> {
+        self.stack
+            .pop()
+            .ok_or_else(|| invalid_const_expr("operand stack underflow"))
+    }
+
+    fn pop_i32(&mut self) -> crate::Result<i32> {
+        match self.pop()? {
+            Val::I32(value) => Ok(value),
+            _ => Err(invalid_const_expr("expected an i32 operand")),
+        }
+    }
+
+    fn pop_i64(&mut self) -> crate::Result<i64> {
+        match self.pop()? {
+            Val::I64(value) => Ok(value),
+            _ => Err(invalid_const_expr("expected an i64 operand")),
+        }
+    }
+}
+
+fn invalid_const_expr(message: &str) -> Error {
+    Error::InvalidWebAssembly {
+        message: format!("invalid constant expression: {message}"),
+        offset: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::{ModuleTypes, TranslatedModule, WasmparserTypeConverter};
+
+    struct NoContext;
+
+    impl ConstEvalContext for NoContext {
+        fn global_get(&mut self, _index: GlobalIndex) -> crate::Result<Val> {
+            unreachable!()
+        }
+
+        fn ref_func(&mut self, _index: FuncIndex) -> crate::Result<Val> {
+            unreachable!()
+        }
+    }
+
+    fn parse_const_expr(wat: &str) -> ConstExpr {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parser = wasmparser::Parser::new(0);
+        for payload in parser.parse_all(&wasm) {
+            if let wasmparser::Payload::GlobalSection(reader) = payload.unwrap() {
+                let global = reader.into_iter().next().unwrap().unwrap();
+                let types = ModuleTypes::default();
+                let module = TranslatedModule::default();
+                let ty_convert = WasmparserTypeConverter::new(&types, &module);
+                let (expr, _) = ConstExpr::from_wasmparser(&global.init_expr, &ty_convert).unwrap();
+                return expr;
+            }
+        }
+        panic!("module has no globals")
+    }
+
+    #[test_log::test]
+    fn extended_const_i32_add() {
+        let expr =
+            parse_const_expr(r#"(module (global i32 (i32.add (i32.const 1) (i32.const 2))))"#);
+
+        let mut const_eval = ConstExprEvaluator::default();
+        let val = expr.eval(&mut const_eval, &mut NoContext).unwrap();
+        assert!(matches!(val, Val::I32(3)));
+    }
+
+    #[test_log::test]
+    fn ref_null_has_declared_type() {
+        let mut const_eval = ConstExprEvaluator::default();
+
+        let expr = parse_const_expr(r#"(module (global funcref (ref.null func)))"#);
+        let val = const_eval.eval(&expr, &mut NoContext).unwrap();
+        assert!(matches!(val, Val::FuncRef(None)));
+
+        let expr = parse_const_expr(r#"(module (global externref (ref.null extern)))"#);
+        let val = const_eval.eval(&expr, &mut NoContext).unwrap();
+        assert!(matches!(val, Val::ExternRef(None)));
+
+        let expr = parse_const_expr(r#"(module (global anyref (ref.null any)))"#);
+        let val = const_eval.eval(&expr, &mut NoContext).unwrap();
+        assert!(matches!(val, Val::AnyRef(None)));
+    }
+
+    #[test_log::test]
+    fn malformed_expressions_are_errors() {
+        let mut const_eval = ConstExprEvaluator::default();
+
+        // the text format doesn't type check the operands
+        for wat in [
+            r#"(module (global i32 (i32.add (i32.const 1))))"#,
+            r#"(module (global i32 (i32.add (i64.const 1) (i32.const 2))))"#,
+            r#"(module (global i32 (i32.const 1) (i32.const 2)))"#,
+        ] {
+            let expr = parse_const_expr(wat);
+            let err = const_eval.eval(&expr, &mut NoContext).unwrap_err();
+            assert!(matches!(err, Error::InvalidWebAssembly { .. }), "{err}");
+        }
     }
 }
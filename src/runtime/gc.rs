@@ -0,0 +1,75 @@
+use crate::placeholder::mmap::Mmap;
+use crate::utils::round_usize_up_to_host_pages;
+
+/// The size of the address range reserved for a GC heap.
+///
+/// GC references are 32-bit offsets into the heap, so it never needs to be larger than this.
+const GC_HEAP_RESERVATION: usize = 1 << 32;
+
+/// The offset of the length field in an array object.
+pub const ARRAY_LENGTH_OFFSET: u32 = 0;
+/// The offset of the first element in an array object, the elements follow each other without
+/// padding.
+///
+/// Objects are 8-byte aligned, so elements of up to 8 bytes are naturally aligned.
+pub const ARRAY_ELEMS_OFFSET: u32 = 8;
+
+/// The alignment of all objects in a GC heap.
+///
+/// This keeps the lowest bit of every GC reference clear, so they can't be mistaken for `i31ref`s.
+const GC_OBJECT_ALIGN: usize = 8;
+
+/// The heap that objects of the GC proposal, i.e. arrays, are allocated in.
+///
+/// Objects are bump-allocated and never freed individually, the whole heap is released together
+/// with its store. References to objects are their offsets from the start of the heap, the first
+/// object is placed after offset `0` so that it is never confused with a null reference.
+#[derive(Debug)]
+pub struct GcHeap {
+    mmap: Mmap,
+    /// The offset the next object is allocated at.
+    next: usize,
+    /// The number of bytes at the start of the reservation that are accessible.
+    accessible: usize,
+}
+
+impl GcHeap {
+    /// Reserves the address range for a new, empty heap.
+    pub fn new() -> crate::Result<Self> {
+        Ok(Self {
+            mmap: Mmap::with_reserve(GC_HEAP_RESERVATION)?,
+            next: GC_OBJECT_ALIGN,
+            accessible: 0,
+        })
+    }
+
+    /// Returns the address of the start of the heap, the base of all GC references.
+    pub fn base(&mut self) -> *mut u8 {
+        self.mmap.as_mut_ptr()
+    }
+
+    /// Allocates a zeroed object of `size` bytes, returning its reference.
+    ///
+    /// Returns `None` if the heap is exhausted or the host failed to provide the memory.
+    pub fn alloc(&mut self, size: usize) -> Option<u32> {
+        let start = self.next;
+        let end = start
+            .checked_add(size)?
+            .checked_next_multiple_of(GC_OBJECT_ALIGN)?;
+        if end > self.mmap.len() {
+            return None;
+        }
+
+        if end > self.accessible {
+            let accessible = round_usize_up_to_host_pages(end);
+            self.mmap
+                .make_accessible(self.accessible, accessible - self.accessible)
+                .ok()?;
+            self.accessible = accessible;
+        }
+
+        // freshly committed memory is zeroed and objects are never freed, so it's still zeroed
+        self.next = end;
+        u32::try_from(start).ok()
+    }
+}
@@ -1,31 +1,39 @@
+use crate::func::Func;
 use crate::indices::{
     DataIndex, DefinedGlobalIndex, DefinedMemoryIndex, DefinedTableIndex, ElemIndex, EntityIndex,
     FuncIndex, GlobalIndex, MemoryIndex, TableIndex, VMSharedTypeIndex,
 };
 use crate::runtime::builtins::VMBuiltinFunctionsArray;
-use crate::runtime::memory::Memory;
+use crate::runtime::memory::{GrowFailure, Memory};
 use crate::runtime::table::Table;
 use crate::runtime::vmcontext::{VMArrayCallFunction, VMGlobalDefinition, VMWasmCallFunction};
 use crate::runtime::{
-    ConstExprEvaluator, Export, ExportedFunction, ExportedGlobal, ExportedMemory, ExportedTable,
-    Imports, InstanceAllocator, OwnedVMContext, VMContext, VMFuncRef, VMFunctionImport,
-    VMGlobalImport, VMMemoryDefinition, VMMemoryImport, VMOffsets, VMOpaqueContext,
-    VMTableDefinition, VMTableImport, VMCONTEXT_MAGIC,
+    ConstEvalContext, ConstExprEvaluator, Export, ExportedFunction, ExportedGlobal, ExportedMemory,
+    ExportedTable, Imports, InstanceAllocator, OwnedVMContext, StaticVMOffsets, StatsEvent,
+    VMContext, VMFuncRef, VMFunctionImport, VMGlobalImport, VMMemoryDefinition, VMMemoryImport,
+    VMOffsets, VMOpaqueContext, VMRuntimeLimits, VMTableDefinition, VMTableImport, VMCONTEXT_MAGIC,
 };
-use crate::translate::{TableInitialValue, TableSegmentElements};
-use crate::{Extern, Module};
+use crate::translate::{ConstExpr, ConstOp, TableInitialValue, TableSegmentElements};
+use crate::trap::Trap;
+use crate::{AnyRef, Extern, Module, Store, Val};
+use alloc::string::ToString;
+use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::mem::ManuallyDrop;
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU64, Ordering};
 use core::{fmt, mem, ptr, slice};
 use cranelift_entity::packed_option::ReservedValue;
 use cranelift_entity::{EntityRef, EntitySet, PrimaryMap};
 
-#[derive(Debug)]
 pub struct Instance {
     module: Module,
+    /// The allocator the resources of this instance were allocated with, they are returned to it
+    /// when the instance is dropped.
+    alloc: Arc<dyn InstanceAllocator>,
 
-    vmctx: OwnedVMContext,
+    vmctx: ManuallyDrop<OwnedVMContext>,
     tables: PrimaryMap<DefinedTableIndex, Table>,
     memories: PrimaryMap<DefinedMemoryIndex, Memory>,
     dropped_elems: EntitySet<ElemIndex>,
@@ -35,36 +43,80 @@ pub struct Instance {
 }
 
 impl Instance {
-    pub unsafe fn new_unchecked(
-        alloc: &dyn InstanceAllocator,
+    pub unsafe fn new_unchecked<T>(
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
         const_eval: &mut ConstExprEvaluator,
         module: Module,
         imports: Imports,
     ) -> crate::Result<Self> {
-        let (mut vmctx, mut tables, mut memories) = alloc.allocate_module(&module)?;
+        let (vmctx, tables, memories) = alloc.allocate_module(&module)?;
+
+        // construct the instance right away, so the resources are returned to the allocator
+        // should initialization fail
+        let mut this = Self {
+            module: module.clone(),
+            alloc,
+            vmctx: ManuallyDrop::new(vmctx),
+            tables,
+            memories,
+            dropped_elems: module.translated().active_table_initializers.clone(),
+            dropped_data: module.translated().active_memory_initializers.clone(),
+            exports: vec![None; module.exports().len()],
+        };
+
+        let mut ctx = InitContext {
+            store,
+            vmctx: this.vmctx.as_mut_ptr(),
+            module: &module,
+        };
 
         initialize_vmctx(
             const_eval,
-            &mut vmctx,
-            &mut tables,
-            &mut memories,
+            &mut ctx,
+            &mut this.vmctx,
+            &mut this.tables,
+            &mut this.memories,
             &module,
             imports,
         )?;
-        initialize_tables(const_eval, &mut tables, &module)?;
-        initialize_memories(const_eval, &mut memories, &module)?;
+        // element segments are applied before data segments, the start function is called by the
+        // caller once the instance is fully initialized
+        initialize_tables(const_eval, &mut ctx, &mut this.tables, &module)?;
+        initialize_memories(const_eval, &mut ctx, &mut this.memories, &module)?;
 
-        let exports = vec![None; module.exports().len()];
+        Ok(this)
+    }
 
-        Ok(Self {
-            vmctx,
-            tables,
-            memories,
-            dropped_elems: module.translated().active_table_initializers.clone(),
-            dropped_data: module.translated().active_memory_initializers.clone(),
-            exports,
-            module,
-        })
+    /// Calls `f` with the `Instance` that owns the given `vmctx`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `vmctx` is a valid pointer to the `VMContext` of an instance
+    /// that is still alive and that had its back-pointer set through [`Instance::set_vmctx_instance`].
+    pub(crate) unsafe fn from_vmctx<R>(
+        vmctx: *mut VMContext,
+        f: impl FnOnce(&mut Instance) -> R,
+    ) -> R {
+        debug_assert!(!vmctx.is_null());
+        let offsets = StaticVMOffsets::new(u8::try_from(size_of::<usize>()).unwrap());
+        let ptr = *vmctx
+            .byte_add(usize::from(offsets.vmctx_instance()))
+            .cast::<*mut Instance>();
+        debug_assert!(!ptr.is_null());
+        f(&mut *ptr)
+    }
+
+    /// Stores a pointer to `self` in this instance's `VMContext`, so builtin functions
+    /// called from JIT code can find their way back to the instance.
+    ///
+    /// # Safety
+    ///
+    /// The instance must not be moved after this call, i.e. it has to be heap allocated.
+    pub(crate) unsafe fn set_vmctx_instance(&mut self) {
+        let offset = u32::from(self.module.offsets().static_.vmctx_instance());
+        let this = ptr::from_mut(self);
+        *self.vmctx.plus_offset_mut::<*mut Instance>(offset) = this;
     }
 
     pub fn module(&self) -> &Module {
@@ -170,6 +222,302 @@ impl Instance {
                 .plus_offset(self.module().offsets().vmctx_vmmemory_import(index))
         }
     }
+    /// Returns the index of the defined memory `definition` points to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `definition` is not one of this instance's memory definitions.
+    pub fn memory_index(&self, definition: *const VMMemoryDefinition) -> DefinedMemoryIndex {
+        // Safety: offsets are small so no overflow *should* happen. TODO ensure this
+        let begin = unsafe {
+            self.vmctx
+                .plus_offset::<VMMemoryDefinition>(self.module().offsets().vmctx_memories_begin())
+        };
+        // Safety: both pointers point into our own `VMContext`
+        let index = unsafe { definition.offset_from(begin) };
+        let index = DefinedMemoryIndex::new(usize::try_from(index).unwrap());
+        assert!(index.index() < self.memories.len());
+        index
+    }
+
+    /// Returns the offset of `addr` relative to the base of the defined memory it falls into.
+    pub fn memory_offset_of(&self, addr: usize) -> Option<u64> {
+        self.memories
+            .values()
+            .find_map(|memory| memory.wasm_offset(addr))
+    }
+
+    /// Grows the memory at `index` by `delta` pages.
+    ///
+    /// Returns the old size of the memory in bytes or the reason the memory could not be grown.
+    pub fn memory_grow(&mut self, index: MemoryIndex, delta: u64) -> Result<usize, GrowFailure> {
+        if let Some(def_index) = self.module().translated().defined_memory_index(index) {
+            self.defined_memory_grow(def_index, delta)
+        } else {
+            let import = self.imported_memory(index);
+            let (from, vmctx) = (import.from, import.vmctx);
+            // Safety: imported memories are always owned by an instance that outlives us
+            unsafe {
+                Instance::from_vmctx(vmctx, |instance| {
+                    let def_index = instance.memory_index(from);
+                    instance.defined_memory_grow(def_index, delta)
+                })
+            }
+        }
+    }
+
+    /// Grows the defined memory at `index` by `delta` pages, consulting the store's
+    /// [`ResourceLimiter`][crate::ResourceLimiter] if one is installed.
+    ///
+    /// Returns the old size of the memory in bytes or the reason the memory could not be grown.
+    pub fn defined_memory_grow(
+        &mut self,
+        index: DefinedMemoryIndex,
+        delta: u64,
+    ) -> Result<usize, GrowFailure> {
+        // Safety: the `VMContext` is initialized, so the pointer is valid
+        let limits = unsafe { &*self.vmctx_runtime_limits() };
+        limits.count(StatsEvent::MemoryGrow);
+        let old_size =
+            self.memories[index].grow(delta, |current, desired, maximum| {
+                match limits.limiter.borrow_mut().as_mut() {
+                    Some(limiter) => limiter.0.memory_growing(current, desired, maximum),
+                    None => true,
+                }
+            })?;
+
+        // update the length JIT code sees
+        let definition = self.memories[index].as_vmmemory_definition();
+        // Safety: we have a `&mut self`, so we have exclusive access to this Instance.
+        unsafe {
+            let ptr = self.memory_ptr(index);
+            // generated code loads the base pointer once and keeps it across `memory.grow`
+            debug_assert_eq!((*ptr).base, definition.base, "memory moved while growing");
+            ptr.write(definition);
+        }
+
+        Ok(old_size)
+    }
+
+    /// Returns a pointer to the `VMMemoryDefinition` of the memory at `index`, regardless of
+    /// whether the memory is defined by this instance or imported.
+    pub fn get_memory_definition(&mut self, index: MemoryIndex) -> *mut VMMemoryDefinition {
+        if let Some(def_index) = self.module().translated().defined_memory_index(index) {
+            self.memory_ptr(def_index)
+        } else {
+            self.imported_memory(index).from
+        }
+    }
+
+    /// Implementation of the `memory.copy` instruction.
+    ///
+    /// Copies `len` bytes from `src` in the memory at `src_index` to `dst` in the memory at
+    /// `dst_index`. The memories may be the same and the ranges may overlap.
+    pub fn memory_copy(
+        &mut self,
+        dst_index: MemoryIndex,
+        dst: u64,
+        src_index: MemoryIndex,
+        src: u64,
+        len: u64,
+    ) -> Result<(), Trap> {
+        let src_mem = self.get_memory_definition(src_index);
+        let dst_mem = self.get_memory_definition(dst_index);
+
+        // Safety: memory definitions point to memories that are alive for as long as we are
+        unsafe {
+            let src = validate_inbounds(&*src_mem, src, len)?;
+            let dst = validate_inbounds(&*dst_mem, dst, len)?;
+
+            // `ptr::copy` (unlike `copy_nonoverlapping`) handles overlapping ranges correctly
+            ptr::copy(
+                (*src_mem).base.add(src),
+                (*dst_mem).base.add(dst),
+                usize::try_from(len).unwrap(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Implementation of the `memory.fill` instruction.
+    ///
+    /// Sets `len` bytes starting at `dst` in the memory at `memory_index` to `val`.
+    pub fn memory_fill(
+        &mut self,
+        memory_index: MemoryIndex,
+        dst: u64,
+        val: u8,
+        len: u64,
+    ) -> Result<(), Trap> {
+        let memory = self.get_memory_definition(memory_index);
+
+        // Safety: memory definitions point to memories that are alive for as long as we are
+        unsafe {
+            let dst = validate_inbounds(&*memory, dst, len)?;
+            ptr::write_bytes((*memory).base.add(dst), val, usize::try_from(len).unwrap());
+        }
+
+        Ok(())
+    }
+
+    /// Implementation of the `memory.init` instruction.
+    ///
+    /// Copies `len` bytes from `src` in the passive data segment `data_index` to `dst` in the
+    /// memory at `memory_index`. Dropped segments are treated as empty.
+    pub fn memory_init(
+        &mut self,
+        memory_index: MemoryIndex,
+        data_index: DataIndex,
+        dst: u64,
+        src: u32,
+        len: u32,
+    ) -> Result<(), Trap> {
+        let module = self.module.clone();
+        let data = if self.dropped_data.contains(data_index) {
+            &[][..]
+        } else {
+            module
+                .translated()
+                .passive_memory_initializers
+                .get(&data_index)
+                .map_or(&[][..], Vec::as_slice)
+        };
+
+        let src = usize::try_from(src).unwrap();
+        let len = usize::try_from(len).unwrap();
+        let data = src
+            .checked_add(len)
+            .and_then(|end| data.get(src..end))
+            .ok_or(Trap::MemoryOutOfBounds)?;
+
+        let memory = self.get_memory_definition(memory_index);
+
+        // Safety: memory definitions point to memories that are alive for as long as we are
+        unsafe {
+            let dst = validate_inbounds(&*memory, dst, u64::try_from(len).unwrap())?;
+            ptr::copy_nonoverlapping(data.as_ptr(), (*memory).base.add(dst), data.len());
+        }
+
+        Ok(())
+    }
+
+    /// Implementation of the `data.drop` instruction.
+    pub fn data_drop(&mut self, data_index: DataIndex) {
+        self.dropped_data.insert(data_index);
+    }
+
+    /// Returns a pointer to the `VMTableDefinition` of the table at `index`, regardless of
+    /// whether the table is defined by this instance or imported.
+    pub fn get_table_definition(&mut self, index: TableIndex) -> *mut VMTableDefinition {
+        if let Some(def_index) = self.module().translated().defined_table_index(index) {
+            self.table_ptr(def_index)
+        } else {
+            self.imported_table(index).from
+        }
+    }
+
+    /// Implementation of the `table.init` instruction.
+    ///
+    /// Copies `len` elements from `src` in the passive element segment `elem_index` to `dst` in
+    /// the table at `table_index`. Dropped segments are treated as empty.
+    pub fn table_init(
+        &mut self,
+        table_index: TableIndex,
+        elem_index: ElemIndex,
+        dst: u64,
+        src: u32,
+        len: u32,
+    ) -> Result<(), Trap> {
+        let module = self.module.clone();
+        let segment = if self.dropped_elems.contains(elem_index) {
+            None
+        } else {
+            module
+                .translated()
+                .passive_table_initializers
+                .get(&elem_index)
+        };
+
+        let src = usize::try_from(src).unwrap();
+        let len = usize::try_from(len).unwrap();
+        let end = src.checked_add(len).ok_or(Trap::TableOutOfBounds)?;
+
+        let elements: Vec<_> = match segment {
+            None if end == 0 => Vec::new(),
+            None => return Err(Trap::TableOutOfBounds),
+            Some(TableSegmentElements::Functions(funcs)) => funcs
+                .get(src..end)
+                .ok_or(Trap::TableOutOfBounds)?
+                .iter()
+                .map(|index| self.get_func_ref(*index).and_then(NonNull::new))
+                .collect(),
+            Some(TableSegmentElements::Expressions(exprs)) => exprs
+                .get(src..end)
+                .ok_or(Trap::TableOutOfBounds)?
+                .iter()
+                .map(|expr| self.elem_expr_func_ref(expr))
+                .collect(),
+        };
+
+        let table = self.get_table_definition(table_index);
+
+        // Safety: table definitions point to tables that are alive for as long as we are
+        unsafe {
+            let table = slice::from_raw_parts_mut(
+                (*table).base.cast::<Option<NonNull<VMFuncRef>>>(),
+                usize::try_from((*table).current_length).unwrap(),
+            );
+
+            let dst = usize::try_from(dst).map_err(|_| Trap::TableOutOfBounds)?;
+            dst.checked_add(len)
+                .and_then(|end| table.get_mut(dst..end))
+                .ok_or(Trap::TableOutOfBounds)?
+                .copy_from_slice(&elements);
+        }
+
+        Ok(())
+    }
+
+    /// Implementation of the `elem.drop` instruction.
+    pub fn elem_drop(&mut self, elem_index: ElemIndex) {
+        self.dropped_elems.insert(elem_index);
+    }
+
+    /// Returns the function reference produced by an element segment expression.
+    ///
+    /// Passive segments are only evaluated once `table.init` runs, where there is no store to
+    /// run the [`ConstExprEvaluator`] with. Expressions of function tables can only be
+    /// `ref.null`, `ref.func` or `global.get` though, so they are resolved directly.
+    fn elem_expr_func_ref(&mut self, expr: &ConstExpr) -> Option<NonNull<VMFuncRef>> {
+        let mut ops = expr.ops();
+        let op = ops.next();
+        debug_assert!(
+            ops.next().is_none(),
+            "unexpected element expression {expr:?}"
+        );
+
+        match op {
+            Some(ConstOp::RefFunc(index)) => self.get_func_ref(index).and_then(NonNull::new),
+            Some(ConstOp::GlobalGet(index)) => {
+                let ty = self.module().translated().globals[index]
+                    .content_type
+                    .clone();
+                let def = if let Some(def_index) =
+                    self.module().translated().defined_global_index(index)
+                {
+                    self.global_ptr(def_index)
+                } else {
+                    self.imported_global(index).from
+                };
+
+                // Safety: globals are initialized before any code of the instance runs
+                let funcref = unsafe { (*def).to_vmval(&ty).get_funcref() };
+                NonNull::new(funcref.cast())
+            }
+            _ => None,
+        }
+    }
 
     pub fn get_exported_global(&mut self, index: GlobalIndex) -> ExportedGlobal {
         let (definition, vmctx) =
@@ -224,7 +572,8 @@ impl Instance {
                         .field("magic", &self.data.vmctx_magic())
                         .field("builtin_functions", &self.data.vmctx_builtin_functions())
                         .field("type_ids", &self.data.vmctx_type_ids())
-                        .field("stack_limit", &(self.data.vmctx_stack_limit() as *const u8))
+                        .field("runtime_limits", &*self.data.vmctx_runtime_limits())
+                        .field("epoch_ptr", &self.data.vmctx_epoch_ptr())
                         .field(
                             "last_wasm_exit_fp",
                             &(self.data.vmctx_last_wasm_exit_fp() as *const u8),
@@ -237,6 +586,7 @@ impl Instance {
                             "last_wasm_entry_fp",
                             &(self.data.vmctx_last_wasm_entry_fp() as *const u8),
                         )
+                        .field("instance", &self.data.vmctx_instance())
                         .field("func_refs", &self.data.vmctx_func_refs())
                         .field("imported_functions", &self.data.vmctx_function_imports())
                         .field("imported_tables", &self.data.vmctx_table_imports())
@@ -272,10 +622,15 @@ impl Instance {
             self.module.offsets().static_.vmctx_builtin_functions(),
         ))
     }
-    pub(crate) unsafe fn vmctx_stack_limit(&self) -> usize {
-        *self
-            .vmctx
-            .plus_offset::<usize>(u32::from(self.module.offsets().static_.vmctx_stack_limit()))
+    pub(crate) unsafe fn vmctx_runtime_limits(&self) -> *const VMRuntimeLimits {
+        *self.vmctx.plus_offset::<*const VMRuntimeLimits>(u32::from(
+            self.module.offsets().static_.vmctx_runtime_limits(),
+        ))
+    }
+    pub(crate) unsafe fn vmctx_epoch_ptr(&self) -> *const AtomicU64 {
+        *self.vmctx.plus_offset::<*const AtomicU64>(u32::from(
+            self.module.offsets().static_.vmctx_epoch_ptr(),
+        ))
     }
     pub(crate) unsafe fn vmctx_last_wasm_exit_fp(&self) -> usize {
         *self.vmctx.plus_offset::<usize>(u32::from(
@@ -292,6 +647,11 @@ impl Instance {
             self.module.offsets().static_.vmctx_last_wasm_entry_fp(),
         ))
     }
+    pub(crate) unsafe fn vmctx_instance(&self) -> *const Instance {
+        *self.vmctx.plus_offset::<*const Instance>(u32::from(
+            self.module.offsets().static_.vmctx_instance(),
+        ))
+    }
     pub(crate) unsafe fn vmctx_table_definitions(&self) -> &[VMTableDefinition] {
         slice::from_raw_parts(
             self.vmctx
@@ -353,12 +713,100 @@ impl Instance {
     }
 }
 
+impl Drop for Instance {
+    fn drop(&mut self) {
+        // Safety: all resources were allocated by `self.alloc` in `Instance::new_unchecked` and,
+        // since the instance is going away, are never used again. The `VMContext` is taken out
+        // last and not touched afterwards.
+        unsafe {
+            self.alloc.deallocate_memories(&mut self.memories);
+            self.alloc.deallocate_tables(&mut self.tables);
+            self.alloc
+                .deallocate_vmctx(ManuallyDrop::take(&mut self.vmctx));
+        }
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("module", &self.module)
+            .field("vmctx", &self.vmctx)
+            .field("tables", &self.tables)
+            .field("memories", &self.memories)
+            .field("dropped_elems", &self.dropped_elems)
+            .field("dropped_data", &self.dropped_data)
+            .field("exports", &self.exports)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The context const expressions are evaluated in during instantiation.
+///
+/// Note that this reads directly from the `VMContext` under construction, so function references
+/// and imports have to be initialized before any const expression is evaluated.
+struct InitContext<'a, T> {
+    store: &'a mut Store<T>,
+    vmctx: *mut VMContext,
+    module: &'a Module,
+}
+
+impl<T> ConstEvalContext for InitContext<'_, T> {
+    fn global_get(&mut self, index: GlobalIndex) -> crate::Result<Val> {
+        let offsets = self.module.offsets();
+
+        // Safety: imports are copied into the `VMContext` and defined globals are initialized in
+        // order before any const expression can refer to them.
+        unsafe {
+            let def = if let Some(def_index) = self.module.translated().defined_global_index(index)
+            {
+                let offset = offsets.vmctx_vmglobal_definition(def_index);
+                self.vmctx
+                    .byte_add(usize::try_from(offset).unwrap())
+                    .cast::<VMGlobalDefinition>()
+            } else {
+                let offset = offsets.vmctx_vmglobal_import(index);
+                let import = self
+                    .vmctx
+                    .byte_add(usize::try_from(offset).unwrap())
+                    .cast::<VMGlobalImport>();
+                (*import).from
+            };
+
+            let ty = &self.module.translated().globals[index].content_type;
+            Ok(Val::from_vmval(self.store, (*def).to_vmval(ty), ty))
+        }
+    }
+
+    fn ref_func(&mut self, index: FuncIndex) -> crate::Result<Val> {
+        let func = &self.module.translated().functions[index];
+        let offset = self.module.offsets().vmctx_vmfunc_ref(func.func_ref);
+
+        // Safety: `VMFuncRef`s are initialized before any const expression is evaluated.
+        let func_ref = unsafe {
+            self.vmctx
+                .byte_add(usize::try_from(offset).unwrap())
+                .cast::<VMFuncRef>()
+        };
+
+        // reuse the handle of functions referenced repeatedly, e.g. by many table elements
+        let func = Func::from_vm_func_ref(self.store, func_ref.cast());
+        debug_assert!(func.is_some());
+        Ok(Val::FuncRef(func))
+    }
+
+    fn ref_i31(&mut self, value: i32) -> crate::Result<Val> {
+        Ok(Val::AnyRef(Some(AnyRef::from_i31(self.store, value))))
+    }
+}
+
 #[expect(
     clippy::needless_pass_by_value,
     reason = "imports should be a linear type"
 )]
-unsafe fn initialize_vmctx(
+unsafe fn initialize_vmctx<T>(
     const_eval: &mut ConstExprEvaluator,
+    ctx: &mut InitContext<T>,
     vmctx: &mut OwnedVMContext,
     tables: &mut PrimaryMap<DefinedTableIndex, Table>,
     memories: &mut PrimaryMap<DefinedMemoryIndex, Memory>,
@@ -379,6 +827,13 @@ unsafe fn initialize_vmctx(
     let type_ids = module.type_ids();
     *vmctx.plus_offset_mut(u32::from(offsets.static_.vmctx_type_ids())) = type_ids.as_ptr();
 
+    // initialize the runtime limits and epoch ptrs
+    *vmctx.plus_offset_mut::<*const VMRuntimeLimits>(u32::from(
+        offsets.static_.vmctx_runtime_limits(),
+    )) = ctx.store.runtime_limits_ptr();
+    *vmctx.plus_offset_mut::<*const AtomicU64>(u32::from(offsets.static_.vmctx_epoch_ptr())) =
+        ctx.store.engine.epoch_counter();
+
     // initialize func_refs array
     initialize_vmfunc_refs(vmctx, &module, &imports, offsets);
 
@@ -432,7 +887,7 @@ unsafe fn initialize_vmctx(
     }
 
     for (def_index, init_expr) in &module.translated().global_initializers {
-        let val = const_eval.eval(init_expr)?;
+        let val = const_eval.eval(init_expr, ctx)?.as_vmval(ctx.store);
         let ptr = vmctx.plus_offset_mut::<VMGlobalDefinition>(
             module.offsets().vmctx_vmglobal_definition(def_index),
         );
@@ -500,8 +955,9 @@ unsafe fn initialize_vmfunc_refs(
     }
 }
 
-unsafe fn initialize_tables(
+unsafe fn initialize_tables<T>(
     const_eval: &mut ConstExprEvaluator,
+    ctx: &mut InitContext<T>,
     tables: &mut PrimaryMap<DefinedTableIndex, Table>,
     module: &Module,
 ) -> crate::Result<()> {
@@ -510,9 +966,12 @@ unsafe fn initialize_tables(
         let val = match init {
             TableInitialValue::RefNull => None,
             TableInitialValue::ConstExpr(expr) => {
-                let funcref = const_eval.eval(expr)?.get_funcref();
+                let funcref = const_eval
+                    .eval(expr, ctx)?
+                    .as_vmval(ctx.store)
+                    .get_funcref();
                 // TODO assert funcref ptr is valid
-                Some(NonNull::new(funcref.cast()).unwrap())
+                NonNull::new(funcref.cast())
             }
         };
 
@@ -522,49 +981,126 @@ unsafe fn initialize_tables(
     // run active elements
     for segment in &module.translated().table_initializers.segments {
         let elements: Vec<_> = match &segment.elements {
-            TableSegmentElements::Functions(_funcs) => {
-                todo!()
-            }
+            TableSegmentElements::Functions(funcs) => funcs
+                .iter()
+                .map(|index| -> crate::Result<Option<NonNull<VMFuncRef>>> {
+                    let funcref = ctx.ref_func(*index)?.as_vmval(ctx.store).get_funcref();
+                    Ok(NonNull::new(funcref.cast()))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
             TableSegmentElements::Expressions(exprs) => exprs
                 .iter()
                 .map(|expr| -> crate::Result<Option<NonNull<VMFuncRef>>> {
-                    let funcref = const_eval.eval(expr)?.get_funcref();
+                    let funcref = const_eval
+                        .eval(expr, ctx)?
+                        .as_vmval(ctx.store)
+                        .get_funcref();
                     // TODO assert funcref ptr is valid
-                    Ok(Some(NonNull::new(funcref.cast()).unwrap()))
+                    Ok(NonNull::new(funcref.cast()))
                 })
                 .collect::<Result<Vec<_>, _>>()?,
         };
 
-        let offset = const_eval.eval(&segment.offset)?;
-        let offset = usize::try_from(offset.get_u64()).unwrap();
-
-        if let Some(def_index) = module.translated().defined_table_index(segment.table_index) {
-            tables[def_index].elements_mut()[offset..offset + elements.len()]
-                .copy_from_slice(&elements);
+        let offset = const_eval.eval(&segment.offset, ctx)?.as_vmval(ctx.store);
+        let offset = if module.translated().tables[segment.table_index].table64 {
+            offset.get_u64()
         } else {
-            todo!("initializing imported table")
-        }
+            u64::from(offset.get_u32())
+        };
+        let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+
+        let dst =
+            if let Some(def_index) = module.translated().defined_table_index(segment.table_index) {
+                tables[def_index].elements_mut()
+            } else {
+                // Imported tables are initialized through the exporter's `VMTableDefinition`,
+                // so the elements written here are visible to every instance sharing the table.
+                let import_offset = module.offsets().vmctx_vmtable_import(segment.table_index);
+                let import = ctx
+                    .vmctx
+                    .byte_add(usize::try_from(import_offset).unwrap())
+                    .cast::<VMTableImport>();
+                let definition = &*(*import).from;
+
+                slice::from_raw_parts_mut(
+                    definition.base.cast::<Option<NonNull<VMFuncRef>>>(),
+                    usize::try_from(definition.current_length).unwrap(),
+                )
+            };
+
+        let dst = offset
+            .checked_add(elements.len())
+            .and_then(|end| dst.get_mut(offset..end))
+            .ok_or_else(|| crate::Error::Trap {
+                trap: Trap::TableOutOfBounds,
+                message: "out of bounds table access".to_string(),
+                faulting_addr: None,
+                wasm_offset: None,
+            })?;
+        dst.copy_from_slice(&elements);
     }
 
     Ok(())
 }
 
-unsafe fn initialize_memories(
+/// Checks that the `len` bytes starting at `addr` lie within the bounds of the given memory and
+/// returns `addr` as an offset from the memory's base.
+fn validate_inbounds(memory: &VMMemoryDefinition, addr: u64, len: u64) -> Result<usize, Trap> {
+    let end = addr.checked_add(len).ok_or(Trap::MemoryOutOfBounds)?;
+    let current_length = u64::try_from(memory.current_length.load(Ordering::Relaxed)).unwrap();
+
+    if end > current_length {
+        Err(Trap::MemoryOutOfBounds)
+    } else {
+        Ok(usize::try_from(addr).unwrap())
+    }
+}
+
+unsafe fn initialize_memories<T>(
     const_eval: &mut ConstExprEvaluator,
+    ctx: &mut InitContext<T>,
     memories: &mut PrimaryMap<DefinedMemoryIndex, Memory>,
     module: &Module,
 ) -> crate::Result<()> {
     for init in &module.translated().memory_initializers {
-        let offset = const_eval.eval(&init.offset)?;
-        let offset = usize::try_from(offset.get_u64()).unwrap();
-
-        if let Some(def_index) = module.translated().defined_memory_index(init.memory_index) {
-            memories[def_index].as_slice_mut()
-                [offset..offset + init.data.len()]
-                .copy_from_slice(&init.data);
+        // the offset may read an imported global, whose value is only known at this point
+        let offset = const_eval.eval(&init.offset, ctx)?.as_vmval(ctx.store);
+        let offset = if module.translated().memories[init.memory_index].memory64 {
+            offset.get_u64()
         } else {
-            todo!("initializing imported table")
-        }
+            u64::from(offset.get_u32())
+        };
+
+        let dst =
+            if let Some(def_index) = module.translated().defined_memory_index(init.memory_index) {
+                memories[def_index].as_slice_mut()
+            } else {
+                // Imported memories are initialized through the exporter's `VMMemoryDefinition`, same
+                // as imported tables.
+                let import_offset = module.offsets().vmctx_vmmemory_import(init.memory_index);
+                let import = ctx
+                    .vmctx
+                    .byte_add(usize::try_from(import_offset).unwrap())
+                    .cast::<VMMemoryImport>();
+                let definition = &*(*import).from;
+
+                slice::from_raw_parts_mut(
+                    definition.base,
+                    definition.current_length.load(Ordering::Relaxed),
+                )
+            };
+
+        let dst = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| Some(offset..offset.checked_add(init.data.len())?))
+            .and_then(|range| dst.get_mut(range))
+            .ok_or_else(|| crate::Error::Trap {
+                trap: Trap::MemoryOutOfBounds,
+                message: "out of bounds memory access".to_string(),
+                faulting_addr: None,
+                wasm_offset: None,
+            })?;
+        dst.copy_from_slice(&init.data);
     }
 
     Ok(())
@@ -1,8 +1,19 @@
+use crate::placeholder::host_page_size;
 use crate::placeholder::mmap::Mmap;
 use crate::runtime::VMMemoryDefinition;
-use crate::translate::MemoryDesc;
+use crate::translate::{MemoryDesc, MemoryStyle};
 use crate::utils::round_usize_up_to_host_pages;
-use crate::MEMORY_MAX;
+use crate::{Error, MEMORY_MAX};
+use alloc::format;
+
+/// The reason growing a memory failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrowFailure {
+    /// The memory would exceed its declared maximum or its index space.
+    Limit,
+    /// The host couldn't or wouldn't provide the memory.
+    HostAllocation,
+}
 
 #[derive(Debug)]
 pub struct Memory {
@@ -28,15 +39,22 @@ impl Memory {
         actual_minimum_bytes: usize,
         actual_maximum_bytes: Option<usize>,
     ) -> crate::Result<Self> {
-        let offset_guard_bytes = usize::try_from(desc.offset_guard_size).unwrap();
-        // Ensure that our guard regions are multiples of the host page size.
-        let offset_guard_bytes = round_usize_up_to_host_pages(offset_guard_bytes);
-
-        let bound_bytes = round_usize_up_to_host_pages(MEMORY_MAX);
-        let allocation_bytes = bound_bytes.min(actual_maximum_bytes.unwrap_or(usize::MAX));
+        let mmap = Mmap::with_reserve(Self::reservation_size(desc, actual_maximum_bytes)?)?;
+        Self::with_reservation(desc, mmap, actual_minimum_bytes, actual_maximum_bytes)
+    }
 
-        let request_bytes = allocation_bytes + offset_guard_bytes;
-        let mut mmap = Mmap::with_reserve(request_bytes)?;
+    /// Creates a memory in an existing reservation, e.g. one handed out by a pool.
+    ///
+    /// The reservation must be inaccessible, zeroed and at least [`Memory::reservation_size`] bytes
+    /// large.
+    pub fn with_reservation(
+        desc: &MemoryDesc,
+        mut mmap: Mmap,
+        actual_minimum_bytes: usize,
+        actual_maximum_bytes: Option<usize>,
+    ) -> crate::Result<Self> {
+        let allocation_bytes = Self::allocation_size(desc, actual_maximum_bytes);
+        debug_assert!(mmap.len() >= Self::reservation_size(desc, actual_maximum_bytes)?);
 
         if actual_minimum_bytes > 0 {
             let accessible = round_usize_up_to_host_pages(actual_minimum_bytes);
@@ -44,14 +62,160 @@ impl Memory {
         }
 
         Ok(Self {
+            // everything past the bytes the memory may grow into acts as a guard region
+            offset_guard_size: mmap.len() - allocation_bytes,
             mmap,
             len: actual_minimum_bytes,
             maximum: actual_maximum_bytes,
             page_size_log2: desc.page_size_log2,
-            offset_guard_size: offset_guard_bytes,
         })
     }
 
+    /// Returns the actual minimum and maximum size in bytes of a memory described by `desc`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the minimum size doesn't fit our address space.
+    pub fn byte_limits(desc: &MemoryDesc) -> crate::Result<(usize, Option<usize>)> {
+        // TODO we could call out to some resource management instance here to obtain
+        // dynamic "minimum" and "maximum" values that reflect the state of the real systems
+        // memory consumption
+
+        // If the minimum memory size overflows the size of our own address
+        // space, then we can't satisfy this request.
+        let minimum = desc
+            .minimum_byte_size()
+            .ok()
+            .and_then(|m| usize::try_from(m).ok())
+            .ok_or_else(|| {
+                Error::AllocationFailed("memory minimum size exceeds the address space".into())
+            })?;
+
+        // The plan stores the maximum size in units of wasm pages, but we
+        // use units of bytes. Unlike for the `minimum` size we silently clamp
+        // the effective maximum size to the limits of what we can track. If the
+        // maximum size exceeds `usize` or `u64` then there's no need to further
+        // keep track of it as some sort of runtime limit will kick in long
+        // before we reach the statically declared maximum size.
+        let maximum = desc
+            .maximum_byte_size()
+            .ok()
+            .and_then(|m| usize::try_from(m).ok());
+
+        Ok((minimum, maximum))
+    }
+
+    /// Returns the number of bytes that need to be reserved for a memory described by `desc`,
+    /// including its guard pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reservation doesn't fit the host address space, e.g. because of a
+    /// large guard region on a 32-bit host.
+    pub fn reservation_size(
+        desc: &MemoryDesc,
+        actual_maximum_bytes: Option<usize>,
+    ) -> crate::Result<usize> {
+        let overflow = || {
+            Error::AllocationFailed(format!(
+                "memory reservation with a guard region of {} bytes exceeds the address space",
+                desc.offset_guard_size
+            ))
+        };
+
+        // Ensure that our guard regions are multiples of the host page size.
+        let offset_guard_bytes = usize::try_from(desc.offset_guard_size)
+            .ok()
+            .and_then(|bytes| bytes.checked_next_multiple_of(host_page_size().get()))
+            .ok_or_else(overflow)?;
+
+        Self::allocation_size(desc, actual_maximum_bytes)
+            .checked_add(offset_guard_bytes)
+            .ok_or_else(overflow)
+    }
+
+    /// Returns the number of bytes a memory may grow into.
+    fn allocation_size(desc: &MemoryDesc, actual_maximum_bytes: Option<usize>) -> usize {
+        let bound_bytes = round_usize_up_to_host_pages(MEMORY_MAX);
+        match desc.style {
+            // accesses to static memories are only checked against the largest possible size of
+            // the memory, so everything up to it needs to be reserved regardless of the maximum
+            MemoryStyle::Static => bound_bytes,
+            MemoryStyle::Dynamic => bound_bytes.min(actual_maximum_bytes.unwrap_or(usize::MAX)),
+        }
+    }
+
+    /// Returns the reservation backing this memory, so it can be reused.
+    pub fn into_mmap(self) -> Mmap {
+        self.mmap
+    }
+
+    /// Returns the current size of this memory in bytes.
+    pub fn byte_size(&self) -> usize {
+        self.len
+    }
+
+    /// Grows this memory by `delta_pages` pages.
+    ///
+    /// `allow` is called with the current size, the requested size and the maximum size of the
+    /// memory in bytes once the growth is known to stay within the memory's declared maximum, and
+    /// may deny it.
+    ///
+    /// Returns the old size of the memory in bytes or the reason the memory could not be grown.
+    ///
+    /// Growing only makes more of the reservation accessible, the memory never moves. Generated
+    /// code relies on this and keeps using the base pointer it loaded before a `memory.grow`.
+    pub fn grow(
+        &mut self,
+        delta_pages: u64,
+        allow: impl FnOnce(usize, usize, Option<usize>) -> bool,
+    ) -> Result<usize, GrowFailure> {
+        let old_byte_size = self.len;
+        if delta_pages == 0 {
+            return Ok(old_byte_size);
+        }
+
+        let new_byte_size = usize::try_from(delta_pages)
+            .ok()
+            .and_then(|delta_pages| delta_pages.checked_mul(1 << self.page_size_log2))
+            .and_then(|delta_bytes| old_byte_size.checked_add(delta_bytes))
+            .ok_or(GrowFailure::Limit)?;
+        if new_byte_size > self.maximum.unwrap_or(usize::MAX) {
+            return Err(GrowFailure::Limit);
+        }
+
+        // we can't grow past the memory we reserved upfront
+        let reserved = self.mmap.len() - self.offset_guard_size;
+        if new_byte_size > reserved || !allow(old_byte_size, new_byte_size, self.maximum) {
+            return Err(GrowFailure::HostAllocation);
+        }
+
+        // Memories with small page sizes might already have the new bytes accessible. Either way
+        // the new bytes read as zero: bytes past `len` are never written to and the reservation
+        // is zeroed to begin with.
+        let old_accessible = round_usize_up_to_host_pages(old_byte_size);
+        let new_accessible = round_usize_up_to_host_pages(new_byte_size);
+        if new_accessible > old_accessible {
+            self.mmap
+                .make_accessible(old_accessible, new_accessible - old_accessible)
+                .map_err(|_| GrowFailure::HostAllocation)?;
+        }
+
+        self.len = new_byte_size;
+        Ok(old_byte_size)
+    }
+
+    /// Returns the offset of `addr` relative to the base of this memory if it falls into the
+    /// memory's reservation, including its guard pages.
+    pub fn wasm_offset(&self, addr: usize) -> Option<u64> {
+        let offset = addr.checked_sub(self.mmap.as_ptr() as usize)?;
+        if offset < self.mmap.len() {
+            u64::try_from(offset).ok()
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn as_slice_mut(&mut self) -> &mut [u8] {
         // Safety: The constructor has to ensure that `self.len` is valid.
         unsafe { self.mmap.slice_mut(0..self.len) }
@@ -115,6 +115,20 @@ impl<T> MmapVec<T> {
         (self.mmap, self.len)
     }
 
+    /// Creates a vector from a mapping previously obtained through [`MmapVec::into_parts`] or
+    /// directly from [`Mmap`].
+    ///
+    /// # Safety
+    ///
+    /// The first `len` elements of `mmap` must be accessible and initialized.
+    pub(crate) unsafe fn from_parts(mmap: Mmap, len: usize) -> Self {
+        Self {
+            mmap,
+            len,
+            _m: PhantomData,
+        }
+    }
+
     fn try_grow(&mut self, additional: usize) -> crate::Result<usize> {
         let old_size = self.len;
         let old_accessible = self.accessible();
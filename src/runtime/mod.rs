@@ -1,11 +1,14 @@
 mod builtins;
 mod code_memory;
 mod const_eval;
+mod gc;
 mod instance;
 mod instance_allocator;
 mod memory;
 mod mmap_vec;
+mod on_demand_allocator;
 mod owned_vmcontext;
+mod pooling_allocator;
 mod table;
 mod vmcontext;
 mod vmoffsets;
@@ -13,19 +16,23 @@ mod vmoffsets;
 use alloc::vec::Vec;
 use core::ptr::NonNull;
 
-use crate::runtime::vmcontext::VMGlobalDefinition;
 use crate::translate::{GlobalDesc, MemoryDesc, TableDesc, TranslatedModule};
 pub use code_memory::CodeMemory;
-pub use const_eval::ConstExprEvaluator;
+pub use const_eval::{ConstEvalContext, ConstExprEvaluator};
+pub use gc::{GcHeap, ARRAY_ELEMS_OFFSET, ARRAY_LENGTH_OFFSET};
 pub use instance::Instance;
 pub use instance_allocator::InstanceAllocator;
-pub use memory::Memory;
+pub use memory::{GrowFailure, Memory};
 pub use mmap_vec::MmapVec;
+pub use on_demand_allocator::OnDemandAllocator;
 pub use owned_vmcontext::OwnedVMContext;
+pub use pooling_allocator::{PoolingAllocator, PoolingConfig};
 pub use table::Table;
 pub use vmcontext::{
-    VMContext, VMFuncRef, VMFunctionImport, VMGlobalImport, VMMemoryDefinition, VMMemoryImport,
-    VMOpaqueContext, VMTableDefinition, VMTableImport, VMVal, VMCONTEXT_MAGIC,
+    EpochDeadline, OutOfFuel, StatsEvent, VMArrayCallFunction, VMArrayCallHostFuncContext,
+    VMContext, VMFuncRef, VMFunctionImport, VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition,
+    VMMemoryImport, VMOpaqueContext, VMRuntimeLimits, VMTableDefinition, VMTableImport, VMVal,
+    VMWasmCallFunction, VMCONTEXT_MAGIC, VM_ARRAY_CALL_HOST_FUNC_MAGIC,
 };
 pub use vmoffsets::{StaticVMOffsets, VMOffsets};
 
@@ -0,0 +1,61 @@
+use crate::indices::{DefinedMemoryIndex, DefinedTableIndex};
+use crate::runtime::{InstanceAllocator, Memory, OwnedVMContext, Table, VMOffsets};
+use crate::translate::{MemoryDesc, TableDesc, TranslatedModule};
+
+/// An allocator that maps the `VMContext`, memories and tables of every instance on demand and
+/// unmaps them again once the instance is torn down.
+///
+/// This has no upfront cost and no limits besides the available address space, but every
+/// instantiation and teardown pays for a handful of `mmap`/`munmap` calls. Embedders that create
+/// many short-lived instances should consider the [`PoolingAllocator`][crate::PoolingAllocator].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OnDemandAllocator;
+
+impl InstanceAllocator for OnDemandAllocator {
+    unsafe fn allocate_vmctx(
+        &self,
+        _module: &TranslatedModule,
+        offsets: &VMOffsets,
+    ) -> crate::Result<OwnedVMContext> {
+        OwnedVMContext::try_new(offsets)
+    }
+
+    unsafe fn deallocate_vmctx(&self, vmctx: OwnedVMContext) {
+        // unmaps the backing memory
+        drop(vmctx);
+    }
+
+    unsafe fn allocate_memory(
+        &self,
+        _module: &TranslatedModule,
+        memory_desc: &MemoryDesc,
+        _memory_index: DefinedMemoryIndex,
+    ) -> crate::Result<Memory> {
+        let (minimum, maximum) = Memory::byte_limits(memory_desc)?;
+        Memory::try_new(memory_desc, minimum, maximum)
+    }
+
+    unsafe fn deallocate_memory(&self, _memory_index: DefinedMemoryIndex, memory: Memory) {
+        // unmaps the memory's whole reservation, including guard pages
+        drop(memory);
+    }
+
+    unsafe fn allocate_table(
+        &self,
+        _module: &TranslatedModule,
+        table_desc: &TableDesc,
+        _table_index: DefinedTableIndex,
+    ) -> crate::Result<Table> {
+        // TODO we could call out to some resource management instance here to obtain
+        // dynamic "minimum" and "maximum" values that reflect the state of the real systems
+        // memory consumption
+        let maximum = table_desc.maximum.and_then(|m| usize::try_from(m).ok());
+
+        Table::try_new(table_desc, maximum)
+    }
+
+    unsafe fn deallocate_table(&self, _table_index: DefinedTableIndex, table: Table) {
+        // unmaps the table's elements
+        drop(table);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::placeholder::mmap::Mmap;
 use crate::runtime::{MmapVec, VMContext, VMOffsets};
 use crate::utils::round_usize_up_to_host_pages;
 
@@ -9,6 +10,25 @@ impl OwnedVMContext {
         let vec = MmapVec::new_zeroed(round_usize_up_to_host_pages(offsets.size() as usize))?;
         Ok(Self(vec))
     }
+    /// Places a `VMContext` described by `offsets` at the start of an existing read-write mapping,
+    /// zeroing the part it occupies.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mmap` is too small to hold the `VMContext`.
+    pub fn with_mmap(mut mmap: Mmap, offsets: &VMOffsets) -> Self {
+        let size = round_usize_up_to_host_pages(usize::try_from(offsets.size()).unwrap());
+        assert!(size <= mmap.len(), "mapping too small for VMContext");
+        // Safety: we just checked that the range is part of the mapping, which is read-write
+        unsafe {
+            mmap.slice_mut(0..size).fill(0);
+            Self(MmapVec::from_parts(mmap, size))
+        }
+    }
+    /// Returns the mapping backing this `VMContext`, so it can be reused.
+    pub fn into_mmap(self) -> Mmap {
+        self.0.into_parts().0
+    }
     pub fn as_ptr(&self) -> *const VMContext {
         self.0.as_ptr().cast()
     }
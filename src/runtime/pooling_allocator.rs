@@ -0,0 +1,232 @@
+use crate::indices::{DefinedMemoryIndex, DefinedTableIndex};
+use crate::placeholder::mmap::Mmap;
+use crate::runtime::{InstanceAllocator, Memory, OwnedVMContext, Table, VMOffsets};
+use crate::translate::{MemoryDesc, TableDesc, TranslatedModule};
+use crate::utils::round_usize_up_to_host_pages;
+use crate::{Error, DEFAULT_OFFSET_GUARD_SIZE, MEMORY_MAX};
+use alloc::format;
+use alloc::vec::Vec;
+use cranelift_entity::EntityRef;
+use spin::Mutex;
+
+/// The limits of a [`PoolingAllocator`].
+#[derive(Debug, Clone)]
+pub struct PoolingConfig {
+    total_instances: usize,
+    max_memories_per_instance: usize,
+    max_tables_per_instance: usize,
+    max_vmctx_size: usize,
+}
+
+impl Default for PoolingConfig {
+    fn default() -> Self {
+        Self {
+            total_instances: 64,
+            max_memories_per_instance: 1,
+            max_tables_per_instance: 1,
+            max_vmctx_size: 1 << 20,
+        }
+    }
+}
+
+impl PoolingConfig {
+    /// Returns a new pooling configuration with default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of instance slots, i.e. how many instances can be alive at the same time.
+    ///
+    /// Every slot reserves address space for the maximum number of memories, each one
+    /// covering the largest possible 32-bit memory plus guard pages. The default is 64.
+    pub fn total_instances(&mut self, total_instances: usize) -> &mut Self {
+        self.total_instances = total_instances;
+        self
+    }
+
+    /// Sets the maximum number of memories a single instance may define. The default is 1.
+    pub fn max_memories_per_instance(&mut self, max: usize) -> &mut Self {
+        self.max_memories_per_instance = max;
+        self
+    }
+
+    /// Sets the maximum number of tables a single instance may define. The default is 1.
+    pub fn max_tables_per_instance(&mut self, max: usize) -> &mut Self {
+        self.max_tables_per_instance = max;
+        self
+    }
+
+    /// Sets the maximum size in bytes of the `VMContext` of an instance, which grows with the
+    /// number of functions, imports and globals of a module. The default is 1 MiB.
+    pub fn max_vmctx_size(&mut self, max: usize) -> &mut Self {
+        self.max_vmctx_size = max;
+        self
+    }
+}
+
+/// An allocator that maps the resources of a fixed number of instance slots upfront and reuses
+/// them, so instantiation and teardown don't have to map and unmap memory.
+///
+/// Resources returned to the pool are decommitted, so they come back zeroed on the next use.
+#[derive(Debug)]
+pub struct PoolingAllocator {
+    max_memories_per_instance: usize,
+    max_tables_per_instance: usize,
+    vmctx_slot_size: usize,
+    memory_slot_size: usize,
+    table_slot_size: usize,
+
+    vmctxs: Mutex<Vec<Mmap>>,
+    memories: Mutex<Vec<Mmap>>,
+    tables: Mutex<Vec<Mmap>>,
+}
+
+impl PoolingAllocator {
+    /// Creates a new pooling allocator, mapping all slots described by `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if mapping the slots fails, e.g. because the configured limits exceed the
+    /// available address space.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configured limits overflow.
+    pub fn new(config: &PoolingConfig) -> crate::Result<Self> {
+        let vmctx_slot_size = round_usize_up_to_host_pages(config.max_vmctx_size);
+        let memory_slot_size = round_usize_up_to_host_pages(MEMORY_MAX)
+            + round_usize_up_to_host_pages(usize::try_from(DEFAULT_OFFSET_GUARD_SIZE).unwrap());
+        let table_slot_size = Table::reservation_size(None);
+
+        let num_memories = config
+            .total_instances
+            .checked_mul(config.max_memories_per_instance)
+            .unwrap();
+        let num_tables = config
+            .total_instances
+            .checked_mul(config.max_tables_per_instance)
+            .unwrap();
+
+        let vmctxs = (0..config.total_instances)
+            .map(|_| Mmap::new(vmctx_slot_size))
+            .collect::<crate::Result<_>>()?;
+        let memories = (0..num_memories)
+            .map(|_| Mmap::with_reserve(memory_slot_size))
+            .collect::<crate::Result<_>>()?;
+        let tables = (0..num_tables)
+            .map(|_| Mmap::with_reserve(table_slot_size))
+            .collect::<crate::Result<_>>()?;
+
+        Ok(Self {
+            max_memories_per_instance: config.max_memories_per_instance,
+            max_tables_per_instance: config.max_tables_per_instance,
+            vmctx_slot_size,
+            memory_slot_size,
+            table_slot_size,
+            vmctxs: Mutex::new(vmctxs),
+            memories: Mutex::new(memories),
+            tables: Mutex::new(tables),
+        })
+    }
+}
+
+/// Returns a decommitted reservation to `pool`.
+///
+/// If decommitting fails the reservation is unmapped instead and the pool shrinks by one slot,
+/// handing out memory that still holds data of a previous instance is never an option.
+fn release(pool: &Mutex<Vec<Mmap>>, mut mmap: Mmap) {
+    match mmap.decommit() {
+        Ok(()) => pool.lock().push(mmap),
+        Err(err) => tracing::warn!("failed to decommit pooled reservation, dropping it: {err}"),
+    }
+}
+
+impl InstanceAllocator for PoolingAllocator {
+    unsafe fn allocate_vmctx(
+        &self,
+        _module: &TranslatedModule,
+        offsets: &VMOffsets,
+    ) -> crate::Result<OwnedVMContext> {
+        let size = usize::try_from(offsets.size()).unwrap();
+        if size > self.vmctx_slot_size {
+            return Err(Error::AllocationFailed(format!(
+                "VMContext of {size} bytes exceeds the maximum of {} bytes",
+                self.vmctx_slot_size
+            )));
+        }
+
+        let mmap = self
+            .vmctxs
+            .lock()
+            .pop()
+            .ok_or_else(|| Error::AllocationFailed("all instance slots are in use".into()))?;
+        Ok(OwnedVMContext::with_mmap(mmap, offsets))
+    }
+
+    unsafe fn deallocate_vmctx(&self, vmctx: OwnedVMContext) {
+        // the `VMContext` is zeroed when it's handed out again, so there is no need to decommit
+        self.vmctxs.lock().push(vmctx.into_mmap());
+    }
+
+    unsafe fn allocate_memory(
+        &self,
+        _module: &TranslatedModule,
+        memory_desc: &MemoryDesc,
+        memory_index: DefinedMemoryIndex,
+    ) -> crate::Result<Memory> {
+        if memory_index.index() >= self.max_memories_per_instance {
+            return Err(Error::AllocationFailed(format!(
+                "instance defines more than {} memories",
+                self.max_memories_per_instance
+            )));
+        }
+
+        let (minimum, maximum) = Memory::byte_limits(memory_desc)?;
+        let size = Memory::reservation_size(memory_desc, maximum)?;
+        if size > self.memory_slot_size {
+            return Err(Error::AllocationFailed(format!(
+                "memory reservation of {size} bytes exceeds the pool's slot size of {} bytes",
+                self.memory_slot_size
+            )));
+        }
+
+        let mmap = self
+            .memories
+            .lock()
+            .pop()
+            .ok_or_else(|| Error::AllocationFailed("all memory slots are in use".into()))?;
+        Memory::with_reservation(memory_desc, mmap, minimum, maximum)
+    }
+
+    unsafe fn deallocate_memory(&self, _memory_index: DefinedMemoryIndex, memory: Memory) {
+        release(&self.memories, memory.into_mmap());
+    }
+
+    unsafe fn allocate_table(
+        &self,
+        _module: &TranslatedModule,
+        table_desc: &TableDesc,
+        table_index: DefinedTableIndex,
+    ) -> crate::Result<Table> {
+        if table_index.index() >= self.max_tables_per_instance {
+            return Err(Error::AllocationFailed(format!(
+                "instance defines more than {} tables",
+                self.max_tables_per_instance
+            )));
+        }
+
+        let maximum = table_desc.maximum.and_then(|m| usize::try_from(m).ok());
+        debug_assert!(Table::reservation_size(maximum) <= self.table_slot_size);
+
+        let mmap = self
+            .tables
+            .lock()
+            .pop()
+            .ok_or_else(|| Error::AllocationFailed("all table slots are in use".into()))?;
+        Table::with_reservation(table_desc, mmap, maximum)
+    }
+
+    unsafe fn deallocate_table(&self, _table_index: DefinedTableIndex, table: Table) {
+        release(&self.tables, table.into_mmap());
+    }
+}
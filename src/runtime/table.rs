@@ -1,3 +1,4 @@
+use crate::placeholder::mmap::Mmap;
 use crate::runtime::{MmapVec, VMFuncRef, VMTableDefinition};
 use crate::translate::TableDesc;
 use crate::utils::round_usize_up_to_host_pages;
@@ -14,21 +15,48 @@ pub struct Table {
 
 impl Table {
     pub fn try_new(desc: &TableDesc, actual_maximum: Option<usize>) -> crate::Result<Self> {
-        let reserve_size = TABLE_MAX.min(actual_maximum.unwrap_or(usize::MAX));
-
-        let elements = if reserve_size == 0 {
-            MmapVec::new()
+        let reserve_size = Self::reservation_size(actual_maximum);
+        let mmap = if reserve_size == 0 {
+            Mmap::new_empty()
         } else {
-            let mut elements = MmapVec::with_reserved(round_usize_up_to_host_pages(reserve_size))?;
-            elements.try_extend_with(usize::try_from(desc.minimum).unwrap(), None)?;
-            elements
+            Mmap::with_reserve(reserve_size)?
         };
 
+        Self::with_reservation(desc, mmap, actual_maximum)
+    }
+
+    /// Creates a table in an existing reservation, e.g. one handed out by a pool.
+    ///
+    /// The reservation must be inaccessible and at least [`Table::reservation_size`] bytes large.
+    pub fn with_reservation(
+        desc: &TableDesc,
+        mmap: Mmap,
+        actual_maximum: Option<usize>,
+    ) -> crate::Result<Self> {
+        debug_assert!(mmap.len() >= Self::reservation_size(actual_maximum));
+
+        // Safety: the vector starts out empty
+        let mut elements = unsafe { MmapVec::from_parts(mmap, 0) };
+        if desc.minimum > 0 {
+            elements.try_extend_with(usize::try_from(desc.minimum).unwrap(), None)?;
+        }
+
         Ok(Self {
             elements,
             maximum: actual_maximum,
         })
     }
+
+    /// Returns the number of bytes that need to be reserved for a table with the given maximum.
+    pub fn reservation_size(actual_maximum: Option<usize>) -> usize {
+        round_usize_up_to_host_pages(TABLE_MAX.min(actual_maximum.unwrap_or(usize::MAX)))
+    }
+
+    /// Returns the reservation backing this table, so it can be reused.
+    pub fn into_mmap(self) -> Mmap {
+        self.elements.into_parts().0
+    }
+
     pub fn elements_mut(&mut self) -> &mut [Option<NonNull<VMFuncRef>>] {
         self.elements.slice_mut()
     }
@@ -1,13 +1,22 @@
 use crate::indices::VMSharedTypeIndex;
+use crate::runtime::GcHeap;
+use crate::store::StoreLimiter;
+#[cfg(feature = "stats")]
+use crate::store::StoreStats;
+use crate::translate::WasmValType;
+use crate::MAX_WASM_STACK;
+use alloc::boxed::Box;
+use core::any::{Any, TypeId};
+use core::cell::{Cell, RefCell};
 use core::ffi::c_void;
 use core::fmt;
 use core::marker::PhantomPinned;
 use core::ptr::NonNull;
 use core::sync::atomic::AtomicUsize;
 use cranelift_entity::Unsigned;
-use wasmparser::ValType;
 
 pub const VMCONTEXT_MAGIC: u32 = u32::from_le_bytes(*b"vmcx");
+pub const VM_ARRAY_CALL_HOST_FUNC_MAGIC: u32 = u32::from_le_bytes(*b"ACHF");
 
 /// The VM "context", which holds guest-side instance state such as
 /// globals, table pointers, memory pointers and other runtime information.
@@ -75,17 +84,120 @@ impl VMOpaqueContext {
     pub fn from_vmcontext(ptr: *mut VMContext) -> *mut VMOpaqueContext {
         ptr.cast()
     }
+
+    /// Helper function to clearly indicate that casts are desired.
+    #[inline]
+    pub fn from_vm_array_call_host_func_context(
+        ptr: *mut VMArrayCallHostFuncContext,
+    ) -> *mut VMOpaqueContext {
+        ptr.cast()
+    }
+}
+
+/// The `vmctx` of host functions that use the array calling convention.
+///
+/// Host functions don't have an instance they belong to, so their `VMFuncRef::vmctx` points to
+/// this context instead. It holds the `VMFuncRef` itself, the host closure that should be
+/// invoked when the function is called and the runtime limits of the store the function belongs
+/// to, through which the closure reaches its store.
+#[repr(C, align(16))] // align 16 so casting to and from `VMOpaqueContext` is sound
+pub struct VMArrayCallHostFuncContext {
+    pub(crate) magic: u32,
+    pub(crate) func_ref: VMFuncRef,
+    host_state: Box<dyn Any + Send + Sync>,
+    runtime_limits: *const VMRuntimeLimits,
+}
+
+impl VMArrayCallHostFuncContext {
+    /// Creates a new host function context.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `array_call` and `wasm_call` are valid function pointers that
+    /// expect this context as their `vmctx` and that `type_index` describes their signature.
+    pub unsafe fn new(
+        array_call: VMArrayCallFunction,
+        wasm_call: NonNull<VMWasmCallFunction>,
+        type_index: VMSharedTypeIndex,
+        host_state: Box<dyn Any + Send + Sync>,
+    ) -> Box<VMArrayCallHostFuncContext> {
+        let mut ctx = Box::new(VMArrayCallHostFuncContext {
+            magic: VM_ARRAY_CALL_HOST_FUNC_MAGIC,
+            func_ref: VMFuncRef {
+                array_call,
+                wasm_call,
+                vmctx: core::ptr::null_mut(),
+                type_index,
+            },
+            host_state,
+            runtime_limits: core::ptr::null(),
+        });
+        // the context is heap allocated, so the self-reference stays valid when the box is moved
+        let vmctx = VMOpaqueContext::from_vm_array_call_host_func_context(&mut *ctx);
+        ctx.func_ref.vmctx = vmctx;
+        ctx
+    }
+
+    /// Returns a pointer to the `VMFuncRef` of this host function.
+    pub fn func_ref(&mut self) -> NonNull<VMFuncRef> {
+        NonNull::from(&mut self.func_ref)
+    }
+
+    /// Returns the host state (i.e. the Rust closure) associated with this function.
+    pub fn host_state(&self) -> &(dyn Any + Send + Sync) {
+        &*self.host_state
+    }
+
+    /// Returns the runtime limits of the store this function belongs to, null until the function
+    /// is added to a store.
+    pub fn runtime_limits(&self) -> *const VMRuntimeLimits {
+        self.runtime_limits
+    }
+
+    /// Sets the runtime limits of the store this function was added to.
+    pub(crate) fn set_runtime_limits(&mut self, runtime_limits: *const VMRuntimeLimits) {
+        self.runtime_limits = runtime_limits;
+    }
+
+    /// Helper function to cast between context types using a debug assertion to
+    /// protect against some mistakes.
+    #[inline]
+    pub unsafe fn from_opaque(opaque: *mut VMOpaqueContext) -> *mut VMArrayCallHostFuncContext {
+        // See `VMContext::from_opaque` for why this is only a debug assertion.
+        debug_assert_eq!((*opaque).magic, VM_ARRAY_CALL_HOST_FUNC_MAGIC);
+        opaque.cast()
+    }
 }
 
+impl fmt::Debug for VMArrayCallHostFuncContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VMArrayCallHostFuncContext")
+            .field("func_ref", &self.func_ref)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A raw, untyped WebAssembly value as passed through the array-call ABI.
+///
+/// Every value occupies 16 bytes and is stored in little-endian byte order, the type of a value
+/// is only known from the signature of the function it is passed to or returned from.
 #[derive(Clone, Copy)]
 pub union VMVal {
+    /// An `i32` value.
     pub i32: i32,
+    /// An `i64` value.
     pub i64: i64,
+    /// The bits of an `f32` value.
     pub f32: u32,
+    /// The bits of an `f64` value.
     pub f64: u64,
+    /// A `v128` value.
     pub v128: [u8; 16],
+    /// A function reference, a pointer to a `VMFuncRef` or null.
     pub funcref: *mut c_void,
+    /// An external reference, only null is supported.
     pub externref: u32,
+    /// An internal reference, only null is supported.
     pub anyref: u32,
 }
 
@@ -97,106 +209,120 @@ impl PartialEq for VMVal {
 }
 
 impl VMVal {
+    /// Creates a value from an `i32`.
     #[inline]
     pub fn i32(i: i32) -> VMVal {
         VMVal::i64(i64::from(i))
     }
+    /// Creates a value from an `i64`.
     #[inline]
     pub fn i64(i: i64) -> VMVal {
         VMVal { i64: i.to_le() }
     }
+    /// Creates a value from a `u32`.
     #[inline]
     pub fn u32(i: u32) -> VMVal {
         VMVal::u64(u64::from(i))
     }
+    /// Creates a value from a `u64`.
     #[inline]
     pub fn u64(i: u64) -> VMVal {
-        VMVal::i64(i64::try_from(i).unwrap())
+        VMVal::i64(i64::from_ne_bytes(i.to_ne_bytes()))
     }
+    /// Creates a value from the bits of an `f32`.
     #[inline]
     pub fn f32(i: u32) -> VMVal {
         VMVal { f32: i.to_le() }
     }
+    /// Creates a value from the bits of an `f64`.
     #[inline]
     pub fn f64(i: u64) -> VMVal {
         VMVal { f64: i.to_le() }
     }
+    /// Creates a value from a `v128`.
     #[inline]
     pub fn v128(i: u128) -> VMVal {
         VMVal {
             v128: i.to_le_bytes(),
         }
     }
+    /// Creates a value from a function reference.
     #[inline]
     pub fn funcref(ptr: *mut c_void) -> VMVal {
         VMVal {
             funcref: ptr.map_addr(usize::to_le),
         }
     }
+    /// Creates a value from an external reference.
     #[inline]
     pub fn externref(e: u32) -> VMVal {
-        assert_eq!(e, 0, "gc not supported");
         VMVal {
             externref: e.to_le(),
         }
     }
+    /// Creates a value from an internal reference.
     #[inline]
     pub fn anyref(r: u32) -> VMVal {
-        assert_eq!(r, 0, "gc not supported");
         VMVal { anyref: r.to_le() }
     }
 
+    /// Returns the value as an `i32`.
     #[inline]
     pub fn get_i32(&self) -> i32 {
         // Safety: we're accessing a union
         unsafe { i32::from_le(self.i32) }
     }
+    /// Returns the value as an `i64`.
     #[inline]
     pub fn get_i64(&self) -> i64 {
         // Safety: we're accessing a union
         unsafe { i64::from_le(self.i64) }
     }
+    /// Returns the value as a `u32`.
     #[inline]
     pub fn get_u32(&self) -> u32 {
         self.get_i32().unsigned()
     }
+    /// Returns the value as a `u64`.
     #[inline]
     pub fn get_u64(&self) -> u64 {
         self.get_i64().unsigned()
     }
+    /// Returns the value as the bits of an `f32`.
     #[inline]
     pub fn get_f32(&self) -> u32 {
         // Safety: we're accessing a union
         unsafe { u32::from_le(self.f32) }
     }
+    /// Returns the value as the bits of an `f64`.
     #[inline]
     pub fn get_f64(&self) -> u64 {
         // Safety: we're accessing a union
         unsafe { u64::from_le(self.f64) }
     }
+    /// Returns the value as a `v128`.
     #[inline]
     pub fn get_v128(&self) -> u128 {
         // Safety: we're accessing a union
         unsafe { u128::from_le_bytes(self.v128) }
     }
+    /// Returns the value as a function reference.
     #[inline]
     pub fn get_funcref(&self) -> *mut c_void {
         // Safety: we're accessing a union
         unsafe { self.funcref.map_addr(usize::from_le) }
     }
+    /// Returns the value as an external reference.
     #[inline]
     pub fn get_externref(&self) -> u32 {
         // Safety: we're accessing a union
-        let externref = u32::from_le(unsafe { self.externref });
-        assert_eq!(externref, 0, "gc not supported");
-        externref
+        u32::from_le(unsafe { self.externref })
     }
+    /// Returns the value as an internal reference.
     #[inline]
     pub fn get_anyref(&self) -> u32 {
         // Safety: we're accessing a union
-        let anyref = u32::from_le(unsafe { self.anyref });
-        assert_eq!(anyref, 0, "gc not supported");
-        anyref
+        u32::from_le(unsafe { self.anyref })
     }
 }
 
@@ -329,6 +455,130 @@ pub struct VMGlobalImport {
     pub vmctx: *mut VMContext,
 }
 
+/// Limits shared by all instances of a store.
+///
+/// Every `VMContext` points to the `VMRuntimeLimits` of its store, so that WebAssembly code of all
+/// instances observes the same stack limit, epoch deadline and fuel, even when calling between
+/// them.
+#[derive(Debug)]
+#[repr(C)]
+pub struct VMRuntimeLimits {
+    /// The lowest address WebAssembly code may grow the stack to, `0` while not executing
+    /// WebAssembly.
+    pub stack_limit: Cell<usize>,
+    /// The number of bytes of stack WebAssembly code may use, this is not accessed by JIT code.
+    pub max_wasm_stack: Cell<usize>,
+    /// The epoch at which executing WebAssembly code is interrupted.
+    pub epoch_deadline: Cell<u64>,
+    /// What happens once the epoch deadline is reached, this is not accessed by JIT code.
+    pub epoch_deadline_behavior: Cell<EpochDeadline>,
+    /// The fuel consumed so far, as a negative number counting up towards zero.
+    ///
+    /// JIT code adds the cost of the operators it executes and runs out of fuel once this is no
+    /// longer negative.
+    pub fuel_consumed: Cell<i64>,
+    /// What happens once the fuel runs out, this is not accessed by JIT code.
+    pub out_of_fuel_behavior: Cell<OutOfFuel>,
+    /// Whether `memory.grow` traps instead of returning `-1` when the host fails to provide the
+    /// memory, this is not accessed by JIT code.
+    pub trap_on_memory_grow_failure: Cell<bool>,
+    /// Consulted before memories grow, this is not accessed by JIT code.
+    pub limiter: RefCell<Option<StoreLimiter>>,
+    /// The start of the store's GC heap, GC references are offsets relative to it.
+    ///
+    /// This is null until the first GC object is allocated.
+    pub gc_heap_base: Cell<*mut u8>,
+    /// The store's GC heap, allocated on first use, this is not accessed by JIT code.
+    pub gc_heap: RefCell<Option<GcHeap>>,
+    /// Counters of events in the store, this is not accessed by JIT code.
+    #[cfg(feature = "stats")]
+    pub stats: Cell<StoreStats>,
+    /// The store while it is calling into WebAssembly or host functions, null otherwise.
+    ///
+    /// Host functions build their `Caller` from this pointer, the call that published it doesn't
+    /// touch the store until the call returns. This is not accessed by JIT code.
+    pub store: Cell<*mut ()>,
+    /// The `TypeId` of the store's data, recorded once the first host function is added to the
+    /// store. Host functions check it before casting `store`, this is not accessed by JIT code.
+    pub store_type: Cell<Option<TypeId>>,
+}
+
+impl Default for VMRuntimeLimits {
+    fn default() -> Self {
+        Self {
+            stack_limit: Cell::new(0),
+            max_wasm_stack: Cell::new(MAX_WASM_STACK),
+            epoch_deadline: Cell::new(0),
+            epoch_deadline_behavior: Cell::new(EpochDeadline::Trap),
+            fuel_consumed: Cell::new(0),
+            out_of_fuel_behavior: Cell::new(OutOfFuel::Trap),
+            trap_on_memory_grow_failure: Cell::new(false),
+            limiter: RefCell::new(None),
+            gc_heap_base: Cell::new(core::ptr::null_mut()),
+            gc_heap: RefCell::new(None),
+            #[cfg(feature = "stats")]
+            stats: Cell::new(StoreStats::default()),
+            store: Cell::new(core::ptr::null_mut()),
+            store_type: Cell::new(None),
+        }
+    }
+}
+
+impl VMRuntimeLimits {
+    /// Counts `event` in the store's statistics.
+    ///
+    /// This compiles to nothing unless the `stats` feature is enabled.
+    #[inline]
+    pub fn count(&self, event: StatsEvent) {
+        #[cfg(feature = "stats")]
+        {
+            let mut stats = self.stats.get();
+            let counter = match event {
+                StatsEvent::WasmCall => &mut stats.wasm_calls,
+                StatsEvent::HostCall => &mut stats.host_calls,
+                StatsEvent::MemoryGrow => &mut stats.memory_grows,
+                StatsEvent::Trap => &mut stats.traps,
+            };
+            *counter = counter.saturating_add(1);
+            self.stats.set(stats);
+        }
+        #[cfg(not(feature = "stats"))]
+        let _ = event;
+    }
+}
+
+/// The events counted by [`VMRuntimeLimits::count`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatsEvent {
+    /// A call from the host into WebAssembly.
+    WasmCall,
+    /// A call from WebAssembly into a host function.
+    HostCall,
+    /// An attempt to grow a memory.
+    MemoryGrow,
+    /// A call into WebAssembly that ended in a trap.
+    Trap,
+}
+
+/// What happens when WebAssembly code reaches the epoch deadline of its store.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EpochDeadline {
+    /// Trap with [`Trap::Interrupt`][crate::Trap::Interrupt].
+    Trap,
+    /// Yield back to the async runtime and extend the deadline by the given number of ticks once
+    /// execution resumes.
+    YieldAndUpdate(u64),
+}
+
+/// What happens when WebAssembly code runs out of fuel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutOfFuel {
+    /// Trap with [`Trap::OutOfFuel`][crate::Trap::OutOfFuel].
+    Trap,
+    /// Yield back to the async runtime and add the given amount of fuel once execution resumes.
+    YieldAndRefuel(u64),
+}
+
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct VMTableDefinition {
@@ -359,22 +609,24 @@ impl VMGlobalDefinition {
         Self { data: vmval.v128 }
     }
 
-    pub unsafe fn to_vmval(&self, wasm_ty: ValType) -> VMVal {
+    pub unsafe fn to_vmval(&self, wasm_ty: &WasmValType) -> VMVal {
         match wasm_ty {
-            ValType::I32 => VMVal {
+            WasmValType::I32 => VMVal {
                 i32: *self.as_i32(),
             },
-            ValType::I64 => VMVal {
+            WasmValType::I64 => VMVal {
                 i64: *self.as_i64(),
             },
-            ValType::F32 => VMVal {
+            WasmValType::F32 => VMVal {
                 f32: *self.as_f32_bits(),
             },
-            ValType::F64 => VMVal {
+            WasmValType::F64 => VMVal {
                 f64: *self.as_f64_bits(),
             },
-            ValType::V128 => VMVal { v128: self.data },
-            ValType::Ref(_) => todo!(),
+            WasmValType::V128 => VMVal { v128: self.data },
+            // `from_vmval` stores all values as their 16 byte representation so just reading
+            // the bytes back out gives us the correct reference.
+            WasmValType::Ref(_) => VMVal { v128: self.data },
         }
     }
 
@@ -4,10 +4,12 @@
 //!     _padding: u32, // on 64-bit platforms
 //!     builtin_functions: *const VMBuiltinFunctionsArray,
 //!     type_ids: *const VMSharedTypeIndex,
-//!     stack_limit: *const u8,
+//!     runtime_limits: *const VMRuntimeLimits,
+//!     epoch_ptr: *const AtomicU64,
 //!     last_wasm_exit_fp: *const u8,
 //!     last_wasm_exit_pc: *const u8,
 //!     last_wasm_entry_fp: *const u8,
+//!     instance: *mut Instance,
 //!     func_refs: [VMFuncRef; num_escaped_funcs],
 //!     imported_functions: [VMFunctionImport; num_imported_functions)],
 //!     imported_tables: [VMTableImport; num_imported_tables],
@@ -25,7 +27,7 @@ use crate::indices::{
 };
 use crate::runtime::vmcontext::{
     VMFuncRef, VMFunctionImport, VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition,
-    VMMemoryImport, VMTableDefinition, VMTableImport,
+    VMMemoryImport, VMRuntimeLimits, VMTableDefinition, VMTableImport,
 };
 use crate::translate::TranslatedModule;
 use crate::u32_offset_of;
@@ -45,10 +47,12 @@ impl fmt::Debug for StaticVMOffsets {
         f.debug_struct("StaticVMOffsets")
             .field("vmctx_magic", &self.size())
             .field("vmctx_builtin_functions", &self.vmctx_builtin_functions())
-            .field("vmctx_stack_limit", &self.vmctx_stack_limit())
+            .field("vmctx_runtime_limits", &self.vmctx_runtime_limits())
+            .field("vmctx_epoch_ptr", &self.vmctx_epoch_ptr())
             .field("vmctx_last_wasm_exit_fp", &self.vmctx_last_wasm_exit_fp())
             .field("vmctx_last_wasm_exit_pc", &self.vmctx_last_wasm_exit_pc())
             .field("vmctx_last_wasm_entry_fp", &self.vmctx_last_wasm_entry_fp())
+            .field("vmctx_instance", &self.vmctx_instance())
             .finish()
     }
 }
@@ -80,16 +84,27 @@ impl StaticVMOffsets {
         self.vmctx_builtin_functions() + self.ptr_size
     }
 
-    /// Offset of the `stack_limit` field in a `VMContext`.
+    /// Offset of the `runtime_limits` field in a `VMContext`.
+    ///
+    /// The field holds a pointer to the `VMRuntimeLimits` of the store, which are shared by all
+    /// its instances.
     #[inline]
-    pub const fn vmctx_stack_limit(&self) -> u8 {
+    pub const fn vmctx_runtime_limits(&self) -> u8 {
         self.vmctx_type_ids() + self.ptr_size
     }
 
+    /// Offset of the `epoch_ptr` field in a `VMContext`.
+    ///
+    /// The field holds a pointer to the epoch counter of the engine.
+    #[inline]
+    pub const fn vmctx_epoch_ptr(&self) -> u8 {
+        self.vmctx_runtime_limits() + self.ptr_size
+    }
+
     /// Offset of the `last_wasm_exit_fp` field in a `VMContext`.
     #[inline]
     pub const fn vmctx_last_wasm_exit_fp(&self) -> u8 {
-        self.vmctx_stack_limit() + self.ptr_size
+        self.vmctx_epoch_ptr() + self.ptr_size
     }
 
     /// Offset of the `last_wasm_exit_pc` field in a `VMContext`.
@@ -104,10 +119,44 @@ impl StaticVMOffsets {
         self.vmctx_last_wasm_exit_pc() + self.ptr_size
     }
 
+    /// Offset of the `instance` field in a `VMContext`.
+    #[inline]
+    pub const fn vmctx_instance(&self) -> u8 {
+        self.vmctx_last_wasm_entry_fp() + self.ptr_size
+    }
+
     /// The size of the statically known part of a `VMContext`.
     #[inline]
     const fn size(&self) -> u8 {
-        self.vmctx_last_wasm_entry_fp() + self.ptr_size
+        self.vmctx_instance() + self.ptr_size
+    }
+
+    /// Offset of the `stack_limit` field in `VMRuntimeLimits`.
+    #[inline]
+    #[expect(clippy::unused_self, reason = "accessor")]
+    pub fn vmruntime_limits_stack_limit(&self) -> u32 {
+        u32_offset_of!(VMRuntimeLimits, stack_limit)
+    }
+
+    /// Offset of the `epoch_deadline` field in `VMRuntimeLimits`.
+    #[inline]
+    #[expect(clippy::unused_self, reason = "accessor")]
+    pub fn vmruntime_limits_epoch_deadline(&self) -> u32 {
+        u32_offset_of!(VMRuntimeLimits, epoch_deadline)
+    }
+
+    /// Offset of the `fuel_consumed` field in `VMRuntimeLimits`.
+    #[inline]
+    #[expect(clippy::unused_self, reason = "accessor")]
+    pub fn vmruntime_limits_fuel_consumed(&self) -> u32 {
+        u32_offset_of!(VMRuntimeLimits, fuel_consumed)
+    }
+
+    /// Offset of the `gc_heap_base` field in `VMRuntimeLimits`.
+    #[inline]
+    #[expect(clippy::unused_self, reason = "accessor")]
+    pub fn vmruntime_limits_gc_heap_base(&self) -> u32 {
+        u32_offset_of!(VMRuntimeLimits, gc_heap_base)
     }
 
     /// Return the size of `VMSharedTypeIndex`.
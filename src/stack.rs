@@ -0,0 +1,72 @@
+use crate::placeholder::host_page_size;
+use crate::Error;
+use alloc::format;
+use core::ops::Range;
+
+/// A region of memory provided by the embedder for WebAssembly code to run on.
+///
+/// By default WebAssembly code runs on the stack of the thread calling into it, embedders that
+/// manage their own stacks can instead provide one through [`Store::set_stack`][crate::Store::set_stack].
+///
+/// Stacks grow downwards, so the guard region has to lie directly below the stack. WebAssembly
+/// code traps with [`Trap::StackOverflow`][crate::Trap::StackOverflow] before it would grow into
+/// the guard region.
+#[derive(Debug, Clone)]
+pub struct StackRegion {
+    stack: Range<usize>,
+    guard: Range<usize>,
+}
+
+impl StackRegion {
+    /// Creates a new stack region from the usable `stack` memory and the `guard` region below it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidStack`] if either region is empty, isn't aligned to the host page
+    /// size, or if the guard region doesn't lie directly below the stack.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `stack` is valid for reads and writes for as long as the region
+    /// is set on a store and that nothing else uses it while WebAssembly code runs on it. The
+    /// `guard` region should be mapped inaccessible, so that host code overflowing the stack
+    /// faults instead of silently corrupting memory.
+    pub unsafe fn new(stack: Range<*mut u8>, guard: Range<*mut u8>) -> crate::Result<Self> {
+        let stack = stack.start as usize..stack.end as usize;
+        let guard = guard.start as usize..guard.end as usize;
+
+        if stack.is_empty() || guard.is_empty() {
+            return Err(Error::InvalidStack(format!(
+                "stack {stack:#x?} and guard {guard:#x?} must not be empty"
+            )));
+        }
+
+        let page_size = host_page_size().get();
+        let is_page_aligned =
+            |range: &Range<usize>| range.start % page_size == 0 && range.end % page_size == 0;
+        if !is_page_aligned(&stack) || !is_page_aligned(&guard) {
+            return Err(Error::InvalidStack(format!(
+                "stack {stack:#x?} and guard {guard:#x?} must be aligned to the host page size ({page_size:#x})"
+            )));
+        }
+
+        if guard.end != stack.start {
+            return Err(Error::InvalidStack(format!(
+                "guard {guard:#x?} must lie directly below the stack {stack:#x?}"
+            )));
+        }
+
+        Ok(Self { stack, guard })
+    }
+
+    /// Returns the initial stack pointer, i.e. the highest address of the stack.
+    pub(crate) fn top(&self) -> usize {
+        self.stack.end
+    }
+
+    /// Returns the lowest address WebAssembly code is allowed to grow the stack to.
+    pub(crate) fn limit(&self) -> usize {
+        debug_assert_eq!(self.guard.end, self.stack.start);
+        self.stack.start
+    }
+}
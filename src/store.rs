@@ -1,40 +1,530 @@
-use crate::runtime::{VMContext, VMOpaqueContext, VMVal};
-use crate::{runtime, Engine};
+use crate::debug::{DebugVariable, TrapFrame};
+use crate::func::HostFunc;
+use crate::global::SharedGlobalDefinition;
+use crate::placeholder::fiber::FiberStack;
+use crate::placeholder::trap_handling::{Backtrace, MAX_FRAME_COPY_SIZE};
+use crate::runtime::{
+    EpochDeadline, OutOfFuel, StatsEvent, VMContext, VMFuncRef, VMGlobalDefinition,
+    VMOpaqueContext, VMRuntimeLimits, VMVal,
+};
+use crate::{runtime, Engine, StackRegion};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
+use core::borrow::{Borrow, BorrowMut};
 use core::marker::PhantomData;
-use core::{fmt, mem};
+use core::num::NonZeroU32;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::{fmt, mem, ptr};
 use hashbrown::HashMap;
 
 /// A store owns WebAssembly instances and their associated data (tables, memories, globals and functions).
+///
+/// Additionally, a store holds a value of the embedder-defined type `T` that host functions can
+/// access through their [`Caller`][crate::Caller], e.g. to share state like counters or IO handles
+/// with the embedder.
+///
+/// Instances live as long as the store they belong to. Dropping the store (or calling
+/// [`Store::reset`]) tears them down and returns their memories, tables and `VMContext`s to the
+/// [`InstanceAllocator`][crate::InstanceAllocator] they were allocated with.
 #[derive(Debug)]
-pub struct Store {
+pub struct Store<T> {
+    /// Tags the handles of this store's items, so items of other stores are told apart.
+    id: StoreId,
+    /// Incremented whenever the GC heap is torn down, so references into an old heap are told
+    /// apart from references into the current one.
+    gc_heap_epoch: u64,
     pub(crate) engine: Engine,
-    instances: Vec<runtime::Instance>,
+    data: T,
+    instances: Vec<Box<runtime::Instance>>,
     exported_funcs: Vec<runtime::ExportedFunction>,
     exported_tables: Vec<runtime::ExportedTable>,
     exported_memories: Vec<runtime::ExportedMemory>,
     exported_globals: Vec<runtime::ExportedGlobal>,
+    host_funcs: Vec<HostFunc>,
+    host_globals: Vec<Box<VMGlobalDefinition>>,
+    shared_globals: Vec<Arc<SharedGlobalDefinition>>,
     wasm_vmval_storage: Vec<VMVal>,
+    stack: Option<StackRegion>,
+    /// The limits shared by all instances of this store, boxed so their address stays stable.
+    runtime_limits: Box<VMRuntimeLimits>,
+    /// The stack used by async calls, allocated on first use and reused afterwards.
+    async_stack: Option<FiberStack>,
+    /// The error of a host function that was called directly by the embedder, it can't be raised
+    /// as a trap since there is no WebAssembly call to unwind to.
+    host_error: Option<crate::Error>,
+    /// The state of the frame that raised the most recent trap, if it was raised by JIT code.
+    last_trap_frame: Option<TrapFrame>,
+    /// The buffer the trap handler copies the stack of a trapping frame into, allocated on first
+    /// use since the trap handler itself must not allocate.
+    trap_stack_buffer: Box<[u8]>,
+    /// Whether traps capture a backtrace.
+    wasm_backtrace_details: WasmBacktraceDetails,
+    /// Whether a backtrace was requested for the next trap in [`WasmBacktraceDetails::OnDemand`] mode.
+    backtrace_requested: bool,
+    /// The backtrace of the most recent trap, if one was captured.
+    last_trap_backtrace: Option<Backtrace>,
+    /// Called on every transition between host and WebAssembly code.
+    call_hook: Option<CallHookFn>,
 
     vmctx2instance: HashMap<*mut VMOpaqueContext, Stored<runtime::Instance>>,
+    /// Maps `VMFuncRef`s to their handles, so every function has a single handle and handles can
+    /// be compared to tell whether they refer to the same function.
+    funcref2func: HashMap<NonNull<VMFuncRef>, Stored<runtime::ExportedFunction>>,
 }
 
-impl Store {
-    /// Constructs a new store with the given engine.
-    pub fn new(engine: &Engine) -> Self {
+/// Whether traps capture a backtrace of the WebAssembly frames on the stack.
+///
+/// See [`Store::set_wasm_backtrace_details`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WasmBacktraceDetails {
+    /// Every trap captures a backtrace.
+    #[default]
+    Enable,
+    /// Traps never capture a backtrace.
+    Disable,
+    /// Only the first trap after a call to [`Store::request_backtrace`] captures a backtrace.
+    OnDemand,
+}
+
+/// A transition between host and WebAssembly code, see [`Store::set_call_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallHook {
+    /// The host is about to call into WebAssembly.
+    CallingWasm,
+    /// WebAssembly returned (or trapped) back to the host.
+    ReturningFromWasm,
+    /// WebAssembly is about to call a host function.
+    CallingHost,
+    /// A host function is about to return back to WebAssembly.
+    ReturningFromHost,
+}
+
+struct CallHookFn(Box<dyn FnMut(CallHook) -> crate::Result<()> + Send + Sync>);
+
+impl fmt::Debug for CallHookFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallHookFn").finish_non_exhaustive()
+    }
+}
+
+/// Decides whether memories of a store may grow, see [`Store::set_limiter`].
+pub trait ResourceLimiter: Send {
+    /// Called before a memory grows from `current` to `desired` bytes, returning `false` denies
+    /// the growth.
+    ///
+    /// `maximum` is the maximum size in bytes declared by the memory's type, `desired` never
+    /// exceeds it.
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> bool;
+}
+
+pub(crate) struct StoreLimiter(pub(crate) Box<dyn ResourceLimiter>);
+
+/// Counters of events in a store, see [`Store::stats`].
+#[cfg(feature = "stats")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StoreStats {
+    /// The number of calls from the host into WebAssembly, including calls made by host functions.
+    pub wasm_calls: u64,
+    /// The number of calls from WebAssembly into host functions.
+    pub host_calls: u64,
+    /// The number of attempts to grow a memory, whether they succeeded or not.
+    ///
+    /// This includes both `memory.grow` and [`Memory::grow`][crate::Memory::grow].
+    pub memory_grows: u64,
+    /// The number of calls into WebAssembly that ended in a trap.
+    pub traps: u64,
+}
+
+impl fmt::Debug for StoreLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreLimiter").finish_non_exhaustive()
+    }
+}
+
+impl<T: Default> Default for Store<T> {
+    fn default() -> Self {
+        Self::new(&Engine::default(), T::default())
+    }
+}
+
+impl<T> Store<T> {
+    /// Constructs a new store with the given engine and embedder-defined data.
+    pub fn new(engine: &Engine, data: T) -> Self {
         Self {
+            id: StoreId::allocate(),
+            gc_heap_epoch: 0,
             engine: engine.clone(),
+            data,
             instances: Vec::new(),
             exported_funcs: Vec::new(),
             exported_tables: Vec::new(),
             exported_memories: Vec::new(),
             exported_globals: Vec::new(),
+            host_funcs: Vec::new(),
+            host_globals: Vec::new(),
+            shared_globals: Vec::new(),
             wasm_vmval_storage: Vec::new(),
+            stack: None,
+            runtime_limits: Box::new(VMRuntimeLimits::default()),
+            async_stack: None,
+            host_error: None,
+            last_trap_frame: None,
+            trap_stack_buffer: Box::default(),
+            wasm_backtrace_details: WasmBacktraceDetails::default(),
+            backtrace_requested: false,
+            last_trap_backtrace: None,
+            call_hook: None,
 
             vmctx2instance: HashMap::new(),
+            funcref2func: HashMap::new(),
+        }
+    }
+
+    /// Returns a shared reference to the embedder-defined data of this store.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Returns a mutable reference to the embedder-defined data of this store.
+    pub fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// Tears down all instances of this store, returning their resources to the allocators they
+    /// were allocated with, and forgets all functions, tables, memories and globals.
+    ///
+    /// The store is left as if it was newly created, except for the embedder-defined data and the
+    /// configured stack and epoch deadline, which are kept. Handles to items of the store obtained
+    /// before the reset, including definitions in a [`Linker`][crate::Linker] that refer to this
+    /// store, must not be used anymore: they either fail to resolve or refer to unrelated items
+    /// created after the reset.
+    pub fn reset(&mut self) {
+        self.vmctx2instance.clear();
+        self.funcref2func.clear();
+        self.exported_funcs.clear();
+        self.exported_tables.clear();
+        self.exported_memories.clear();
+        self.exported_globals.clear();
+        // instances may point to host functions and globals, so they have to go first
+        self.instances.clear();
+        self.host_funcs.clear();
+        self.host_globals.clear();
+        self.shared_globals.clear();
+        self.runtime_limits.gc_heap_base.set(core::ptr::null_mut());
+        *self.runtime_limits.gc_heap.borrow_mut() = None;
+        self.gc_heap_epoch += 1;
+        #[cfg(feature = "stats")]
+        self.runtime_limits.stats.set(StoreStats::default());
+        self.last_trap_frame = None;
+        self.last_trap_backtrace = None;
+    }
+
+    /// Makes WebAssembly code called through this store run on the given embedder-provided stack
+    /// instead of the stack of the calling thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`][crate::Error::Unsupported] if switching stacks isn't
+    /// supported on the host architecture.
+    pub fn set_stack(&mut self, region: StackRegion) -> crate::Result<()> {
+        crate::placeholder::arch::ensure_stack_switching()?;
+        self.stack = Some(region);
+        Ok(())
+    }
+
+    /// Sets the number of bytes of stack WebAssembly code called through this store may use
+    /// before it traps with [`Trap::StackOverflow`][crate::Trap::StackOverflow].
+    ///
+    /// This defaults to [`MAX_WASM_STACK`][crate::MAX_WASM_STACK], embedders running on small
+    /// stacks should lower it so WebAssembly code traps before the host stack is exhausted. The
+    /// budget is measured from the stack pointer of the outermost call into WebAssembly, or from
+    /// the top of the stack set through [`Store::set_stack`], and takes effect on the next such
+    /// call.
+    pub fn set_stack_limit(&mut self, bytes: usize) {
+        self.runtime_limits.max_wasm_stack.set(bytes);
+    }
+
+    /// Sets a hook that is called on every transition between host and WebAssembly code.
+    ///
+    /// This is meant for tracing and auditing: the hook is called with [`CallHook::CallingWasm`]
+    /// and [`CallHook::ReturningFromWasm`] around every call into WebAssembly made through this
+    /// store, and with [`CallHook::CallingHost`] and [`CallHook::ReturningFromHost`] around every
+    /// call from WebAssembly into a host function. Returning an error from the hook traps the
+    /// call, the error is returned to the caller of the outermost call into WebAssembly.
+    pub fn set_call_hook(
+        &mut self,
+        hook: impl FnMut(CallHook) -> crate::Result<()> + Send + Sync + 'static,
+    ) {
+        self.call_hook = Some(CallHookFn(Box::new(hook)));
+    }
+
+    /// Returns the counters of events in this store, e.g. the number of calls into WebAssembly.
+    ///
+    /// Only available with the `stats` feature, which makes every store count these events.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> StoreStats {
+        self.runtime_limits.stats.get()
+    }
+
+    /// Installs a limiter that is consulted whenever a memory of this store grows.
+    ///
+    /// Growth denied by the limiter makes `memory.grow` return `-1`, or trap when
+    /// [`Store::trap_on_memory_grow_failure`] is enabled.
+    pub fn set_limiter(&mut self, limiter: impl ResourceLimiter + 'static) {
+        *self.runtime_limits.limiter.borrow_mut() = Some(StoreLimiter(Box::new(limiter)));
+    }
+
+    /// Configures whether `memory.grow` traps with
+    /// [`Trap::HostAllocationFailed`][crate::Trap::HostAllocationFailed] instead of returning `-1`
+    /// when the host fails to provide the memory.
+    ///
+    /// Growth past the maximum declared by the memory's type is always reported as `-1`, the
+    /// module asked for something it can't have. Running out of the memory's reservation, being
+    /// denied by the [`ResourceLimiter`] or failing to map the memory is something the module
+    /// can't do anything about though, trapping surfaces it to the embedder instead. This is
+    /// disabled by default.
+    ///
+    /// This only applies to memories, `table.grow` is unaffected.
+    pub fn trap_on_memory_grow_failure(&mut self, enable: bool) {
+        self.runtime_limits.trap_on_memory_grow_failure.set(enable);
+    }
+
+    /// Counts `event` in the statistics of this store, see [`Store::stats`].
+    pub(crate) fn count(&self, event: StatsEvent) {
+        self.runtime_limits.count(event);
+    }
+
+    pub(crate) fn call_hook(&mut self, transition: CallHook) -> crate::Result<()> {
+        match transition {
+            CallHook::CallingWasm => self.count(StatsEvent::WasmCall),
+            CallHook::CallingHost => self.count(StatsEvent::HostCall),
+            CallHook::ReturningFromWasm | CallHook::ReturningFromHost => {}
+        }
+
+        match &mut self.call_hook {
+            Some(hook) => (hook.0)(transition),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the embedder-provided stack WebAssembly code runs on, if any.
+    pub(crate) fn stack(&self) -> Option<&StackRegion> {
+        self.stack.as_ref()
+    }
+
+    /// Sets the epoch deadline to `ticks_beyond_current` ticks after the current epoch of the
+    /// engine.
+    ///
+    /// This only has an effect if [`Config::epoch_interruption`][crate::Config::epoch_interruption]
+    /// is enabled. WebAssembly code checks the deadline at function entries and loop headers, what
+    /// happens once it is reached is configured by [`Store::epoch_deadline_trap`] and
+    /// [`Store::epoch_deadline_async_yield_and_update`]. The deadline defaults to epoch `0`, which
+    /// is always reached, so it must be set before calling into WebAssembly.
+    pub fn set_epoch_deadline(&mut self, ticks_beyond_current: u64) {
+        let deadline = self
+            .engine
+            .current_epoch()
+            .saturating_add(ticks_beyond_current);
+        self.runtime_limits.epoch_deadline.set(deadline);
+    }
+
+    /// Makes WebAssembly code trap with [`Trap::Interrupt`][crate::Trap::Interrupt] once the epoch
+    /// deadline is reached.
+    ///
+    /// This is the default.
+    pub fn epoch_deadline_trap(&mut self) {
+        self.runtime_limits
+            .epoch_deadline_behavior
+            .set(EpochDeadline::Trap);
+    }
+
+    /// Makes WebAssembly code yield back to the async runtime once the epoch deadline is reached,
+    /// when resumed the deadline is extended by `delta` ticks.
+    ///
+    /// Yielding is only possible in calls made through [`Func::call_async`][crate::Func::call_async],
+    /// synchronous calls trap with [`Trap::Interrupt`][crate::Trap::Interrupt] instead.
+    pub fn epoch_deadline_async_yield_and_update(&mut self, delta: u64) {
+        self.runtime_limits
+            .epoch_deadline_behavior
+            .set(EpochDeadline::YieldAndUpdate(delta));
+    }
+
+    /// Sets the fuel remaining for WebAssembly code executed in this store.
+    ///
+    /// This only has an effect if [`Config::consume_fuel`][crate::Config::consume_fuel] is enabled.
+    /// Every executed operator consumes roughly one unit of fuel, what happens once the fuel runs
+    /// out is configured by [`Store::out_of_fuel_trap`] and [`Store::out_of_fuel_async_yield`].
+    /// Stores start out without any fuel.
+    pub fn set_fuel(&mut self, fuel: u64) {
+        let fuel = i64::try_from(fuel).unwrap_or(i64::MAX);
+        self.runtime_limits.fuel_consumed.set(-fuel);
+    }
+
+    /// Returns the fuel remaining for WebAssembly code executed in this store.
+    pub fn get_fuel(&self) -> u64 {
+        let consumed = self.runtime_limits.fuel_consumed.get();
+        // consumption above zero means the fuel ran out while the accounting was still catching up
+        u64::try_from(consumed.saturating_neg()).unwrap_or(0)
+    }
+
+    /// Makes WebAssembly code trap with [`Trap::OutOfFuel`][crate::Trap::OutOfFuel] once it runs
+    /// out of fuel.
+    ///
+    /// This is the default.
+    pub fn out_of_fuel_trap(&mut self) {
+        self.runtime_limits
+            .out_of_fuel_behavior
+            .set(OutOfFuel::Trap);
+    }
+
+    /// Makes WebAssembly code yield back to the async runtime once it runs out of fuel, when
+    /// resumed `fuel_to_inject` fuel is added to the store.
+    ///
+    /// This allows cooperatively scheduling long-running WebAssembly code, yielding after a fixed
+    /// amount of work. Yielding is only possible in calls made through
+    /// [`Func::call_async`][crate::Func::call_async], synchronous calls trap with
+    /// [`Trap::Interrupt`][crate::Trap::Interrupt] instead.
+    pub fn out_of_fuel_async_yield(&mut self, fuel_to_inject: u64) {
+        self.runtime_limits
+            .out_of_fuel_behavior
+            .set(OutOfFuel::YieldAndRefuel(fuel_to_inject));
+    }
+
+    /// Returns the state of the frame that raised the most recent trap in WebAssembly code called
+    /// through this store.
+    ///
+    /// Returns `None` if no call trapped yet, or if the most recent trap was raised by a builtin
+    /// function instead of JIT-compiled code.
+    pub fn last_trap_frame(&self) -> Option<&TrapFrame> {
+        self.last_trap_frame.as_ref()
+    }
+
+    /// Resolves the values of the named locals and parameters of the function that trapped in
+    /// `frame` through the DWARF debug info of its module.
+    ///
+    /// This requires [`Config::debug_info`][crate::Config::debug_info] to be enabled and the
+    /// module to contain DWARF sections, otherwise no variables are returned. Variables whose
+    /// location is unknown at the trap have no value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module's DWARF sections are malformed.
+    pub fn debug_variables(&self, frame: &TrapFrame) -> crate::Result<Vec<DebugVariable>> {
+        match crate::placeholder::code_registry::lookup_module(frame.pc()) {
+            Some((module, text_offset)) => crate::debug::variables(&module, text_offset, frame),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the offset of `addr` relative to the base of the memory of this store it falls into.
+    pub(crate) fn memory_offset_of(&self, addr: usize) -> Option<u64> {
+        self.instances
+            .iter()
+            .find_map(|instance| instance.memory_offset_of(addr))
+    }
+
+    pub(crate) fn set_last_trap_frame(&mut self, frame: Option<TrapFrame>) {
+        self.last_trap_frame = frame;
+    }
+
+    /// Returns the buffer a trap in the next call into WebAssembly should copy the stack of the
+    /// trapping frame into, if it should be copied at all.
+    ///
+    /// Only [`Store::debug_variables`] reads the copied stack, so it's only copied if the engine
+    /// generates debug info.
+    pub(crate) fn trap_stack_buffer(&mut self) -> Option<NonNull<[u8]>> {
+        if !self.engine.config().is_debug_info() {
+            return None;
+        }
+
+        if self.trap_stack_buffer.is_empty() {
+            self.trap_stack_buffer = vec![0; MAX_FRAME_COPY_SIZE].into_boxed_slice();
+        }
+        Some(NonNull::from(&mut *self.trap_stack_buffer))
+    }
+
+    /// Configures whether traps raised by WebAssembly code called through this store capture a
+    /// backtrace.
+    ///
+    /// Walking the stack on every trap isn't free, embedders that trap often and don't need the
+    /// backtraces can turn them off or only capture them when asked for through
+    /// [`Store::request_backtrace`]. Backtraces are enabled by default.
+    pub fn set_wasm_backtrace_details(&mut self, details: WasmBacktraceDetails) {
+        self.wasm_backtrace_details = details;
+    }
+
+    /// Makes the next trap capture a backtrace when backtraces are captured
+    /// [on demand][WasmBacktraceDetails::OnDemand].
+    pub fn request_backtrace(&mut self) {
+        self.backtrace_requested = true;
+    }
+
+    /// Returns the backtrace of the most recent trap in WebAssembly code called through this
+    /// store.
+    ///
+    /// Returns `None` if no call trapped yet or the most recent trap didn't capture a backtrace,
+    /// see [`Store::set_wasm_backtrace_details`].
+    pub fn last_trap_backtrace(&self) -> Option<&Backtrace> {
+        self.last_trap_backtrace.as_ref()
+    }
+
+    /// Returns whether a trap in the next call into WebAssembly should capture a backtrace.
+    pub(crate) fn capture_backtrace(&self) -> bool {
+        // backtraces are collected by walking the frame pointer chain
+        if !self.engine.config().is_preserve_frame_pointers() {
+            return false;
+        }
+
+        match self.wasm_backtrace_details {
+            WasmBacktraceDetails::Enable => true,
+            WasmBacktraceDetails::Disable => false,
+            WasmBacktraceDetails::OnDemand => self.backtrace_requested,
         }
     }
 
+    pub(crate) fn set_last_trap_backtrace(&mut self, backtrace: Option<Backtrace>) {
+        if backtrace.is_some() {
+            // the request has been served
+            self.backtrace_requested = false;
+        }
+        self.last_trap_backtrace = backtrace;
+    }
+
+    /// Returns a pointer to the limits shared by all instances of this store.
+    ///
+    /// The pointer is stable for the lifetime of the store.
+    pub(crate) fn runtime_limits_ptr(&self) -> *const VMRuntimeLimits {
+        ptr::from_ref(&*self.runtime_limits)
+    }
+
+    /// Returns a pointer to the stack limit shared by all instances of this store.
+    ///
+    /// The pointer is stable for the lifetime of the store.
+    pub(crate) fn stack_limit_ptr(&self) -> *mut usize {
+        self.runtime_limits.stack_limit.as_ptr()
+    }
+
+    /// Returns the number of bytes of stack WebAssembly code called through this store may use.
+    pub(crate) fn max_wasm_stack(&self) -> usize {
+        self.runtime_limits.max_wasm_stack.get()
+    }
+
+    /// Takes the stack used by async calls, allocating it if necessary.
+    pub(crate) fn take_async_stack(&mut self) -> crate::Result<FiberStack> {
+        match self.async_stack.take() {
+            Some(stack) => Ok(stack),
+            None => FiberStack::new(self.engine.config().get_async_stack_size()),
+        }
+    }
+
+    /// Returns the stack used by async calls, allowing it to be reused for the next async call.
+    pub(crate) fn return_async_stack(&mut self, stack: FiberStack) {
+        self.async_stack = Some(stack);
+    }
+
     /// Takes the `Vec<VMVal>` storage used for passing arguments using the array call convention.
     pub(crate) fn take_wasm_vmval_storage(&mut self) -> Vec<VMVal> {
         mem::take(&mut self.wasm_vmval_storage)
@@ -45,6 +535,14 @@ impl Store {
         self.wasm_vmval_storage = storage;
     }
 
+    pub(crate) fn set_host_error(&mut self, err: crate::Error) {
+        self.host_error = Some(err);
+    }
+
+    pub(crate) fn take_host_error(&mut self) -> Option<crate::Error> {
+        self.host_error.take()
+    }
+
     /// Looks up the instance handle associated with the given `vmctx` pointer.
     pub(crate) fn get_instance_from_vmctx(
         &self,
@@ -57,9 +555,16 @@ impl Store {
     /// Inserts a new instance into the store and returns a handle to it.
     pub(crate) fn push_instance(
         &mut self,
-        mut instance: runtime::Instance,
+        instance: runtime::Instance,
     ) -> Stored<runtime::Instance> {
-        let handle = Stored::new(self.instances.len());
+        // instances are boxed so their address stays stable, JIT code and builtins rely on that
+        let mut instance = Box::new(instance);
+        // Safety: the instance is heap allocated and never moved out of the store
+        unsafe {
+            instance.set_vmctx_instance();
+        }
+
+        let handle = Stored::new(self.id, self.instances.len());
         self.vmctx2instance.insert(
             VMOpaqueContext::from_vmcontext(instance.vmctx_mut()),
             handle,
@@ -69,13 +574,58 @@ impl Store {
     }
 
     /// Inserts a new function into the store and returns a handle to it.
+    ///
+    /// Functions already in the store are not inserted again, their existing handle is returned.
     pub(crate) fn push_function(
         &mut self,
         func: runtime::ExportedFunction,
     ) -> Stored<runtime::ExportedFunction> {
-        let index = self.exported_funcs.len();
-        self.exported_funcs.push(func);
-        Stored::new(index)
+        *self.funcref2func.entry(func.func_ref).or_insert_with(|| {
+            let index = self.exported_funcs.len();
+            self.exported_funcs.push(func);
+            Stored::new(self.id, index)
+        })
+    }
+
+    /// Inserts a new host function into the store and returns a handle to it.
+    ///
+    /// The store takes ownership of the host function, keeping its `VMFuncRef` alive for as long
+    /// as the store lives.
+    pub(crate) fn push_host_func(&mut self, mut func: HostFunc) -> Stored<runtime::ExportedFunction>
+    where
+        T: 'static,
+    {
+        self.runtime_limits
+            .store_type
+            .set(Some(core::any::TypeId::of::<T>()));
+        func.set_runtime_limits(self.runtime_limits_ptr());
+        let func_ref = func.func_ref();
+        self.host_funcs.push(func);
+        self.push_function(runtime::ExportedFunction { func_ref })
+    }
+
+    /// Takes ownership of the definition of a host-defined global and returns its address.
+    ///
+    /// Definitions are boxed so their address stays stable, imports of the global point to it.
+    pub(crate) fn push_host_global(
+        &mut self,
+        definition: VMGlobalDefinition,
+    ) -> *mut VMGlobalDefinition {
+        let mut definition = Box::new(definition);
+        let ptr = ptr::from_mut(definition.as_mut());
+        self.host_globals.push(definition);
+        ptr
+    }
+
+    /// Keeps the definition of a shared global alive for as long as this store and returns its
+    /// address.
+    pub(crate) fn push_shared_global(
+        &mut self,
+        definition: Arc<SharedGlobalDefinition>,
+    ) -> *mut VMGlobalDefinition {
+        let ptr = definition.get();
+        self.shared_globals.push(definition);
+        ptr
     }
 
     /// Inserts a new table into the store and returns a handle to it.
@@ -85,7 +635,7 @@ impl Store {
     ) -> Stored<runtime::ExportedTable> {
         let index = self.exported_tables.len();
         self.exported_tables.push(table);
-        Stored::new(index)
+        Stored::new(self.id, index)
     }
 
     /// Inserts a new memory into the store and returns a handle to it.
@@ -95,7 +645,7 @@ impl Store {
     ) -> Stored<runtime::ExportedMemory> {
         let index = self.exported_memories.len();
         self.exported_memories.push(memory);
-        Stored::new(index)
+        Stored::new(self.id, index)
     }
 
     /// Inserts a new global into the store and returns a handle to it.
@@ -105,34 +655,39 @@ impl Store {
     ) -> Stored<runtime::ExportedGlobal> {
         let index = self.exported_globals.len();
         self.exported_globals.push(global);
-        Stored::new(index)
+        Stored::new(self.id, index)
     }
 }
 
 macro_rules! stored_impls {
     ($bind:ident $(($ty:path, $has:ident, $get:ident, $get_mut:ident, $field:expr))*) => {
         $(
-            impl Store {
+            impl<T> Store<T> {
                 #[expect(missing_docs, reason = "inside macro")]
                 pub fn $has(&self, index: Stored<$ty>) -> bool {
-                    let $bind = self;
-                    $field.get(index.index).is_some()
+                    self.$get(index).is_some()
                 }
 
                 #[expect(missing_docs, reason = "inside macro")]
                 pub fn $get(&self, index: Stored<$ty>) -> Option<&$ty> {
+                    if index.store_id != self.id {
+                        return None;
+                    }
                     let $bind = self;
-                    $field.get(index.index)
+                    $field.get(index.index).map(Borrow::<$ty>::borrow)
                 }
 
                 #[expect(missing_docs, reason = "inside macro")]
                 pub fn $get_mut(&mut self, index: Stored<$ty>) -> Option<&mut $ty> {
+                    if index.store_id != self.id {
+                        return None;
+                    }
                     let $bind = self;
-                    $field.get_mut(index.index)
+                    $field.get_mut(index.index).map(BorrowMut::<$ty>::borrow_mut)
                 }
             }
 
-            impl ::core::ops::Index<Stored<$ty>> for Store {
+            impl<T> ::core::ops::Index<Stored<$ty>> for Store<T> {
                 type Output = $ty;
 
                 fn index(&self, index: Stored<$ty>) -> &Self::Output {
@@ -140,7 +695,7 @@ macro_rules! stored_impls {
                 }
             }
 
-            impl ::core::ops::IndexMut<Stored<$ty>> for Store {
+            impl<T> ::core::ops::IndexMut<Stored<$ty>> for Store<T> {
                 fn index_mut(&mut self, index: Stored<$ty>) -> &mut Self::Output {
                     self.$get_mut(index).unwrap()
                 }
@@ -158,14 +713,72 @@ stored_impls! {
     (runtime::ExportedGlobal, has_global, get_global, get_global_mut, s.exported_globals)
 }
 
+impl<T> Store<T> {
+    /// Wraps the raw GC reference `raw` produced by WebAssembly code of this store, returning
+    /// `None` for the null reference.
+    pub(crate) fn wrap_gc_ref(&self, raw: u32) -> Option<GcRef> {
+        Some(GcRef {
+            store_id: self.id,
+            heap_epoch: self.gc_heap_epoch,
+            raw: NonZeroU32::new(raw)?,
+        })
+    }
+
+    /// Returns the raw GC reference `gc_ref` wraps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gc_ref` belongs to a different store or was obtained before the store was
+    /// reset, it would refer to an object that doesn't exist anymore.
+    pub(crate) fn unwrap_gc_ref(&self, gc_ref: GcRef) -> u32 {
+        assert!(
+            gc_ref.store_id == self.id && gc_ref.heap_epoch == self.gc_heap_epoch,
+            "GC reference doesn't belong to the store"
+        );
+        gc_ref.raw.get()
+    }
+}
+
+/// A reference to an object in the GC heap of a store or an `i31ref`.
+///
+/// Unlike the handles of other items, it is the raw reference itself, tagged with the heap it
+/// refers into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GcRef {
+    store_id: StoreId,
+    heap_epoch: u64,
+    raw: NonZeroU32,
+}
+
+impl GcRef {
+    /// Returns the raw reference, which is only meaningful to the store it belongs to.
+    pub(crate) fn raw(self) -> u32 {
+        self.raw.get()
+    }
+}
+
+/// A process-wide unique identifier of a [`Store`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StoreId(u64);
+
+impl StoreId {
+    fn allocate() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
 pub struct Stored<T> {
+    store_id: StoreId,
     index: usize,
-    _m: PhantomData<T>,
+    // `Stored` is just an index, so it is `Send` and `Sync` regardless of `T`
+    _m: PhantomData<fn() -> T>,
 }
 
 impl<T> Stored<T> {
-    pub fn new(index: usize) -> Self {
+    fn new(store_id: StoreId, index: usize) -> Self {
         Self {
+            store_id,
             index,
             _m: PhantomData,
         }
@@ -180,8 +793,208 @@ impl<T> Clone for Stored<T> {
 
 impl<T> Copy for Stored<T> {}
 
+impl<T> PartialEq for Stored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.store_id == other.store_id && self.index == other.index
+    }
+}
+
+impl<T> Eq for Stored<T> {}
+
 impl<T> fmt::Debug for Stored<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("Stored").field(&self.index).finish()
+        f.debug_tuple("Stored")
+            .field(&self.store_id.0)
+            .field(&self.index)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indices::{DefinedMemoryIndex, DefinedTableIndex};
+    use crate::runtime::{InstanceAllocator, Memory, OwnedVMContext, Table, VMOffsets};
+    use crate::translate::{MemoryDesc, TableDesc, TranslatedModule};
+    use crate::{ConstExprEvaluator, Linker, Module, PlaceholderAllocatorDontUse};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use wasmparser::Validator;
+
+    /// Delegates to [`PlaceholderAllocatorDontUse`] while keeping track of the live allocations.
+    struct CountingAllocator {
+        live: AtomicUsize,
+    }
+
+    impl CountingAllocator {
+        fn live(&self) -> usize {
+            self.live.load(Ordering::Relaxed)
+        }
+
+        fn allocated<R>(&self, res: crate::Result<R>) -> crate::Result<R> {
+            if res.is_ok() {
+                self.live.fetch_add(1, Ordering::Relaxed);
+            }
+            res
+        }
+
+        fn deallocated(&self) {
+            self.live.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    impl InstanceAllocator for CountingAllocator {
+        unsafe fn allocate_vmctx(
+            &self,
+            module: &TranslatedModule,
+            offsets: &VMOffsets,
+        ) -> crate::Result<OwnedVMContext> {
+            self.allocated(PlaceholderAllocatorDontUse.allocate_vmctx(module, offsets))
+        }
+
+        unsafe fn deallocate_vmctx(&self, vmctx: OwnedVMContext) {
+            self.deallocated();
+            PlaceholderAllocatorDontUse.deallocate_vmctx(vmctx);
+        }
+
+        unsafe fn allocate_memory(
+            &self,
+            module: &TranslatedModule,
+            memory_desc: &MemoryDesc,
+            memory_index: DefinedMemoryIndex,
+        ) -> crate::Result<Memory> {
+            self.allocated(PlaceholderAllocatorDontUse.allocate_memory(
+                module,
+                memory_desc,
+                memory_index,
+            ))
+        }
+
+        unsafe fn deallocate_memory(&self, memory_index: DefinedMemoryIndex, memory: Memory) {
+            self.deallocated();
+            PlaceholderAllocatorDontUse.deallocate_memory(memory_index, memory);
+        }
+
+        unsafe fn allocate_table(
+            &self,
+            module: &TranslatedModule,
+            table_desc: &TableDesc,
+            table_index: DefinedTableIndex,
+        ) -> crate::Result<Table> {
+            self.allocated(PlaceholderAllocatorDontUse.allocate_table(
+                module,
+                table_desc,
+                table_index,
+            ))
+        }
+
+        unsafe fn deallocate_table(&self, table_index: DefinedTableIndex, table: Table) {
+            self.deallocated();
+            PlaceholderAllocatorDontUse.deallocate_table(table_index, table);
+        }
+    }
+
+    /// Instantiates `module` a hundred times into `store`, making every instance commit some of its
+    /// memory.
+    fn instantiate_many(
+        store: &mut Store<()>,
+        alloc: &Arc<CountingAllocator>,
+        linker: &Linker,
+        const_eval: &mut ConstExprEvaluator,
+        module: &Module,
+    ) {
+        let live_before = alloc.live();
+        for i in 0..100 {
+            let instance = linker
+                .instantiate(store, alloc.clone(), const_eval, module)
+                .unwrap();
+            let touch = instance.get_typed_func::<i32, i32>(store, "touch").unwrap();
+            assert_eq!(touch.call(store, i).unwrap(), i * 0x0101_0101);
+
+            // a `VMContext`, a memory and a table per instance
+            let instances = usize::try_from(i).unwrap() + 1;
+            assert_eq!(alloc.live(), live_before + instances * 3);
+        }
+    }
+
+    #[test_log::test]
+    fn instances_are_deallocated() {
+        let str = r#"
+        (module
+            (memory 16)
+            (table 8 funcref)
+            (func (export "touch") (param i32) (result i32)
+                (memory.fill (i32.const 0) (local.get 0) (i32.const 0x10000))
+                (i32.load (i32.const 0x8000))
+            )
+        )"#;
+
+        let engine = Engine::default();
+        let mut validator = Validator::new();
+        let mut store = Store::new(&engine, ());
+        let linker = Linker::new(&engine);
+        let mut const_eval = ConstExprEvaluator::default();
+        let module = Module::from_str(&engine, &mut validator, str).unwrap();
+        let alloc = Arc::new(CountingAllocator {
+            live: AtomicUsize::new(0),
+        });
+
+        // resetting returns everything, and the store is usable afterwards
+        instantiate_many(&mut store, &alloc, &linker, &mut const_eval, &module);
+        store.reset();
+        assert_eq!(alloc.live(), 0);
+        instantiate_many(&mut store, &alloc, &linker, &mut const_eval, &module);
+        store.reset();
+        assert_eq!(alloc.live(), 0);
+
+        // and so does dropping the store
+        instantiate_many(&mut store, &alloc, &linker, &mut const_eval, &module);
+        drop(store);
+        assert_eq!(alloc.live(), 0);
+    }
+
+    #[test_log::test]
+    fn funcrefs_reuse_their_handle() {
+        let str = r#"
+        (module
+            (func $f)
+            (elem declare func $f)
+            (func (export "get") (result funcref)
+                (ref.func $f)
+            )
+            (func (export "id") (param funcref) (result funcref)
+                (local.get 0)
+            )
+        )"#;
+
+        let engine = Engine::default();
+        let mut validator = Validator::new();
+        let mut store = Store::new(&engine, ());
+        let linker = Linker::new(&engine);
+        let mut const_eval = ConstExprEvaluator::default();
+        let module = Module::from_str(&engine, &mut validator, str).unwrap();
+        let instance = linker
+            .instantiate(
+                &mut store,
+                Arc::new(PlaceholderAllocatorDontUse),
+                &mut const_eval,
+                &module,
+            )
+            .unwrap();
+
+        let get = instance
+            .get_typed_func::<(), Option<crate::Func>>(&mut store, "get")
+            .unwrap();
+        let id = instance
+            .get_typed_func::<Option<crate::Func>, Option<crate::Func>>(&mut store, "id")
+            .unwrap();
+
+        let func = get.call(&mut store, ()).unwrap();
+        let funcs = store.exported_funcs.len();
+        // passing the same function back and forth doesn't add new entries to the store
+        for _ in 0..100 {
+            assert_eq!(get.call(&mut store, ()).unwrap(), func);
+            assert_eq!(id.call(&mut store, func).unwrap(), func);
+        }
+        assert_eq!(store.exported_funcs.len(), funcs);
     }
 }
@@ -1,28 +1,178 @@
-use crate::runtime::VMTableImport;
+use crate::indices::EntityIndex;
+use crate::runtime::{InstanceAllocator, VMFuncRef, VMTableImport};
 use crate::store::Stored;
-use crate::{runtime, Store};
+use crate::translate::{TableDesc, TableInitialValue, TranslatedModule, WasmRefType};
+use crate::trap::Trap;
+use crate::values::Ref;
+use crate::{runtime, wasm_unsupported, Error, Instance, Store};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::ptr::NonNull;
+use wasmparser::{AbstractHeapType, HeapType, TableType, WasmFeatures};
 
 /// A WebAssembly table instance.
 #[derive(Debug, Clone, Copy)]
 pub struct Table(Stored<runtime::ExportedTable>);
 
 impl Table {
-    // pub fn new(_store: &mut Store, _ty: TableType, _init: ()) -> crate::Result<Self> {
-    //     todo!()
-    // }
+    /// Creates a new host-defined table of type `ty` with all elements set to `init`, allocating it
+    /// through `alloc`.
+    ///
+    /// Host-defined tables can be passed to WebAssembly through imports, e.g. using
+    /// [`Linker::define`][crate::Linker::define]. They live as long as the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ty` is invalid, e.g. because its minimum exceeds its maximum, requires
+    /// WebAssembly features that are disabled in the engine, or if the allocation fails. Only
+    /// unshared tables of nullable function references are supported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `init` is a function reference from a different store.
+    pub fn new<T>(
+        store: &mut Store<T>,
+        alloc: Arc<dyn InstanceAllocator>,
+        ty: TableType,
+        init: Ref,
+    ) -> crate::Result<Self> {
+        let is_funcref = ty.element_type.is_nullable()
+            && matches!(
+                ty.element_type.heap_type(),
+                HeapType::Abstract {
+                    shared: false,
+                    ty: AbstractHeapType::Func
+                }
+            );
+        if !is_funcref {
+            return Err(wasm_unsupported!(
+                "host-defined table of type {}",
+                ty.element_type
+            ));
+        }
+        if ty.shared {
+            return Err(wasm_unsupported!("host-defined shared table"));
+        }
+
+        let mut required = WasmFeatures::empty();
+        required.set(WasmFeatures::MEMORY64, ty.table64);
+        store.engine.check_features(required)?;
+
+        // the same limits validation enforces for the tables of modules
+        let max_elements = if ty.table64 {
+            u64::MAX
+        } else {
+            u64::from(u32::MAX)
+        };
+        let invalid = |message: &str| Error::InvalidWebAssembly {
+            message: message.to_string(),
+            offset: 0,
+        };
+        if ty.initial > max_elements || ty.maximum.is_some_and(|max| max > max_elements) {
+            return Err(invalid(
+                "table size must be at most the size of the index space",
+            ));
+        }
+        if ty.maximum.is_some_and(|max| ty.initial > max) {
+            return Err(invalid("size minimum must not be greater than maximum"));
+        }
+
+        let mut translated = TranslatedModule::default();
+        let index = translated.tables.push(TableDesc {
+            element_type: WasmRefType::FUNCREF,
+            table64: ty.table64,
+            minimum: ty.initial,
+            maximum: ty.maximum,
+            shared: false,
+        });
+        translated
+            .table_initializers
+            .initial_values
+            .push(TableInitialValue::RefNull);
+        translated
+            .exports
+            .insert(String::new(), EntityIndex::Table(index));
+
+        let instance = Instance::new_host(store, alloc, translated)?;
+        let table = instance.get_table(store, "").unwrap();
+
+        // tables start out with null references
+        if init.is_non_null() {
+            for index in 0..ty.initial {
+                table.set(store, index, init.clone())?;
+            }
+        }
+
+        Ok(table)
+    }
+
     // pub fn ty(&self, _store: &Store) -> &TableType {
     //     todo!()
     // }
-    pub(crate) fn as_vmtable_import(&self, store: &Store) -> VMTableImport {
+
+    /// Stores `val` at the given `index` in this table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `index` is out of bounds for this table or `val` isn't a function
+    /// reference, tables of external and internal references aren't supported yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `val` is a function reference from a different store.
+    pub fn set<T>(&self, store: &mut Store<T>, index: u64, val: Ref) -> crate::Result<()> {
+        let elem = match val {
+            Ref::Func(func) => func.and_then(|func| {
+                assert!(func.comes_from_same_store(store));
+                // Safety: the function comes from this store, so its `VMFuncRef` is valid
+                NonNull::new(unsafe { func.as_raw(store) }.cast::<VMFuncRef>())
+            }),
+            Ref::Extern(_) | Ref::Any(_) => {
+                return Err(wasm_unsupported!(
+                    "tables of external and internal references"
+                ))
+            }
+        };
+
+        // Safety: the table definition is owned by an instance in this store and therefore valid
+        unsafe {
+            let definition = &*store[self.0].definition;
+            if index >= definition.current_length {
+                return Err(crate::Error::Trap {
+                    trap: Trap::TableOutOfBounds,
+                    message: "table index out of bounds".to_string(),
+                    faulting_addr: None,
+                    wasm_offset: None,
+                });
+            }
+
+            let base = definition.base.cast::<Option<NonNull<VMFuncRef>>>();
+            *base.add(usize::try_from(index).unwrap()) = elem;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn ty<'a, T>(&self, store: &'a Store<T>) -> &'a TableDesc {
+        &store[self.0].table
+    }
+
+    /// Returns the current number of elements in this table.
+    pub(crate) fn size<T>(&self, store: &Store<T>) -> u64 {
+        // Safety: the table definition is owned by an instance in this store and therefore valid
+        unsafe { (*store[self.0].definition).current_length }
+    }
+
+    pub(crate) fn as_vmtable_import<T>(&self, store: &Store<T>) -> VMTableImport {
         VMTableImport {
             from: store[self.0].definition,
             vmctx: store[self.0].vmctx,
         }
     }
-    pub(crate) fn from_vm_export(store: &mut Store, export: runtime::ExportedTable) -> Self {
+    pub(crate) fn from_vm_export<T>(store: &mut Store<T>, export: runtime::ExportedTable) -> Self {
         Self(store.push_table(export))
     }
-    pub(crate) fn comes_from_same_store(self, store: &Store) -> bool {
+    pub(crate) fn comes_from_same_store<T>(self, store: &Store<T>) -> bool {
         store.has_table(self.0)
     }
 }
@@ -1,5 +1,7 @@
 use crate::indices::{FuncIndex, GlobalIndex};
-use crate::wasm_unsupported;
+use crate::runtime::{ConstEvalContext, ConstExprEvaluator};
+use crate::translate::{WasmHeapType, WasmparserTypeConverter};
+use crate::{wasm_unsupported, Val};
 use smallvec::SmallVec;
 
 /// A constant expression.
@@ -17,6 +19,7 @@ impl ConstExpr {
     /// indices that appeared in `ref.func` instructions, if any.
     pub fn from_wasmparser(
         expr: &wasmparser::ConstExpr<'_>,
+        ty_convert: &WasmparserTypeConverter<'_>,
     ) -> crate::Result<(Self, SmallVec<[FuncIndex; 1]>)> {
         let mut iter = expr
             .get_operators_reader()
@@ -41,39 +44,78 @@ impl ConstExpr {
                 escaped.push(FuncIndex::from_u32(*function_index));
             }
 
-            ops.push(ConstOp::from_wasmparser(op, offset)?);
+            ops.push(ConstOp::from_wasmparser(op, offset, ty_convert)?);
         }
         Ok((Self { ops }, escaped))
     }
 
+    /// Returns an iterator over the operators of this expression.
     pub fn ops(&self) -> impl ExactSizeIterator<Item = ConstOp> + use<'_> {
         self.ops.iter().copied()
     }
+
+    /// Evaluate this const expression using the given evaluator, see
+    /// [`ConstExprEvaluator::eval`].
+    ///
+    /// `global.get` and `ref.func` operators are resolved through `ctx`, which makes this usable
+    /// outside of instantiation too, e.g. for evaluating extended-const global initializers
+    /// in tooling.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression contains unsupported operators, doesn't type check or if
+    /// `ctx` fails to resolve a global or function.
+    pub fn eval(
+        &self,
+        evaluator: &mut ConstExprEvaluator,
+        ctx: &mut dyn ConstEvalContext,
+    ) -> crate::Result<Val> {
+        evaluator.eval(self, ctx)
+    }
 }
 
 /// The subset of Wasm opcodes that are constant.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum ConstOp {
+    /// `i32.const`
     I32Const(i32),
+    /// `i64.const`
     I64Const(i64),
+    /// `f32.const`, holds the raw bits of the float.
     F32Const(u32),
+    /// `f64.const`, holds the raw bits of the float.
     F64Const(u64),
+    /// `v128.const`
     V128Const(u128),
+    /// `global.get`
     GlobalGet(GlobalIndex),
+    /// `ref.i31`
     RefI31,
-    RefNull,
+    /// `ref.null`, holds the heap type of the null reference.
+    RefNull(WasmHeapType),
+    /// `ref.func`
     RefFunc(FuncIndex),
+    /// `i32.add`
     I32Add,
+    /// `i32.sub`
     I32Sub,
+    /// `i32.mul`
     I32Mul,
+    /// `i64.add`
     I64Add,
+    /// `i64.sub`
     I64Sub,
+    /// `i64.mul`
     I64Mul,
 }
 
 impl ConstOp {
     /// Convert a `wasmparser::Operator` to a `ConstOp`.
-    pub fn from_wasmparser(op: wasmparser::Operator<'_>, offset: usize) -> crate::Result<Self> {
+    pub fn from_wasmparser(
+        op: wasmparser::Operator<'_>,
+        offset: usize,
+        ty_convert: &WasmparserTypeConverter<'_>,
+    ) -> crate::Result<Self> {
         use wasmparser::Operator as O;
         Ok(match op {
             O::I32Const { value } => Self::I32Const(value),
@@ -81,7 +123,7 @@ impl ConstOp {
             O::F32Const { value } => Self::F32Const(value.bits()),
             O::F64Const { value } => Self::F64Const(value.bits()),
             O::V128Const { value } => Self::V128Const(u128::from_le_bytes(*value.bytes())),
-            O::RefNull { hty: _ } => Self::RefNull,
+            O::RefNull { hty } => Self::RefNull(ty_convert.convert_heap_type(hty)),
             O::RefFunc { function_index } => Self::RefFunc(FuncIndex::from_u32(function_index)),
             O::GlobalGet { global_index } => Self::GlobalGet(GlobalIndex::from_u32(global_index)),
             O::RefI31 => Self::RefI31,
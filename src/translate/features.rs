@@ -0,0 +1,319 @@
+use wasmparser::{
+    AbstractHeapType, CompositeInnerType, ConstExpr, DataKind, ElementItems, ElementKind, HeapType,
+    MemoryType, Operator, Parser, Payload, RecGroup, RefType, TableInit, TableType, TypeRef,
+    ValType, VisitOperator, WasmFeatures,
+};
+
+/// Returns the feature corresponding to a name in the `target_features` custom section.
+pub(crate) fn target_feature(name: &str) -> Option<WasmFeatures> {
+    let feature = match name {
+        "atomics" => WasmFeatures::THREADS,
+        "bulk-memory" => WasmFeatures::BULK_MEMORY,
+        "exception-handling" => WasmFeatures::EXCEPTIONS,
+        "multivalue" => WasmFeatures::MULTI_VALUE,
+        "mutable-globals" => WasmFeatures::MUTABLE_GLOBAL,
+        "nontrapping-fptoint" => WasmFeatures::SATURATING_FLOAT_TO_INT,
+        "sign-ext" => WasmFeatures::SIGN_EXTENSION,
+        "simd128" => WasmFeatures::SIMD,
+        "tail-call" => WasmFeatures::TAIL_CALL,
+        "reference-types" => WasmFeatures::REFERENCE_TYPES,
+        "gc" => WasmFeatures::GC,
+        "memory64" => WasmFeatures::MEMORY64,
+        "relaxed-simd" => WasmFeatures::RELAXED_SIMD,
+        "extended-const" => WasmFeatures::EXTENDED_CONST,
+        "multimemory" => WasmFeatures::MULTI_MEMORY,
+        "shared-everything" => WasmFeatures::SHARED_EVERYTHING_THREADS,
+        _ => return None,
+    };
+    Some(feature)
+}
+
+/// Scans `bytes` for the WebAssembly proposals the module uses.
+///
+/// This looks at the `target_features` custom section, the types, imports and definitions of the
+/// module and every operator of its function bodies and constant expressions. The module isn't
+/// validated, features of the MVP are never reported.
+pub(crate) fn detect_features(bytes: &[u8]) -> crate::Result<WasmFeatures> {
+    let mut features = WasmFeatures::empty();
+    let mut num_memories = 0_usize;
+
+    for payload in Parser::new(0).parse_all(bytes) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for rec_group in reader {
+                    rec_group_features(&rec_group?, &mut features);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    match import?.ty {
+                        TypeRef::Func(_) => {}
+                        TypeRef::Table(ty) => table_type_features(&ty, &mut features),
+                        TypeRef::Memory(ty) => {
+                            num_memories += 1;
+                            memory_type_features(&ty, &mut features);
+                        }
+                        TypeRef::Global(ty) => {
+                            if ty.mutable {
+                                features.insert(WasmFeatures::MUTABLE_GLOBAL);
+                            }
+                            if ty.shared {
+                                features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
+                            }
+                            val_type_features(ty.content_type, &mut features);
+                        }
+                        TypeRef::Tag(_) => features.insert(WasmFeatures::EXCEPTIONS),
+                    }
+                }
+            }
+            Payload::TableSection(reader) => {
+                for table in reader {
+                    let table = table?;
+                    table_type_features(&table.ty, &mut features);
+                    if let TableInit::Expr(expr) = table.init {
+                        features.insert(WasmFeatures::FUNCTION_REFERENCES);
+                        const_expr_features(&expr, &mut features)?;
+                    }
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for ty in reader {
+                    num_memories += 1;
+                    memory_type_features(&ty?, &mut features);
+                }
+            }
+            Payload::TagSection(_) => features.insert(WasmFeatures::EXCEPTIONS),
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    let global = global?;
+                    if global.ty.shared {
+                        features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
+                    }
+                    val_type_features(global.ty.content_type, &mut features);
+                    const_expr_features(&global.init_expr, &mut features)?;
+                }
+            }
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    let element = element?;
+                    match element.kind {
+                        ElementKind::Active { offset_expr, .. } => {
+                            const_expr_features(&offset_expr, &mut features)?;
+                        }
+                        ElementKind::Passive => features.insert(WasmFeatures::BULK_MEMORY),
+                        ElementKind::Declared => features.insert(WasmFeatures::REFERENCE_TYPES),
+                    }
+                    if let ElementItems::Expressions(ty, exprs) = element.items {
+                        ref_type_features(ty, &mut features);
+                        for expr in exprs {
+                            const_expr_features(&expr?, &mut features)?;
+                        }
+                    }
+                }
+            }
+            Payload::DataCountSection { .. } => features.insert(WasmFeatures::BULK_MEMORY),
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    match data?.kind {
+                        DataKind::Active { offset_expr, .. } => {
+                            const_expr_features(&offset_expr, &mut features)?;
+                        }
+                        DataKind::Passive => features.insert(WasmFeatures::BULK_MEMORY),
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                for local in body.get_locals_reader()? {
+                    let (_, ty) = local?;
+                    val_type_features(ty, &mut features);
+                }
+
+                let mut reader = body.get_operators_reader()?;
+                while !reader.eof() {
+                    reader.visit_operator(&mut FeatureVisitor(&mut features))?;
+                }
+            }
+            Payload::CustomSection(reader) if reader.name() == "target_features" => {
+                target_features_section(reader.data(), &mut features);
+            }
+            _ => {}
+        }
+    }
+
+    if num_memories > 1 {
+        features.insert(WasmFeatures::MULTI_MEMORY);
+    }
+
+    Ok(features)
+}
+
+/// Adds the features listed in a `target_features` section, malformed sections are ignored.
+fn target_features_section(data: &[u8], features: &mut WasmFeatures) {
+    let mut reader = wasmparser::BinaryReader::new(data, 0);
+    let Ok(count) = reader.read_var_u32() else {
+        return;
+    };
+
+    for _ in 0..count {
+        let (Ok(prefix), Ok(name)) = (reader.read_u8(), reader.read_string()) else {
+            return;
+        };
+        if prefix == 0x2b {
+            features.insert(target_feature(name).unwrap_or(WasmFeatures::empty()));
+        }
+    }
+}
+
+fn rec_group_features(rec_group: &RecGroup, features: &mut WasmFeatures) {
+    if rec_group.is_explicit_rec_group() {
+        features.insert(WasmFeatures::GC);
+    }
+
+    for ty in rec_group.types() {
+        if !ty.is_final || ty.supertype_idx.is_some() {
+            features.insert(WasmFeatures::GC);
+        }
+        if ty.composite_type.shared {
+            features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
+        }
+
+        match &ty.composite_type.inner {
+            CompositeInnerType::Func(ty) => {
+                if ty.results().len() > 1 {
+                    features.insert(WasmFeatures::MULTI_VALUE);
+                }
+                for ty in ty.params().iter().chain(ty.results()) {
+                    val_type_features(*ty, features);
+                }
+            }
+            CompositeInnerType::Array(_) | CompositeInnerType::Struct(_) => {
+                features.insert(WasmFeatures::GC);
+            }
+            CompositeInnerType::Cont(_) => features.insert(WasmFeatures::STACK_SWITCHING),
+        }
+    }
+}
+
+fn table_type_features(ty: &TableType, features: &mut WasmFeatures) {
+    if ty.element_type != RefType::FUNCREF {
+        features.insert(WasmFeatures::REFERENCE_TYPES);
+    }
+    if ty.table64 {
+        features.insert(WasmFeatures::MEMORY64);
+    }
+    if ty.shared {
+        features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
+    }
+    ref_type_features(ty.element_type, features);
+}
+
+fn memory_type_features(ty: &MemoryType, features: &mut WasmFeatures) {
+    if ty.memory64 {
+        features.insert(WasmFeatures::MEMORY64);
+    }
+    if ty.shared {
+        features.insert(WasmFeatures::THREADS);
+    }
+    if ty.page_size_log2.is_some() {
+        features.insert(WasmFeatures::CUSTOM_PAGE_SIZES);
+    }
+}
+
+fn val_type_features(ty: ValType, features: &mut WasmFeatures) {
+    match ty {
+        ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64 => {}
+        ValType::V128 => features.insert(WasmFeatures::SIMD),
+        ValType::Ref(ty) => {
+            features.insert(WasmFeatures::REFERENCE_TYPES);
+            ref_type_features(ty, features);
+        }
+    }
+}
+
+fn ref_type_features(ty: RefType, features: &mut WasmFeatures) {
+    if !ty.is_nullable() {
+        features.insert(WasmFeatures::FUNCTION_REFERENCES);
+    }
+
+    match ty.heap_type() {
+        HeapType::Concrete(_) => features.insert(WasmFeatures::FUNCTION_REFERENCES),
+        HeapType::Abstract { shared, ty } => {
+            if shared {
+                features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
+            }
+            match ty {
+                AbstractHeapType::Func | AbstractHeapType::Extern => {}
+                AbstractHeapType::Exn | AbstractHeapType::NoExn => {
+                    features.insert(WasmFeatures::EXCEPTIONS);
+                }
+                AbstractHeapType::Cont | AbstractHeapType::NoCont => {
+                    features.insert(WasmFeatures::STACK_SWITCHING);
+                }
+                _ => features.insert(WasmFeatures::GC),
+            }
+        }
+    }
+}
+
+fn const_expr_features(expr: &ConstExpr<'_>, features: &mut WasmFeatures) -> crate::Result<()> {
+    let mut reader = expr.get_operators_reader();
+    while !reader.eof() {
+        let op = reader.read()?;
+        if matches!(
+            op,
+            Operator::I32Add
+                | Operator::I32Sub
+                | Operator::I32Mul
+                | Operator::I64Add
+                | Operator::I64Sub
+                | Operator::I64Mul
+        ) {
+            features.insert(WasmFeatures::EXTENDED_CONST);
+        }
+        FeatureVisitor(&mut *features).visit_operator(&op);
+    }
+    Ok(())
+}
+
+/// Returns the feature introducing the operators of `proposal`, as named by
+/// [`wasmparser::for_each_operator`].
+fn proposal_features(proposal: &str) -> WasmFeatures {
+    match proposal {
+        "exceptions" => WasmFeatures::EXCEPTIONS,
+        "legacy_exceptions" => WasmFeatures::LEGACY_EXCEPTIONS,
+        "tail_call" => WasmFeatures::TAIL_CALL,
+        "reference_types" => WasmFeatures::REFERENCE_TYPES,
+        "sign_extension" => WasmFeatures::SIGN_EXTENSION,
+        "saturating_float_to_int" => WasmFeatures::SATURATING_FLOAT_TO_INT,
+        "bulk_memory" => WasmFeatures::BULK_MEMORY,
+        "threads" => WasmFeatures::THREADS,
+        "simd" => WasmFeatures::SIMD,
+        "relaxed_simd" => WasmFeatures::RELAXED_SIMD,
+        "gc" => WasmFeatures::GC,
+        "function_references" => WasmFeatures::FUNCTION_REFERENCES,
+        "memory_control" => WasmFeatures::MEMORY_CONTROL,
+        "shared_everything_threads" => WasmFeatures::SHARED_EVERYTHING_THREADS,
+        "stack_switching" => WasmFeatures::STACK_SWITCHING,
+        _ => WasmFeatures::empty(),
+    }
+}
+
+/// Records the proposal every visited operator belongs to.
+struct FeatureVisitor<'f>(&'f mut WasmFeatures);
+
+macro_rules! define_visit_operator {
+    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident $(($($ann:tt)*))?)*) => {
+        $(
+            fn $visit(&mut self $($(, $arg: $argty)*)?) {
+                $($(let _ = $arg;)*)?
+                self.0.insert(proposal_features(stringify!($proposal)));
+            }
+        )*
+    };
+}
+
+impl<'a> VisitOperator<'a> for FeatureVisitor<'_> {
+    type Output = ();
+
+    wasmparser::for_each_operator!(define_visit_operator);
+}
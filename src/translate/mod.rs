@@ -1,4 +1,5 @@
 mod const_expr;
+mod features;
 mod module_translator;
 mod module_types;
 mod type_convert;
@@ -10,20 +11,22 @@ use crate::indices::{
     ElemIndex, EntityIndex, FieldIndex, FuncIndex, FuncRefIndex, GlobalIndex, LabelIndex,
     LocalIndex, MemoryIndex, ModuleInternedTypeIndex, TableIndex, TagIndex, TypeIndex,
 };
-use crate::{DEFAULT_OFFSET_GUARD_SIZE, WASM32_MAX_SIZE};
+use crate::{Error, DEFAULT_OFFSET_GUARD_SIZE, WASM32_MAX_SIZE};
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 pub use const_expr::{ConstExpr, ConstOp};
 use cranelift_entity::packed_option::ReservedValue;
 use cranelift_entity::{EntitySet, PrimaryMap};
+pub(crate) use features::detect_features;
 use hashbrown::HashMap;
 pub use module_translator::ModuleTranslator;
 pub use module_types::ModuleTypes;
 pub use type_convert::WasmparserTypeConverter;
 pub use types::{
-    EntityType, WasmFuncType, WasmHeapTopTypeInner, WasmHeapType, WasmHeapTypeInner, WasmRecGroup,
-    WasmRefType, WasmSubType, WasmValType,
+    EntityType, WasmCompositeType, WasmFuncType, WasmHeapTopTypeInner, WasmHeapType,
+    WasmHeapTypeInner, WasmRecGroup, WasmRefType, WasmStorageType, WasmSubType, WasmValType,
 };
 use wasmparser::collections::IndexMap;
 use wasmparser::WasmFeatures;
@@ -53,11 +56,38 @@ impl Default for ModuleTranslation<'_> {
     }
 }
 
+/// The dynamic linking information of a side module, read from its `dylink.0` custom section.
+///
+/// A loader uses it to reserve space for the module's data and table segments, which are placed
+/// relative to the `env.__memory_base` and `env.__table_base` globals the module imports, and to
+/// load the shared libraries it depends on first. See the [tool conventions] for details.
+///
+/// [tool conventions]: https://github.com/WebAssembly/tool-conventions/blob/main/DynamicLinking.md
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DylinkInfo {
+    /// The size in bytes of the memory area the module's data is placed in.
+    pub memory_size: u32,
+    /// The required alignment of the memory area in bytes, encoded as a power of two.
+    pub memory_alignment: u32,
+    /// The number of table elements the module's table segments occupy.
+    pub table_size: u32,
+    /// The required alignment of the table area in elements, encoded as a power of two.
+    pub table_alignment: u32,
+    /// The names of the shared libraries the module depends on.
+    pub needed: Vec<String>,
+}
+
 /// A translated WebAssembly module.
 #[derive(Debug, Default)]
 pub struct TranslatedModule {
     /// The name of this wasm module, if found,
     pub name: Option<String>,
+    /// The features declared in the module's `target_features` custom section, including ones
+    /// this crate doesn't know about.
+    pub target_features: Vec<String>,
+    /// The dynamic linking information from the module's `dylink.0` custom section, present
+    /// for Emscripten side modules. `None` if the section is missing or malformed.
+    pub dylink_info: Option<DylinkInfo>,
     /// The types declared in this module.
     pub types: PrimaryMap<TypeIndex, ModuleInternedTypeIndex>,
 
@@ -76,7 +106,7 @@ pub struct TranslatedModule {
     pub start: Option<FuncIndex>,
     /// Imports declared in this module.
     pub imports: Vec<Import>,
-    /// Exports declared in this module.
+    /// Exports declared in this module, in declaration order.
     pub exports: IndexMap<String, EntityIndex>,
 
     /// Initialization expressions for globals defined in this module.
@@ -287,6 +317,18 @@ impl TableDesc {
     }
 }
 
+/// How accesses to a linear memory are bounds-checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryStyle {
+    /// The memory reserves address space for its largest possible size followed by a guard
+    /// region, out-of-bounds accesses that land in the guard region fault and need no explicit
+    /// check.
+    Static,
+    /// Every access is explicitly compared against the current length of the memory, no guard
+    /// region is reserved.
+    Dynamic,
+}
+
 #[derive(Debug, Clone)]
 pub struct MemoryDesc {
     /// The minimum size of this memory, in wasm pages.
@@ -300,6 +342,8 @@ pub struct MemoryDesc {
     pub maximum: Option<u64>,
     /// The size in bytes of the offset guard region.
     pub offset_guard_size: u64,
+    /// How accesses to this memory are bounds-checked.
+    pub style: MemoryStyle,
     /// The log2 of this memory's page size, in bytes.
     ///
     /// By default, the page size is 64KiB (0x10000; 2**16; 1<<16; 65536) but the
@@ -327,19 +371,41 @@ impl MemoryDesc {
     };
 
     /// Creates a new `MemoryPlan` for the given `wasmparser::MemoryType`.
-    pub fn from_wasmparser(ty: wasmparser::MemoryType) -> Self {
-        Self {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory declares a page size other than `1` or `64KiB`, the only
+    /// page sizes the custom-page-sizes proposal allows.
+    pub fn from_wasmparser(ty: wasmparser::MemoryType, offset: usize) -> crate::Result<Self> {
+        let page_size_log2 = match ty.page_size_log2 {
+            None => Self::DEFAULT_PAGE_SIZE_LOG2,
+            Some(0) => 0,
+            Some(log2) if log2 == u32::from(Self::DEFAULT_PAGE_SIZE_LOG2) => {
+                Self::DEFAULT_PAGE_SIZE_LOG2
+            }
+            Some(log2) => {
+                return Err(Error::InvalidWebAssembly {
+                    message: format!("invalid custom page size 2^{log2}, must be 1 or 2^16"),
+                    offset,
+                })
+            }
+        };
+
+        Ok(Self {
             minimum: ty.initial,
             maximum: ty.maximum,
             shared: ty.shared,
             memory64: ty.memory64,
-            page_size_log2: ty
-                .page_size_log2
-                .map_or(Self::DEFAULT_PAGE_SIZE_LOG2, |log2| {
-                    u8::try_from(log2).unwrap()
-                }),
+            page_size_log2,
             offset_guard_size: DEFAULT_OFFSET_GUARD_SIZE,
-        }
+            style: MemoryStyle::Static,
+        })
+    }
+
+    /// Switches this memory to explicit bounds checks, which don't need a guard region.
+    pub fn make_dynamic(&mut self) {
+        self.style = MemoryStyle::Dynamic;
+        self.offset_guard_size = 0;
     }
 
     /// Returns the minimum size, in bytes, that this memory must be.
@@ -2,14 +2,15 @@ use crate::indices::{
     CanonicalizedTypeIndex, DataIndex, ElemIndex, EntityIndex, FieldIndex, FuncIndex, FuncRefIndex,
     GlobalIndex, LabelIndex, LocalIndex, MemoryIndex, TableIndex, TagIndex, TypeIndex,
 };
+use crate::translate::features::target_feature;
 use crate::translate::module_types::{ModuleTypes, ModuleTypesBuilder};
 use crate::translate::type_convert::WasmparserTypeConverter;
 use crate::translate::types::EntityType;
 use crate::translate::{
-    ConstExpr, FunctionBodyData, FunctionDesc, GlobalDesc, Import, MemoryDesc, MemoryInitializer,
-    ModuleTranslation, ProducersLanguage, ProducersLanguageField, ProducersSdk, ProducersSdkField,
-    ProducersTool, ProducersToolField, TableDesc, TableInitialValue, TableSegment,
-    TableSegmentElements,
+    ConstExpr, DylinkInfo, FunctionBodyData, FunctionDesc, GlobalDesc, Import, MemoryDesc,
+    MemoryInitializer, ModuleTranslation, ProducersLanguage, ProducersLanguageField, ProducersSdk,
+    ProducersSdkField, ProducersTool, ProducersToolField, TableDesc, TableInitialValue,
+    TableSegment, TableSegmentElements,
 };
 use crate::wasm_unsupported;
 use alloc::string::ToString;
@@ -18,11 +19,12 @@ use alloc::vec::Vec;
 use cranelift_entity::packed_option::ReservedValue;
 use hashbrown::HashMap;
 use wasmparser::{
-    BinaryReader, CustomSectionReader, DataKind, DataSectionReader, ElementItems, ElementKind,
-    ElementSectionReader, ExportSectionReader, ExternalKind, FunctionSectionReader,
-    GlobalSectionReader, ImportSectionReader, IndirectNameMap, MemorySectionReader, Name, NameMap,
-    NameSectionReader, Parser, Payload, ProducersFieldValue, ProducersSectionReader, TableInit,
-    TableSectionReader, TagSectionReader, TypeRef, TypeSectionReader, Validator, WasmFeatures,
+    BinaryReader, CustomSectionReader, DataKind, DataSectionReader, Dylink0SectionReader,
+    Dylink0Subsection, ElementItems, ElementKind, ElementSectionReader, ExportSectionReader,
+    ExternalKind, FunctionSectionReader, GlobalSectionReader, ImportSectionReader, IndirectNameMap,
+    MemorySectionReader, Name, NameMap, NameSectionReader, Parser, Payload, ProducersFieldValue,
+    ProducersSectionReader, TableInit, TableSectionReader, TagSectionReader, TypeRef,
+    TypeSectionReader, Validator, WasmFeatures,
 };
 
 /// A translator for converting the output of `wasmparser` into types used by this crate.
@@ -142,6 +144,15 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
             }
             Payload::CustomSection(section) => match section.name() {
                 "target_features" => self.parse_target_feature_section(&section),
+                "dylink.0" => {
+                    // like any other custom section, a malformed one doesn't make the module
+                    // invalid, it just isn't a side module we can link
+                    if let Err(err) = self.translate_dylink_section(Dylink0SectionReader::new(
+                        BinaryReader::new(section.data(), section.data_offset()),
+                    )) {
+                        tracing::warn!("ignoring malformed dylink.0 section: {err}");
+                    }
+                }
                 "name" => {
                     self.translate_name_section(NameSectionReader::new(BinaryReader::new(
                         section.data(),
@@ -250,8 +261,8 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
             .imports
             .reserve_exact(imports.count() as usize);
 
-        for import in imports {
-            let import = import?;
+        for import in imports.into_iter_with_offsets() {
+            let (offset, import) = import?;
 
             let index = match import.ty {
                 TypeRef::Func(index) => {
@@ -278,7 +289,7 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
                 TypeRef::Memory(ty) => {
                     self.result.module.num_imported_memories += 1;
 
-                    let memory = MemoryDesc::from_wasmparser(ty);
+                    let memory = MemoryDesc::from_wasmparser(ty, offset)?;
                     self.result.module.memories.push(memory.clone());
                     EntityType::Memory(memory)
                 }
@@ -353,7 +364,10 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
             let init = match table.init {
                 TableInit::RefNull => TableInitialValue::RefNull,
                 TableInit::Expr(expr) => {
-                    let (expr, escaped) = ConstExpr::from_wasmparser(&expr)?;
+                    let (expr, escaped) = ConstExpr::from_wasmparser(
+                        &expr,
+                        &WasmparserTypeConverter::new(&self.types.types, &self.result.module),
+                    )?;
                     for func in escaped {
                         self.flag_func_as_escaped(func);
                     }
@@ -379,11 +393,12 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
             .memories
             .reserve_exact(memories.count() as usize);
 
-        for ty in memories {
+        for ty in memories.into_iter_with_offsets() {
+            let (offset, ty) = ty?;
             self.result
                 .module
                 .memories
-                .push(MemoryDesc::from_wasmparser(ty?));
+                .push(MemoryDesc::from_wasmparser(ty, offset)?);
         }
 
         Ok(())
@@ -418,7 +433,10 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
                 shared: global.ty.shared,
             });
 
-            let (init_expr, escaped) = ConstExpr::from_wasmparser(&global.init_expr)?;
+            let (init_expr, escaped) = ConstExpr::from_wasmparser(
+                &global.init_expr,
+                &WasmparserTypeConverter::new(&self.types.types, &self.result.module),
+            )?;
             for func in escaped {
                 self.flag_func_as_escaped(func);
             }
@@ -508,7 +526,10 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
                     let mut out = Vec::with_capacity(exprs.count() as usize);
 
                     for expr in exprs {
-                        let (expr, escaped) = ConstExpr::from_wasmparser(&expr?)?;
+                        let (expr, escaped) = ConstExpr::from_wasmparser(
+                            &expr?,
+                            &WasmparserTypeConverter::new(&self.types.types, &self.result.module),
+                        )?;
                         for func in escaped {
                             self.flag_func_as_escaped(func);
                         }
@@ -524,7 +545,10 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
                     offset_expr,
                 } => {
                     let table_index = TableIndex::from_u32(table_index.unwrap_or(0));
-                    let (offset, escaped) = ConstExpr::from_wasmparser(&offset_expr)?;
+                    let (offset, escaped) = ConstExpr::from_wasmparser(
+                        &offset_expr,
+                        &WasmparserTypeConverter::new(&self.types.types, &self.result.module),
+                    )?;
                     debug_assert!(escaped.is_empty());
 
                     self.result
@@ -565,7 +589,10 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
                     offset_expr,
                 } => {
                     let memory_index = MemoryIndex::from_u32(memory_index);
-                    let (offset, escaped) = ConstExpr::from_wasmparser(&offset_expr)?;
+                    let (offset, escaped) = ConstExpr::from_wasmparser(
+                        &offset_expr,
+                        &WasmparserTypeConverter::new(&self.types.types, &self.result.module),
+                    )?;
                     debug_assert!(escaped.is_empty());
 
                     self.result
@@ -611,29 +638,11 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
             let len = r.read_var_u64().unwrap();
             let feature = r.read_bytes(usize::try_from(len).unwrap()).unwrap();
             let feature = core::str::from_utf8(feature).unwrap();
+            self.result.module.target_features.push(feature.to_string());
 
-            match feature {
-                "atomics" => required_features.insert(WasmFeatures::THREADS),
-                "bulk-memory" => required_features.insert(WasmFeatures::BULK_MEMORY),
-                "exception-handling" => required_features.insert(WasmFeatures::EXCEPTIONS),
-                "multivalue" => required_features.insert(WasmFeatures::MULTI_VALUE),
-                "mutable-globals" => required_features.insert(WasmFeatures::MUTABLE_GLOBAL),
-                "nontrapping-fptoint" => {
-                    required_features.insert(WasmFeatures::SATURATING_FLOAT_TO_INT);
-                }
-                "sign-ext" => required_features.insert(WasmFeatures::SIGN_EXTENSION),
-                "simd128" => required_features.insert(WasmFeatures::SIMD),
-                "tail-call" => required_features.insert(WasmFeatures::TAIL_CALL),
-                "reference-types" => required_features.insert(WasmFeatures::REFERENCE_TYPES),
-                "gc" => required_features.insert(WasmFeatures::GC),
-                "memory64" => required_features.insert(WasmFeatures::MEMORY64),
-                "relaxed-simd" => required_features.insert(WasmFeatures::RELAXED_SIMD),
-                "extended-const" => required_features.insert(WasmFeatures::EXTENDED_CONST),
-                "multimemory" => required_features.insert(WasmFeatures::MULTI_MEMORY),
-                "shared-everything" => {
-                    required_features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
-                }
-                _ => tracing::warn!("unknown required WASM feature `{feature}`"),
+            match target_feature(feature) {
+                Some(feature) => required_features.insert(feature),
+                None => tracing::warn!("unknown required WASM feature `{feature}`"),
             }
         }
 
@@ -821,6 +830,36 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
         Ok(())
     }
 
+    fn translate_dylink_section(
+        &mut self,
+        section: Dylink0SectionReader<'data>,
+    ) -> crate::Result<()> {
+        let mut info = DylinkInfo::default();
+
+        for subsection in section {
+            match subsection? {
+                Dylink0Subsection::MemInfo(mem_info) => {
+                    info.memory_size = mem_info.memory_size;
+                    info.memory_alignment = mem_info.memory_alignment;
+                    info.table_size = mem_info.table_size;
+                    info.table_alignment = mem_info.table_alignment;
+                }
+                Dylink0Subsection::Needed(needed) => {
+                    info.needed
+                        .extend(needed.into_iter().map(ToString::to_string));
+                }
+                // symbol flags only matter to tools that merge modules
+                Dylink0Subsection::ExportInfo(_) | Dylink0Subsection::ImportInfo(_) => {}
+                Dylink0Subsection::Unknown { ty, .. } => {
+                    tracing::warn!("unknown dylink.0 subsection {ty}");
+                }
+            }
+        }
+
+        self.result.module.dylink_info = Some(info);
+        Ok(())
+    }
+
     fn translate_producers_section(
         &mut self,
         section: ProducersSectionReader<'data>,
@@ -900,8 +939,8 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
         let data = section.data();
         let slice = gimli::EndianSlice::new(data, endian);
 
-        let mut dwarf = gimli::Dwarf::default();
         let info = &mut self.result.debug_info;
+        let dwarf = &mut info.dwarf;
 
         match name {
             // `gimli::Dwarf` fields.
@@ -941,9 +980,7 @@ impl<'a, 'data> ModuleTranslator<'a, 'data> {
             }
         }
 
-        dwarf.ranges = gimli::RangeLists::new(info.debug_ranges, info.debug_rnglists);
-        dwarf.locations = gimli::LocationLists::new(info.debug_loc, info.debug_loclists);
-
-        info.dwarf = dwarf;
+        info.dwarf.ranges = gimli::RangeLists::new(info.debug_ranges, info.debug_rnglists);
+        info.dwarf.locations = gimli::LocationLists::new(info.debug_loc, info.debug_loclists);
     }
 }
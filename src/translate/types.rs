@@ -74,6 +74,10 @@ impl WasmRefType {
         nullable: true,
         heap_type: WasmHeapType::new(false, WasmHeapTypeInner::Func),
     };
+    pub const ANYREF: WasmRefType = WasmRefType {
+        nullable: true,
+        heap_type: WasmHeapType::new(false, WasmHeapTypeInner::Any),
+    };
 
     /// Is this a type that is represented as a `VMGcRef`?
     #[inline]
@@ -97,6 +101,7 @@ impl fmt::Display for WasmRefType {
         match *self {
             Self::FUNCREF => write!(f, "funcref"),
             Self::EXTERNREF => write!(f, "externref"),
+            Self::ANYREF => write!(f, "anyref"),
             _ => {
                 if self.nullable {
                     write!(f, "(ref null {})", self.heap_type)
@@ -124,7 +129,7 @@ impl TypeTrace for WasmRefType {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub struct WasmHeapType {
     pub shared: bool,
     pub ty: WasmHeapTypeInner,
@@ -185,7 +190,7 @@ impl WasmHeapType {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum WasmHeapTypeInner {
     // External types.
     Extern,
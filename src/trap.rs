@@ -18,55 +18,85 @@ pub const TRAP_NULL_REFERENCE: TrapCode =
     TrapCode::unwrap_user(Trap::NullReference as u8 + TRAP_OFFSET);
 pub const TRAP_I31_NULL_REFERENCE: TrapCode =
     TrapCode::unwrap_user(Trap::NullI31Ref as u8 + TRAP_OFFSET);
+pub const TRAP_CAST_FAILURE: TrapCode =
+    TrapCode::unwrap_user(Trap::CastFailure as u8 + TRAP_OFFSET);
+pub const TRAP_ARRAY_OUT_OF_BOUNDS: TrapCode =
+    TrapCode::unwrap_user(Trap::ArrayOutOfBounds as u8 + TRAP_OFFSET);
 
-#[derive(Debug, Copy, Clone)]
+/// The reason for a trap raised while executing WebAssembly code.
+///
+/// The discriminants are explicit since they are encoded into the trap codes of JIT code, new
+/// variants have to take the next free value rather than shifting existing ones.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u8)]
 pub enum Trap {
     /// Internal assertion failed
-    InternalAssertionFailed,
+    InternalAssertionFailed = 0,
     /// A wasm atomic operation was presented with a not-naturally-aligned linear-memory address.
-    HeapMisaligned,
+    HeapMisaligned = 1,
     /// Out-of-bounds access to a table.
-    TableOutOfBounds,
+    TableOutOfBounds = 2,
     /// Indirect call to a null table entry.
-    IndirectCallToNull,
+    IndirectCallToNull = 3,
     /// Signature mismatch on indirect call.
-    BadSignature,
+    BadSignature = 4,
     /// Code that was supposed to have been unreachable was reached.
-    UnreachableCodeReached,
+    UnreachableCodeReached = 5,
     /// Call to a null reference.
-    NullReference,
+    NullReference = 6,
     /// Attempt to get the bits of a null `i31ref`.
-    NullI31Ref,
+    NullI31Ref = 7,
+    /// A `ref.cast` was given a reference that doesn't match the target type.
+    CastFailure = 15,
 
     /// The current stack space was exhausted.
-    StackOverflow,
+    StackOverflow = 8,
     /// An out-of-bounds memory access.
-    MemoryOutOfBounds,
+    MemoryOutOfBounds = 9,
     /// An integer arithmetic operation caused an overflow.
-    IntegerOverflow,
+    IntegerOverflow = 10,
     /// An integer division by zero.
-    IntegerDivisionByZero,
+    IntegerDivisionByZero = 11,
     /// Failed float-to-int conversion.
-    BadConversionToInteger,
+    BadConversionToInteger = 12,
+    /// Execution was interrupted because the store's epoch deadline was reached.
+    Interrupt = 13,
+    /// Execution ran out of fuel.
+    OutOfFuel = 14,
+    /// The host failed to provide the memory requested by a `memory.grow` instruction.
+    ///
+    /// Only raised when enabled through [`Store::trap_on_memory_grow_failure`][crate::Store::trap_on_memory_grow_failure].
+    HostAllocationFailed = 16,
+    /// Out-of-bounds access to an array.
+    ArrayOutOfBounds = 17,
+    /// An array was allocated that is larger than the GC heap can hold.
+    AllocationTooLarge = 18,
 }
 
+/// The messages match the ones used by the WebAssembly specification's test suite.
 impl fmt::Display for Trap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Trap::InternalAssertionFailed => f.write_str("internal assertion failed"),
-            Trap::HeapMisaligned => f.write_str("unaligned atomic operation"),
+            Trap::HeapMisaligned => f.write_str("unaligned atomic"),
             Trap::TableOutOfBounds => f.write_str("out of bounds table access"),
-            Trap::IndirectCallToNull => f.write_str("accessed uninitialized table element"),
-            Trap::BadSignature => f.write_str("indirect call signature mismatch"),
-            Trap::UnreachableCodeReached => f.write_str("unreachable code executed"),
-            Trap::NullReference => f.write_str("null reference called"),
-            Trap::NullI31Ref => f.write_str("null i32 reference called"),
+            Trap::IndirectCallToNull => f.write_str("uninitialized element"),
+            Trap::BadSignature => f.write_str("indirect call type mismatch"),
+            Trap::UnreachableCodeReached => f.write_str("unreachable"),
+            Trap::NullReference => f.write_str("null reference"),
+            Trap::NullI31Ref => f.write_str("null i31 reference"),
+            Trap::CastFailure => f.write_str("cast failure"),
 
             Trap::StackOverflow => f.write_str("call stack exhausted"),
             Trap::MemoryOutOfBounds => f.write_str("out of bounds memory access"),
             Trap::IntegerOverflow => f.write_str("integer overflow"),
             Trap::IntegerDivisionByZero => f.write_str("integer divide by zero"),
             Trap::BadConversionToInteger => f.write_str("invalid conversion to integer"),
+            Trap::Interrupt => f.write_str("interrupt"),
+            Trap::OutOfFuel => f.write_str("all fuel consumed by WebAssembly"),
+            Trap::HostAllocationFailed => f.write_str("host allocation failed"),
+            Trap::ArrayOutOfBounds => f.write_str("out of bounds array access"),
+            Trap::AllocationTooLarge => f.write_str("allocation size too large"),
         }
     }
 }
@@ -90,6 +120,8 @@ impl Trap {
             TRAP_UNREACHABLE => Some(Trap::UnreachableCodeReached),
             TRAP_NULL_REFERENCE => Some(Trap::NullReference),
             TRAP_I31_NULL_REFERENCE => Some(Trap::NullI31Ref),
+            TRAP_CAST_FAILURE => Some(Trap::CastFailure),
+            TRAP_ARRAY_OUT_OF_BOUNDS => Some(Trap::ArrayOutOfBounds),
             c => {
                 tracing::warn!("unknown trap code {c}");
                 None
@@ -100,22 +132,7 @@ impl Trap {
 
 impl From<Trap> for u8 {
     fn from(value: Trap) -> Self {
-        match value {
-            Trap::InternalAssertionFailed => 0,
-            Trap::HeapMisaligned => 1,
-            Trap::TableOutOfBounds => 2,
-            Trap::IndirectCallToNull => 3,
-            Trap::BadSignature => 4,
-            Trap::UnreachableCodeReached => 5,
-            Trap::NullReference => 6,
-            Trap::NullI31Ref => 7,
-
-            Trap::StackOverflow => 8,
-            Trap::MemoryOutOfBounds => 9,
-            Trap::IntegerOverflow => 10,
-            Trap::IntegerDivisionByZero => 11,
-            Trap::BadConversionToInteger => 12,
-        }
+        value as u8
     }
 }
 
@@ -138,7 +155,84 @@ impl TryFrom<u8> for Trap {
             10 => Ok(Self::IntegerOverflow),
             11 => Ok(Self::IntegerDivisionByZero),
             12 => Ok(Self::BadConversionToInteger),
+            13 => Ok(Self::Interrupt),
+            14 => Ok(Self::OutOfFuel),
+            15 => Ok(Self::CastFailure),
+            16 => Ok(Self::HostAllocationFailed),
+            17 => Ok(Self::ArrayOutOfBounds),
+            18 => Ok(Self::AllocationTooLarge),
             _ => Err(()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trap_codes_roundtrip() {
+        // every trap raised by JIT code has to map back to its own `Trap`, not a catch-all
+        let codes = [
+            (TRAP_INTERNAL_ASSERT, Trap::InternalAssertionFailed),
+            (TRAP_HEAP_MISALIGNED, Trap::HeapMisaligned),
+            (TRAP_TABLE_OUT_OF_BOUNDS, Trap::TableOutOfBounds),
+            (TRAP_INDIRECT_CALL_TO_NULL, Trap::IndirectCallToNull),
+            (TRAP_BAD_SIGNATURE, Trap::BadSignature),
+            (TRAP_UNREACHABLE, Trap::UnreachableCodeReached),
+            (TRAP_NULL_REFERENCE, Trap::NullReference),
+            (TRAP_I31_NULL_REFERENCE, Trap::NullI31Ref),
+            (TRAP_CAST_FAILURE, Trap::CastFailure),
+            (TRAP_ARRAY_OUT_OF_BOUNDS, Trap::ArrayOutOfBounds),
+            (TrapCode::STACK_OVERFLOW, Trap::StackOverflow),
+            (TrapCode::HEAP_OUT_OF_BOUNDS, Trap::MemoryOutOfBounds),
+            (TrapCode::INTEGER_OVERFLOW, Trap::IntegerOverflow),
+            (
+                TrapCode::INTEGER_DIVISION_BY_ZERO,
+                Trap::IntegerDivisionByZero,
+            ),
+            (
+                TrapCode::BAD_CONVERSION_TO_INTEGER,
+                Trap::BadConversionToInteger,
+            ),
+        ];
+
+        for (code, trap) in codes {
+            assert_eq!(Trap::from_trap_code(code), Some(trap), "{code}");
+        }
+    }
+
+    #[test]
+    fn discriminants_roundtrip() {
+        let traps = [
+            Trap::InternalAssertionFailed,
+            Trap::HeapMisaligned,
+            Trap::TableOutOfBounds,
+            Trap::IndirectCallToNull,
+            Trap::BadSignature,
+            Trap::UnreachableCodeReached,
+            Trap::NullReference,
+            Trap::NullI31Ref,
+            Trap::CastFailure,
+            Trap::StackOverflow,
+            Trap::MemoryOutOfBounds,
+            Trap::IntegerOverflow,
+            Trap::IntegerDivisionByZero,
+            Trap::BadConversionToInteger,
+            Trap::Interrupt,
+            Trap::OutOfFuel,
+            Trap::HostAllocationFailed,
+            Trap::ArrayOutOfBounds,
+            Trap::AllocationTooLarge,
+        ];
+
+        for trap in traps {
+            assert_eq!(Trap::try_from(u8::from(trap)), Ok(trap), "{trap:?}");
+        }
+        // every value that decodes to a trap is one of the above
+        let decoded = (0..=u8::MAX)
+            .filter(|value| Trap::try_from(*value).is_ok())
+            .count();
+        assert_eq!(decoded, traps.len());
+    }
+}
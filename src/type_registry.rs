@@ -106,12 +106,19 @@ pub trait TypeTrace {
 
 #[derive(Debug)]
 pub struct RuntimeTypeCollection {
-    engine: Engine,
+    /// The registry rather than the engine, since modules, which own their type collection, are
+    /// kept in the engine's module cache.
+    registry: Arc<TypeRegistry>,
     rec_groups: Vec<RecGroupEntry>,
     types: PrimaryMap<ModuleInternedTypeIndex, VMSharedTypeIndex>,
 }
 
 impl RuntimeTypeCollection {
+    /// Returns the registry the types are registered with.
+    pub fn registry(&self) -> &Arc<TypeRegistry> {
+        &self.registry
+    }
+
     /// Gets the map from `ModuleInternedTypeIndex` to `VMSharedTypeIndex`
     pub fn type_map(&self) -> &PrimaryMap<ModuleInternedTypeIndex, VMSharedTypeIndex> {
         &self.types
@@ -127,23 +134,44 @@ impl RuntimeTypeCollection {
 impl Drop for RuntimeTypeCollection {
     fn drop(&mut self) {
         if !self.rec_groups.is_empty() {
-            self.engine
-                .type_registry()
-                .0
-                .write()
-                .unregister_type_collection(self);
+            self.registry.0.write().unregister_type_collection(self);
         }
     }
 }
 
 pub struct RegisteredType {
-    engine: Engine,
+    registry: Arc<TypeRegistry>,
     entry: RecGroupEntry,
     ty: Arc<WasmSubType>,
     index: VMSharedTypeIndex,
 }
 
 impl RegisteredType {
+    /// Registers the given type, which must not reference any other types, with the engine's
+    /// type registry.
+    ///
+    /// This is used for types that don't originate from a module such as the types of host
+    /// functions. Because types are hash consed, the registered type will share its
+    /// `VMSharedTypeIndex` with structurally equal types registered by modules.
+    pub fn new(engine: &Engine, ty: WasmSubType) -> RegisteredType {
+        let (entry, index, ty) = {
+            let mut inner = engine.type_registry().0.write();
+
+            let entry = inner.register_singleton_rec_group(ty);
+            let index = entry.0.shared_type_indices[0];
+            let id = shared_type_index_to_slab_id(index);
+            let ty = inner.types.get(id).unwrap().clone();
+            (entry, index, ty)
+        };
+
+        RegisteredType {
+            registry: engine.type_registry().clone(),
+            entry,
+            ty,
+            index,
+        }
+    }
+
     pub fn index(&self) -> VMSharedTypeIndex {
         self.index
     }
@@ -152,7 +180,7 @@ impl RegisteredType {
 impl Debug for RegisteredType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let RegisteredType {
-            engine: _,
+            registry: _,
             entry: _,
             ty,
             index,
@@ -168,7 +196,7 @@ impl Clone for RegisteredType {
     fn clone(&self) -> Self {
         self.entry.incr_ref_count("cloning RegisteredType");
         RegisteredType {
-            engine: self.engine.clone(),
+            registry: self.registry.clone(),
             entry: self.entry.clone(),
             ty: self.ty.clone(),
             index: self.index,
@@ -179,11 +207,7 @@ impl Clone for RegisteredType {
 impl Drop for RegisteredType {
     fn drop(&mut self) {
         if self.entry.decr_ref_count("dropping RegisteredType") {
-            self.engine
-                .type_registry()
-                .0
-                .write()
-                .unregister_entry(self.entry.clone());
+            self.registry.0.write().unregister_entry(self.entry.clone());
         }
     }
 }
@@ -202,10 +226,10 @@ impl PartialEq for RegisteredType {
 
         if cfg!(debug_assertions) {
             if eq {
-                assert!(Engine::same(&self.engine, &other.engine));
+                assert!(Arc::ptr_eq(&self.registry, &other.registry));
                 assert_eq!(self.ty, other.ty);
             } else {
-                assert!(self.ty != other.ty || !Engine::same(&self.engine, &other.engine));
+                assert!(self.ty != other.ty || !Arc::ptr_eq(&self.registry, &other.registry));
             }
         }
 
@@ -231,21 +255,17 @@ impl TypeRegistry {
         Self::default()
     }
 
-    pub fn register_module_types(
-        &self,
-        engine: &Engine,
-        types: ModuleTypes,
-    ) -> RuntimeTypeCollection {
+    pub fn register_module_types(self: &Arc<Self>, types: ModuleTypes) -> RuntimeTypeCollection {
         let (rec_groups, types) = self.0.write().register_module_types(types);
 
         RuntimeTypeCollection {
-            engine: engine.clone(),
+            registry: self.clone(),
             rec_groups,
             types,
         }
     }
 
-    pub fn get_type(&self, engine: &Engine, index: VMSharedTypeIndex) -> Option<RegisteredType> {
+    pub fn get_type(self: &Arc<Self>, index: VMSharedTypeIndex) -> Option<RegisteredType> {
         let id = shared_type_index_to_slab_id(index);
         let inner = self.0.read();
 
@@ -256,7 +276,7 @@ impl TypeRegistry {
 
         debug_assert!(entry.0.registrations.load(Acquire) != 0);
         Some(RegisteredType {
-            engine: engine.clone(),
+            registry: self.clone(),
             entry,
             ty,
             index,
@@ -402,6 +422,19 @@ impl TypeRegistryInner {
         entry
     }
 
+    /// Registers a rec group consisting of only the given type.
+    ///
+    /// The type must not reference other types since there is no module index space to
+    /// resolve those references against.
+    fn register_singleton_rec_group(&mut self, ty: WasmSubType) -> RecGroupEntry {
+        // a singleton rec group has no preceding types, so an empty map and a range
+        // covering just the type itself is all that's needed
+        let map = PrimaryMap::<ModuleInternedTypeIndex, VMSharedTypeIndex>::default();
+        let range = ModuleInternedTypeIndex::from_u32(0)..ModuleInternedTypeIndex::from_u32(1);
+
+        self.register_rec_group(&map, range, core::iter::once(ty))
+    }
+
     #[tracing::instrument]
     fn insert_one_type_from_rec_group(
         &mut self,
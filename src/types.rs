@@ -0,0 +1,175 @@
+//! Descriptions of the types of WebAssembly items, as seen by embedders.
+//!
+//! The `Display` implementations print tables, memories and globals in WebAssembly text syntax,
+//! and function types as signatures like `(i32, i32) -> i64`.
+
+use crate::func::FuncType;
+use crate::translate::{GlobalDesc, MemoryDesc, TableDesc, WasmRefType, WasmValType};
+use core::fmt;
+
+/// The type of a WebAssembly value, e.g. of a parameter or a global.
+pub use crate::translate::WasmValType as ValType;
+
+/// The type of an imported or exported item.
+#[derive(Debug, Clone)]
+pub enum ExternType {
+    /// The item is a function.
+    Func(FuncType),
+    /// The item is a table.
+    Table(TableType),
+    /// The item is a linear memory.
+    Memory(MemoryType),
+    /// The item is a global.
+    Global(GlobalType),
+}
+
+impl fmt::Display for ExternType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternType::Func(ty) => f.write_fmt(format_args!("(func {ty})")),
+            ExternType::Table(ty) => fmt::Display::fmt(ty, f),
+            ExternType::Memory(ty) => fmt::Display::fmt(ty, f),
+            ExternType::Global(ty) => fmt::Display::fmt(ty, f),
+        }
+    }
+}
+
+/// The type of a WebAssembly table.
+#[derive(Debug, Clone)]
+pub struct TableType(TableDesc);
+
+impl TableType {
+    pub(crate) fn from_desc(desc: TableDesc) -> Self {
+        Self(desc)
+    }
+
+    /// Returns the type of the table's elements.
+    pub fn element(&self) -> &WasmRefType {
+        &self.0.element_type
+    }
+
+    /// Returns the minimum size of the table, in elements.
+    pub fn minimum(&self) -> u64 {
+        self.0.minimum
+    }
+
+    /// Returns the maximum size of the table, in elements, if it has one.
+    pub fn maximum(&self) -> Option<u64> {
+        self.0.maximum
+    }
+
+    /// Returns whether this is a 64-bit table of the memory64 proposal.
+    pub fn is_64(&self) -> bool {
+        self.0.table64
+    }
+}
+
+impl fmt::Display for TableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(table")?;
+        if self.0.table64 {
+            f.write_str(" i64")?;
+        }
+        f.write_fmt(format_args!(" {}", self.0.minimum))?;
+        if let Some(maximum) = self.0.maximum {
+            f.write_fmt(format_args!(" {maximum}"))?;
+        }
+        if self.0.shared {
+            f.write_str(" shared")?;
+        }
+        f.write_fmt(format_args!(" {})", self.0.element_type))
+    }
+}
+
+/// The type of a WebAssembly linear memory.
+#[derive(Debug, Clone)]
+pub struct MemoryType(MemoryDesc);
+
+impl MemoryType {
+    pub(crate) fn from_desc(desc: MemoryDesc) -> Self {
+        Self(desc)
+    }
+
+    /// Returns the minimum size of the memory, in pages.
+    pub fn minimum(&self) -> u64 {
+        self.0.minimum
+    }
+
+    /// Returns the maximum size of the memory, in pages, if it has one.
+    pub fn maximum(&self) -> Option<u64> {
+        self.0.maximum
+    }
+
+    /// Returns the log2 of the memory's page size in bytes, see the custom-page-sizes proposal.
+    pub fn page_size_log2(&self) -> u8 {
+        self.0.page_size_log2
+    }
+
+    /// Returns whether this is a 64-bit memory of the memory64 proposal.
+    pub fn is_64(&self) -> bool {
+        self.0.memory64
+    }
+
+    /// Returns whether this memory can be shared between threads.
+    pub fn is_shared(&self) -> bool {
+        self.0.shared
+    }
+}
+
+impl fmt::Display for MemoryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(memory")?;
+        if self.0.memory64 {
+            f.write_str(" i64")?;
+        }
+        f.write_fmt(format_args!(" {}", self.0.minimum))?;
+        if let Some(maximum) = self.0.maximum {
+            f.write_fmt(format_args!(" {maximum}"))?;
+        }
+        if self.0.shared {
+            f.write_str(" shared")?;
+        }
+        if self.0.page_size_log2 != MemoryDesc::DEFAULT_PAGE_SIZE_LOG2 {
+            let page_size = 1_u64 << self.0.page_size_log2;
+            f.write_fmt(format_args!(" (pagesize {page_size})"))?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// The type of a WebAssembly global.
+#[derive(Debug, Clone)]
+pub struct GlobalType(GlobalDesc);
+
+impl GlobalType {
+    pub(crate) fn from_desc(desc: GlobalDesc) -> Self {
+        Self(desc)
+    }
+
+    /// Returns the type of the global's value.
+    pub fn content(&self) -> &WasmValType {
+        &self.0.content_type
+    }
+
+    /// Returns whether the global can be mutated.
+    pub fn is_mutable(&self) -> bool {
+        self.0.mutable
+    }
+
+    /// Returns whether the global can be shared between threads.
+    pub fn is_shared(&self) -> bool {
+        self.0.shared
+    }
+}
+
+impl fmt::Display for GlobalType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ty = &self.0.content_type;
+        let shared = if self.0.shared { "shared " } else { "" };
+        if self.0.mutable {
+            f.write_fmt(format_args!("(global {shared}(mut {ty}))"))
+        } else {
+            f.write_fmt(format_args!("(global {shared}{ty})"))
+        }
+    }
+}
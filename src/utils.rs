@@ -98,7 +98,9 @@ fn blank_sig(isa: &dyn TargetIsa, call_conv: CallConv) -> Signature {
 }
 
 pub fn wasm_call_signature(isa: &dyn TargetIsa, func_ty: &WasmFuncType) -> Signature {
-    let mut sig = blank_sig(isa, CallConv::Fast);
+    // Wasm functions use the `tail` calling convention so they can be the target of tail calls,
+    // this is purely internal to wasm code since the host always calls through trampolines.
+    let mut sig = blank_sig(isa, CallConv::Tail);
 
     let cvt = |ty: &WasmValType| AbiParam::new(value_type(ty, isa.pointer_type()));
     sig.params.extend(func_ty.params.iter().map(&cvt));
@@ -1,9 +1,39 @@
 use crate::func::Func;
 use crate::runtime::VMVal;
-use crate::translate::{WasmHeapTopTypeInner, WasmHeapType, WasmValType};
-use crate::{enum_accessors, Store};
+use crate::store::GcRef;
+use crate::translate::{
+    WasmHeapTopTypeInner, WasmHeapType, WasmHeapTypeInner, WasmRefType, WasmValType,
+};
+use crate::{enum_accessors, Error, Store};
+use alloc::string::ToString;
 use core::ptr;
 
+/// Generates constructors and accessors that convert between a `v128` and its lanes.
+///
+/// Lane 0 occupies the least significant bits, matching the little-endian layout of vectors in
+/// WebAssembly memory.
+macro_rules! v128_lanes {
+    ($(($lane:ty, $n:literal, $from:ident, $as:ident))*) => ($(
+        #[doc = concat!("Creates a `v128` value from ", stringify!($n), " `", stringify!($lane), "` lanes.")]
+        pub fn $from(lanes: [$lane; $n]) -> Self {
+            let mut bytes = [0; 16];
+            for (chunk, lane) in bytes.chunks_exact_mut(size_of::<$lane>()).zip(lanes) {
+                chunk.copy_from_slice(&lane.to_le_bytes());
+            }
+            Self::V128(u128::from_le_bytes(bytes))
+        }
+
+        #[doc = concat!("Returns the lanes of a `v128` value interpreted as ", stringify!($n), " `", stringify!($lane), "`s, returning None if it is not a `v128`.")]
+        pub fn $as(&self) -> Option<[$lane; $n]> {
+            let bytes = self.v128()?.to_le_bytes();
+            Some(core::array::from_fn(|i| {
+                let chunk = &bytes[i * size_of::<$lane>()..][..size_of::<$lane>()];
+                <$lane>::from_le_bytes(chunk.try_into().unwrap())
+            }))
+        }
+    )*)
+}
+
 /// A reference value that a WebAssembly module can consume or produce.
 #[derive(Debug, Clone, Copy)]
 pub enum Val {
@@ -25,10 +55,10 @@ pub enum Val {
     V128(u128),
     /// A function reference.
     FuncRef(Option<Func>),
-    // /// An external reference.
-    // ExternRef(Option<Rooted<ExternRef>>),
-    // /// An internal reference.
-    // AnyRef(Option<Rooted<AnyRef>>),
+    /// An external reference.
+    ExternRef(Option<ExternRef>),
+    /// An internal reference.
+    AnyRef(Option<AnyRef>),
 }
 
 impl Val {
@@ -47,13 +77,31 @@ impl Val {
         Self::FuncRef(None)
     }
 
+    /// Returns the most precise type of this value.
+    pub(crate) fn ty(&self) -> WasmValType {
+        match self {
+            Val::I32(_) => WasmValType::I32,
+            Val::I64(_) => WasmValType::I64,
+            Val::F32(_) => WasmValType::F32,
+            Val::F64(_) => WasmValType::F64,
+            Val::V128(_) => WasmValType::V128,
+            Val::FuncRef(_) => WasmValType::Ref(WasmRefType::FUNCREF),
+            Val::ExternRef(_) => WasmValType::Ref(WasmRefType::EXTERNREF),
+            Val::AnyRef(_) => WasmValType::Ref(WasmRefType::ANYREF),
+        }
+    }
+
     /// Convenience method to convert this [`Val`] into a [`ValRaw`].
     ///
     /// # Safety
     ///
     /// Returned reference are essentially raw pointers and live only as long as
     /// the store does. It should be used with care.
-    pub unsafe fn as_vmval(&self, store: &mut Store) -> VMVal {
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is an external or internal reference from a different store.
+    pub unsafe fn as_vmval<T>(&self, store: &mut Store<T>) -> VMVal {
         match self {
             Val::I32(i) => VMVal::i32(*i),
             Val::I64(i) => VMVal::i64(*i),
@@ -64,6 +112,8 @@ impl Val {
                 Some(f) => f.as_raw(store),
                 None => ptr::null_mut(),
             }),
+            Val::ExternRef(e) => VMVal::externref(e.map_or(0, |e| store.unwrap_gc_ref(e.0))),
+            Val::AnyRef(r) => VMVal::anyref(r.map_or(0, |r| store.unwrap_gc_ref(r.0))),
         }
     }
 
@@ -73,14 +123,25 @@ impl Val {
     ///
     /// There is no way to know the actual type of `raw` so it is the callers responsibility
     /// to provide the correct type here.
-    pub unsafe fn from_vmval(_store: &mut Store, raw: VMVal, ty: &WasmValType) -> Self {
+    pub unsafe fn from_vmval<T>(store: &mut Store<T>, raw: VMVal, ty: &WasmValType) -> Self {
         match ty {
             WasmValType::I32 => Self::I32(raw.get_i32()),
             WasmValType::I64 => Self::I64(raw.get_i64()),
             WasmValType::F32 => Self::F32(raw.get_f32()),
             WasmValType::F64 => Self::F64(raw.get_f64()),
             WasmValType::V128 => Self::V128(raw.get_v128()),
-            WasmValType::Ref(_) => todo!(),
+            WasmValType::Ref(ref_ty) => match ref_ty.heap_type.top().inner {
+                WasmHeapTopTypeInner::Func => {
+                    Self::FuncRef(Func::from_vm_func_ref(store, raw.get_funcref()))
+                }
+                WasmHeapTopTypeInner::Extern => {
+                    Self::ExternRef(store.wrap_gc_ref(raw.get_externref()).map(ExternRef))
+                }
+                WasmHeapTopTypeInner::Any => {
+                    Self::AnyRef(store.wrap_gc_ref(raw.get_anyref()).map(AnyRef))
+                }
+                ty => todo!("heap type: {ty:?}"),
+            },
         }
     }
 
@@ -92,6 +153,13 @@ impl Val {
         (F64(f64) is_f64 f64 unwrap_f64 f64::from_bits(*e))
         (V128(u128) is_v128 v128 unwrap_v128 *e)
     }
+
+    v128_lanes! {
+        (i8, 16, v128_from_i8x16, as_i8x16)
+        (i16, 8, v128_from_i16x8, as_i16x8)
+        (i32, 4, v128_from_i32x4, as_i32x4)
+        (i64, 2, v128_from_i64x2, as_i64x2)
+    }
 }
 
 impl From<i32> for Val {
@@ -122,19 +190,60 @@ impl From<f64> for Val {
     }
 }
 
+impl From<u128> for Val {
+    #[inline]
+    fn from(val: u128) -> Val {
+        Val::V128(val)
+    }
+}
+
 impl From<Ref> for Val {
     #[inline]
     fn from(val: Ref) -> Val {
         match val {
             Ref::Func(f) => Val::FuncRef(f),
+            Ref::Extern(e) => Val::ExternRef(e),
+            Ref::Any(r) => Val::AnyRef(r),
         }
     }
 }
 
+/// Implements `TryFrom<Val>` for the Rust types of the numeric WebAssembly value types.
+macro_rules! impl_try_from_val {
+    ($($ty:ty => $accessor:ident $valtype:ident,)*) => ($(
+        impl TryFrom<Val> for $ty {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(val: Val) -> Result<Self, Self::Error> {
+                val.$accessor().ok_or_else(|| Error::ValTypeMismatch {
+                    expected: WasmValType::$valtype.to_string(),
+                    actual: val.ty().to_string(),
+                })
+            }
+        }
+    )*)
+}
+
+impl_try_from_val! {
+    i32 => i32 I32,
+    i64 => i64 I64,
+    f32 => f32 F32,
+    f64 => f64 F64,
+    u128 => v128 V128,
+}
+
 /// A reference value that a WebAssembly module can consume or produce.
+///
+/// References compare equal if they are both null or refer to the same item of the same store.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Ref {
     /// A function reference.
     Func(Option<Func>),
+    /// An external reference.
+    Extern(Option<ExternRef>),
+    /// An internal reference.
+    Any(Option<AnyRef>),
 }
 
 impl Ref {
@@ -143,6 +252,8 @@ impl Ref {
     pub fn null(heap_type: &WasmHeapType) -> Self {
         match heap_type.top().inner {
             WasmHeapTopTypeInner::Func => Ref::Func(None),
+            WasmHeapTopTypeInner::Extern => Ref::Extern(None),
+            WasmHeapTopTypeInner::Any => Ref::Any(None),
             ty => todo!("heap type: {ty:?}"),
         }
     }
@@ -151,8 +262,8 @@ impl Ref {
     #[inline]
     pub fn is_null(&self) -> bool {
         match self {
-            Self::Func(None) => true,
-            Self::Func(Some(_)) => false,
+            Self::Func(None) | Self::Extern(None) | Self::Any(None) => true,
+            Self::Func(Some(_)) | Self::Extern(Some(_)) | Self::Any(Some(_)) => false,
         }
     }
 
@@ -163,10 +274,107 @@ impl Ref {
     }
 }
 
-#[expect(irrefutable_let_patterns, reason = "we only have one enum variant rn")]
 impl Ref {
     enum_accessors! {
         e
         (Func(&Option<Func>) is_func get_func unwrap_func e)
+        (Extern(&Option<ExternRef>) is_extern get_extern unwrap_extern e)
+        (Any(&Option<AnyRef>) is_any get_any unwrap_any e)
+    }
+}
+
+/// A reference to a value outside of WebAssembly.
+///
+/// External references are opaque to the embedder, they can only be passed back to WebAssembly
+/// code of the store they were obtained from. They compare equal if they refer to the same value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExternRef(pub(crate) GcRef);
+
+/// A reference to a value managed by WebAssembly, i.e. an array or an `i31ref`.
+///
+/// Internal references can only be passed back to WebAssembly code of the store they were
+/// obtained from, and only until that store is [reset][crate::Store::reset]. They compare equal
+/// if they refer to the same object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnyRef(pub(crate) GcRef);
+
+impl AnyRef {
+    /// Creates an `i31ref` holding the low 31 bits of `value`.
+    pub(crate) fn from_i31<T>(store: &Store<T>, value: i32) -> Self {
+        let raw = (u32::from_ne_bytes(value.to_ne_bytes()) << 1) | 1;
+        Self(store.wrap_gc_ref(raw).expect("i31refs are never null"))
+    }
+
+    /// Returns whether this is an `i31ref` rather than a reference to an object.
+    pub fn is_i31(&self) -> bool {
+        self.0.raw() & 1 != 0
+    }
+
+    /// Returns the sign-extended value of this `i31ref`, or `None` if it refers to an object.
+    pub fn as_i31(&self) -> Option<i32> {
+        self.is_i31()
+            .then(|| i32::from_ne_bytes(self.0.raw().to_ne_bytes()) >> 1)
+    }
+
+    /// Returns whether this reference can be passed where the heap type `ty`, which has to be in
+    /// the internal hierarchy, is expected.
+    ///
+    /// Arrays don't record their concrete type, so they only match the abstract array type.
+    pub(crate) fn matches(&self, ty: &WasmHeapType) -> bool {
+        match ty.ty {
+            WasmHeapTypeInner::Any | WasmHeapTypeInner::Eq => true,
+            WasmHeapTypeInner::I31 => self.is_i31(),
+            WasmHeapTypeInner::Array => !self.is_i31(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_val() {
+        assert_eq!(Val::from(-1_i32).unwrap_i32(), -1);
+        assert_eq!(Val::from(i64::MIN).unwrap_i64(), i64::MIN);
+        assert_eq!(Val::from(1.5_f32).unwrap_f32().to_bits(), 1.5_f32.to_bits());
+        assert_eq!(
+            Val::from(f64::MIN_POSITIVE).unwrap_f64().to_bits(),
+            f64::MIN_POSITIVE.to_bits()
+        );
+        assert_eq!(Val::from(u128::MAX).unwrap_v128(), u128::MAX);
+    }
+
+    #[test]
+    fn try_from_val() {
+        assert_eq!(i32::try_from(Val::I32(7)).unwrap(), 7);
+        assert_eq!(i64::try_from(Val::I64(-7)).unwrap(), -7);
+        assert_eq!(
+            f32::try_from(Val::from(2.5_f32)).unwrap().to_bits(),
+            2.5_f32.to_bits()
+        );
+        assert_eq!(
+            f64::try_from(Val::from(f64::NAN)).unwrap().to_bits(),
+            f64::NAN.to_bits()
+        );
+        assert_eq!(u128::try_from(Val::V128(1 << 100)).unwrap(), 1 << 100);
+    }
+
+    #[test]
+    fn try_from_val_mismatch() {
+        let err = i32::try_from(Val::I64(1)).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::ValTypeMismatch { expected, actual } if expected == "i32" && actual == "i64"
+            ),
+            "{err}"
+        );
+
+        let err = f64::try_from(Val::null_func_ref()).unwrap_err();
+        assert!(matches!(err, Error::ValTypeMismatch { .. }), "{err}");
+        let err = u128::try_from(Val::F32(0)).unwrap_err();
+        assert!(matches!(err, Error::ValTypeMismatch { .. }), "{err}");
     }
 }
@@ -0,0 +1,54 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Module};
+use wasmparser::{Parser, Payload, Validator};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().debug_info(true));
+    let mut validator = Validator::new();
+
+    let wasm = wat::parse_str(include_str!("./fib_cpp.wat"))?;
+    let module = Module::from_bytes(&engine, &mut validator, &wasm)?;
+    let map = module.address_map().collect::<Vec<_>>();
+
+    assert!(!map.is_empty());
+    assert!(map.windows(2).all(|pair| pair[0].0 <= pair[1].0), "{map:?}");
+    assert!(map
+        .iter()
+        .all(|(text_offset, _)| *text_offset < module.text().len()));
+
+    // every function body is covered and nothing points outside of them
+    let bodies = Parser::new(0)
+        .parse_all(&wasm)
+        .filter_map(|payload| match payload {
+            Ok(Payload::CodeSectionEntry(body)) => Some(body.range()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(!bodies.is_empty());
+    for body in &bodies {
+        assert!(
+            map.iter()
+                .any(|(_, wasm_offset)| body.contains(&usize::try_from(*wasm_offset).unwrap())),
+            "no code maps to the function at {body:?}"
+        );
+    }
+    for (_, wasm_offset) in &map {
+        let wasm_offset = usize::try_from(*wasm_offset).unwrap();
+        assert!(bodies.iter().any(|body| body.contains(&wasm_offset)));
+    }
+
+    Ok(())
+}
+
+#[test_log::test]
+fn disabled_by_default() -> Result<(), Error> {
+    let engine = Engine::default();
+
+    let module = common::compile(&engine, include_str!("./fib_cpp.wat"))?;
+    assert!(!module.text().is_empty());
+    assert_eq!(module.address_map().len(), 0);
+
+    Ok(())
+}
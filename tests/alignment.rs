@@ -0,0 +1,83 @@
+mod common;
+
+use k23vm::{Engine, Error};
+
+#[test_log::test]
+fn under_aligned_hints() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1)
+        (func (export "i32_roundtrip") (param $addr i32) (param $val i32) (result i32)
+            (i32.store align=1 (local.get $addr) (local.get $val))
+            (i32.load align=1 (local.get $addr))
+        )
+        (func (export "i64_roundtrip") (param $addr i32) (param $val i64) (result i64)
+            (i64.store align=2 (local.get $addr) (local.get $val))
+            (i64.load align=2 (local.get $addr))
+        )
+        (func (export "f64_roundtrip") (param $addr i32) (param $val f64) (result f64)
+            (f64.store align=4 (local.get $addr) (local.get $val))
+            (f64.load align=4 (local.get $addr))
+        )
+        ;; the hint doesn't change how many bytes are accessed
+        (func (export "load16") (param $addr i32) (result i32)
+            (i32.load16_u align=1 (local.get $addr))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let i32_roundtrip = instance.get_typed_func::<(i32, i32), i32>(&mut store, "i32_roundtrip")?;
+    let i64_roundtrip = instance.get_typed_func::<(i32, i64), i64>(&mut store, "i64_roundtrip")?;
+    let f64_roundtrip = instance.get_typed_func::<(i32, f64), f64>(&mut store, "f64_roundtrip")?;
+    let load16 = instance.get_typed_func::<i32, i32>(&mut store, "load16")?;
+
+    // every misaligned address works, even those that don't match the (already lower) hint
+    for addr in 1..8 {
+        assert_eq!(
+            i32_roundtrip.call(&mut store, (addr, 0x1234_5678))?,
+            0x1234_5678
+        );
+        assert_eq!(
+            i64_roundtrip.call(&mut store, (addr, 0x0102_0304_0506_0708))?,
+            0x0102_0304_0506_0708
+        );
+        assert_eq!(
+            f64_roundtrip.call(&mut store, (addr, 1.5))?.to_bits(),
+            1.5f64.to_bits()
+        );
+    }
+
+    i32_roundtrip.call(&mut store, (0x101, 0x1234_5678))?;
+    assert_eq!(load16.call(&mut store, 0x101)?, 0x5678);
+    assert_eq!(load16.call(&mut store, 0x103)?, 0x1234);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn over_aligned_hint() {
+    let engine = Engine::default();
+
+    // the hint may be at most the natural alignment of the access
+    let str = r#"
+    (module
+        (memory 1)
+        (func (param i32) (result i32)
+            (i32.load align=8 (local.get 0))
+        )
+    )"#;
+    let err = common::compile(&engine, str).unwrap_err();
+    assert!(matches!(err, Error::InvalidWebAssembly { .. }), "{err}");
+
+    let str = r#"
+    (module
+        (memory 1)
+        (func (param i32)
+            (i64.store16 align=4 (local.get 0) (i64.const 0))
+        )
+    )"#;
+    let err = common::compile(&engine, str).unwrap_err();
+    assert!(matches!(err, Error::InvalidWebAssembly { .. }), "{err}");
+}
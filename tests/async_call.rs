@@ -0,0 +1,125 @@
+mod common;
+
+use common::block_on;
+use k23vm::{Caller, Config, Engine, Error, Func, Instance, Linker, Store, Trap, Val};
+use std::pin::pin;
+use std::task::Context;
+
+fn setup() -> Result<(Store<()>, Instance), Error> {
+    let str = r#"
+    (module
+        (import "env" "tick" (func $tick))
+
+        (func (export "run") (result i32)
+            (call $tick)
+            ;; the epoch check at the loop header notices the new epoch
+            (loop $l)
+            (i32.const 42)
+        )
+    )"#;
+
+    let engine = Engine::new(Config::new().epoch_interruption(true));
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let tick = Func::wrap(&mut store, |caller: Caller<'_, ()>| {
+        caller.engine().increment_epoch();
+    })?;
+    linker.define("env", "tick", tick)?;
+
+    let instance = common::instantiate(&mut store, &linker, &common::compile(&engine, str)?)?;
+
+    Ok((store, instance))
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_async_yield_and_update(1);
+
+    let run = instance.get_func(&mut store, "run").unwrap();
+    let mut results = [Val::I32(0)];
+    let (res, polls) = block_on(run.call_async(&mut store, &[], &mut results));
+    res?;
+
+    // the call yielded once at the loop header and then ran to completion
+    assert_eq!(polls, 2);
+    assert_eq!(results[0].unwrap_i32(), 42);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn dropping_the_future_interrupts_the_call() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_async_yield_and_update(1);
+
+    let run = instance.get_func(&mut store, "run").unwrap();
+    let mut results = [Val::I32(0)];
+    {
+        let waker = common::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = pin!(run.call_async(&mut store, &[], &mut results));
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+    }
+
+    // the store is still usable after the suspended call was dropped
+    store.set_epoch_deadline(10);
+    let (res, polls) = block_on(run.call_async(&mut store, &[], &mut results));
+    res?;
+    assert_eq!(polls, 1);
+    assert_eq!(results[0].unwrap_i32(), 42);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn epoch_deadline_traps() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    store.set_epoch_deadline(1);
+
+    let run = instance.get_func(&mut store, "run").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: `run` takes no parameters and returns a single `i32`
+    let err = unsafe { run.call_unchecked(&mut store, &[], &mut results) }.unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::Interrupt));
+
+    // synchronous calls can't yield, so they trap as well
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_async_yield_and_update(1);
+    // Safety: `run` takes no parameters and returns a single `i32`
+    let err = unsafe { run.call_unchecked(&mut store, &[], &mut results) }.unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::Interrupt));
+
+    Ok(())
+}
+
+#[test_log::test]
+fn arguments_are_checked() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+
+    let run = instance.get_func(&mut store, "run").unwrap();
+    let mut results = [Val::I32(0)];
+    let (res, polls) = block_on(run.call_async(&mut store, &[Val::I32(1)], &mut results));
+    let err = res.unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::WrongArgCount {
+                expected: 0,
+                actual: 1
+            }
+        ),
+        "{err}"
+    );
+    // the call was rejected before it started running
+    assert_eq!(polls, 1);
+
+    let (res, _) = block_on(run.call_async(&mut store, &[], &mut []));
+    let err = res.unwrap_err();
+    assert!(matches!(err, Error::WrongResultCount { .. }), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn unaligned_atomics_trap() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1)
+        (func (export "load") (param i32) (result i32)
+            (i32.atomic.load (local.get 0))
+        )
+        (func (export "load_offset") (param i32) (result i32)
+            (i32.atomic.load offset=2 (local.get 0))
+        )
+        (func (export "load16") (param i32) (result i32)
+            (i32.atomic.load16_u (local.get 0))
+        )
+        (func (export "store") (param i32) (param i64)
+            (i64.atomic.store (local.get 0) (local.get 1))
+        )
+        (func (export "rmw_add") (param i32) (param i32) (result i32)
+            (i32.atomic.rmw.add (local.get 0) (local.get 1))
+        )
+        ;; regular accesses don't care about alignment
+        (func (export "plain_load") (param i32) (result i32)
+            (i32.load (local.get 0))
+        )
+    )"#;
+
+    let features = WasmFeatures::WASM2 | WasmFeatures::THREADS;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    let load_offset = instance.get_typed_func::<i32, i32>(&mut store, "load_offset")?;
+    let load16 = instance.get_typed_func::<i32, i32>(&mut store, "load16")?;
+    let atomic_store = instance.get_typed_func::<(i32, i64), ()>(&mut store, "store")?;
+    let rmw_add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "rmw_add")?;
+    let plain_load = instance.get_typed_func::<i32, i32>(&mut store, "plain_load")?;
+
+    atomic_store.call(&mut store, (8, 0x0102_0304_0506_0708))?;
+    assert_eq!(load.call(&mut store, 8)?, 0x0506_0708);
+    assert_eq!(load16.call(&mut store, 10)?, 0x0506);
+    assert_eq!(rmw_add.call(&mut store, (12, 1))?, 0x0102_0304);
+    assert_eq!(load.call(&mut store, 12)?, 0x0102_0305);
+    // the static offset counts towards the alignment of the effective address
+    assert_eq!(load_offset.call(&mut store, 10)?, 0x0102_0305);
+    assert_eq!(plain_load.call(&mut store, 9)?, 0x0505_0607);
+
+    let misaligned = [
+        load.call(&mut store, 9).unwrap_err(),
+        load_offset.call(&mut store, 8).unwrap_err(),
+        load16.call(&mut store, 11).unwrap_err(),
+        atomic_store.call(&mut store, (12, 0)).unwrap_err(),
+        rmw_add.call(&mut store, (14, 1)).unwrap_err(),
+    ];
+    for err in misaligned {
+        assert_eq!(err.trap_code(), Some(Trap::HeapMisaligned), "{err}");
+    }
+
+    // aligned but out of bounds is reported as such, misaligned and out of bounds as misaligned
+    let err = load.call(&mut store, 0x1_0000).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+    let err = load.call(&mut store, 0x1_0001).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::HeapMisaligned), "{err}");
+
+    Ok(())
+}
+
+#[test_log::test]
+fn unaligned_atomic_trap_is_distinct() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1)
+        (func (export "load") (param i32) (result i32)
+            (i32.atomic.load (local.get 0))
+        )
+        (func (export "cmpxchg") (param i32) (result i64)
+            (i64.atomic.rmw.cmpxchg (local.get 0) (i64.const 0) (i64.const 1))
+        )
+        ;; byte-sized accesses are always aligned
+        (func (export "load8") (param i32) (result i32)
+            (i32.atomic.load8_u (local.get 0))
+        )
+    )"#;
+
+    let features = WasmFeatures::WASM2 | WasmFeatures::THREADS;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    let cmpxchg = instance.get_typed_func::<i32, i64>(&mut store, "cmpxchg")?;
+    let load8 = instance.get_typed_func::<i32, i32>(&mut store, "load8")?;
+
+    let err = load.call(&mut store, 2).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::HeapMisaligned), "{err}");
+    // the message the spec test suite expects
+    assert_eq!(Trap::HeapMisaligned.to_string(), "unaligned atomic");
+
+    let err = cmpxchg.call(&mut store, 4).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::HeapMisaligned), "{err}");
+    assert_eq!(cmpxchg.call(&mut store, 8)?, 0);
+    assert_eq!(cmpxchg.call(&mut store, 8)?, 1);
+
+    assert_eq!(load8.call(&mut store, 8)?, 1);
+    assert_eq!(load8.call(&mut store, 7)?, 0);
+
+    Ok(())
+}
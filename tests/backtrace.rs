@@ -0,0 +1,94 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Store, Trap, WasmBacktraceDetails};
+
+#[test_log::test]
+fn backtrace_details() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $trap
+            (unreachable)
+        )
+        (func (export "run")
+            (call $trap)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+
+    // backtraces are captured by default
+    let err = run.call(&mut store, ()).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::UnreachableCodeReached), "{err}");
+    let backtrace = store.last_trap_backtrace().unwrap();
+    assert!(backtrace.frames().next().is_some());
+
+    // with capture disabled traps are still reported correctly
+    store.set_wasm_backtrace_details(WasmBacktraceDetails::Disable);
+    let err = run.call(&mut store, ()).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::UnreachableCodeReached), "{err}");
+    assert!(store.last_trap_backtrace().is_none());
+
+    // on demand only the trap after a request captures a backtrace
+    store.set_wasm_backtrace_details(WasmBacktraceDetails::OnDemand);
+    run.call(&mut store, ()).unwrap_err();
+    assert!(store.last_trap_backtrace().is_none());
+    store.request_backtrace();
+    run.call(&mut store, ()).unwrap_err();
+    assert!(store.last_trap_backtrace().is_some());
+    run.call(&mut store, ()).unwrap_err();
+    assert!(store.last_trap_backtrace().is_none());
+
+    Ok(())
+}
+
+#[test_log::test]
+fn frame_pointer_walk() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $trap
+            (unreachable)
+        )
+        (func $middle
+            (call $trap)
+        )
+        (func $outer
+            (call $middle)
+        )
+        (func (export "run")
+            (call $outer)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+
+    run.call(&mut store, ()).unwrap_err();
+    let frames = store
+        .last_trap_backtrace()
+        .unwrap()
+        .frames()
+        .map(|frame| (frame.pc, frame.fp))
+        .collect::<Vec<_>>();
+
+    // exactly the four WebAssembly frames, innermost first, the walk stops at the trampoline
+    assert_eq!(frames.len(), 4, "{frames:x?}");
+    assert!(
+        frames
+            .iter()
+            .all(|(pc, _)| module.text_offset(*pc).is_some()),
+        "{frames:x?}"
+    );
+    assert!(
+        frames.windows(2).all(|pair| pair[0].1 < pair[1].1),
+        "{frames:x?}"
+    );
+
+    Ok(())
+}
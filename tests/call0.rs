@@ -0,0 +1,37 @@
+mod common;
+
+use k23vm::{Engine, Val};
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (global $counter (mut i32) (i32.const 0))
+
+        (func (export "init")
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+        )
+        (func (export "get") (result i32)
+            (global.get $counter)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let init = instance.get_func(&mut store, "init").unwrap();
+    init.call0(&mut store).unwrap();
+    init.call0(&mut store).unwrap();
+
+    // the calls above incremented the counter
+    let get = instance.get_func(&mut store, "get").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: `get` takes no parameters and returns a single i32
+    unsafe {
+        get.call_unchecked(&mut store, &[], &mut results).unwrap();
+    }
+    assert_eq!(results[0].unwrap_i32(), 2);
+
+    // functions with parameters or results are rejected
+    assert!(get.call0(&mut store).is_err());
+}
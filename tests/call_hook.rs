@@ -0,0 +1,87 @@
+mod common;
+
+use k23vm::{CallHook, Engine, Error, Func, Linker, Store, Trap};
+use std::sync::{Arc, Mutex};
+
+const WAT: &str = r#"
+(module
+    (import "env" "log" (func $log (param i32)))
+    (func (export "run") (param i32) (result i32)
+        (call $log (local.get 0))
+        (i32.add (local.get 0) (i32.const 1))
+    )
+)"#;
+
+#[test_log::test]
+fn transitions_are_reported() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let log = Func::wrap(&mut store, |_: i32| {})?;
+    linker.define("env", "log", log)?;
+    let module = common::compile(&engine, WAT)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let run = instance.get_typed_func::<i32, i32>(&mut store, "run")?;
+
+    let transitions = Arc::new(Mutex::new(Vec::new()));
+    let recorded = transitions.clone();
+    store.set_call_hook(move |transition| {
+        recorded.lock().unwrap().push(transition);
+        Ok(())
+    });
+
+    assert_eq!(run.call(&mut store, 41)?, 42);
+    assert_eq!(
+        *transitions.lock().unwrap(),
+        [
+            CallHook::CallingWasm,
+            CallHook::CallingHost,
+            CallHook::ReturningFromHost,
+            CallHook::ReturningFromWasm,
+        ]
+    );
+
+    let wasm_entries = transitions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|t| **t == CallHook::CallingWasm)
+        .count();
+    assert_eq!(wasm_entries, 1);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn hook_error_traps() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let log = Func::wrap(&mut store, |_: i32| {})?;
+    linker.define("env", "log", log)?;
+    let module = common::compile(&engine, WAT)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let run = instance.get_typed_func::<i32, i32>(&mut store, "run")?;
+
+    // deny calls into the host
+    store.set_call_hook(|transition| match transition {
+        CallHook::CallingHost => Err(Error::Trap {
+            trap: Trap::Interrupt,
+            message: "host calls are not allowed".to_string(),
+            faulting_addr: None,
+            wasm_offset: None,
+        }),
+        _ => Ok(()),
+    });
+
+    let err = run.call(&mut store, 41).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::Interrupt), "{err}");
+
+    // the store is still usable once the hook allows the call again
+    store.set_call_hook(|_| Ok(()));
+    assert_eq!(run.call(&mut store, 41)?, 42);
+
+    Ok(())
+}
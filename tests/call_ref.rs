@@ -1,5 +1,7 @@
-use k23vm::{Engine, Module};
-use wasmparser::Validator;
+mod common;
+
+use k23vm::{Config, Engine};
+use wasmparser::WasmFeatures;
 
 #[test_log::test]
 fn main() {
@@ -12,8 +14,8 @@ fn main() {
         )
     )";
 
-    let engine = Engine::default();
-    let mut validator = Validator::new();
+    let features = WasmFeatures::default() | WasmFeatures::FUNCTION_REFERENCES;
+    let engine = Engine::new(Config::new().wasm_features(features));
 
-    let _module = Module::from_str(&engine, &mut validator, str).unwrap();
+    let _module = common::compile(&engine, str).unwrap();
 }
@@ -0,0 +1,39 @@
+mod common;
+
+use k23vm::{Engine, Error, VMVal};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $fib (export "fib") (param i32) (result i32)
+            (if (result i32) (i32.lt_u (local.get 0) (i32.const 2))
+                (then (local.get 0))
+                (else
+                    (i32.add
+                        (call $fib (i32.sub (local.get 0) (i32.const 1)))
+                        (call $fib (i32.sub (local.get 0) (i32.const 2)))
+                    )
+                )
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let fib = instance.get_typed_func::<i32, i32>(&mut store, "fib")?;
+    let raw = instance.get_func(&mut store, "fib").unwrap();
+
+    for n in 0..20 {
+        // the single slot holds the argument going in and the result coming out
+        let mut args_results = [VMVal::i32(n)];
+        // Safety: `fib` takes and returns a single i32
+        unsafe {
+            raw.call_unchecked_raw(&mut store, args_results.as_mut_ptr(), args_results.len())?;
+        }
+        assert_eq!(args_results[0].get_i32(), fib.call(&mut store, n)?);
+    }
+
+    Ok(())
+}
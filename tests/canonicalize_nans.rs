@@ -0,0 +1,57 @@
+mod common;
+
+use k23vm::{Config, Engine, Error};
+
+const CANONICAL_F32: u32 = 0x7fc0_0000;
+const CANONICAL_F64: u64 = 0x7ff8_0000_0000_0000;
+
+/// Returns the results of `f32.add` and `f64.mul` with a NaN operand carrying a payload.
+fn nan_results(config: &Config) -> Result<(u32, u64), Error> {
+    let str = r#"
+    (module
+        (func (export "add") (param f32 f32) (result f32)
+            (f32.add (local.get 0) (local.get 1))
+        )
+        (func (export "mul") (param f64 f64) (result f64)
+            (f64.mul (local.get 0) (local.get 1))
+        )
+    )"#;
+
+    let engine = Engine::new(config);
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let add = instance.get_typed_func::<(f32, f32), f32>(&mut store, "add")?;
+    let mul = instance.get_typed_func::<(f64, f64), f64>(&mut store, "mul")?;
+
+    let f32_nan = f32::from_bits(0xffe0_1234);
+    let f64_nan = f64::from_bits(0xfffc_0000_0000_1234);
+    let f32_res = add.call(&mut store, (f32_nan, 1.0))?;
+    let f64_res = mul.call(&mut store, (2.0, f64_nan))?;
+
+    Ok((f32_res.to_bits(), f64_res.to_bits()))
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    // hosts propagate the payload of the NaN operand
+    let (f32_bits, f64_bits) = nan_results(&Config::new())?;
+    assert!(f32::from_bits(f32_bits).is_nan());
+    assert!(f64::from_bits(f64_bits).is_nan());
+    assert_ne!(f32_bits, CANONICAL_F32, "{f32_bits:#x}");
+    assert_ne!(f64_bits, CANONICAL_F64, "{f64_bits:#x}");
+
+    let (f32_bits, f64_bits) = nan_results(Config::new().canonicalize_nans(true))?;
+    assert_eq!(f32_bits, CANONICAL_F32, "{f32_bits:#x}");
+    assert_eq!(f64_bits, CANONICAL_F64, "{f64_bits:#x}");
+
+    Ok(())
+}
+
+#[test_log::test]
+fn deterministic_implies_canonical_nans() -> Result<(), Error> {
+    let (f32_bits, f64_bits) = nan_results(Config::new().deterministic(true))?;
+    assert_eq!(f32_bits, CANONICAL_F32, "{f32_bits:#x}");
+    assert_eq!(f64_bits, CANONICAL_F64, "{f64_bits:#x}");
+
+    Ok(())
+}
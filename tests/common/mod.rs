@@ -0,0 +1,73 @@
+//! Helpers shared between the integration tests.
+//!
+//! Every test binary compiles this module separately and only uses some of it.
+#![expect(
+    clippy::allow_attributes,
+    reason = "which helpers are dead differs between test binaries, so it can't be expected"
+)]
+#![allow(dead_code, reason = "not every test binary uses every helper")]
+
+use k23vm::{
+    ConstExprEvaluator, Engine, Error, Instance, Linker, Module, PlaceholderAllocatorDontUse, Store,
+};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use wasmparser::Validator;
+
+/// Compiles `wat`, validating it with the features enabled in `engine`.
+pub fn compile(engine: &Engine, wat: &str) -> Result<Module, Error> {
+    let mut validator = Validator::new_with_features(engine.features());
+    Module::from_str(engine, &mut validator, wat)
+}
+
+/// Instantiates `module` in `store`, resolving its imports through `linker`.
+pub fn instantiate<T>(
+    store: &mut Store<T>,
+    linker: &Linker,
+    module: &Module,
+) -> Result<Instance, Error> {
+    linker.instantiate(
+        store,
+        Arc::new(PlaceholderAllocatorDontUse),
+        &mut ConstExprEvaluator::default(),
+        module,
+    )
+}
+
+/// Instantiates `wat`, which must not have any imports, in a new store.
+pub fn setup(engine: &Engine, wat: &str) -> Result<(Store<()>, Instance), Error> {
+    let mut store = Store::new(engine, ());
+    let linker = Linker::new(engine);
+    let instance = instantiate(&mut store, &linker, &compile(engine, wat)?)?;
+
+    Ok((store, instance))
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Returns a waker that does nothing when woken.
+pub fn noop_waker() -> Waker {
+    Waker::from(Arc::new(NoopWaker))
+}
+
+/// A minimal executor that polls `fut` until it completes, returning its output and the number
+/// of times it was polled.
+pub fn block_on<F: Future>(fut: F) -> (F::Output, usize) {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+
+    let mut polls = 0;
+    loop {
+        polls += 1;
+        if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+            return (out, polls);
+        }
+    }
+}
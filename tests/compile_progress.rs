@@ -0,0 +1,37 @@
+use core::ops::ControlFlow;
+use k23vm::{Engine, Error, Module};
+use wasmparser::Validator;
+
+#[test_log::test]
+fn progress_and_cancellation() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut validator = Validator::new();
+    let bytes = wat::parse_str(include_str!("./kiwi-editor.wat"))?;
+
+    let mut calls = Vec::new();
+    Module::from_bytes_with_progress(&engine, &mut validator, &bytes, |done, total| {
+        calls.push((done, total));
+        ControlFlow::Continue(())
+    })?;
+    let total = calls[0].1;
+    assert!(total > 1);
+    assert_eq!(
+        calls,
+        (1..=total).map(|done| (done, total)).collect::<Vec<_>>()
+    );
+
+    let mut calls = 0;
+    let err = Module::from_bytes_with_progress(&engine, &mut validator, &bytes, |done, _| {
+        calls += 1;
+        if done == 3 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+    .unwrap_err();
+    assert!(matches!(err, Error::Cancelled), "{err}");
+    assert_eq!(calls, 3);
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Linker, Store};
+
+const WAT: &str = r#"
+(module
+    (memory 1)
+    (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1))
+    )
+)"#;
+
+/// A target with a different architecture than the host.
+const FOREIGN_TRIPLE: &str = if cfg!(target_arch = "aarch64") {
+    "x86_64-unknown-none"
+} else {
+    "aarch64-unknown-none"
+};
+
+#[test_log::test]
+fn invalid_target() {
+    let err = Engine::for_target(&Config::default(), "not-a-triple-at-all", "").unwrap_err();
+    assert!(matches!(err, Error::UnsupportedTarget(_)), "{err}");
+
+    let host = target_lexicon::HOST.to_string();
+    let err = Engine::for_target(&Config::default(), &host, "no_such_feature").unwrap_err();
+    assert!(matches!(err, Error::UnsupportedTarget(_)), "{err}");
+}
+
+#[test_log::test]
+fn host_target() -> Result<(), Error> {
+    let engine = Engine::for_target(&Config::default(), &target_lexicon::HOST.to_string(), "")?;
+    let (mut store, instance) = common::setup(&engine, WAT)?;
+    let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
+    assert_eq!(add.call(&mut store, (1, 2))?, 3);
+
+    Ok(())
+}
+
+#[cfg(feature = "cross-compile")]
+#[test_log::test]
+fn foreign_target() -> Result<(), Error> {
+    let engine = Engine::for_target(&Config::default(), FOREIGN_TRIPLE, "")?;
+    assert_eq!(engine.target().to_string(), FOREIGN_TRIPLE);
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    // the code can be compiled and inspected, but not run
+    let module = common::compile(&engine, WAT)?;
+    assert!(!module.text().is_empty());
+    let err = common::instantiate(&mut store, &linker, &module).unwrap_err();
+    assert!(matches!(err, Error::UnsupportedTarget(_)), "{err}");
+
+    Ok(())
+}
+
+#[cfg(not(feature = "cross-compile"))]
+#[test_log::test]
+fn foreign_target_disabled() {
+    let err = Engine::for_target(&Config::default(), FOREIGN_TRIPLE, "").unwrap_err();
+    assert!(matches!(err, Error::UnsupportedTarget(_)), "{err}");
+}
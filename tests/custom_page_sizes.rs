@@ -0,0 +1,71 @@
+mod common;
+
+use k23vm::{Config, Engine, Store, Val};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (memory (export "memory") 0 (pagesize 1))
+
+        (func (export "grow") (param $delta i32) (result i32)
+            (memory.grow (local.get $delta))
+        )
+        (func (export "size") (result i32)
+            (memory.size)
+        )
+        (func (export "load") (param $addr i32) (result i32)
+            (i32.load8_u (local.get $addr))
+        )
+        (func (export "store") (param $addr i32) (param $val i32)
+            (i32.store8 (local.get $addr) (local.get $val))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::CUSTOM_PAGE_SIZES;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str, params: &[Val]| -> Option<Val> {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        let len = func.ty(store).as_wasm_func_type().results.len();
+        // Safety: the parameters match the signatures of the functions above
+        unsafe {
+            func.call_unchecked(store, params, &mut results[..len])
+                .unwrap();
+        }
+        results[..len].first().copied()
+    };
+
+    // grow byte-by-byte
+    let old = call(&mut store, "grow", &[Val::I32(4)]).unwrap();
+    assert_eq!(old.unwrap_i32(), 0);
+    let old = call(&mut store, "grow", &[Val::I32(1)]).unwrap();
+    assert_eq!(old.unwrap_i32(), 4);
+    assert_eq!(call(&mut store, "size", &[]).unwrap().unwrap_i32(), 5);
+
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.size(&store), 5);
+
+    // the last byte is accessible
+    call(&mut store, "store", &[Val::I32(4), Val::I32(0xab)]);
+    assert_eq!(
+        call(&mut store, "load", &[Val::I32(4)])
+            .unwrap()
+            .unwrap_i32(),
+        0xab
+    );
+
+    // but the byte after it is not, even though it lives in the same host page
+    let func = instance.get_func(&mut store, "load").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of `load`
+    let res = unsafe { func.call_unchecked(&mut store, &[Val::I32(5)], &mut results) };
+    assert!(res.is_err());
+
+    // growing from the host uses the same page size
+    assert_eq!(memory.grow(&mut store, 3).unwrap(), 5);
+    assert_eq!(call(&mut store, "size", &[]).unwrap().unwrap_i32(), 8);
+}
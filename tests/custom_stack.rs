@@ -0,0 +1,139 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, StackRegion, Store, Trap, Val};
+use std::ptr;
+
+const PAGE_SIZE: usize = 4096;
+const STACK_SIZE: usize = 256 * PAGE_SIZE;
+
+/// An embedder-managed stack with a single inaccessible guard page below it.
+struct Stack {
+    base: *mut u8,
+    len: usize,
+}
+
+impl Stack {
+    fn new() -> Self {
+        let len = PAGE_SIZE + STACK_SIZE;
+
+        // Safety: we map fresh anonymous memory and only change the protection of that mapping
+        unsafe {
+            let base = rustix::mm::mmap_anonymous(
+                ptr::null_mut(),
+                len,
+                rustix::mm::ProtFlags::empty(),
+                rustix::mm::MapFlags::PRIVATE,
+            )
+            .unwrap();
+
+            rustix::mm::mprotect(
+                base.byte_add(PAGE_SIZE),
+                STACK_SIZE,
+                rustix::mm::MprotectFlags::READ | rustix::mm::MprotectFlags::WRITE,
+            )
+            .unwrap();
+
+            Self {
+                base: base.cast(),
+                len,
+            }
+        }
+    }
+
+    fn guard(&self) -> std::ops::Range<*mut u8> {
+        // Safety: the guard page is part of the mapping
+        self.base..unsafe { self.base.add(PAGE_SIZE) }
+    }
+
+    fn stack(&self) -> std::ops::Range<*mut u8> {
+        // Safety: the stack is part of the mapping
+        unsafe { self.base.add(PAGE_SIZE)..self.base.add(self.len) }
+    }
+}
+
+impl Drop for Stack {
+    fn drop(&mut self) {
+        // Safety: `base` and `len` describe the mapping created in `Stack::new`
+        unsafe {
+            rustix::mm::munmap(self.base.cast(), self.len).unwrap();
+        }
+    }
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $count (export "count") (param $n i32) (result i32)
+            (if (result i32) (i32.eqz (local.get $n))
+                (then (i32.const 0))
+                (else
+                    (i32.add
+                        (i32.const 1)
+                        (call $count (i32.sub (local.get $n) (i32.const 1)))
+                    )
+                )
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let stack = Stack::new();
+    // Safety: the stack outlives the store and is used by nothing else
+    let region = unsafe { StackRegion::new(stack.stack(), stack.guard())? };
+    store.set_stack(region)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let func = instance.get_func(&mut store, "count").unwrap();
+    let mut results = [Val::I32(0)];
+
+    // Safety: the parameters match the signature of `count`
+    unsafe { func.call_unchecked(&mut store, &[Val::I32(100)], &mut results)? };
+    assert_eq!(results[0].unwrap_i32(), 100);
+
+    // recursing deep enough to exhaust the provided stack traps at its guard
+    // Safety: the parameters match the signature of `count`
+    let err = unsafe { func.call_unchecked(&mut store, &[Val::I32(i32::MAX)], &mut results) }
+        .unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::StackOverflow));
+
+    // the store is still usable after the overflow
+    // Safety: the parameters match the signature of `count`
+    unsafe { func.call_unchecked(&mut store, &[Val::I32(10)], &mut results)? };
+    assert_eq!(results[0].unwrap_i32(), 10);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn invalid_regions() {
+    let stack = Stack::new();
+    let guard = stack.guard();
+    let region = stack.stack();
+
+    // Safety: the regions are rejected before they could be used
+    unsafe {
+        // misaligned stack
+        let res = StackRegion::new(region.start.add(8)..region.end, guard.clone());
+        assert!(matches!(res, Err(Error::InvalidStack(_))));
+
+        // guard not directly below the stack
+        let res = StackRegion::new(region.start.add(PAGE_SIZE)..region.end, guard.clone());
+        assert!(matches!(res, Err(Error::InvalidStack(_))));
+
+        // guard above the stack
+        let res = StackRegion::new(guard.clone(), region.clone());
+        assert!(matches!(res, Err(Error::InvalidStack(_))));
+
+        // empty guard
+        let res = StackRegion::new(region.clone(), guard.end..guard.end);
+        assert!(matches!(res, Err(Error::InvalidStack(_))));
+
+        assert!(StackRegion::new(region, guard).is_ok());
+    }
+}
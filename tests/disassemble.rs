@@ -0,0 +1,58 @@
+mod common;
+
+use k23vm::{Engine, Error, FuncIndex};
+
+#[test_log::test]
+fn annotated_disassembly() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $fib (param i32) (result i32)
+            (if (result i32) (i32.lt_u (local.get 0) (i32.const 2))
+                (then (local.get 0))
+                (else
+                    (i32.add
+                        (call $fib (i32.sub (local.get 0) (i32.const 1)))
+                        (call $fib (i32.sub (local.get 0) (i32.const 2)))
+                    )
+                )
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let module = common::compile(&engine, str)?;
+
+    let disas = module.disassemble(FuncIndex::from_u32(0))?;
+    tracing::debug!("{disas}");
+
+    assert!(disas.starts_with("wasm[0]::function[0]::fib:\n"), "{disas}");
+    // both recursive calls are relocated against the function itself
+    let relocs = disas
+        .lines()
+        .filter(|line| line.contains("reloc") && line.contains("wasm[0]::function[0]::fib"))
+        .count();
+    assert_eq!(relocs, 2, "{disas}");
+    assert!(disas.contains("; @0x"), "{disas}");
+
+    Ok(())
+}
+
+#[cfg(feature = "cross-compile")]
+#[test_log::test]
+fn foreign_target() -> Result<(), Error> {
+    // a target with a different architecture than the host
+    let (triple, prologue) = if cfg!(target_arch = "aarch64") {
+        ("x86_64-unknown-none", "push")
+    } else {
+        ("aarch64-unknown-none", "stp")
+    };
+
+    let engine = Engine::for_target(&k23vm::Config::default(), triple, "")?;
+    let module = common::compile(&engine, "(module (func))")?;
+
+    // the code is decoded as the target's instructions, not the host's
+    let disas = module.disassemble(FuncIndex::from_u32(0))?;
+    assert!(disas.contains(prologue), "{disas}");
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Linker, Module, Store, Trap};
+use wasmparser::{Parser, Payload, Validator};
+
+const WAT: &str = r#"
+(module
+    (memory 1)
+    (func (export "load") (param $addr i32) (param $b i32) (result i32)
+        (i32.add (i32.load (local.get $addr)) (local.get $b))
+    )
+)"#;
+
+fn uleb(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = u8::try_from(value & 0x7f).unwrap();
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut payload = Vec::new();
+    uleb(&mut payload, name.len());
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(data);
+
+    wasm.push(0);
+    uleb(wasm, payload.len());
+    wasm.extend_from_slice(&payload);
+}
+
+/// Appends hand-assembled DWARF 4 describing the `load` function to `wasm`.
+///
+/// `$addr` is described by a single location expression while `$b` uses a location list.
+fn with_dwarf(mut wasm: Vec<u8>) -> Vec<u8> {
+    // DWARF addresses are offsets relative to the start of the code section
+    let mut code_start = 0;
+    let mut func = 0..0;
+    for payload in Parser::new(0).parse_all(&wasm) {
+        match payload.unwrap() {
+            Payload::CodeSectionStart { range, .. } => code_start = range.start,
+            Payload::CodeSectionEntry(body) => func = body.range(),
+            _ => {}
+        }
+    }
+    let low_pc = u32::try_from(func.start - code_start).unwrap();
+    let len = u32::try_from(func.len()).unwrap();
+
+    #[rustfmt::skip]
+    let abbrev = [
+        // 1: compile unit with children, low_pc (addr)
+        1, 0x11, 1, 0x11, 0x01, 0, 0,
+        // 2: subprogram with children, name (string), low_pc (addr), high_pc (data4)
+        2, 0x2e, 1, 0x03, 0x08, 0x11, 0x01, 0x12, 0x06, 0, 0,
+        // 3: formal parameter, name (string), location (exprloc)
+        3, 0x05, 0, 0x03, 0x08, 0x02, 0x18, 0, 0,
+        // 4: formal parameter, name (string), location (sec_offset)
+        4, 0x05, 0, 0x03, 0x08, 0x02, 0x17, 0, 0,
+        0,
+    ];
+
+    let mut entries = Vec::new();
+    entries.push(1);
+    entries.extend_from_slice(&0u32.to_le_bytes());
+    entries.push(2);
+    entries.extend_from_slice(b"load\0");
+    entries.extend_from_slice(&low_pc.to_le_bytes());
+    entries.extend_from_slice(&len.to_le_bytes());
+    // DW_OP_WASM_location local 0
+    entries.push(3);
+    entries.extend_from_slice(b"addr\0");
+    entries.extend_from_slice(&[3, 0xed, 0x00, 0x00]);
+    // location list at offset 0 of `.debug_loc`
+    entries.push(4);
+    entries.extend_from_slice(b"b\0");
+    entries.extend_from_slice(&0u32.to_le_bytes());
+    entries.extend_from_slice(&[0, 0]);
+
+    let mut info = Vec::new();
+    info.extend_from_slice(&u32::try_from(entries.len() + 7).unwrap().to_le_bytes());
+    info.extend_from_slice(&4u16.to_le_bytes());
+    info.extend_from_slice(&0u32.to_le_bytes());
+    info.push(4);
+    info.extend_from_slice(&entries);
+
+    // DW_OP_WASM_location local 1, DW_OP_stack_value over the whole function
+    let mut loc = Vec::new();
+    loc.extend_from_slice(&low_pc.to_le_bytes());
+    loc.extend_from_slice(&(low_pc + len).to_le_bytes());
+    loc.extend_from_slice(&4u16.to_le_bytes());
+    loc.extend_from_slice(&[0xed, 0x00, 0x01, 0x9f]);
+    loc.extend_from_slice(&[0; 8]);
+
+    custom_section(&mut wasm, ".debug_abbrev", &abbrev);
+    custom_section(&mut wasm, ".debug_info", &info);
+    custom_section(&mut wasm, ".debug_loc", &loc);
+    wasm
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().debug_info(true));
+    let mut validator = Validator::new();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let wasm = with_dwarf(wat::parse_str(WAT)?);
+    let module = Module::from_bytes(&engine, &mut validator, &wasm)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let load = instance.get_typed_func::<(i32, i32), i32>(&mut store, "load")?;
+    let err = load.call(&mut store, (-16, 42)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+
+    let frame = store.last_trap_frame().unwrap().clone();
+    let vars = store.debug_variables(&frame)?;
+
+    let names: Vec<_> = vars.iter().map(|var| var.name.as_str()).collect();
+    assert_eq!(names, ["addr", "b"]);
+    // `$b` is still needed after the trapping load, so its value is available
+    assert_eq!(vars[1].value.and_then(|val| val.i32()), Some(42));
+
+    Ok(())
+}
+
+#[test_log::test]
+fn disabled_by_default() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut validator = Validator::new();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let wasm = with_dwarf(wat::parse_str(WAT)?);
+    let module = Module::from_bytes(&engine, &mut validator, &wasm)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let load = instance.get_typed_func::<(i32, i32), i32>(&mut store, "load")?;
+    load.call(&mut store, (-16, 42)).unwrap_err();
+
+    let frame = store.last_trap_frame().unwrap().clone();
+    assert!(store.debug_variables(&frame)?.is_empty());
+
+    Ok(())
+}
+
+#[test_log::test]
+fn spilled_local() -> Result<(), Error> {
+    // Keeps enough values live across the trapping load that the register allocator has to spill
+    // some of them, the long-lived `$b` being the prime candidate.
+    const LIVE: usize = 48;
+    let mut wat = String::from(
+        r#"(module (memory 1) (func (export "load") (param $addr i32) (param $b i32) (result i32)"#,
+    );
+    for i in 0..LIVE {
+        wat.push_str(&format!(" (local $v{i} i32)"));
+    }
+    for i in 0..LIVE {
+        wat.push_str(&format!(
+            " (local.set $v{i} (i32.load offset={} (i32.const 0)))",
+            i * 4
+        ));
+    }
+    wat.push_str(" (i32.load (local.get $addr))");
+    for i in 0..LIVE {
+        wat.push_str(&format!(" (local.get $v{i}) i32.add"));
+    }
+    wat.push_str(" (local.get $b) i32.add))");
+
+    let engine = Engine::new(Config::new().debug_info(true));
+    let mut validator = Validator::new();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let wasm = with_dwarf(wat::parse_str(&wat)?);
+    let module = Module::from_bytes(&engine, &mut validator, &wasm)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let load = instance.get_typed_func::<(i32, i32), i32>(&mut store, "load")?;
+    let err = load.call(&mut store, (-16, 42)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+
+    // calling into WebAssembly again reuses the stack the trapping frame lived on
+    let frame = store.last_trap_frame().unwrap().clone();
+    load.call(&mut store, (0, 0))?;
+
+    let vars = store.debug_variables(&frame)?;
+    assert_eq!(vars[1].name, "b");
+    assert_eq!(vars[1].value.and_then(|val| val.i32()), Some(42));
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use k23vm::{DylinkInfo, Engine, Error};
+
+#[test_log::test]
+fn side_module() -> Result<(), Error> {
+    // a memory info subsection (1024 bytes aligned to 16, 3 table slots) followed by a needed
+    // subsection listing two libraries
+    let str = r#"
+    (module
+        (@custom "dylink.0" (before first) "\01\05\80\08\04\03\00\02\11\02\07libc.so\07libm.so")
+        (import "env" "memory" (memory 1))
+        (import "env" "__memory_base" (global i32))
+        (import "env" "__table_base" (global i32))
+        (func (export "answer") (result i32)
+            (i32.const 42)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let module = common::compile(&engine, str)?;
+
+    assert_eq!(
+        module.dylink_info(),
+        Some(&DylinkInfo {
+            memory_size: 1024,
+            memory_alignment: 4,
+            table_size: 3,
+            table_alignment: 0,
+            needed: vec!["libc.so".to_string(), "libm.so".to_string()],
+        })
+    );
+
+    Ok(())
+}
+
+#[test_log::test]
+fn main_module() -> Result<(), Error> {
+    let engine = Engine::default();
+    let module = common::compile(&engine, "(module (memory 1))")?;
+
+    assert_eq!(module.dylink_info(), None);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn malformed_section() -> Result<(), Error> {
+    // the memory info subsection claims more bytes than the section has
+    let str = r#"
+    (module
+        (@custom "dylink.0" (before first) "\01\05\80\08")
+        (memory 1)
+    )"#;
+
+    let engine = Engine::default();
+    let module = common::compile(&engine, str)?;
+
+    assert_eq!(module.dylink_info(), None);
+
+    Ok(())
+}
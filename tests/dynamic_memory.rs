@@ -0,0 +1,56 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap};
+
+#[test_log::test]
+fn explicit_bounds_checks() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1 2)
+        (func (export "load") (param i32) (result i32)
+            (i32.load (local.get 0))
+        )
+        (func (export "load_offset") (param i32) (result i32)
+            (i32.load offset=0x10000 (local.get 0))
+        )
+        (func (export "store") (param i32 i32)
+            (i32.store (local.get 0) (local.get 1))
+        )
+        (func (export "grow") (param i32) (result i32)
+            (memory.grow (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::new(Config::new().dynamic_memories(true));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    let load_offset = instance.get_typed_func::<i32, i32>(&mut store, "load_offset")?;
+    let store_fn = instance.get_typed_func::<(i32, i32), ()>(&mut store, "store")?;
+    let grow = instance.get_typed_func::<i32, i32>(&mut store, "grow")?;
+
+    store_fn.call(&mut store, (0xfffc, 42))?;
+    assert_eq!(load.call(&mut store, 0xfffc)?, 42);
+
+    let oob = [
+        load.call(&mut store, 0x1_0000).unwrap_err(),
+        // straddles the end of the memory
+        load.call(&mut store, 0xfffd).unwrap_err(),
+        store_fn.call(&mut store, (0xfffe, 1)).unwrap_err(),
+        load_offset.call(&mut store, 0).unwrap_err(),
+        // far beyond where a guard region would end
+        load.call(&mut store, -4).unwrap_err(),
+    ];
+    for err in oob {
+        assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+    }
+
+    // accesses are checked against the current length, not the length at compile time
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    assert_eq!(load.call(&mut store, 0x1_0000)?, 0);
+    assert_eq!(load_offset.call(&mut store, 0xfffc)?, 0);
+    let err = load.call(&mut store, 0x2_0000).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+
+    Ok(())
+}
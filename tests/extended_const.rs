@@ -0,0 +1,58 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Global, Linker, Module, Store, Val};
+use wasmparser::{Validator, WasmFeatures};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "env" "base" (global $base i32))
+        (memory 1)
+        (data (i32.add (global.get $base) (i32.const 16)) "\2a")
+
+        (global (export "answer") i64
+            (i64.sub (i64.mul (i64.const 6) (i64.const 7)) (i64.const 2))
+        )
+
+        (func (export "load") (param $addr i32) (result i32)
+            (i32.load8_u (local.get $addr))
+        )
+    )"#;
+
+    let engine = Engine::new(
+        Config::new().wasm_features(WasmFeatures::WASM2 | WasmFeatures::EXTENDED_CONST),
+    );
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let base = Global::new(&mut store, Val::I32(100), false);
+    linker.define("env", "base", base)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    // the data segment was placed at the computed offset
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    assert_eq!(load.call(&mut store, 115)?, 0);
+    assert_eq!(load.call(&mut store, 116)?, 42);
+    assert_eq!(load.call(&mut store, 117)?, 0);
+
+    let answer = instance.get_global(&mut store, "answer").unwrap();
+    assert_eq!(answer.get(&mut store).unwrap_i64(), 40);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn disabled() {
+    let str = r#"
+    (module
+        (memory 1)
+        (data (i32.add (i32.const 1) (i32.const 16)) "\2a")
+    )"#;
+
+    let engine = Engine::default();
+    let err = Module::from_str(&engine, &mut Validator::new(), str).unwrap_err();
+    assert!(matches!(err, Error::InvalidWebAssembly { .. }), "{err}");
+}
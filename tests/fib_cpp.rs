@@ -1,27 +1,18 @@
-use k23vm::{ConstExprEvaluator, Engine, Linker, Module, PlaceholderAllocatorDontUse, Store};
-use wasmparser::Validator;
+mod common;
+
+use k23vm::{Engine, Linker, Store};
 
 #[test_log::test]
 fn main() {
     let engine = Engine::default();
-    let mut validator = Validator::new();
     let mut linker = Linker::new(&engine);
-    let mut store = Store::new(&engine);
-    let mut const_eval = ConstExprEvaluator::default();
+    let mut store = Store::new(&engine, ());
 
     // instantiate & define the fib_cpp module
     {
-        let module =
-            Module::from_str(&engine, &mut validator, include_str!("./fib_cpp.wat")).unwrap();
-
-        let instance = linker
-            .instantiate(
-                &mut store,
-                &PlaceholderAllocatorDontUse,
-                &mut const_eval,
-                &module,
-            )
-            .unwrap();
+        let module = common::compile(&engine, include_str!("./fib_cpp.wat")).unwrap();
+
+        let instance = common::instantiate(&mut store, &linker, &module).unwrap();
         instance.debug_vmctx(&store);
 
         linker
@@ -31,22 +22,16 @@ fn main() {
 
     // instantiate the test module
     {
-        let module =
-            Module::from_str(&engine, &mut validator, include_str!("./fib_test.wat")).unwrap();
-
-        let instance = linker
-            .instantiate(
-                &mut store,
-                &PlaceholderAllocatorDontUse,
-                &mut const_eval,
-                &module,
-            )
-            .unwrap();
+        let module = common::compile(&engine, include_str!("./fib_test.wat")).unwrap();
+
+        let instance = common::instantiate(&mut store, &linker, &module).unwrap();
 
         instance.debug_vmctx(&store);
 
         let func = instance.get_func(&mut store, "fib_test").unwrap();
         // TODO replace with checked
-        unsafe { func.call_unchecked(&mut store, &[], &mut []).unwrap(); }
+        unsafe {
+            func.call_unchecked(&mut store, &[], &mut []).unwrap();
+        }
     }
 }
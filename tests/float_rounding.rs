@@ -0,0 +1,132 @@
+mod common;
+
+use k23vm::{Engine, Store, Val};
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (func (export "f32.nearest") (param f32) (result f32) (f32.nearest (local.get 0)))
+        (func (export "f32.trunc") (param f32) (result f32) (f32.trunc (local.get 0)))
+        (func (export "f32.ceil") (param f32) (result f32) (f32.ceil (local.get 0)))
+        (func (export "f32.floor") (param f32) (result f32) (f32.floor (local.get 0)))
+        (func (export "f32.sqrt") (param f32) (result f32) (f32.sqrt (local.get 0)))
+        (func (export "f32.abs") (param f32) (result f32) (f32.abs (local.get 0)))
+        (func (export "f32.copysign") (param f32 f32) (result f32)
+            (f32.copysign (local.get 0) (local.get 1))
+        )
+
+        (func (export "f64.nearest") (param f64) (result f64) (f64.nearest (local.get 0)))
+        (func (export "f64.trunc") (param f64) (result f64) (f64.trunc (local.get 0)))
+        (func (export "f64.ceil") (param f64) (result f64) (f64.ceil (local.get 0)))
+        (func (export "f64.floor") (param f64) (result f64) (f64.floor (local.get 0)))
+        (func (export "f64.sqrt") (param f64) (result f64) (f64.sqrt (local.get 0)))
+        (func (export "f64.abs") (param f64) (result f64) (f64.abs (local.get 0)))
+        (func (export "f64.copysign") (param f64 f64) (result f64)
+            (f64.copysign (local.get 0) (local.get 1))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str, params: &[Val]| -> Val {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        // Safety: the parameters match the signatures of the functions above
+        unsafe {
+            func.call_unchecked(store, params, &mut results).unwrap();
+        }
+        results[0]
+    };
+    let call_f32 = |store: &mut Store<()>, name: &str, params: &[f32]| -> u32 {
+        let params: Vec<_> = params.iter().map(|p| Val::F32(p.to_bits())).collect();
+        call(store, name, &params).unwrap_f32().to_bits()
+    };
+    let call_f64 = |store: &mut Store<()>, name: &str, params: &[f64]| -> u64 {
+        let params: Vec<_> = params.iter().map(|p| Val::F64(p.to_bits())).collect();
+        call(store, name, &params).unwrap_f64().to_bits()
+    };
+
+    // `nearest` rounds half to even, not away from zero
+    assert_eq!(
+        call_f32(&mut store, "f32.nearest", &[2.5]),
+        2.0f32.to_bits()
+    );
+    assert_eq!(
+        call_f32(&mut store, "f32.nearest", &[3.5]),
+        4.0f32.to_bits()
+    );
+    assert_eq!(
+        call_f32(&mut store, "f32.nearest", &[-2.5]),
+        (-2.0f32).to_bits()
+    );
+    assert_eq!(
+        call_f32(&mut store, "f32.nearest", &[-0.5]),
+        (-0.0f32).to_bits()
+    );
+
+    assert_eq!(
+        call_f32(&mut store, "f32.trunc", &[-1.7]),
+        (-1.0f32).to_bits()
+    );
+    assert_eq!(call_f32(&mut store, "f32.ceil", &[1.2]), 2.0f32.to_bits());
+    assert_eq!(
+        call_f32(&mut store, "f32.ceil", &[-0.5]),
+        (-0.0f32).to_bits()
+    );
+    assert_eq!(
+        call_f32(&mut store, "f32.floor", &[-1.2]),
+        (-2.0f32).to_bits()
+    );
+    assert_eq!(call_f32(&mut store, "f32.sqrt", &[16.0]), 4.0f32.to_bits());
+    assert_eq!(call_f32(&mut store, "f32.abs", &[-3.0]), 3.0f32.to_bits());
+
+    // `copysign` takes the sign of negative zero
+    assert_eq!(
+        call_f32(&mut store, "f32.copysign", &[1.5, -0.0]),
+        (-1.5f32).to_bits()
+    );
+    assert_eq!(
+        call_f32(&mut store, "f32.copysign", &[-1.5, 0.0]),
+        1.5f32.to_bits()
+    );
+
+    assert_eq!(
+        call_f64(&mut store, "f64.nearest", &[2.5]),
+        2.0f64.to_bits()
+    );
+    assert_eq!(
+        call_f64(&mut store, "f64.nearest", &[3.5]),
+        4.0f64.to_bits()
+    );
+    assert_eq!(
+        call_f64(&mut store, "f64.nearest", &[-2.5]),
+        (-2.0f64).to_bits()
+    );
+    assert_eq!(
+        call_f64(&mut store, "f64.nearest", &[-0.5]),
+        (-0.0f64).to_bits()
+    );
+
+    assert_eq!(
+        call_f64(&mut store, "f64.trunc", &[-1.7]),
+        (-1.0f64).to_bits()
+    );
+    assert_eq!(call_f64(&mut store, "f64.ceil", &[1.2]), 2.0f64.to_bits());
+    assert_eq!(
+        call_f64(&mut store, "f64.floor", &[-1.2]),
+        (-2.0f64).to_bits()
+    );
+    assert_eq!(call_f64(&mut store, "f64.sqrt", &[16.0]), 4.0f64.to_bits());
+    assert_eq!(call_f64(&mut store, "f64.abs", &[-3.0]), 3.0f64.to_bits());
+
+    assert_eq!(
+        call_f64(&mut store, "f64.copysign", &[1.5, -0.0]),
+        (-1.5f64).to_bits()
+    );
+    assert_eq!(
+        call_f64(&mut store, "f64.copysign", &[-1.5, 0.0]),
+        1.5f64.to_bits()
+    );
+}
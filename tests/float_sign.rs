@@ -0,0 +1,120 @@
+mod common;
+
+use k23vm::{Engine, Error, Instance, Store, Val};
+
+const F32_SIGN: u32 = 0x8000_0000;
+const F64_SIGN: u64 = 0x8000_0000_0000_0000;
+
+// NaNs with non-canonical payloads, a canonicalizing implementation would change these.
+const F32_NAN: u32 = 0x7fa0_0123;
+const F64_NAN: u64 = 0x7ff4_0000_0000_0123;
+
+fn setup() -> Result<(Store<()>, Instance), Error> {
+    let str = r#"
+    (module
+        (func (export "f32.neg") (param f32) (result f32) (f32.neg (local.get 0)))
+        (func (export "f32.abs") (param f32) (result f32) (f32.abs (local.get 0)))
+        (func (export "f32.copysign") (param f32 f32) (result f32)
+            (f32.copysign (local.get 0) (local.get 1))
+        )
+        (func (export "f64.neg") (param f64) (result f64) (f64.neg (local.get 0)))
+        (func (export "f64.abs") (param f64) (result f64) (f64.abs (local.get 0)))
+        (func (export "f64.copysign") (param f64 f64) (result f64)
+            (f64.copysign (local.get 0) (local.get 1))
+        )
+    )"#;
+
+    common::setup(&Engine::default(), str)
+}
+
+fn call(store: &mut Store<()>, instance: Instance, name: &str, params: &[Val]) -> Val {
+    let func = instance.get_func(store, name).unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: all functions above take `params.len()` parameters of the type of their result
+    unsafe {
+        func.call_unchecked(store, params, &mut results).unwrap();
+    }
+    results[0]
+}
+
+fn f32_bits(val: Val) -> u32 {
+    match val {
+        Val::F32(bits) => bits,
+        val => panic!("expected f32, got {val:?}"),
+    }
+}
+
+fn f64_bits(val: Val) -> u64 {
+    match val {
+        Val::F64(bits) => bits,
+        val => panic!("expected f64, got {val:?}"),
+    }
+}
+
+#[test_log::test]
+fn f32_sign_bit() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    let store = &mut store;
+
+    // `neg` flips only the sign bit of a NaN
+    let res = call(store, instance, "f32.neg", &[Val::F32(F32_NAN)]);
+    assert_eq!(f32_bits(res), F32_NAN | F32_SIGN);
+    let res = call(store, instance, "f32.neg", &[Val::F32(F32_NAN | F32_SIGN)]);
+    assert_eq!(f32_bits(res), F32_NAN);
+
+    // `abs` clears only the sign bit
+    let res = call(store, instance, "f32.abs", &[Val::F32(F32_NAN | F32_SIGN)]);
+    assert_eq!(f32_bits(res), F32_NAN);
+
+    // `copysign` transfers only the sign
+    let res = call(
+        store,
+        instance,
+        "f32.copysign",
+        &[Val::F32(F32_NAN), Val::F32((-1.5f32).to_bits())],
+    );
+    assert_eq!(f32_bits(res), F32_NAN | F32_SIGN);
+    let res = call(
+        store,
+        instance,
+        "f32.copysign",
+        &[Val::F32(2.0f32.to_bits()), Val::F32(F32_NAN | F32_SIGN)],
+    );
+    assert_eq!(f32_bits(res), (-2.0f32).to_bits());
+
+    Ok(())
+}
+
+#[test_log::test]
+fn f64_sign_bit() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    let store = &mut store;
+
+    // `neg` flips only the sign bit of a NaN
+    let res = call(store, instance, "f64.neg", &[Val::F64(F64_NAN)]);
+    assert_eq!(f64_bits(res), F64_NAN | F64_SIGN);
+    let res = call(store, instance, "f64.neg", &[Val::F64(F64_NAN | F64_SIGN)]);
+    assert_eq!(f64_bits(res), F64_NAN);
+
+    // `abs` clears only the sign bit
+    let res = call(store, instance, "f64.abs", &[Val::F64(F64_NAN | F64_SIGN)]);
+    assert_eq!(f64_bits(res), F64_NAN);
+
+    // `copysign` transfers only the sign
+    let res = call(
+        store,
+        instance,
+        "f64.copysign",
+        &[Val::F64(F64_NAN), Val::F64((-1.5f64).to_bits())],
+    );
+    assert_eq!(f64_bits(res), F64_NAN | F64_SIGN);
+    let res = call(
+        store,
+        instance,
+        "f64.copysign",
+        &[Val::F64(2.0f64.to_bits()), Val::F64(F64_NAN | F64_SIGN)],
+    );
+    assert_eq!(f64_bits(res), (-2.0f64).to_bits());
+
+    Ok(())
+}
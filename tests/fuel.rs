@@ -0,0 +1,71 @@
+mod common;
+
+use common::block_on;
+use k23vm::{Config, Engine, Error, Instance, Store, Trap, Val};
+
+fn setup() -> Result<(Store<()>, Instance), Error> {
+    let str = r#"
+    (module
+        (func (export "sum") (param $n i32) (result i32)
+            (local $acc i32)
+            (block $done
+                (loop $l
+                    (br_if $done (i32.eqz (local.get $n)))
+                    (local.set $acc (i32.add (local.get $acc) (local.get $n)))
+                    (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                    (br $l)
+                )
+            )
+            (local.get $acc)
+        )
+    )"#;
+
+    common::setup(&Engine::new(Config::new().consume_fuel(true)), str)
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    // not nearly enough for the whole loop
+    store.set_fuel(100);
+    store.out_of_fuel_async_yield(100);
+
+    let sum = instance.get_func(&mut store, "sum").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: `sum` takes a single `i32` and returns a single `i32`
+    let (res, polls) =
+        block_on(unsafe { sum.call_async_unchecked(&mut store, &[Val::I32(100)], &mut results) });
+    res?;
+
+    // the loop yielded every time it ran out of fuel, got refueled and completed eventually
+    assert!(
+        polls > 1,
+        "expected the call to yield, but it was polled once"
+    );
+    assert_eq!(results[0].unwrap_i32(), 5050);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn out_of_fuel_traps() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    store.set_fuel(100);
+
+    let sum = instance.get_func(&mut store, "sum").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: `sum` takes a single `i32` and returns a single `i32`
+    let err =
+        unsafe { sum.call_unchecked(&mut store, &[Val::I32(100)], &mut results) }.unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::OutOfFuel));
+    assert_eq!(store.get_fuel(), 0);
+
+    // with enough fuel the same call succeeds
+    store.set_fuel(10_000);
+    // Safety: `sum` takes a single `i32` and returns a single `i32`
+    unsafe { sum.call_unchecked(&mut store, &[Val::I32(100)], &mut results)? };
+    assert_eq!(results[0].unwrap_i32(), 5050);
+    assert!(store.get_fuel() < 10_000);
+
+    Ok(())
+}
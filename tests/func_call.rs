@@ -0,0 +1,105 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Store, Val};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(
+        &engine,
+        r#"
+        (module
+            (func (export "divmod") (param i32 i32) (result i32 i32)
+                (i32.div_u (local.get 0) (local.get 1))
+                (i32.rem_u (local.get 0) (local.get 1))
+            )
+            (func $f (export "f"))
+            (func (export "is_null") (param funcref) (result i32)
+                (ref.is_null (local.get 0))
+            )
+            (elem declare func $f)
+        )"#,
+    )?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let divmod = instance.get_func(&mut store, "divmod").unwrap();
+
+    let mut results = [Val::I32(0), Val::I32(0)];
+    divmod.call(&mut store, &[Val::I32(17), Val::I32(5)], &mut results)?;
+    assert_eq!(results[0].unwrap_i32(), 3);
+    assert_eq!(results[1].unwrap_i32(), 2);
+
+    // results buffers that are too small or too large are rejected
+    let err = divmod
+        .call(&mut store, &[Val::I32(17), Val::I32(5)], &mut results[..1])
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::WrongResultCount {
+                expected: 2,
+                actual: 1
+            }
+        ),
+        "{err}"
+    );
+    let mut too_many = [Val::I32(0), Val::I32(0), Val::I32(0)];
+    let err = divmod
+        .call(&mut store, &[Val::I32(17), Val::I32(5)], &mut too_many)
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::WrongResultCount {
+                expected: 2,
+                actual: 3
+            }
+        ),
+        "{err}"
+    );
+
+    // so are the wrong number of arguments or arguments of the wrong type
+    let err = divmod
+        .call(&mut store, &[Val::I32(17)], &mut results)
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::WrongArgCount {
+                expected: 2,
+                actual: 1
+            }
+        ),
+        "{err}"
+    );
+    let err = divmod
+        .call(&mut store, &[Val::I32(17), Val::I64(5)], &mut results)
+        .unwrap_err();
+    assert!(matches!(err, Error::ValTypeMismatch { .. }), "{err}");
+
+    // function references are checked too
+    let f = instance.get_func(&mut store, "f").unwrap();
+    let is_null = instance.get_func(&mut store, "is_null").unwrap();
+    let mut results = [Val::I32(0)];
+    is_null.call(&mut store, &[Val::FuncRef(Some(f))], &mut results)?;
+    assert_eq!(results[0].unwrap_i32(), 0);
+    is_null.call(&mut store, &[Val::FuncRef(None)], &mut results)?;
+    assert_eq!(results[0].unwrap_i32(), 1);
+    let err = is_null
+        .call(&mut store, &[Val::I32(0)], &mut results)
+        .unwrap_err();
+    assert!(matches!(err, Error::ValTypeMismatch { .. }), "{err}");
+
+    // as is the store a function reference belongs to
+    let mut other_store = Store::new(&engine, ());
+    let other_instance = common::instantiate(&mut other_store, &linker, &module)?;
+    let other_f = other_instance.get_func(&mut other_store, "f").unwrap();
+    let err = is_null
+        .call(&mut store, &[Val::FuncRef(Some(other_f))], &mut results)
+        .unwrap_err();
+    assert!(matches!(err, Error::CrossStore), "{err}");
+
+    Ok(())
+}
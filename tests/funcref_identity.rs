@@ -0,0 +1,37 @@
+mod common;
+
+use k23vm::{Engine, Error, Func, Ref};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (elem declare func $a $b)
+        (func $a (export "a") (result funcref)
+            (ref.func $a)
+        )
+        (func $b (export "b") (result funcref)
+            (ref.func $b)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+    let a = instance.get_typed_func::<(), Option<Func>>(&mut store, "a")?;
+    let b = instance.get_typed_func::<(), Option<Func>>(&mut store, "b")?;
+
+    // repeated calls produce the same reference, which is also the exported function
+    let first = a.call(&mut store, ())?.unwrap();
+    let second = a.call(&mut store, ())?.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first, instance.get_func(&mut store, "a")?);
+    assert_eq!(Ref::Func(Some(first)), Ref::Func(Some(second)));
+
+    let other = b.call(&mut store, ())?.unwrap();
+    assert_ne!(first, other);
+    assert_eq!(other, b.call(&mut store, ())?.unwrap());
+    assert_ne!(Ref::Func(Some(first)), Ref::Func(None));
+    assert_eq!(Ref::Func(None), Ref::Func(None));
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $arr (array (mut i32)))
+        (type $bytes (array (mut i8)))
+
+        ;; creates [1, 1, 1, 1], copies [7, 8] over its middle and returns the element at `index`
+        (func (export "copy") (param $index i32) (result i32)
+            (local $a (ref $arr))
+            (local.set $a (array.new_default $arr (i32.const 4)))
+            (array.fill $arr (local.get $a) (i32.const 0) (i32.const 1) (i32.const 4))
+            (array.copy $arr $arr
+                (local.get $a) (i32.const 1)
+                (array.new_fixed $arr 2 (i32.const 7) (i32.const 8)) (i32.const 0)
+                (i32.const 2)
+            )
+            (array.get $arr (local.get $a) (local.get $index))
+        )
+        ;; copies the first three elements of [1, 2, 3, 4] one to the right, overlapping
+        (func (export "copy_overlapping") (param $index i32) (result i32)
+            (local $a (ref $arr))
+            (local.set $a (array.new_fixed $arr 4 (i32.const 1) (i32.const 2) (i32.const 3) (i32.const 4)))
+            (array.copy $arr $arr (local.get $a) (i32.const 1) (local.get $a) (i32.const 0) (i32.const 3))
+            (array.get $arr (local.get $a) (local.get $index))
+        )
+        (func (export "len") (param $len i32) (result i32)
+            (array.len (array.new $arr (i32.const 0) (local.get $len)))
+        )
+        (func (export "set") (param $index i32) (param $value i32) (result i32)
+            (local $a (ref $arr))
+            (local.set $a (array.new $arr (i32.const 0) (i32.const 4)))
+            (array.set $arr (local.get $a) (local.get $index) (local.get $value))
+            (array.get $arr (local.get $a) (local.get $index))
+        )
+        (func (export "fill") (param $index i32) (param $len i32)
+            (array.fill $arr (array.new $arr (i32.const 0) (i32.const 4)) (local.get $index) (i32.const 1) (local.get $len))
+        )
+        (func (export "packed") (param $value i32) (result i32 i32)
+            (local $a (ref $bytes))
+            (local.set $a (array.new $bytes (local.get $value) (i32.const 1)))
+            (array.get_s $bytes (local.get $a) (i32.const 0))
+            (array.get_u $bytes (local.get $a) (i32.const 0))
+        )
+        (func (export "null") (result i32)
+            (array.len (ref.null $arr))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::GC;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let copy = instance.get_typed_func::<i32, i32>(&mut store, "copy")?;
+    for (index, expected) in [1, 7, 8, 1].into_iter().enumerate() {
+        let index = i32::try_from(index).unwrap();
+        assert_eq!(copy.call(&mut store, index)?, expected);
+    }
+    let err = copy.call(&mut store, 4).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::ArrayOutOfBounds), "{err}");
+
+    let copy_overlapping = instance.get_typed_func::<i32, i32>(&mut store, "copy_overlapping")?;
+    for (index, expected) in [1, 1, 2, 3].into_iter().enumerate() {
+        let index = i32::try_from(index).unwrap();
+        assert_eq!(copy_overlapping.call(&mut store, index)?, expected);
+    }
+
+    let len = instance.get_typed_func::<i32, i32>(&mut store, "len")?;
+    assert_eq!(len.call(&mut store, 0)?, 0);
+    assert_eq!(len.call(&mut store, 1000)?, 1000);
+    let err = len.call(&mut store, -1).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::AllocationTooLarge), "{err}");
+
+    let set = instance.get_typed_func::<(i32, i32), i32>(&mut store, "set")?;
+    assert_eq!(set.call(&mut store, (3, 42))?, 42);
+    let err = set.call(&mut store, (-1, 42)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::ArrayOutOfBounds), "{err}");
+
+    let fill = instance.get_typed_func::<(i32, i32), ()>(&mut store, "fill")?;
+    fill.call(&mut store, (4, 0))?;
+    let err = fill.call(&mut store, (3, 2)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::ArrayOutOfBounds), "{err}");
+    let err = fill.call(&mut store, (1, -1)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::ArrayOutOfBounds), "{err}");
+
+    let packed = instance.get_typed_func::<i32, (i32, i32)>(&mut store, "packed")?;
+    assert_eq!(packed.call(&mut store, 0x1ff)?, (-1, 0xff));
+
+    let null = instance.get_typed_func::<(), i32>(&mut store, "null")?;
+    let err = null.call(&mut store, ()).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::NullReference), "{err}");
+
+    Ok(())
+}
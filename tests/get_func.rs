@@ -0,0 +1,92 @@
+mod common;
+
+use k23vm::{Engine, Error, ExternKind, Linker, Store};
+
+#[test_log::test]
+fn missing_and_wrong_kind() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(
+        &engine,
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "nop"))
+        )"#,
+    )?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    instance.get_func(&mut store, "nop")?;
+
+    let err = instance.get_func(&mut store, "memory").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::WrongExportKind {
+                expected: ExternKind::Func,
+                found: ExternKind::Memory,
+                ..
+            }
+        ),
+        "{err}"
+    );
+
+    let err = instance.get_func(&mut store, "missing").unwrap_err();
+    assert!(matches!(err, Error::MissingExport { .. }), "{err}");
+
+    // the typed variant reports the same errors
+    let err = instance
+        .get_typed_func::<(), ()>(&mut store, "memory")
+        .unwrap_err();
+    assert!(matches!(err, Error::WrongExportKind { .. }), "{err}");
+
+    Ok(())
+}
+
+#[test_log::test]
+fn exports_in_declaration_order() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(
+        &engine,
+        r#"(module
+            (func $f)
+            (export "zeta" (func $f))
+            (memory (export "memory") 1)
+            (export "alpha" (func $f))
+            (global (export "mid") i32 (i32.const 0))
+            (table (export "beta") 1 funcref)
+            (export "again" (memory 0))
+        )"#,
+    )?;
+    let expected = [
+        ("zeta", ExternKind::Func),
+        ("memory", ExternKind::Memory),
+        ("alpha", ExternKind::Func),
+        ("mid", ExternKind::Global),
+        ("beta", ExternKind::Table),
+        ("again", ExternKind::Memory),
+    ];
+
+    let names = module.exports().map(|(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names, expected.map(|(name, _)| name));
+
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    // resolving a single export first doesn't change the order of the rest
+    instance.get_func(&mut store, "alpha")?;
+
+    let exports = instance
+        .exports(&mut store)
+        .map(|export| (export.name.to_string(), export.value.kind()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        expected.map(|(name, kind)| (name.to_string(), kind))
+    );
+
+    Ok(())
+}
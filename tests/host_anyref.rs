@@ -0,0 +1,114 @@
+mod common;
+
+use k23vm::{AnyRef, Config, Engine, Error, Func, Instance, Linker, Store, Val};
+use wasmparser::WasmFeatures;
+
+const WAT: &str = r#"
+    (module
+        (type $arr (array (mut i32)))
+        (import "env" "roundtrip" (func $roundtrip (param anyref) (result anyref)))
+
+        (func (export "new_array") (param $len i32) (result anyref)
+            (array.new_default $arr (local.get $len))
+        )
+        (func (export "new_i31") (param i32) (result anyref)
+            (ref.i31 (local.get 0))
+        )
+        (func (export "array_len") (param anyref) (result i32)
+            (array.len (ref.cast (ref array) (local.get 0)))
+        )
+        ;; hands a fresh array to the host and measures what comes back
+        (func (export "through_host") (param $len i32) (result i32)
+            (array.len
+                (ref.cast (ref array)
+                    (call $roundtrip (array.new_default $arr (local.get $len)))
+                )
+            )
+        )
+        (func (export "null_extern") (result externref)
+            (ref.null extern)
+        )
+    )"#;
+
+fn features() -> WasmFeatures {
+    WasmFeatures::default() | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::GC
+}
+
+fn instantiate(engine: &Engine, store: &mut Store<()>) -> Result<Instance, Error> {
+    let mut linker = Linker::new(engine);
+
+    let roundtrip = Func::wrap(&mut *store, |r: Option<AnyRef>| {
+        assert!(r.is_some_and(|r| !r.is_i31()));
+        r
+    })?;
+    linker.define("env", "roundtrip", roundtrip)?;
+
+    common::instantiate(store, &linker, &common::compile(engine, WAT)?)
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().wasm_features(features()));
+    let mut store = Store::new(&engine, ());
+    let instance = instantiate(&engine, &mut store)?;
+
+    // arrays leave WebAssembly as opaque references and can be passed back in
+    let new_array = instance.get_typed_func::<i32, Option<AnyRef>>(&mut store, "new_array")?;
+    let array = new_array.call(&mut store, 3)?.unwrap();
+    assert!(!array.is_i31());
+    assert_eq!(array.as_i31(), None);
+    assert_ne!(Some(array), new_array.call(&mut store, 3)?);
+
+    let array_len = instance.get_func(&mut store, "array_len").unwrap();
+    let mut results = [Val::I32(0)];
+    array_len.call(&mut store, &[Val::AnyRef(Some(array))], &mut results)?;
+    assert_eq!(results[0].unwrap_i32(), 3);
+
+    // i31refs are values, so they can be read by the host
+    let new_i31 = instance.get_typed_func::<i32, Option<AnyRef>>(&mut store, "new_i31")?;
+    let i31 = new_i31.call(&mut store, -5)?.unwrap();
+    assert!(i31.is_i31());
+    assert_eq!(i31.as_i31(), Some(-5));
+    assert_eq!(Some(i31), new_i31.call(&mut store, -5)?);
+
+    // host functions receive and return references
+    let through_host = instance.get_typed_func::<i32, i32>(&mut store, "through_host")?;
+    assert_eq!(through_host.call(&mut store, 7)?, 7);
+
+    // arguments are checked against the parameter types
+    let err = array_len
+        .call(&mut store, &[Val::null_func_ref()], &mut results)
+        .unwrap_err();
+    assert!(matches!(err, Error::ValTypeMismatch { .. }), "{err}");
+    let err = array_len
+        .call(&mut store, &[Val::ExternRef(None)], &mut results)
+        .unwrap_err();
+    assert!(matches!(err, Error::ValTypeMismatch { .. }), "{err}");
+
+    let null_extern = instance.get_func(&mut store, "null_extern").unwrap();
+    let mut results = [Val::I32(0)];
+    null_extern.call(&mut store, &[], &mut results)?;
+    assert!(matches!(results[0], Val::ExternRef(None)));
+
+    Ok(())
+}
+
+#[test_log::test]
+#[should_panic = "GC reference doesn't belong to the store"]
+fn stale_reference() {
+    let engine = Engine::new(Config::new().wasm_features(features()));
+    let mut store = Store::new(&engine, ());
+    let instance = instantiate(&engine, &mut store).unwrap();
+    let new_array = instance
+        .get_typed_func::<i32, Option<AnyRef>>(&mut store, "new_array")
+        .unwrap();
+    let array = new_array.call(&mut store, 3).unwrap();
+
+    // the array is gone together with the heap it lived in
+    store.reset();
+    let instance = instantiate(&engine, &mut store).unwrap();
+    let array_len = instance
+        .get_typed_func::<Option<AnyRef>, i32>(&mut store, "array_len")
+        .unwrap();
+    let _ = array_len.call(&mut store, array);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use k23vm::{Caller, Engine, Error, Func, Linker, Store};
+
+#[test_log::test]
+fn cleanup_runs_after_the_call() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "env" "alloc" (func $alloc (result i32)))
+        (import "env" "check" (func $check))
+
+        (func (export "run") (result i32)
+            (local $buf i32)
+            (local.set $buf (call $alloc))
+            ;; the guest is still using the buffer
+            (call $check)
+            (local.get $buf)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, Vec::<&'static str>::new());
+    let mut linker = Linker::new(&engine);
+
+    let alloc = Func::wrap_with_cleanup(
+        &mut store,
+        |mut caller: Caller<'_, Vec<&'static str>>| {
+            caller.data_mut().push("alloc");
+            1024_i32
+        },
+        |mut caller: Caller<'_, Vec<&'static str>>| caller.data_mut().push("cleanup"),
+    )?;
+    let check = Func::wrap(&mut store, |mut caller: Caller<'_, Vec<&'static str>>| {
+        caller.data_mut().push("check");
+    })?;
+    linker
+        .define("env", "alloc", alloc)?
+        .define("env", "check", check)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let run = instance.get_typed_func::<(), i32>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, ())?, 1024);
+    assert_eq!(store.data().as_slice(), ["alloc", "check", "cleanup"]);
+
+    // every call gets its own cleanup
+    store.data_mut().clear();
+    assert_eq!(run.call(&mut store, ())?, 1024);
+    assert_eq!(store.data().as_slice(), ["alloc", "check", "cleanup"]);
+
+    Ok(())
+}
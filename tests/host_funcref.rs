@@ -0,0 +1,141 @@
+mod common;
+
+use k23vm::{Engine, Error, Func, Linker, Ref, Store, Val};
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (type $i32_to_i32 (func (param i32) (result i32)))
+        (table (export "table") 1 1 funcref)
+
+        (func (export "call") (param $arg i32) (result i32)
+            (call_indirect (type $i32_to_i32) (local.get $arg) (i32.const 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let host_func = Func::wrap(&mut store, |arg: i32| arg * 2 + 1).unwrap();
+    let table = instance.get_table(&mut store, "table").unwrap();
+    table
+        .set(&mut store, 0, Ref::Func(Some(host_func)))
+        .unwrap();
+
+    // wasm calls the host function through `call_indirect`
+    let func = instance.get_func(&mut store, "call").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of `call`
+    unsafe {
+        func.call_unchecked(&mut store, &[Val::I32(20)], &mut results)
+            .unwrap();
+    }
+    assert_eq!(results[0].unwrap_i32(), 41);
+
+    // the host function can also be called directly
+    // Safety: the parameters match the signature of the host function
+    unsafe {
+        host_func
+            .call_unchecked(&mut store, &[Val::I32(3)], &mut results)
+            .unwrap();
+    }
+    assert_eq!(results[0].unwrap_i32(), 7);
+
+    // setting an element past the end of the table fails
+    assert!(table.set(&mut store, 1, Ref::Func(None)).is_err());
+}
+
+#[test_log::test]
+fn table_set_from_wasm() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $i32_to_i32 (func (param i32) (result i32)))
+        (table 2 funcref)
+
+        (func (export "set") (param $idx i32) (param $func funcref)
+            (table.set (local.get $idx) (local.get $func))
+        )
+        (func (export "call") (param $idx i32) (param $arg i32) (result i32)
+            (call_indirect (type $i32_to_i32) (local.get $arg) (local.get $idx))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    // both host functions share the trampoline of their signature but keep their own state
+    let double = Func::wrap(&mut store, |arg: i32| arg * 2)?;
+    let square = Func::wrap(&mut store, |arg: i32| arg * arg)?;
+
+    let set = instance.get_typed_func::<(i32, Option<Func>), ()>(&mut store, "set")?;
+    let call = instance.get_typed_func::<(i32, i32), i32>(&mut store, "call")?;
+
+    set.call(&mut store, (0, Some(double)))?;
+    set.call(&mut store, (1, Some(square)))?;
+    assert_eq!(call.call(&mut store, (0, 7))?, 14);
+    assert_eq!(call.call(&mut store, (1, 7))?, 49);
+
+    // host functions are valid in tables of other stores of the same engine too
+    let mut store2 = Store::new(&engine, ());
+    let instance2 = common::instantiate(&mut store2, &linker, &module)?;
+    let negate = Func::wrap(&mut store2, |arg: i32| -arg)?;
+    let set2 = instance2.get_typed_func::<(i32, Option<Func>), ()>(&mut store2, "set")?;
+    let call2 = instance2.get_typed_func::<(i32, i32), i32>(&mut store2, "call")?;
+    set2.call(&mut store2, (0, Some(negate)))?;
+    assert_eq!(call2.call(&mut store2, (0, 7))?, -7);
+    assert_eq!(call.call(&mut store, (0, 7))?, 14);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn panic_becomes_error() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $i32_to_i32 (func (param i32) (result i32)))
+        (table 1 funcref)
+
+        (func (export "set") (param $func funcref)
+            (table.set (i32.const 0) (local.get $func))
+        )
+        (func (export "call") (param $arg i32) (result i32)
+            (call_indirect (type $i32_to_i32) (local.get $arg) (i32.const 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let host_func = Func::wrap(&mut store, |arg: i32| -> i32 {
+        assert!(arg >= 0, "negative argument");
+        arg
+    })?;
+
+    let set = instance.get_typed_func::<Option<Func>, ()>(&mut store, "set")?;
+    let call = instance.get_typed_func::<i32, i32>(&mut store, "call")?;
+    set.call(&mut store, Some(host_func))?;
+
+    // the panic doesn't unwind through the WebAssembly frames but surfaces as an error
+    let err = call.call(&mut store, -1).unwrap_err();
+    assert!(
+        matches!(&err, Error::HostPanic(message) if message == "negative argument"),
+        "{err:?}"
+    );
+    // direct calls from the host report the panic the same way
+    let typed = host_func.typed::<i32, i32>(&store)?;
+    assert!(matches!(
+        typed.call(&mut store, -1),
+        Err(Error::HostPanic(_))
+    ));
+
+    // the store is still usable afterwards
+    assert_eq!(call.call(&mut store, 5)?, 5);
+    assert_eq!(typed.call(&mut store, 6)?, 6);
+
+    Ok(())
+}
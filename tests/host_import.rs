@@ -0,0 +1,42 @@
+mod common;
+
+use k23vm::{Engine, Error, Func, Linker, Store};
+
+#[test_log::test]
+fn call_imported_host_functions() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "env" "double" (func $double (param i32) (result i32)))
+        (import "env" "triple" (func $triple (param i32) (result i32)))
+        (import "env" "add" (func $add (param i64 i64) (result i64)))
+
+        (func (export "run") (param i32) (result i32)
+            (call $triple (call $double (local.get 0)))
+        )
+        (func (export "add") (param i64 i64) (result i64)
+            (call $add (local.get 0) (local.get 1))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let double = Func::wrap(&mut store, |arg: i32| arg * 2)?;
+    let triple = Func::wrap(&mut store, |arg: i32| arg * 3)?;
+    let add = Func::wrap(&mut store, |a: i64, b: i64| a + b)?;
+    linker
+        .define("env", "double", double)?
+        .define("env", "triple", triple)?
+        .define("env", "add", add)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let run = instance.get_typed_func::<i32, i32>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, 7)?, 42);
+    let add = instance.get_typed_func::<(i64, i64), i64>(&mut store, "add")?;
+    assert_eq!(add.call(&mut store, (1 << 40, 2))?, (1 << 40) + 2);
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use k23vm::{
+    Config, Engine, Error, Func, Linker, Memory, PlaceholderAllocatorDontUse, Ref, Store, Table,
+};
+use std::sync::Arc;
+use wasmparser::{MemoryType, RefType, TableType};
+
+#[test_log::test]
+fn host_memory() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "host" "memory" (memory 1 2))
+        (func (export "load") (param i32) (result i32)
+            (i32.load (local.get 0))
+        )
+        (func (export "grow") (param i32) (result i32)
+            (memory.grow (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let ty = MemoryType {
+        memory64: false,
+        shared: false,
+        initial: 1,
+        maximum: Some(2),
+        page_size_log2: None,
+    };
+    let memory = Memory::new(&mut store, Arc::new(PlaceholderAllocatorDontUse), ty)?;
+    assert_eq!(memory.size(&store), 1);
+    memory.write_u32(&mut store, 8, 0x1234_5678)?;
+    linker.define("host", "memory", memory)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    let grow = instance.get_typed_func::<i32, i32>(&mut store, "grow")?;
+
+    assert_eq!(load.call(&mut store, 8)?, 0x1234_5678);
+    // growing from WebAssembly is visible to the host and the maximum is enforced
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    assert_eq!(memory.size(&store), 2);
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+    assert!(memory.grow(&mut store, 1).is_err());
+
+    Ok(())
+}
+
+#[test_log::test]
+fn nothing_is_compiled() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().module_cache_capacity(8));
+    let mut store = Store::new(&engine, ());
+
+    let ty = MemoryType {
+        memory64: false,
+        shared: false,
+        initial: 1,
+        maximum: None,
+        page_size_log2: None,
+    };
+    Memory::new(&mut store, Arc::new(PlaceholderAllocatorDontUse), ty)?;
+    let ty = TableType {
+        element_type: RefType::FUNCREF,
+        table64: false,
+        initial: 1,
+        maximum: None,
+        shared: false,
+    };
+    Table::new(
+        &mut store,
+        Arc::new(PlaceholderAllocatorDontUse),
+        ty,
+        Ref::Func(None),
+    )?;
+
+    // host definitions don't go through the module compiler and thus never hit the module cache
+    assert_eq!(engine.module_cache_misses(), 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn invalid_memory_type() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+
+    let ty = MemoryType {
+        memory64: false,
+        shared: false,
+        initial: 3,
+        maximum: Some(2),
+        page_size_log2: None,
+    };
+    assert!(Memory::new(&mut store, Arc::new(PlaceholderAllocatorDontUse), ty).is_err());
+}
+
+#[test_log::test]
+fn host_table() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "host" "table" (table 2 funcref))
+        (func (export "call") (param i32) (result i32)
+            (call_indirect (param i32) (result i32) (i32.const 21) (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let double = Func::wrap(&mut store, |arg: i32| arg * 2)?;
+    let ty = TableType {
+        element_type: RefType::FUNCREF,
+        table64: false,
+        initial: 2,
+        maximum: Some(2),
+        shared: false,
+    };
+    let table = Table::new(
+        &mut store,
+        Arc::new(PlaceholderAllocatorDontUse),
+        ty,
+        Ref::Func(Some(double)),
+    )?;
+    linker.define("host", "table", table)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    // every element starts out as the initial value
+    let call = instance.get_typed_func::<i32, i32>(&mut store, "call")?;
+    assert_eq!(call.call(&mut store, 0)?, 42);
+    assert_eq!(call.call(&mut store, 1)?, 42);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn invalid_table_type() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+
+    let ty = TableType {
+        element_type: RefType::EXTERNREF,
+        table64: false,
+        initial: 1,
+        maximum: None,
+        shared: false,
+    };
+    let res = Table::new(
+        &mut store,
+        Arc::new(PlaceholderAllocatorDontUse),
+        ty,
+        Ref::Func(None),
+    );
+    assert!(res.is_err());
+
+    let ty = TableType {
+        element_type: RefType::FUNCREF,
+        table64: false,
+        initial: 2,
+        maximum: Some(1),
+        shared: false,
+    };
+    let res = Table::new(
+        &mut store,
+        Arc::new(PlaceholderAllocatorDontUse),
+        ty,
+        Ref::Func(None),
+    );
+    assert!(res.is_err());
+}
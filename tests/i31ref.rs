@@ -0,0 +1,90 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Store, Trap, Val};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (func (export "roundtrip_s") (param i32) (result i32)
+            (i31.get_s (ref.i31 (local.get 0)))
+        )
+        (func (export "roundtrip_u") (param i32) (result i32)
+            (i31.get_u (ref.i31 (local.get 0)))
+        )
+        (func (export "get_null") (result i32)
+            (i31.get_s (ref.null i31))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::GC;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str, params: &[Val]| -> Result<i32, Error> {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        // Safety: the parameters match the signatures of the functions above
+        unsafe {
+            func.call_unchecked(store, params, &mut results)?;
+        }
+        Ok(results[0].unwrap_i32())
+    };
+
+    for value in [0, 1, -1, 42, -42, 0x3fff_ffff, -0x4000_0000] {
+        assert_eq!(
+            call(&mut store, "roundtrip_s", &[Val::I32(value)]).unwrap(),
+            value
+        );
+    }
+
+    // the top bit is lost and the remaining 31 bits are sign extended
+    assert_eq!(
+        call(&mut store, "roundtrip_s", &[Val::I32(0x4000_0000)]).unwrap(),
+        -0x4000_0000
+    );
+    assert_eq!(
+        call(&mut store, "roundtrip_s", &[Val::I32(i32::MAX)]).unwrap(),
+        -1
+    );
+
+    // ...or zero extended
+    assert_eq!(
+        call(&mut store, "roundtrip_u", &[Val::I32(-1)]).unwrap(),
+        0x7fff_ffff
+    );
+    assert_eq!(
+        call(&mut store, "roundtrip_u", &[Val::I32(42)]).unwrap(),
+        42
+    );
+
+    let err = call(&mut store, "get_null", &[]).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::NullI31Ref));
+}
+
+#[test_log::test]
+fn const_expr() {
+    let str = r#"
+    (module
+        (global (export "global") i31ref (ref.i31 (i32.const -42)))
+        (func (export "get") (result i32)
+            (i31.get_s (global.get 0))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::GC;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let global = instance.get_global(&mut store, "global").unwrap();
+    let Val::AnyRef(Some(value)) = global.get(&mut store) else {
+        panic!("expected a non-null i31ref");
+    };
+    assert_eq!(value.as_i31(), Some(-42));
+
+    let get = instance
+        .get_typed_func::<(), i32>(&mut store, "get")
+        .unwrap();
+    assert_eq!(get.call(&mut store, ()).unwrap(), -42);
+}
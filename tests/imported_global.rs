@@ -0,0 +1,235 @@
+mod common;
+
+use k23vm::{
+    Engine, Error, Func, Global, Linker, Memory, PlaceholderAllocatorDontUse, Store, Trap, Val,
+};
+use std::sync::Arc;
+use wasmparser::MemoryType;
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (import "env" "counter" (global $counter (mut i32)))
+
+        (func (export "get") (result i32)
+            (global.get $counter)
+        )
+        (func (export "inc")
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let counter = Global::new(&mut store, Val::I32(41), true);
+    linker.define("env", "counter", counter).unwrap();
+
+    let module = common::compile(&engine, str).unwrap();
+    let instance = common::instantiate(&mut store, &linker, &module).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str| -> Option<Val> {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        let len = func.ty(store).as_wasm_func_type().results.len();
+        // Safety: the functions above take no parameters
+        unsafe {
+            func.call_unchecked(store, &[], &mut results[..len])
+                .unwrap();
+        }
+        results[..len].first().copied()
+    };
+
+    // wasm reads the host's initial value
+    assert_eq!(call(&mut store, "get").unwrap().unwrap_i32(), 41);
+
+    // writes from wasm are visible to the host
+    call(&mut store, "inc");
+    assert_eq!(counter.get(&mut store).unwrap_i32(), 42);
+
+    // and writes from the host are visible to wasm
+    counter.set(&mut store, Val::I32(100)).unwrap();
+    assert_eq!(call(&mut store, "get").unwrap().unwrap_i32(), 100);
+    call(&mut store, "inc");
+    assert_eq!(counter.get(&mut store).unwrap_i32(), 101);
+}
+
+#[test_log::test]
+fn set_checks_type_and_mutability() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+
+    let immutable = Global::new(&mut store, Val::I64(1), false);
+    assert!(matches!(
+        immutable.set(&mut store, Val::I64(2)),
+        Err(Error::ImmutableGlobal)
+    ));
+    assert_eq!(immutable.get(&mut store).unwrap_i64(), 1);
+
+    let mutable = Global::new(&mut store, Val::I64(1), true);
+    assert!(matches!(
+        mutable.set(&mut store, Val::I32(2)),
+        Err(Error::GlobalTypeMismatch { .. })
+    ));
+}
+
+#[test_log::test]
+fn import_type_mismatch() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let global_i32 = Global::new(&mut store, Val::I32(666), false);
+    linker.define("spectest", "global_i32", global_i32).unwrap();
+
+    let mut instantiate = |str: &str| {
+        let module = common::compile(&engine, str).unwrap();
+        common::instantiate(&mut store, &linker, &module).map(|_| ())
+    };
+
+    instantiate(r#"(module (import "spectest" "global_i32" (global i32)))"#).unwrap();
+
+    let err = instantiate(r#"(module (import "spectest" "global_i32" (global i64)))"#).unwrap_err();
+    assert!(
+        matches!(err, Error::IncompatibleImport { .. }),
+        "unexpected error {err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "incompatible import type for spectest::global_i32: expected global i64, found global i32"
+    );
+
+    let err =
+        instantiate(r#"(module (import "spectest" "global_i32" (global (mut i32))))"#).unwrap_err();
+    assert!(
+        matches!(err, Error::IncompatibleImport { .. }),
+        "unexpected error {err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "incompatible import type for spectest::global_i32: expected global (mut i32), found global i32"
+    );
+
+    let err = instantiate(r#"(module (import "spectest" "global_i32" (func)))"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "incompatible import type for spectest::global_i32: expected function, found global"
+    );
+}
+
+#[test_log::test]
+fn import_func_type_mismatch() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let add_one = Func::wrap(&mut store, |x: i32| x + 1).unwrap();
+    linker.define("env", "add_one", add_one).unwrap();
+    let memory = Memory::new(
+        &mut store,
+        Arc::new(PlaceholderAllocatorDontUse),
+        MemoryType {
+            memory64: false,
+            shared: false,
+            initial: 1,
+            maximum: None,
+            page_size_log2: None,
+        },
+    )
+    .unwrap();
+    linker.define("env", "memory", memory).unwrap();
+
+    let mut instantiate = |str: &str| {
+        let module = common::compile(&engine, str).unwrap();
+        common::instantiate(&mut store, &linker, &module).map(|_| ())
+    };
+
+    instantiate(r#"(module (import "env" "add_one" (func (param i32) (result i32))))"#).unwrap();
+    instantiate(r#"(module (import "env" "memory" (memory 1)))"#).unwrap();
+
+    let err = instantiate(r#"(module (import "env" "add_one" (func (param i64) (result i32))))"#)
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::IncompatibleImport { .. }),
+        "unexpected error {err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "incompatible import type for env::add_one: expected function (func (param i64) (result i32)), found function (func (param i32) (result i32))"
+    );
+
+    let err = instantiate(r#"(module (import "env" "add_one" (func)))"#).unwrap_err();
+    assert!(
+        matches!(err, Error::IncompatibleImport { .. }),
+        "unexpected error {err}"
+    );
+
+    // the memory has no maximum, so it could grow past the one the import requires
+    let err = instantiate(r#"(module (import "env" "memory" (memory 1 2)))"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "incompatible import type for env::memory: expected (memory 1 2), found (memory 1)"
+    );
+}
+
+#[test_log::test]
+fn data_offset_from_imported_global() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "env" "offset" (global $offset i32))
+        (memory 1)
+        (data (global.get $offset) "\2a\2b\2c\2d")
+
+        (func (export "load") (param i32) (result i32)
+            (i32.load8_u (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let offset = Global::new(&mut store, Val::I32(1000), false);
+    linker.define("env", "offset", offset)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+
+    assert_eq!(load.call(&mut store, 999)?, 0);
+    for (i, byte) in [0x2a, 0x2b, 0x2c, 0x2d].into_iter().enumerate() {
+        assert_eq!(
+            load.call(&mut store, 1000 + i32::try_from(i).unwrap())?,
+            byte
+        );
+    }
+    assert_eq!(load.call(&mut store, 1004)?, 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn data_offset_out_of_bounds() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "env" "offset" (global $offset i32))
+        (memory 1)
+        (data (global.get $offset) "\2a")
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    // read as an unsigned 32-bit offset, well past the end of the memory
+    let offset = Global::new(&mut store, Val::I32(-1), false);
+    linker.define("env", "offset", offset)?;
+
+    let module = common::compile(&engine, str)?;
+    let err = common::instantiate(&mut store, &linker, &module).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Store};
+
+#[test_log::test]
+fn enumerate_and_lookup() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(
+        &engine,
+        r#"(module
+            (func (export "fib"))
+            (memory (export "memory") 1)
+        )"#,
+    )?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    // the exports can be enumerated
+    let exports: Vec<_> = instance
+        .exports(&mut store)
+        .map(|export| (export.name.to_string(), export.value))
+        .collect();
+    assert_eq!(exports.len(), 2);
+    assert!(exports
+        .iter()
+        .any(|(name, value)| name == "fib" && value.is_func()));
+
+    // and looked up by type
+    assert!(instance.get_func(&mut store, "fib").is_ok());
+    assert!(instance.get_memory(&mut store, "memory").is_some());
+    assert!(instance.get_memory(&mut store, "fib").is_none());
+    assert!(instance.get_table(&mut store, "fib").is_none());
+    assert!(instance.get_global(&mut store, "fib").is_none());
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use k23vm::{
+    ConstExprEvaluator, Engine, Error, Global, Linker, PlaceholderAllocatorDontUse, Store, Val,
+};
+use std::sync::Arc;
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (global $counter (mut i32) (i32.const 0))
+        (func (export "inc") (result i32)
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+            (global.get $counter)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let linker = Linker::new(&engine);
+    let mut const_eval = ConstExprEvaluator::default();
+
+    let module = common::compile(&engine, str)?;
+    let pre = linker.instantiate_pre(&module)?;
+
+    let mut store1 = Store::new(&engine, ());
+    let mut store2 = Store::new(&engine, ());
+    let instance1 = pre.instantiate(
+        &mut store1,
+        Arc::new(PlaceholderAllocatorDontUse),
+        &mut const_eval,
+    )?;
+    let instance2 = pre.instantiate(
+        &mut store2,
+        Arc::new(PlaceholderAllocatorDontUse),
+        &mut const_eval,
+    )?;
+
+    let inc1 = instance1.get_typed_func::<(), i32>(&mut store1, "inc")?;
+    let inc2 = instance2.get_typed_func::<(), i32>(&mut store2, "inc")?;
+
+    // each instance has its own state
+    assert_eq!(inc1.call(&mut store1, ())?, 1);
+    assert_eq!(inc1.call(&mut store1, ())?, 2);
+    assert_eq!(inc2.call(&mut store2, ())?, 1);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn with_imports() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (import "env" "base" (global $base i32))
+        (func (export "get") (result i32)
+            (global.get $base)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    let mut const_eval = ConstExprEvaluator::default();
+
+    let module = common::compile(&engine, str)?;
+
+    // imports are resolved when the `InstancePre` is created
+    let err = linker.instantiate_pre(&module).unwrap_err();
+    assert!(matches!(err, Error::MissingImport { .. }), "{err}");
+
+    let base = Global::new(&mut store, Val::I32(42), false);
+    linker.define("env", "base", base)?;
+    let pre = linker.instantiate_pre(&module)?;
+
+    for _ in 0..2 {
+        let instance = pre.instantiate(
+            &mut store,
+            Arc::new(PlaceholderAllocatorDontUse),
+            &mut const_eval,
+        )?;
+        let get = instance.get_typed_func::<(), i32>(&mut store, "get")?;
+        assert_eq!(get.call(&mut store, ())?, 42);
+    }
+
+    Ok(())
+}
+
+#[test_log::test]
+#[should_panic]
+fn foreign_store_definition() {
+    let str = r#"
+    (module
+        (import "env" "base" (global $base i32))
+    )"#;
+
+    let engine = Engine::default();
+    let mut store1 = Store::new(&engine, ());
+    let mut store2 = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    let mut const_eval = ConstExprEvaluator::default();
+
+    let module = common::compile(&engine, str).unwrap();
+
+    // `store1` has a global at the same index, so only the store id tells them apart
+    Global::new(&mut store1, Val::I32(1), false);
+    let base = Global::new(&mut store2, Val::I32(42), false);
+    linker.define("env", "base", base).unwrap();
+    let pre = linker.instantiate_pre(&module).unwrap();
+
+    let _ = pre.instantiate(
+        &mut store1,
+        Arc::new(PlaceholderAllocatorDontUse),
+        &mut const_eval,
+    );
+}
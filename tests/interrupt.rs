@@ -0,0 +1,99 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Instance, Store, Trap};
+use std::thread;
+use std::time::Duration;
+
+/// A function spinning in a bare `loop` forever, counting the iterations in `iterations`.
+const WAT: &str = r#"
+(module
+    (global $iterations (export "iterations") (mut i64) (i64.const 0))
+    (func (export "spin")
+        (loop $l
+            (global.set $iterations (i64.add (global.get $iterations) (i64.const 1)))
+            (br $l)
+        )
+    )
+)"#;
+
+fn setup(config: &Config) -> Result<(Engine, Store<()>, Instance), Error> {
+    let engine = Engine::new(config);
+    let (store, instance) = common::setup(&engine, WAT)?;
+
+    Ok((engine, store, instance))
+}
+
+/// Calls `spin` and returns the trap it was interrupted with and how often the loop ran.
+fn spin(store: &mut Store<()>, instance: Instance) -> Result<(Option<Trap>, i64), Error> {
+    let err = instance
+        .get_typed_func::<(), ()>(&mut *store, "spin")?
+        .call(&mut *store, ())
+        .unwrap_err();
+    let iterations = instance
+        .get_global(&mut *store, "iterations")
+        .unwrap()
+        .get(&mut *store)
+        .unwrap_i64();
+    Ok((err.trap_code(), iterations))
+}
+
+/// Advances the epoch of `engine` after a short delay, so code is already running by then.
+fn increment_epoch_later(engine: &Engine) -> thread::JoinHandle<()> {
+    let engine = engine.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        engine.increment_epoch();
+    })
+}
+
+#[test_log::test]
+fn fuel() -> Result<(), Error> {
+    let (_, mut store, instance) = setup(Config::new().consume_fuel(true))?;
+    store.set_fuel(1000);
+
+    let (trap, iterations) = spin(&mut store, instance)?;
+    assert_eq!(trap, Some(Trap::OutOfFuel));
+    assert!(iterations > 0);
+    assert_eq!(store.get_fuel(), 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn epoch() -> Result<(), Error> {
+    let (engine, mut store, instance) = setup(Config::new().epoch_interruption(true))?;
+    store.set_epoch_deadline(1);
+
+    let ticker = increment_epoch_later(&engine);
+    let (trap, iterations) = spin(&mut store, instance)?;
+    ticker.join().unwrap();
+    assert_eq!(trap, Some(Trap::Interrupt));
+    assert!(iterations > 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn fuel_and_epoch() -> Result<(), Error> {
+    let mut config = Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+
+    // whichever limit is hit first interrupts the loop
+    let (_, mut store, instance) = setup(&config)?;
+    store.set_fuel(1000);
+    store.set_epoch_deadline(u64::MAX);
+    let (trap, iterations) = spin(&mut store, instance)?;
+    assert_eq!(trap, Some(Trap::OutOfFuel));
+    assert!(iterations > 0);
+
+    let (engine, mut store, instance) = setup(&config)?;
+    store.set_fuel(u64::MAX);
+    store.set_epoch_deadline(1);
+    let ticker = increment_epoch_later(&engine);
+    let (trap, iterations) = spin(&mut store, instance)?;
+    ticker.join().unwrap();
+    assert_eq!(trap, Some(Trap::Interrupt));
+    assert!(iterations > 0);
+
+    Ok(())
+}
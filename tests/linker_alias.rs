@@ -0,0 +1,62 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Store};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let provider = r#"
+    (module
+        (func (export "answer") (result i32)
+            (i32.const 42)
+        )
+    )"#;
+    let consumer = r#"
+    (module
+        (import "env" "answer" (func $a (result i32)))
+        (import "renamed" "the_answer" (func $b (result i32)))
+        (func (export "sum") (result i32)
+            (i32.add (call $a) (call $b))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, provider)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    linker.define_instance(&mut store, "provider", instance)?;
+    linker.alias_module("provider", "env")?;
+    linker.alias("env", "answer", "renamed", "the_answer")?;
+
+    // all names resolve to the same function
+    for (module, name) in [
+        ("provider", "answer"),
+        ("env", "answer"),
+        ("renamed", "the_answer"),
+    ] {
+        let func = linker
+            .get(module, name)
+            .unwrap()
+            .clone()
+            .into_func()
+            .unwrap();
+        assert_eq!(func.typed::<(), i32>(&store)?.call(&mut store, ())?, 42);
+    }
+
+    let module = common::compile(&engine, consumer)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let sum = instance.get_typed_func::<(), i32>(&mut store, "sum")?;
+    assert_eq!(sum.call(&mut store, ())?, 84);
+
+    let err = linker
+        .alias("env", "missing", "renamed", "missing")
+        .unwrap_err();
+    assert!(matches!(err, Error::Undefined { .. }), "{err}");
+    let err = linker
+        .alias("provider", "answer", "renamed", "the_answer")
+        .unwrap_err();
+    assert!(matches!(err, Error::AlreadyDefined { .. }), "{err}");
+
+    Ok(())
+}
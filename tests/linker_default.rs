@@ -0,0 +1,59 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Store};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let command = r#"
+    (module
+        (global $ran (export "ran") (mut i32) (i32.const 0))
+        (func (export "_start")
+            (global.set $ran (i32.add (global.get $ran) (i32.const 1)))
+        )
+        (func (export "") unreachable)
+    )"#;
+    let reactor = r#"
+    (module
+        (func (export "") (result i32) (i32.const 1))
+        (memory (export "_start") 1)
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    for (name, wat) in [("command", command), ("reactor", reactor)] {
+        let module = common::compile(&engine, wat)?;
+        let instance = common::instantiate(&mut store, &linker, &module)?;
+        linker.define_instance(&mut store, name, instance)?;
+    }
+
+    // `_start` takes precedence over the default export
+    let start = linker.get_default(&mut store, "command")?;
+    start.call0(&mut store)?;
+    start.call0(&mut store)?;
+    let ran = linker
+        .get("command", "ran")
+        .unwrap()
+        .clone()
+        .into_global()
+        .unwrap();
+    assert_eq!(ran.get(&mut store).unwrap_i32(), 2);
+
+    let err = linker.get_default(&mut store, "reactor").unwrap_err();
+    assert!(matches!(err, Error::WrongExportKind { .. }), "{err}");
+    let err = linker.get_default(&mut store, "missing").unwrap_err();
+    assert!(matches!(err, Error::Undefined { .. }), "{err}");
+
+    // the default export is used in the absence of `_start`, but has to be `() -> ()`
+    linker.alias("reactor", "", "fallback", "")?;
+    let err = linker.get_default(&mut store, "fallback").unwrap_err();
+    assert!(matches!(err, Error::FuncTypeMismatch { .. }), "{err}");
+    linker.alias("command", "_start", "fallback2", "")?;
+    linker
+        .get_default(&mut store, "fallback2")?
+        .call0(&mut store)?;
+    assert_eq!(ran.get(&mut store).unwrap_i32(), 3);
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn grow_and_access_large_offsets() -> Result<(), Error> {
+    // the maximum is far larger than any reservation could be
+    let str = r#"
+    (module
+        (memory (export "memory") i64 1 0x1_0000_0000)
+        (func (export "load") (param i64) (result i64)
+            (i64.load (local.get 0))
+        )
+        (func (export "store") (param i64 i64)
+            (i64.store (local.get 0) (local.get 1))
+        )
+        (func (export "grow") (param i64) (result i64)
+            (memory.grow (local.get 0))
+        )
+        (func (export "size") (result i64)
+            (memory.size)
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::MEMORY64;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let load = instance.get_typed_func::<i64, i64>(&mut store, "load")?;
+    let store_fn = instance.get_typed_func::<(i64, i64), ()>(&mut store, "store")?;
+    let grow = instance.get_typed_func::<i64, i64>(&mut store, "grow")?;
+    let size = instance.get_typed_func::<(), i64>(&mut store, "size")?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+
+    // grow to 2 GiB, from wasm and from the host
+    assert_eq!(grow.call(&mut store, 0x3fff)?, 1);
+    assert_eq!(memory.grow(&mut store, 0x4000)?, 0x4000);
+    assert_eq!(size.call(&mut store, ())?, 0x8000);
+    assert_eq!(memory.size(&store), 0x8000);
+
+    store_fn.call(&mut store, (0x7fff_fff8, 42))?;
+    assert_eq!(load.call(&mut store, 0x7fff_fff8)?, 42);
+
+    let oob = [
+        load.call(&mut store, 0x7fff_fffc).unwrap_err(),
+        load.call(&mut store, 0x8000_0000).unwrap_err(),
+        // beyond the end of the reservation
+        load.call(&mut store, 0x10_0000_0000).unwrap_err(),
+        load.call(&mut store, -8).unwrap_err(),
+    ];
+    for err in oob {
+        assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+    }
+
+    // growing past what was reserved fails even though the declared maximum allows it
+    assert_eq!(grow.call(&mut store, 0x1_0000)?, -1);
+    assert!(memory.grow(&mut store, 0x1_0000).is_err());
+    assert_eq!(memory.size(&store), 0x8000);
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use k23vm::{Engine, Error, Instance, Memory, Store, Trap};
+
+const PAGE_SIZE: usize = 0x1_0000;
+
+fn setup() -> Result<(Store<()>, Instance, Memory), Error> {
+    let str = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "load") (param $addr i32) (result i32)
+            (i32.load (local.get $addr))
+        )
+        (func (export "store") (param $addr i32) (param $val i32)
+            (i32.store (local.get $addr) (local.get $val))
+        )
+    )"#;
+
+    let (mut store, instance) = common::setup(&Engine::default(), str)?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+
+    Ok((store, instance, memory))
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let (mut store, instance, memory) = setup()?;
+
+    memory.write(&mut store, 100, b"hello")?;
+    let mut buf = [0; 5];
+    memory.read(&store, 100, &mut buf)?;
+    assert_eq!(&buf, b"hello");
+
+    // the host and wasm agree on the byte order
+    memory.write_u32(&mut store, 200, 0x1234_5678)?;
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    assert_eq!(load.call(&mut store, 200)?, 0x1234_5678);
+
+    let store_ = instance.get_typed_func::<(i32, i32), ()>(&mut store, "store")?;
+    store_.call(&mut store, (300, 0x0102_0304))?;
+    assert_eq!(memory.read_u32(&store, 300)?, 0x0102_0304);
+    memory.read(&store, 300, &mut buf[..4])?;
+    assert_eq!(buf[..4], [4, 3, 2, 1]);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn end_of_memory() -> Result<(), Error> {
+    let (mut store, _instance, memory) = setup()?;
+
+    // the last bytes of memory are accessible
+    memory.write(&mut store, PAGE_SIZE - 4, &[1, 2, 3, 4])?;
+    assert_eq!(memory.read_u32(&store, PAGE_SIZE - 4)?, 0x0403_0201);
+    memory.write_u32(&mut store, PAGE_SIZE - 4, 42)?;
+    assert_eq!(memory.read_u32(&store, PAGE_SIZE - 4)?, 42);
+
+    // empty accesses right at the end are fine too
+    memory.write(&mut store, PAGE_SIZE, &[])?;
+    memory.read(&store, PAGE_SIZE, &mut [])?;
+
+    // but one byte further is out of bounds
+    let err = memory.read_u32(&store, PAGE_SIZE - 3).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = memory.write_u32(&mut store, PAGE_SIZE - 3, 0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = memory.write(&mut store, PAGE_SIZE, &[0]).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = memory.read(&store, PAGE_SIZE + 1, &mut []).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = memory.read(&store, usize::MAX, &mut [0; 2]).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+
+    // failed writes leave memory untouched
+    assert_eq!(memory.read_u32(&store, PAGE_SIZE - 4)?, 42);
+
+    // growing the memory makes the next page accessible
+    memory.grow(&mut store, 1)?;
+    memory.write_u32(&mut store, PAGE_SIZE - 3, 7)?;
+    assert_eq!(memory.read_u32(&store, PAGE_SIZE - 3)?, 7);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn faulting_address() -> Result<(), Error> {
+    let (mut store, instance, memory) = setup()?;
+
+    // well past the end of the memory, so the store hits the guard pages
+    let store_ = instance.get_typed_func::<(i32, i32), ()>(&mut store, "store")?;
+    let err = store_.call(&mut store, (0x3_0010, 1)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    assert!(err.faulting_addr().is_some());
+    assert_eq!(err.wasm_offset(), Some(0x3_0010));
+
+    // host accesses are bounds-checked explicitly and never fault
+    let err = memory.read_u32(&store, PAGE_SIZE).unwrap_err();
+    assert_eq!(err.faulting_addr(), None);
+    assert_eq!(err.wasm_offset(), None);
+
+    Ok(())
+}
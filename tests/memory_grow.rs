@@ -0,0 +1,76 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap};
+
+/// Functions that access memory both before and after growing it, without returning in between.
+const WAT: &str = r#"
+(module
+    (memory 1)
+    ;; grows the memory by one page and stores `value` into the new page, then loads it back
+    (func (export "grow_and_store") (param $value i32) (result i32)
+        (local $addr i32)
+        (i32.store (i32.const 0) (i32.const 1))
+        (local.set $addr
+            (i32.add
+                (i32.mul (memory.grow (i32.const 1)) (i32.const 0x10000))
+                (i32.const 0xfffc)
+            )
+        )
+        (i32.store (local.get $addr) (local.get $value))
+        (i32.add (i32.load (i32.const 0)) (i32.load (local.get $addr)))
+    )
+    ;; grows the memory one page at a time, writing to each new page right away
+    (func (export "grow_loop") (param $pages i32) (result i32)
+        (local $sum i32)
+        (local $page i32)
+        (loop $l
+            (local.set $page (memory.grow (i32.const 1)))
+            (i32.store (i32.mul (local.get $page) (i32.const 0x10000)) (local.get $page))
+            (local.set $sum
+                (i32.add (local.get $sum) (i32.load (i32.mul (local.get $page) (i32.const 0x10000))))
+            )
+            (local.set $pages (i32.sub (local.get $pages) (i32.const 1)))
+            (br_if $l (local.get $pages))
+        )
+        (local.get $sum)
+    )
+    ;; accesses the page past the end of the memory, first before and then after growing
+    (func (export "store_past_end") (param $grow i32)
+        (local $end i32)
+        (local.set $end (i32.mul (memory.size) (i32.const 0x10000)))
+        (if (local.get $grow)
+            (then (drop (memory.grow (i32.const 1))))
+        )
+        (i32.store (local.get $end) (i32.const 1))
+    )
+)"#;
+
+fn grow_and_store(config: &Config) -> Result<(), Error> {
+    let engine = Engine::new(config);
+    let (mut store, instance) = common::setup(&engine, WAT)?;
+
+    let grow_and_store = instance.get_typed_func::<i32, i32>(&mut store, "grow_and_store")?;
+    assert_eq!(grow_and_store.call(&mut store, 41)?, 42);
+    assert_eq!(grow_and_store.call(&mut store, 99)?, 100);
+
+    let grow_loop = instance.get_typed_func::<i32, i32>(&mut store, "grow_loop")?;
+    // the memory has 3 pages now, the loop adds pages 3 to 7
+    assert_eq!(grow_loop.call(&mut store, 5)?, 3 + 4 + 5 + 6 + 7);
+
+    let store_past_end = instance.get_typed_func::<i32, ()>(&mut store, "store_past_end")?;
+    let err = store_past_end.call(&mut store, 0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+    store_past_end.call(&mut store, 1)?;
+
+    Ok(())
+}
+
+#[test_log::test]
+fn static_memory() -> Result<(), Error> {
+    grow_and_store(&Config::new())
+}
+
+#[test_log::test]
+fn dynamic_memory() -> Result<(), Error> {
+    grow_and_store(Config::new().dynamic_memories(true))
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Linker, Store, Trap};
+
+#[test_log::test]
+fn small_guard_region() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1 2)
+        (func (export "load") (param i32) (result i32)
+            (i32.load (local.get 0))
+        )
+        (func (export "load_small_offset") (param i32) (result i32)
+            (i32.load offset=0x100 (local.get 0))
+        )
+        (func (export "load_large_offset") (param i32) (result i32)
+            (i32.load offset=0x10000 (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::new(Config::new().memory_guard_size(0x1000));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    let load_small_offset = instance.get_typed_func::<i32, i32>(&mut store, "load_small_offset")?;
+    let load_large_offset = instance.get_typed_func::<i32, i32>(&mut store, "load_large_offset")?;
+
+    assert_eq!(load.call(&mut store, 0xfffc)?, 0);
+    assert_eq!(load_small_offset.call(&mut store, 0xfefc)?, 0);
+
+    let oob = [
+        load.call(&mut store, 0x1_0000).unwrap_err(),
+        load_small_offset.call(&mut store, 0xfefd).unwrap_err(),
+        load_large_offset.call(&mut store, 0).unwrap_err(),
+        // offsets larger than the guard region can't reach past the end of the reservation
+        load_small_offset.call(&mut store, -4).unwrap_err(),
+        load_large_offset.call(&mut store, -4).unwrap_err(),
+    ];
+    for err in oob {
+        assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+    }
+
+    Ok(())
+}
+
+#[test_log::test]
+fn guard_region_exceeds_address_space() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().memory_guard_size(u64::MAX));
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, "(module (memory 1))")?;
+    let err = common::instantiate(&mut store, &linker, &module).unwrap_err();
+    assert!(matches!(err, Error::AllocationFailed(_)), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use k23vm::{Engine, Error, Instance, Store, Trap, Val};
+
+fn setup() -> Result<(Store<()>, Instance), Error> {
+    let str = r#"
+    (module
+        (memory 1)
+        (data $seg "\01\02\03\04")
+
+        (func (export "init") (param $dst i32) (param $src i32) (param $len i32)
+            (memory.init $seg (local.get $dst) (local.get $src) (local.get $len))
+        )
+        (func (export "drop")
+            (data.drop $seg)
+        )
+        (func (export "load") (param $addr i32) (result i32)
+            (i32.load8_u (local.get $addr))
+        )
+    )"#;
+
+    common::setup(&Engine::default(), str)
+}
+
+fn init(
+    store: &mut Store<()>,
+    instance: Instance,
+    dst: i32,
+    src: i32,
+    len: i32,
+) -> Result<(), Error> {
+    let init = instance.get_func(store, "init").unwrap();
+    // Safety: `init` takes three `i32`s and returns nothing
+    unsafe {
+        init.call_unchecked(
+            store,
+            &[Val::I32(dst), Val::I32(src), Val::I32(len)],
+            &mut [],
+        )
+    }
+}
+
+fn load(store: &mut Store<()>, instance: Instance, addr: i32) -> i32 {
+    let load = instance.get_func(store, "load").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: `load` takes a single `i32` and returns a single `i32`
+    unsafe {
+        load.call_unchecked(store, &[Val::I32(addr)], &mut results)
+            .unwrap();
+    }
+    results[0].unwrap_i32()
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+
+    init(&mut store, instance, 100, 1, 3)?;
+    assert_eq!(load(&mut store, instance, 99), 0);
+    assert_eq!(load(&mut store, instance, 100), 2);
+    assert_eq!(load(&mut store, instance, 101), 3);
+    assert_eq!(load(&mut store, instance, 102), 4);
+    assert_eq!(load(&mut store, instance, 103), 0);
+
+    // zero-length copies at the very ends of the segment and the memory are fine
+    init(&mut store, instance, 0x1_0000, 4, 0)?;
+
+    Ok(())
+}
+
+#[test_log::test]
+fn src_out_of_range() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+
+    let err = init(&mut store, instance, 0, 2, 3).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = init(&mut store, instance, 0, 5, 0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    // the offset and length are unsigned, so this doesn't wrap around
+    let err = init(&mut store, instance, 0, 1, -1).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+
+    // nothing was written
+    assert_eq!(load(&mut store, instance, 0), 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn dst_out_of_range() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+
+    let err = init(&mut store, instance, 0xfffe, 0, 4).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = init(&mut store, instance, 0x1_0001, 0, 0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+
+    // the in-bounds part wasn't written either
+    assert_eq!(load(&mut store, instance, 0xfffe), 0);
+    assert_eq!(load(&mut store, instance, 0xffff), 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn init_after_drop() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+
+    let drop = instance.get_func(&mut store, "drop").unwrap();
+    drop.call0(&mut store)?;
+
+    // a dropped segment behaves like an empty one
+    let err = init(&mut store, instance, 0, 0, 1).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    let err = init(&mut store, instance, 0, 1, 0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    init(&mut store, instance, 0, 0, 0)?;
+
+    // dropping twice is allowed
+    drop.call0(&mut store)?;
+
+    Ok(())
+}
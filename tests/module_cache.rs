@@ -0,0 +1,73 @@
+mod common;
+
+use k23vm::{Config, Engine, Error};
+
+const A: &str = r#"(module (func (export "a") (result i32) (i32.const 1)))"#;
+const B: &str = r#"(module (func (export "b") (result i32) (i32.const 2)))"#;
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().module_cache_capacity(4));
+
+    common::compile(&engine, A)?;
+    assert_eq!(engine.module_cache_hits(), 0);
+    assert_eq!(engine.module_cache_misses(), 1);
+
+    common::compile(&engine, A)?;
+    assert_eq!(engine.module_cache_hits(), 1);
+    assert_eq!(engine.module_cache_misses(), 1);
+
+    // different bytes are compiled separately
+    common::compile(&engine, B)?;
+    assert_eq!(engine.module_cache_hits(), 1);
+    assert_eq!(engine.module_cache_misses(), 2);
+
+    engine.clear_module_cache();
+    common::compile(&engine, A)?;
+    assert_eq!(engine.module_cache_hits(), 1);
+    assert_eq!(engine.module_cache_misses(), 3);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn disabled_by_default() -> Result<(), Error> {
+    let engine = Engine::default();
+
+    common::compile(&engine, A)?;
+    common::compile(&engine, A)?;
+    assert_eq!(engine.module_cache_hits(), 0);
+    assert_eq!(engine.module_cache_misses(), 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn evicts_least_recently_used() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().module_cache_capacity(1));
+
+    common::compile(&engine, A)?;
+    common::compile(&engine, B)?;
+    // `A` was evicted to make room for `B`
+    common::compile(&engine, A)?;
+    assert_eq!(engine.module_cache_hits(), 0);
+    assert_eq!(engine.module_cache_misses(), 3);
+
+    common::compile(&engine, A)?;
+    assert_eq!(engine.module_cache_hits(), 1);
+
+    engine.clear_module_cache();
+
+    Ok(())
+}
+
+#[test_log::test]
+fn invalid_modules_are_not_cached() {
+    let engine = Engine::new(Config::new().module_cache_capacity(4));
+
+    let str = r#"(module (func (result i32) (i64.const 1)))"#;
+    assert!(common::compile(&engine, str).is_err());
+    assert!(common::compile(&engine, str).is_err());
+    assert_eq!(engine.module_cache_hits(), 0);
+    assert_eq!(engine.module_cache_misses(), 2);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Module, Store};
+use wasmparser::Validator;
+
+#[test_log::test]
+fn fib_cpp_in_chunks() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut validator = Validator::new();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let bytes = wat::parse_str(include_str!("./fib_cpp.wat"))?;
+    let streamed = Module::from_stream(&engine, &mut validator, bytes.chunks(64))?;
+    let module = Module::from_bytes(&engine, &mut validator, &bytes)?;
+
+    assert_eq!(
+        streamed.exports().collect::<Vec<_>>(),
+        module.exports().collect::<Vec<_>>()
+    );
+    assert_eq!(streamed.imports().len(), module.imports().len());
+    assert_eq!(
+        streamed.function_names().collect::<Vec<_>>(),
+        module.function_names().collect::<Vec<_>>()
+    );
+
+    let mut fib = |module: &Module| -> Result<i32, Error> {
+        let instance = common::instantiate(&mut store, &linker, module)?;
+        instance
+            .get_typed_func::<i32, i32>(&mut store, "fib")?
+            .call(&mut store, 20)
+    };
+    assert_eq!(fib(&streamed)?, fib(&module)?);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn malformed_stream() {
+    let engine = Engine::default();
+    let mut validator = Validator::new();
+
+    let mut bytes = wat::parse_str(include_str!("./fib_cpp.wat")).unwrap();
+    // truncate the module in the middle of its code section
+    bytes.truncate(bytes.len() / 2);
+    let err = Module::from_stream(&engine, &mut validator, bytes.chunks(64)).unwrap_err();
+    assert!(matches!(err, Error::InvalidWebAssembly { .. }), "{err}");
+
+    // the validator can be reused afterward
+    Module::from_stream(&engine, &mut validator, [b"\0asm\x01\0\0\0"]).unwrap();
+}
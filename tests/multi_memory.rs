@@ -0,0 +1,106 @@
+mod common;
+
+use k23vm::{Config, Engine, Store, Val};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (memory $mem0 1)
+        (memory $mem1 1)
+        (data $data "\2a\2b")
+
+        (func (export "store1") (param $addr i32) (param $val i32)
+            (i32.store $mem1 (local.get $addr) (local.get $val))
+        )
+        (func (export "copy1to0") (param $dst i32) (param $src i32) (param $len i32)
+            (memory.copy $mem0 $mem1 (local.get $dst) (local.get $src) (local.get $len))
+        )
+        (func (export "fill1") (param $dst i32) (param $val i32) (param $len i32)
+            (memory.fill $mem1 (local.get $dst) (local.get $val) (local.get $len))
+        )
+        (func (export "init1") (param $dst i32)
+            (memory.init $mem1 $data (local.get $dst) (i32.const 0) (i32.const 2))
+        )
+        (func (export "load0") (param $addr i32) (result i32)
+            (i32.load $mem0 (local.get $addr))
+        )
+        (func (export "load1") (param $addr i32) (result i32)
+            (i32.load $mem1 (local.get $addr))
+        )
+        (func (export "grow1") (param $delta i32) (result i32)
+            (memory.grow $mem1 (local.get $delta))
+        )
+        (func (export "size0") (result i32)
+            (memory.size $mem0)
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::MULTI_MEMORY;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str, params: &[Val]| -> Option<Val> {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        let len = func.ty(store).as_wasm_func_type().results.len();
+        // Safety: the parameters match the signatures of the functions above
+        unsafe {
+            func.call_unchecked(store, params, &mut results[..len])
+                .unwrap();
+        }
+        results[..len].first().copied()
+    };
+
+    // a value stored in memory 1 only shows up in memory 1
+    call(&mut store, "store1", &[Val::I32(8), Val::I32(0x1234_5678)]);
+    assert_eq!(
+        call(&mut store, "load1", &[Val::I32(8)])
+            .unwrap()
+            .unwrap_i32(),
+        0x1234_5678
+    );
+    assert_eq!(
+        call(&mut store, "load0", &[Val::I32(8)])
+            .unwrap()
+            .unwrap_i32(),
+        0
+    );
+
+    // copy it over to memory 0 and read it back
+    call(
+        &mut store,
+        "copy1to0",
+        &[Val::I32(16), Val::I32(8), Val::I32(4)],
+    );
+    assert_eq!(
+        call(&mut store, "load0", &[Val::I32(16)])
+            .unwrap()
+            .unwrap_i32(),
+        0x1234_5678
+    );
+
+    // fill and init target memory 1
+    call(
+        &mut store,
+        "fill1",
+        &[Val::I32(32), Val::I32(0xff), Val::I32(4)],
+    );
+    call(&mut store, "init1", &[Val::I32(32)]);
+    assert_eq!(
+        call(&mut store, "load1", &[Val::I32(32)])
+            .unwrap()
+            .unwrap_i32(),
+        i32::from_le_bytes([0x2a, 0x2b, 0xff, 0xff])
+    );
+
+    // growing memory 1 leaves memory 0 untouched
+    assert_eq!(
+        call(&mut store, "grow1", &[Val::I32(1)])
+            .unwrap()
+            .unwrap_i32(),
+        1
+    );
+    assert_eq!(call(&mut store, "size0", &[]).unwrap().unwrap_i32(), 1);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use k23vm::{Engine, Error};
+
+#[test_log::test]
+fn block_results() -> Result<(), Error> {
+    let str = r#"
+    (module
+        ;; falls through if $branch is zero, branches out with the same pair otherwise
+        (func (export "block") (param $branch i32) (result i32 i32)
+            (block (result i32 i32)
+                (i32.const 1)
+                (i32.const 2)
+                (br_if 0 (local.get $branch))
+                (drop)
+                (drop)
+                (i32.const 1)
+                (i32.const 2)
+            )
+        )
+        (func (export "br") (param $branch i32) (result i32 i32)
+            (block $out (result i32 i32)
+                (block $inner
+                    (br_if $inner (i32.eqz (local.get $branch)))
+                    (br $out (i32.const 1) (i32.const 2))
+                )
+                (i32.const 1)
+                (i32.const 2)
+            )
+        )
+        (func (export "if") (param i32) (result i32 i32)
+            (if (result i32 i32) (local.get 0)
+                (then (i32.const 1) (i32.const 2))
+                (else (i32.const 3) (i32.const 4))
+            )
+        )
+        ;; the pair is threaded through every iteration as the loop's parameters
+        (func (export "loop") (param $n i32) (result i32 i32)
+            (local $marker i32)
+            (i32.const 0)
+            (i32.const 7)
+            (loop $continue (param i32 i32) (result i32 i32)
+                (local.set $marker)
+                (i32.const 1)
+                (i32.add)
+                (local.get $marker)
+                (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+                (br_if $continue)
+            )
+        )
+        (func (export "swap") (param i32 i32) (result i32 i32)
+            (local.get 1)
+            (local.get 0)
+            (block (param i32 i32) (result i32 i32))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    // both exit paths produce the same pair
+    for name in ["block", "br"] {
+        let func = instance.get_typed_func::<i32, (i32, i32)>(&mut store, name)?;
+        assert_eq!(func.call(&mut store, 0)?, (1, 2), "{name} falling through");
+        assert_eq!(func.call(&mut store, 1)?, (1, 2), "{name} branching");
+    }
+
+    let if_ = instance.get_typed_func::<i32, (i32, i32)>(&mut store, "if")?;
+    assert_eq!(if_.call(&mut store, 1)?, (1, 2));
+    assert_eq!(if_.call(&mut store, 0)?, (3, 4));
+
+    let loop_ = instance.get_typed_func::<i32, (i32, i32)>(&mut store, "loop")?;
+    assert_eq!(loop_.call(&mut store, 1)?, (1, 7));
+    assert_eq!(loop_.call(&mut store, 4)?, (4, 7));
+
+    let swap = instance.get_typed_func::<(i32, i32), (i32, i32)>(&mut store, "swap")?;
+    assert_eq!(swap.call(&mut store, (1, 2))?, (2, 1));
+
+    Ok(())
+}
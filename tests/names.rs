@@ -0,0 +1,49 @@
+mod common;
+
+use k23vm::{Engine, Error, FuncIndex};
+
+#[test_log::test]
+fn name_section() -> Result<(), Error> {
+    let str = r#"
+    (module $calculator
+        (import "env" "log" (func $log (param i32)))
+        (func $add (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))
+        )
+        (func (result i32)
+            (i32.const 0)
+        )
+        (func $sub (param i32 i32) (result i32)
+            (i32.sub (local.get 0) (local.get 1))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let module = common::compile(&engine, str)?;
+
+    assert_eq!(module.name(), Some("calculator"));
+    assert_eq!(module.function_name(FuncIndex::from_u32(0)), Some("log"));
+    assert_eq!(module.function_name(FuncIndex::from_u32(1)), Some("add"));
+    assert_eq!(module.function_name(FuncIndex::from_u32(2)), None);
+    assert_eq!(module.function_name(FuncIndex::from_u32(3)), Some("sub"));
+
+    let names = module
+        .function_names()
+        .map(|(index, name)| (index.as_u32(), name))
+        .collect::<Vec<_>>();
+    assert_eq!(names, [(0, "log"), (1, "add"), (3, "sub")]);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn no_name_section() -> Result<(), Error> {
+    let engine = Engine::default();
+    let module = common::compile(&engine, "(module (func))")?;
+
+    assert_eq!(module.name(), None);
+    assert_eq!(module.function_name(FuncIndex::from_u32(0)), None);
+    assert_eq!(module.function_names().len(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, OptLevel};
+
+const FIB: &str = r#"
+(module
+    (func $fib (export "fib") (param i32) (result i32)
+        (if (result i32) (i32.lt_u (local.get 0) (i32.const 2))
+            (then (local.get 0))
+            (else
+                (i32.add
+                    (call $fib (i32.sub (local.get 0) (i32.const 1)))
+                    (call $fib (i32.sub (local.get 0) (i32.const 2)))
+                )
+            )
+        )
+    )
+)"#;
+
+fn run_fib(config: &Config) -> Result<i32, Error> {
+    let engine = Engine::new(config);
+    let (mut store, instance) = common::setup(&engine, FIB)?;
+
+    instance
+        .get_typed_func::<i32, i32>(&mut store, "fib")?
+        .call(&mut store, 20)
+}
+
+#[test_log::test]
+fn opt_levels() -> Result<(), Error> {
+    for level in [OptLevel::None, OptLevel::Speed, OptLevel::SpeedAndSize] {
+        assert_eq!(run_fib(Config::new().opt_level(level))?, 6765, "{level:?}");
+    }
+
+    Ok(())
+}
+
+#[test_log::test]
+fn without_frame_pointers_and_probestack() -> Result<(), Error> {
+    let mut config = Config::new();
+    config
+        .opt_level(OptLevel::Speed)
+        .preserve_frame_pointers(false)
+        .probestack(false);
+    assert_eq!(run_fib(&config)?, 6765);
+
+    Ok(())
+}
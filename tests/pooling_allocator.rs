@@ -0,0 +1,192 @@
+mod common;
+
+use k23vm::{
+    Config, ConstExprEvaluator, Engine, Error, InstanceAllocator, Linker, Module,
+    OnDemandAllocator, PoolingAllocator, PoolingConfig, Store,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wasmparser::WasmFeatures;
+
+const WAT: &str = r#"
+(module
+    (memory 1)
+    (table 1 funcref)
+    (func (export "load") (result i32)
+        (i32.load (i32.const 0))
+    )
+    (func (export "store") (param i32)
+        (i32.store (i32.const 0) (local.get 0))
+    )
+)"#;
+
+fn pool(total_instances: usize) -> Arc<PoolingAllocator> {
+    let pool =
+        PoolingAllocator::new(PoolingConfig::new().total_instances(total_instances)).unwrap();
+    Arc::new(pool)
+}
+
+#[test_log::test]
+fn slots_are_reused() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+    let mut const_eval = ConstExprEvaluator::default();
+    let module = common::compile(&engine, WAT)?;
+    let alloc = pool(2);
+
+    for _ in 0..2 {
+        let instance = linker.instantiate(&mut store, alloc.clone(), &mut const_eval, &module)?;
+        instance
+            .get_typed_func::<i32, ()>(&mut store, "store")?
+            .call(&mut store, 42)?;
+    }
+
+    // both slots are taken
+    let err = linker
+        .instantiate(&mut store, alloc.clone(), &mut const_eval, &module)
+        .unwrap_err();
+    assert!(matches!(err, Error::AllocationFailed(_)), "{err}");
+
+    // tearing down the instances frees the slots, memories come back zeroed
+    store.reset();
+    for _ in 0..2 {
+        let instance = linker.instantiate(&mut store, alloc.clone(), &mut const_eval, &module)?;
+        let load = instance.get_typed_func::<(), i32>(&mut store, "load")?;
+        assert_eq!(load.call(&mut store, ())?, 0);
+    }
+
+    Ok(())
+}
+
+#[test_log::test]
+fn grown_pages_are_zeroed() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+    let mut const_eval = ConstExprEvaluator::default();
+    let module = common::compile(
+        &engine,
+        r#"
+        (module
+            (memory 1)
+            (func (export "grow") (result i32)
+                (memory.grow (i32.const 2))
+            )
+            (func (export "fill") (param i32)
+                (memory.fill (i32.const 0) (local.get 0) (i32.mul (memory.size) (i32.const 65536)))
+            )
+            (func (export "sum") (result i64)
+                (local $addr i32)
+                (local $sum i64)
+                (loop $continue
+                    (local.set $sum (i64.add (local.get $sum) (i64.load (local.get $addr))))
+                    (local.set $addr (i32.add (local.get $addr) (i32.const 8)))
+                    (br_if $continue
+                        (i32.lt_u (local.get $addr) (i32.mul (memory.size) (i32.const 65536)))
+                    )
+                )
+                (local.get $sum)
+            )
+        )"#,
+    )?;
+    let alloc = pool(1);
+
+    // dirty all pages the memory grows into
+    let instance = linker.instantiate(&mut store, alloc.clone(), &mut const_eval, &module)?;
+    assert_eq!(
+        instance
+            .get_typed_func::<(), i32>(&mut store, "grow")?
+            .call(&mut store, ())?,
+        1
+    );
+    instance
+        .get_typed_func::<i32, ()>(&mut store, "fill")?
+        .call(&mut store, 0xAA)?;
+    assert_ne!(
+        instance
+            .get_typed_func::<(), i64>(&mut store, "sum")?
+            .call(&mut store, ())?,
+        0
+    );
+
+    // the next instance gets the same slot, neither its initial nor its grown pages may hold the
+    // previous instance's data
+    store.reset();
+    let instance = linker.instantiate(&mut store, alloc.clone(), &mut const_eval, &module)?;
+    let sum = instance.get_typed_func::<(), i64>(&mut store, "sum")?;
+    assert_eq!(sum.call(&mut store, ())?, 0);
+    assert_eq!(
+        instance
+            .get_typed_func::<(), i32>(&mut store, "grow")?
+            .call(&mut store, ())?,
+        1
+    );
+    assert_eq!(sum.call(&mut store, ())?, 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn too_many_memories() -> Result<(), Error> {
+    let features = WasmFeatures::default() | WasmFeatures::MULTI_MEMORY;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+    let mut const_eval = ConstExprEvaluator::default();
+    let alloc = pool(1);
+
+    let module = common::compile(&engine, "(module (memory 1) (memory 1))")?;
+    let err = linker
+        .instantiate(&mut store, alloc.clone(), &mut const_eval, &module)
+        .unwrap_err();
+    assert!(matches!(err, Error::AllocationFailed(_)), "{err}");
+
+    // the failed instantiation didn't leak the slot
+    let module = common::compile(&engine, "(module (memory 1))")?;
+    linker.instantiate(&mut store, alloc.clone(), &mut const_eval, &module)?;
+
+    Ok(())
+}
+
+/// Returns the fastest of a couple of runs of instantiating and tearing down `module` a hundred
+/// times.
+fn instantiate_teardown(
+    engine: &Engine,
+    alloc: Arc<dyn InstanceAllocator>,
+    module: &Module,
+) -> Duration {
+    let mut store = Store::new(engine, ());
+    let linker = Linker::new(engine);
+    let mut const_eval = ConstExprEvaluator::default();
+
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..100 {
+                linker
+                    .instantiate(&mut store, alloc.clone(), &mut const_eval, module)
+                    .unwrap();
+                store.reset();
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+#[test_log::test]
+fn pooling_is_faster() -> Result<(), Error> {
+    let engine = Engine::default();
+    let module = common::compile(&engine, WAT)?;
+
+    let on_demand = instantiate_teardown(&engine, Arc::new(OnDemandAllocator), &module);
+    let pooling = instantiate_teardown(&engine, pool(1), &module);
+    tracing::info!("on-demand {on_demand:?} pooling {pooling:?}");
+    assert!(
+        pooling < on_demand,
+        "on-demand {on_demand:?} pooling {pooling:?}"
+    );
+
+    Ok(())
+}
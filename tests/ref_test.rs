@@ -0,0 +1,95 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Store, Trap};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn ref_test_and_cast() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $a (sub (func)))
+        (type $b (sub $a (func)))
+        (type $c (func (param i32)))
+
+        (func $fa (type $a))
+        (func $fb (type $b))
+        (func $fc (type $c))
+
+        ;; the last slot stays null
+        (table 4 funcref)
+        (elem (i32.const 0) func $fa $fb $fc)
+
+        (func (export "test_a") (param i32) (result i32)
+            (ref.test (ref $a) (table.get (local.get 0)))
+        )
+        (func (export "test_null_a") (param i32) (result i32)
+            (ref.test (ref null $a) (table.get (local.get 0)))
+        )
+        (func (export "test_b") (param i32) (result i32)
+            (ref.test (ref $b) (table.get (local.get 0)))
+        )
+        (func (export "test_func") (param i32) (result i32)
+            (ref.test (ref func) (table.get (local.get 0)))
+        )
+        (func (export "cast_a") (param i32)
+            (drop (ref.cast (ref $a) (table.get (local.get 0))))
+        )
+
+        (func (export "test_i31") (param i32) (result i32)
+            (ref.test (ref i31) (ref.i31 (local.get 0)))
+        )
+        (func (export "test_i31_null") (result i32)
+            (ref.test (ref i31) (ref.null any))
+        )
+        (func (export "test_none_null") (result i32)
+            (ref.test (ref null none) (ref.null any))
+        )
+        (func (export "cast_none") (param i32)
+            (drop (ref.cast (ref none) (ref.i31 (local.get 0))))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::GC;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let test = |store: &mut Store<()>, name: &str, slot: i32| -> Result<i32, Error> {
+        instance
+            .get_typed_func::<i32, i32>(store, name)?
+            .call(store, slot)
+    };
+
+    // slots hold $fa, $fb (a subtype of $a), $fc (unrelated) and null
+    let cases = [
+        ("test_a", [1, 1, 0, 0]),
+        ("test_null_a", [1, 1, 0, 1]),
+        ("test_b", [0, 1, 0, 0]),
+        ("test_func", [1, 1, 1, 0]),
+    ];
+    for (name, expected) in cases {
+        for (slot, expected) in (0..).zip(expected) {
+            assert_eq!(test(&mut store, name, slot)?, expected, "{name} {slot}");
+        }
+    }
+
+    let cast_a = instance.get_typed_func::<i32, ()>(&mut store, "cast_a")?;
+    cast_a.call(&mut store, 0)?;
+    cast_a.call(&mut store, 1)?;
+    for slot in [2, 3] {
+        let err = cast_a.call(&mut store, slot).unwrap_err();
+        assert_eq!(err.trap_code(), Some(Trap::CastFailure), "{err}");
+    }
+
+    assert_eq!(test(&mut store, "test_i31", 42)?, 1);
+    let test_i31_null = instance.get_typed_func::<(), i32>(&mut store, "test_i31_null")?;
+    assert_eq!(test_i31_null.call(&mut store, ())?, 0);
+    let test_none_null = instance.get_typed_func::<(), i32>(&mut store, "test_none_null")?;
+    assert_eq!(test_none_null.call(&mut store, ())?, 1);
+    let err = instance
+        .get_typed_func::<i32, ()>(&mut store, "cast_none")?
+        .call(&mut store, 42)
+        .unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::CastFailure), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use k23vm::{Config, Engine, Error};
+use wasmparser::WasmFeatures;
+
+/// Computes `a * b + c` through `f32x4.relaxed_madd` and returns the first lane.
+fn relaxed_madd(deterministic: bool, a: f32, b: f32, c: f32) -> Result<f32, Error> {
+    let str = r#"
+    (module
+        (func (export "madd") (param f32 f32 f32) (result f32)
+            (f32x4.extract_lane 0
+                (f32x4.relaxed_madd
+                    (f32x4.splat (local.get 0))
+                    (f32x4.splat (local.get 1))
+                    (f32x4.splat (local.get 2))
+                )
+            )
+        )
+    )"#;
+
+    let features = WasmFeatures::WASM2 | WasmFeatures::RELAXED_SIMD;
+    let engine = Engine::new(
+        Config::new()
+            .wasm_features(features)
+            .relaxed_simd_deterministic(deterministic),
+    );
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let madd = instance.get_typed_func::<(f32, f32, f32), f32>(&mut store, "madd")?;
+    madd.call(&mut store, (a, b, c))
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    // (1 + 2^-23)^2 = 1 + 2^-22 + 2^-46, where the last term is lost when the product is rounded
+    // before the addition
+    let a = f32::from_bits(0x3f80_0001);
+    let c = -f32::from_bits(0x3f80_0002);
+    let fused = f32::from_bits(0x2880_0000);
+
+    // the deterministic lowering always fuses
+    assert_eq!(relaxed_madd(true, a, a, c)?.to_bits(), fused.to_bits());
+
+    // otherwise it depends on the host, but must be one of the two
+    let result = relaxed_madd(false, a, a, c)?;
+    assert!(
+        result.to_bits() == fused.to_bits() || result.to_bits() == 0,
+        "{result}"
+    );
+
+    Ok(())
+}
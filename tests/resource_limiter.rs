@@ -0,0 +1,53 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, ResourceLimiter, Store, Trap};
+
+const WAT: &str = r#"
+(module
+    (memory 1 4)
+    (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0))
+    )
+)"#;
+
+/// Allows memories to grow up to `max` bytes.
+struct Limiter {
+    max: usize,
+}
+
+impl ResourceLimiter for Limiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> bool {
+        desired <= self.max
+    }
+}
+
+#[test_log::test]
+fn denied_growth() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    store.set_limiter(Limiter { max: 2 * 65536 });
+
+    let module = common::compile(&engine, WAT)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let grow = instance.get_typed_func::<i32, i32>(&mut store, "grow")?;
+
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    // denied by the limiter
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+    // past the declared maximum
+    assert_eq!(grow.call(&mut store, 3)?, -1);
+
+    store.trap_on_memory_grow_failure(true);
+
+    // the module asking for more than its maximum is still not a trap
+    assert_eq!(grow.call(&mut store, 3)?, -1);
+    let err = grow.call(&mut store, 1).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::HostAllocationFailed), "{err}");
+
+    // the failed attempts didn't change the size
+    assert_eq!(grow.call(&mut store, 0)?, 2);
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $t (func (param i64 i64) (result i64)))
+        (elem declare func $sum)
+
+        ;; sums up all numbers from 1 to $n, each step tail-calls the next one
+        (func $sum (type $t) (param $n i64) (param $acc i64) (result i64)
+            (if (result i64) (i64.eqz (local.get $n))
+                (then (local.get $acc))
+                (else
+                    (return_call_ref $t
+                        (i64.sub (local.get $n) (i64.const 1))
+                        (i64.add (local.get $acc) (local.get $n))
+                        (ref.func $sum)
+                    )
+                )
+            )
+        )
+
+        (func (export "sum") (param $n i64) (result i64)
+            (return_call_ref $t (local.get $n) (i64.const 0) (ref.func $sum))
+        )
+        (func (export "null") (result i64)
+            (return_call_ref $t (i64.const 0) (i64.const 0) (ref.null $t))
+        )
+    )"#;
+
+    let features =
+        WasmFeatures::WASM2 | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::TAIL_CALL;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let sum = instance.get_typed_func::<i64, i64>(&mut store, "sum")?;
+    assert_eq!(sum.call(&mut store, 10)?, 55);
+    // this would overflow the stack many times over if the calls weren't tail calls
+    assert_eq!(sum.call(&mut store, 1_000_000)?, 500_000_500_000);
+
+    let null = instance.get_typed_func::<(), i64>(&mut store, "null")?;
+    let err = null.call(&mut store, ()).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::NullReference));
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use k23vm::{Engine, Error, Val};
+
+#[test_log::test]
+fn untyped_select_on_references_is_invalid() {
+    let str = r#"
+    (module
+        (func $f)
+        (elem declare func $f)
+
+        (func (param i32) (result funcref)
+            (select (ref.func $f) (ref.null func) (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+
+    let err = common::compile(&engine, str).unwrap_err();
+    match err {
+        Error::InvalidWebAssembly { message, .. } => {
+            assert_eq!(message, "type mismatch: select only takes integral types");
+        }
+        err => panic!("unexpected error {err}"),
+    }
+}
+
+#[test_log::test]
+fn typed_select_on_references() {
+    let str = r#"
+    (module
+        (func $f)
+        (elem declare func $f)
+
+        (func (export "select_is_null") (param i32) (result i32)
+            (ref.is_null
+                (select (result funcref) (ref.func $f) (ref.null func) (local.get 0))
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let func = instance.get_func(&mut store, "select_is_null").unwrap();
+    let mut results = [Val::I32(0)];
+
+    // Safety: the parameters match the signature of `select_is_null`
+    unsafe {
+        func.call_unchecked(&mut store, &[Val::I32(1)], &mut results)
+            .unwrap();
+    }
+    assert_eq!(results[0].unwrap_i32(), 0);
+
+    // Safety: the parameters match the signature of `select_is_null`
+    unsafe {
+        func.call_unchecked(&mut store, &[Val::I32(0)], &mut results)
+            .unwrap();
+    }
+    assert_eq!(results[0].unwrap_i32(), 1);
+}
+
+#[test_log::test]
+fn typed_select_between_funcrefs() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $to_i32 (func (result i32)))
+        (func $one (type $to_i32) (i32.const 1))
+        (func $two (type $to_i32) (i32.const 2))
+        (elem declare func $one $two)
+        (table 1 funcref)
+
+        (func (export "select_and_call") (param i32) (result i32)
+            (table.set (i32.const 0)
+                (select (result funcref) (ref.func $one) (ref.func $two) (local.get 0))
+            )
+            (call_indirect (type $to_i32) (i32.const 0))
+        )
+        ;; the untyped form keeps working for numbers
+        (func (export "select_i64") (param i32) (result i64)
+            (select (i64.const 1) (i64.const 2) (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let select_and_call = instance.get_typed_func::<i32, i32>(&mut store, "select_and_call")?;
+    assert_eq!(select_and_call.call(&mut store, 1)?, 1);
+    assert_eq!(select_and_call.call(&mut store, 0)?, 2);
+
+    let select_i64 = instance.get_typed_func::<i32, i64>(&mut store, "select_i64")?;
+    assert_eq!(select_i64.call(&mut store, 1)?, 1);
+    assert_eq!(select_i64.call(&mut store, 0)?, 2);
+
+    Ok(())
+}
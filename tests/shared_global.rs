@@ -0,0 +1,69 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Linker, SharedGlobal, Store, Val};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn shared_between_stores() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (global $counter (import "env" "counter") (shared mut i64))
+        (func (export "get") (result i64)
+            (global.get $counter)
+        )
+        (func (export "add") (param i64)
+            (global.set $counter (i64.add (global.get $counter) (local.get 0)))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::SHARED_EVERYTHING_THREADS;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let module = common::compile(&engine, str)?;
+
+    let counter = SharedGlobal::new(Val::I64(1))?;
+
+    let mut store_a = Store::new(&engine, ());
+    let mut linker_a = Linker::new(&engine);
+    let global_a = counter.attach(&mut store_a);
+    linker_a.define("env", "counter", global_a)?;
+    let instance_a = common::instantiate(&mut store_a, &linker_a, &module)?;
+
+    let mut store_b = Store::new(&engine, ());
+    let mut linker_b = Linker::new(&engine);
+    let global_b = counter.attach(&mut store_b);
+    linker_b.define("env", "counter", global_b)?;
+    let instance_b = common::instantiate(&mut store_b, &linker_b, &module)?;
+
+    let add_a = instance_a.get_typed_func::<i64, ()>(&mut store_a, "add")?;
+    let get_b = instance_b.get_typed_func::<(), i64>(&mut store_b, "get")?;
+
+    // writes made by wasm in one store are observed by wasm in the other
+    add_a.call(&mut store_a, 41)?;
+    assert_eq!(get_b.call(&mut store_b, ())?, 42);
+    assert!(matches!(global_b.get(&mut store_b), Val::I64(42)));
+
+    // as are writes made by the host
+    global_b.set(&mut store_b, Val::I64(7))?;
+    assert!(matches!(global_a.get(&mut store_a), Val::I64(7)));
+    add_a.call(&mut store_a, 1)?;
+    assert_eq!(get_b.call(&mut store_b, ())?, 8);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn shared_global_requires_shared_import() -> Result<(), Error> {
+    let features = WasmFeatures::default() | WasmFeatures::SHARED_EVERYTHING_THREADS;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, r#"(module (global (import "env" "g") (mut i32)))"#)?;
+    let global = SharedGlobal::new(Val::I32(0))?.attach(&mut store);
+    linker.define("env", "g", global)?;
+
+    let err = common::instantiate(&mut store, &linker, &module).unwrap_err();
+    assert!(matches!(err, Error::IncompatibleImport { .. }), "{err}");
+
+    Ok(())
+}
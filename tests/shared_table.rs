@@ -0,0 +1,62 @@
+mod common;
+
+use k23vm::{Engine, Linker, Store, Val};
+
+#[test_log::test]
+fn main() {
+    let a = r#"
+    (module
+        (type $t (func (result i32)))
+        (table (export "table") 4 4 funcref)
+        (elem (i32.const 0) $f)
+        (func $f (result i32) (i32.const 42))
+        (func (export "call") (param i32) (result i32)
+            (call_indirect (type $t) (local.get 0))
+        )
+    )"#;
+    let b = r#"
+    (module
+        (type $t (func (result i32)))
+        (import "a" "table" (table 4 4 funcref))
+        (elem (i32.const 1) $g)
+        (func $g (result i32) (i32.const 7))
+        (func (export "call") (param i32) (result i32)
+            (call_indirect (type $t) (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let module_a = common::compile(&engine, a).unwrap();
+    let instance_a = common::instantiate(&mut store, &linker, &module_a).unwrap();
+    linker.define_instance(&mut store, "a", instance_a).unwrap();
+
+    let module_b = common::compile(&engine, b).unwrap();
+    let instance_b = common::instantiate(&mut store, &linker, &module_b).unwrap();
+
+    let call = |store: &mut Store<()>, instance: k23vm::Instance, index: i32| -> i32 {
+        let func = instance.get_func(store, "call").unwrap();
+        let mut results = [Val::I32(0)];
+        // Safety: the parameters match the signature of `call`
+        unsafe {
+            func.call_unchecked(store, &[Val::I32(index)], &mut results)
+                .unwrap();
+        }
+        results[0].unwrap_i32()
+    };
+
+    // B sees the element populated by A...
+    assert_eq!(call(&mut store, instance_b, 0), 42);
+    // ...and A sees the element B wrote through its import
+    assert_eq!(call(&mut store, instance_a, 1), 7);
+    assert_eq!(call(&mut store, instance_b, 1), 7);
+
+    // elements are still null in both instances
+    let func = instance_b.get_func(&mut store, "call").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of `call`
+    let res = unsafe { func.call_unchecked(&mut store, &[Val::I32(2)], &mut results) };
+    assert!(res.is_err());
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Linker, Module, SourceLoc, Store, Trap};
+use wasmparser::{Operator, Parser, Payload, Validator};
+
+const WAT: &str = r#"
+(module
+    (memory 1)
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0))
+    )
+)"#;
+
+fn uleb(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = u8::try_from(value & 0x7f).unwrap();
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut payload = Vec::new();
+    uleb(&mut payload, name.len());
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(data);
+
+    wasm.push(0);
+    uleb(wasm, payload.len());
+    wasm.extend_from_slice(&payload);
+}
+
+/// Appends a hand-assembled DWARF 4 line number program for the `load` function to `wasm`.
+///
+/// The function starts at `src/fib.c:3:0`, the `i32.load` is attributed to `src/fib.c:4:12`.
+fn with_line_program(mut wasm: Vec<u8>) -> Vec<u8> {
+    // DWARF addresses are offsets relative to the start of the code section
+    let mut code_start = 0;
+    let mut func = 0..0;
+    let mut load = 0;
+    for payload in Parser::new(0).parse_all(&wasm) {
+        match payload.unwrap() {
+            Payload::CodeSectionStart { range, .. } => code_start = range.start,
+            Payload::CodeSectionEntry(body) => {
+                func = body.range();
+                let mut ops = body.get_operators_reader().unwrap();
+                while !ops.eof() {
+                    if let (Operator::I32Load { .. }, offset) = ops.read_with_offset().unwrap() {
+                        load = offset;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let low_pc = func.start - code_start;
+    let load_pc = load - code_start;
+    let high_pc = func.end - code_start;
+
+    #[rustfmt::skip]
+    let mut header = vec![
+        // minimum_instruction_length, maximum_operations_per_instruction, default_is_stmt,
+        // line_base, line_range, opcode_base
+        1, 1, 1, 0xfb, 14, 13,
+        // standard_opcode_lengths
+        0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1,
+    ];
+    header.extend_from_slice(b"src\0\0");
+    header.extend_from_slice(b"fib.c\0");
+    header.extend_from_slice(&[1, 0, 0, 0]);
+
+    let mut program = Vec::new();
+    // DW_LNE_set_address
+    program.extend_from_slice(&[0, 5, 2]);
+    program.extend_from_slice(&u32::try_from(low_pc).unwrap().to_le_bytes());
+    // DW_LNS_advance_line, DW_LNS_copy
+    program.extend_from_slice(&[3, 2, 1]);
+    // DW_LNS_advance_pc, DW_LNS_advance_line, DW_LNS_set_column, DW_LNS_copy
+    program.push(2);
+    uleb(&mut program, load_pc - low_pc);
+    program.extend_from_slice(&[3, 1, 5, 12, 1]);
+    // DW_LNS_advance_pc, DW_LNE_end_sequence
+    program.push(2);
+    uleb(&mut program, high_pc - load_pc);
+    program.extend_from_slice(&[0, 1, 1]);
+
+    let mut line = Vec::new();
+    let unit_length = 2 + 4 + header.len() + program.len();
+    line.extend_from_slice(&u32::try_from(unit_length).unwrap().to_le_bytes());
+    line.extend_from_slice(&4u16.to_le_bytes());
+    line.extend_from_slice(&u32::try_from(header.len()).unwrap().to_le_bytes());
+    line.extend_from_slice(&header);
+    line.extend_from_slice(&program);
+
+    // 1: compile unit without children, stmt_list (sec_offset)
+    let abbrev = [1, 0x11, 0, 0x10, 0x17, 0, 0, 0];
+
+    let mut info = Vec::new();
+    info.extend_from_slice(&12u32.to_le_bytes());
+    info.extend_from_slice(&4u16.to_le_bytes());
+    info.extend_from_slice(&0u32.to_le_bytes());
+    info.push(4);
+    info.push(1);
+    info.extend_from_slice(&0u32.to_le_bytes());
+
+    custom_section(&mut wasm, ".debug_abbrev", &abbrev);
+    custom_section(&mut wasm, ".debug_info", &info);
+    custom_section(&mut wasm, ".debug_line", &line);
+    wasm
+}
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::new(Config::new().debug_info(true));
+    let mut validator = Validator::new();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let wasm = with_line_program(wat::parse_str(WAT)?);
+    let module = Module::from_bytes(&engine, &mut validator, &wasm)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    let err = load.call(&mut store, -16).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+
+    let pc = store.last_trap_frame().unwrap().pc();
+    let text_offset = module.text_offset(pc).unwrap();
+    assert_eq!(
+        module.source_location(text_offset),
+        Some(SourceLoc {
+            file: "src/fib.c".to_string(),
+            line: 4,
+            column: 12,
+        })
+    );
+
+    // addresses outside of the module's code have no source location
+    assert_eq!(module.text_offset(0), None);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn disabled_by_default() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut validator = Validator::new();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let wasm = with_line_program(wat::parse_str(WAT)?);
+    let module = Module::from_bytes(&engine, &mut validator, &wasm)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    load.call(&mut store, -16).unwrap_err();
+
+    let pc = store.last_trap_frame().unwrap().pc();
+    let text_offset = module.text_offset(pc).unwrap();
+    assert_eq!(module.source_location(text_offset), None);
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Ref, Store, Trap, Val};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $recurse (export "recurse") (param $n i32) (result i32)
+            (i32.add
+                (i32.const 1)
+                (call $recurse (i32.add (local.get $n) (i32.const 1)))
+            )
+        )
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let recurse = instance.get_func(&mut store, "recurse").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of `recurse`
+    let err =
+        unsafe { recurse.call_unchecked(&mut store, &[Val::I32(0)], &mut results) }.unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::StackOverflow));
+
+    // the store is still usable after the overflow
+    let add = instance.get_func(&mut store, "add").unwrap();
+    // Safety: the parameters match the signature of `add`
+    unsafe { add.call_unchecked(&mut store, &[Val::I32(2), Val::I32(3)], &mut results)? };
+    assert_eq!(results[0].unwrap_i32(), 5);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn across_instances() -> Result<(), Error> {
+    let a = r#"
+    (module
+        (import "b" "ping" (func $ping (param i32) (result i32)))
+        (func (export "pong") (param $n i32) (result i32)
+            (call $ping (i32.add (local.get $n) (i32.const 1)))
+        )
+    )"#;
+    let b = r#"
+    (module
+        (type $f (func (param i32) (result i32)))
+        (table (export "table") 1 funcref)
+        (func (export "ping") (param $n i32) (result i32)
+            (i32.add
+                (i32.const 1)
+                (call_indirect (type $f) (local.get $n) (i32.const 0))
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    // `b` calls into `a` through its table and `a` calls back into `b` through its import, so
+    // the recursion alternates between the two instances.
+    let module_b = common::compile(&engine, b)?;
+    let instance_b = common::instantiate(&mut store, &linker, &module_b)?;
+    linker.define_instance(&mut store, "b", instance_b)?;
+
+    let module_a = common::compile(&engine, a)?;
+    let instance_a = common::instantiate(&mut store, &linker, &module_a)?;
+
+    let pong = instance_a.get_func(&mut store, "pong").unwrap();
+    let table = instance_b.get_table(&mut store, "table").unwrap();
+    table.set(&mut store, 0, Ref::Func(Some(pong)))?;
+
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of `pong`
+    let err = unsafe { pong.call_unchecked(&mut store, &[Val::I32(0)], &mut results) }.unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::StackOverflow));
+
+    Ok(())
+}
+
+#[test_log::test]
+fn stack_limit() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $depth (export "depth") (param $n i32) (result i32)
+            (if (result i32) (i32.eqz (local.get $n))
+                (then (i32.const 0))
+                (else
+                    (i32.add
+                        (i32.const 1)
+                        (call $depth (i32.sub (local.get $n) (i32.const 1)))
+                    )
+                )
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let depth = instance.get_func(&mut store, "depth").unwrap();
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of `depth`
+    unsafe { depth.call_unchecked(&mut store, &[Val::I32(4000)], &mut results)? };
+    assert_eq!(results[0].unwrap_i32(), 4000);
+
+    // the same recursion no longer fits into a smaller budget
+    store.set_stack_limit(16 * 1024);
+    // Safety: the parameters match the signature of `depth`
+    let err =
+        unsafe { depth.call_unchecked(&mut store, &[Val::I32(4000)], &mut results) }.unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::StackOverflow));
+
+    // shallow recursion still works
+    // Safety: the parameters match the signature of `depth`
+    unsafe { depth.call_unchecked(&mut store, &[Val::I32(10)], &mut results)? };
+    assert_eq!(results[0].unwrap_i32(), 10);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use k23vm::{Engine, Error, FuncIndex, Linker, Module, Store, Trap};
+use wasmparser::Validator;
+
+#[test_log::test]
+fn main() {
+    let engine = Engine::default();
+
+    let str = r#"
+    (module
+        (import "env" "log" (func $log))
+        (func $helper)
+        (func $init
+            (call $helper)
+        )
+        (start $init)
+    )"#;
+    let module = common::compile(&engine, str).unwrap();
+    // imported functions come first in the index space
+    assert_eq!(module.start_func(), Some(FuncIndex::from_u32(2)));
+
+    let str = r#"
+    (module
+        (func (export "main"))
+    )"#;
+    let module = Module::from_str(&engine, &mut Validator::new(), str).unwrap();
+    assert_eq!(module.start_func(), None);
+}
+
+#[test_log::test]
+fn runs_after_initializers() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1)
+        (table 1 funcref)
+        (func $offset (result i32)
+            (i32.const 0x1200)
+        )
+        (func $init
+            ;; reads the active data segment and calls through the active element segment
+            (i32.store (i32.const 4)
+                (i32.add
+                    (i32.load8_u (i32.const 0))
+                    (call_indirect (result i32) (i32.const 0))
+                )
+            )
+        )
+        (func (export "sentinel") (result i32)
+            (i32.load (i32.const 4))
+        )
+        (elem (i32.const 0) $offset)
+        (data (i32.const 0) "\34")
+        (start $init)
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let sentinel = instance.get_typed_func::<(), i32>(&mut store, "sentinel")?;
+    assert_eq!(sentinel.call(&mut store, ())?, 0x1234);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn trap_fails_instantiation() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $init
+            (unreachable)
+        )
+        (start $init)
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, str)?;
+    let err = common::instantiate(&mut store, &linker, &module).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::UnreachableCodeReached), "{err}");
+
+    Ok(())
+}
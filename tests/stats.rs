@@ -0,0 +1,50 @@
+mod common;
+
+use k23vm::{Engine, Error, Func, Linker, Store, StoreStats};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let host = Func::wrap(&mut store, || {})?;
+    linker.define("env", "host", host)?;
+    let module = common::compile(
+        &engine,
+        r#"
+        (module
+            (import "env" "host" (func $host))
+            (memory 1)
+            (func (export "grow_twice")
+                (call $host)
+                (drop (memory.grow (i32.const 1)))
+                (drop (memory.grow (i32.const 1)))
+            )
+            (func (export "trap")
+                unreachable
+            )
+        )"#,
+    )?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    assert_eq!(store.stats(), StoreStats::default());
+
+    instance
+        .get_typed_func::<(), ()>(&mut store, "grow_twice")?
+        .call(&mut store, ())?;
+    let stats = store.stats();
+    assert_eq!(stats.memory_grows, 2);
+    assert_eq!(stats.wasm_calls, 1);
+    assert_eq!(stats.host_calls, 1);
+    assert_eq!(stats.traps, 0);
+
+    instance
+        .get_typed_func::<(), ()>(&mut store, "trap")?
+        .call(&mut store, ())
+        .unwrap_err();
+    let stats = store.stats();
+    assert_eq!(stats.wasm_calls, 2);
+    assert_eq!(stats.traps, 1);
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use k23vm::{Caller, Engine, Func, Linker, Ref, Store, Val};
+
+#[derive(Debug, Default)]
+struct Counter {
+    calls: u32,
+    total: i32,
+}
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (type $add (func (param i32) (result i32)))
+        (table (export "table") 1 funcref)
+
+        (func (export "run") (param $n i32)
+            (loop $continue
+                (drop (call_indirect (type $add) (local.get $n) (i32.const 0)))
+                (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                (br_if $continue (local.get $n))
+            )
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, Counter::default());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, str).unwrap();
+    let instance = common::instantiate(&mut store, &linker, &module).unwrap();
+
+    let host_func = Func::wrap(&mut store, |mut caller: Caller<'_, Counter>, arg: i32| {
+        let counter = caller.data_mut();
+        counter.calls += 1;
+        counter.total += arg;
+        counter.total
+    })
+    .unwrap();
+    let table = instance.get_table(&mut store, "table").unwrap();
+    table
+        .set(&mut store, 0, Ref::Func(Some(host_func)))
+        .unwrap();
+
+    // every call from wasm mutates the store's data
+    let func = instance.get_func(&mut store, "run").unwrap();
+    // Safety: the parameters match the signature of `run`
+    unsafe {
+        func.call_unchecked(&mut store, &[Val::I32(4)], &mut [])
+            .unwrap();
+    }
+    assert_eq!(store.data().calls, 4);
+    assert_eq!(store.data().total, 4 + 3 + 2 + 1);
+
+    // calling the host function directly sees the same data
+    store.data_mut().total = 100;
+    let mut results = [Val::I32(0)];
+    // Safety: the parameters match the signature of the host function
+    unsafe {
+        host_func
+            .call_unchecked(&mut store, &[Val::I32(5)], &mut results)
+            .unwrap();
+    }
+    assert_eq!(results[0].unwrap_i32(), 105);
+    assert_eq!(store.data().calls, 5);
+}
+
+#[test_log::test]
+fn default_store() {
+    let _store: Store<()> = Store::default();
+
+    let store: Store<Counter> = Store::default();
+    assert_eq!(store.data().calls, 0);
+}
+
+#[test_log::test]
+fn store_moved_between_calls() {
+    let str = r#"
+    (module
+        (type $add (func (param i32) (result i32)))
+        (table (export "table") 1 funcref)
+
+        (func (export "add") (param $n i32) (result i32)
+            (call_indirect (type $add) (local.get $n) (i32.const 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, Counter::default());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, str).unwrap();
+    let instance = common::instantiate(&mut store, &linker, &module).unwrap();
+    let host_func = Func::wrap(&mut store, |mut caller: Caller<'_, Counter>, arg: i32| {
+        caller.data_mut().total += arg;
+        caller.data().total
+    })
+    .unwrap();
+    let table = instance.get_table(&mut store, "table").unwrap();
+    table
+        .set(&mut store, 0, Ref::Func(Some(host_func)))
+        .unwrap();
+    let add = instance
+        .get_typed_func::<i32, i32>(&mut store, "add")
+        .unwrap();
+    assert_eq!(add.call(&mut store, 1).unwrap(), 1);
+
+    // host functions find their store through the call, not the address it had when they were
+    // created
+    let mut store = Box::new(store);
+    assert_eq!(add.call(&mut store, 2).unwrap(), 3);
+    let mut stores = vec![*store];
+    assert_eq!(add.call(&mut stores[0], 3).unwrap(), 6);
+    assert_eq!(stores[0].data().total, 6);
+}
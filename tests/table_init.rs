@@ -0,0 +1,120 @@
+mod common;
+
+use k23vm::{Engine, Error, Linker, Store, Trap};
+
+#[test_log::test]
+fn init_and_drop() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (type $ret_i32 (func (result i32)))
+        (table 4 funcref)
+        (elem $funcs func $one $two)
+        (elem $exprs funcref (ref.func $three) (ref.null func))
+
+        (func $one (result i32) (i32.const 1))
+        (func $two (result i32) (i32.const 2))
+        (func $three (result i32) (i32.const 3))
+
+        (func (export "init_funcs") (param $dst i32) (param $src i32) (param $len i32)
+            (table.init $funcs (local.get $dst) (local.get $src) (local.get $len))
+        )
+        (func (export "init_exprs") (param $dst i32) (param $src i32) (param $len i32)
+            (table.init $exprs (local.get $dst) (local.get $src) (local.get $len))
+        )
+        (func (export "drop")
+            (elem.drop $funcs)
+        )
+        (func (export "call") (param i32) (result i32)
+            (call_indirect (type $ret_i32) (local.get 0))
+        )
+        (func (export "is_null") (param i32) (result i32)
+            (ref.is_null (table.get (local.get 0)))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let init_funcs = instance.get_typed_func::<(i32, i32, i32), ()>(&mut store, "init_funcs")?;
+    let init_exprs = instance.get_typed_func::<(i32, i32, i32), ()>(&mut store, "init_exprs")?;
+    let drop = instance.get_typed_func::<(), ()>(&mut store, "drop")?;
+    let call = instance.get_typed_func::<i32, i32>(&mut store, "call")?;
+    let is_null = instance.get_typed_func::<i32, i32>(&mut store, "is_null")?;
+
+    init_funcs.call(&mut store, (0, 0, 2))?;
+    init_exprs.call(&mut store, (2, 0, 2))?;
+    assert_eq!(call.call(&mut store, 0)?, 1);
+    assert_eq!(call.call(&mut store, 1)?, 2);
+    assert_eq!(call.call(&mut store, 2)?, 3);
+    assert_eq!(is_null.call(&mut store, 3)?, 1);
+
+    // out of bounds of the segment or the table, nothing is written in either case
+    let err = init_funcs.call(&mut store, (3, 1, 2)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds), "{err}");
+    let err = init_funcs.call(&mut store, (3, 0, 2)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds), "{err}");
+    assert_eq!(is_null.call(&mut store, 3)?, 1);
+
+    // a dropped segment behaves like an empty one
+    drop.call(&mut store, ())?;
+    let err = init_funcs.call(&mut store, (0, 0, 1)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds), "{err}");
+    init_funcs.call(&mut store, (4, 0, 0))?;
+    let err = init_funcs.call(&mut store, (5, 0, 0)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds), "{err}");
+
+    // dropping twice is allowed and the table keeps its elements
+    drop.call(&mut store, ())?;
+    assert_eq!(call.call(&mut store, 0)?, 1);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn active_segment_out_of_bounds() -> Result<(), Error> {
+    let provider = r#"
+    (module
+        (type $ret_i32 (func (result i32)))
+        (table (export "table") 4 funcref)
+        (memory (export "memory") 1)
+        (func (export "call") (param i32) (result i32)
+            (call_indirect (type $ret_i32) (local.get 0))
+        )
+    )"#;
+    let consumer = r#"
+    (module
+        (import "provider" "table" (table 4 funcref))
+        (import "provider" "memory" (memory 1))
+        (func $one (result i32) (i32.const 1))
+        (func $two (result i32) (i32.const 2))
+        (elem (i32.const 0) func $one)
+        (elem (i32.const 3) func $two $two)
+        (elem (i32.const 1) func $two)
+        (data (i32.const 0) "\ff")
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+
+    let module = common::compile(&engine, provider)?;
+    let provider = common::instantiate(&mut store, &linker, &module)?;
+    linker.define_instance(&mut store, "provider", provider)?;
+
+    let module = common::compile(&engine, consumer)?;
+    let err = common::instantiate(&mut store, &linker, &module).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds), "{err}");
+
+    // segments are applied in order, the ones before the failing segment stay written while
+    // neither the failing segment, later segments nor data segments are applied
+    let call = provider.get_typed_func::<i32, i32>(&mut store, "call")?;
+    assert_eq!(call.call(&mut store, 0)?, 1);
+    for index in 1..4 {
+        let err = call.call(&mut store, index).unwrap_err();
+        assert_eq!(err.trap_code(), Some(Trap::IndirectCallToNull), "{err}");
+    }
+    let memory = provider.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.read_u32(&store, 0)?, 0);
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use k23vm::{Engine, Error, Store, Trap, Val};
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (type $t (func (result i32)))
+        (table 2 funcref)
+        (elem (i32.const 0) $f)
+        (func $f (result i32) (i32.const 42))
+
+        (func (export "set-null") (param i32)
+            (table.set (local.get 0) (ref.null func))
+        )
+        (func (export "is-null") (param i32) (result i32)
+            (ref.is_null (table.get (local.get 0)))
+        )
+        (func (export "call") (param i32) (result i32)
+            (call_indirect (type $t) (local.get 0))
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str, index: i32| -> Result<Option<i32>, Error> {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        let len = func.ty(store).as_wasm_func_type().results.len();
+        // Safety: the parameters match the signatures of the functions above
+        unsafe {
+            func.call_unchecked(store, &[Val::I32(index)], &mut results[..len])?;
+        }
+        Ok(results[..len].first().map(|val| val.unwrap_i32()))
+    };
+
+    assert_eq!(call(&mut store, "is-null", 0).unwrap(), Some(0));
+    assert_eq!(call(&mut store, "call", 0).unwrap(), Some(42));
+
+    // the second element was never initialized
+    assert_eq!(call(&mut store, "is-null", 1).unwrap(), Some(1));
+
+    // set-null then get-null
+    call(&mut store, "set-null", 0).unwrap();
+    assert_eq!(call(&mut store, "is-null", 0).unwrap(), Some(1));
+
+    // and calling it traps
+    let err = call(&mut store, "call", 0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::IndirectCallToNull));
+
+    // accessing elements past the end of the table traps as well
+    let err = call(&mut store, "is-null", 2).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds));
+    let err = call(&mut store, "set-null", 2).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::TableOutOfBounds));
+}
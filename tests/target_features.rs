@@ -0,0 +1,84 @@
+mod common;
+
+use k23vm::{Engine, Error};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    // a section like the one clang emits for `-mcpu=mvp -mmutable-globals -msign-ext`, plus a
+    // feature this crate has never heard of
+    let str = r#"
+    (module
+        (@custom "target_features"
+            "\03"
+            "\2b\0fmutable-globals"
+            "\2b\08sign-ext"
+            "\2b\0afrobnicate"
+        )
+        (func (export "f"))
+    )"#;
+
+    let engine = Engine::default();
+
+    let module = common::compile(&engine, str)?;
+    assert_eq!(
+        module.target_feature_strings().collect::<Vec<_>>(),
+        ["mutable-globals", "sign-ext", "frobnicate"]
+    );
+
+    Ok(())
+}
+
+#[test_log::test]
+fn no_section() -> Result<(), Error> {
+    let engine = Engine::default();
+
+    let module = common::compile(&engine, "(module)")?;
+    assert_eq!(module.target_feature_strings().len(), 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn detect_features() -> Result<(), Error> {
+    let engine = Engine::default();
+
+    // no `target_features` section, the operators give it away
+    let simd = wat::parse_str(
+        r#"(module
+            (func (export "add") (param i32 i32) (result i32)
+                (i32x4.extract_lane 0
+                    (i32x4.add (i32x4.splat (local.get 0)) (i32x4.splat (local.get 1)))
+                )
+            )
+        )"#,
+    )
+    .unwrap();
+    let features = engine.detect_features(&simd)?;
+    assert!(features.contains(WasmFeatures::SIMD), "{features:?}");
+    assert!(!features.contains(WasmFeatures::THREADS), "{features:?}");
+
+    let threads = wat::parse_str(
+        r#"(module
+            (memory 1 1 shared)
+            (func (param i32) (result i32)
+                (return_call 1 (local.get 0))
+            )
+            (func (param i32) (result i32)
+                (i32.atomic.load (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    let features = engine.detect_features(&threads)?;
+    assert_eq!(
+        features,
+        WasmFeatures::THREADS | WasmFeatures::TAIL_CALL,
+        "{features:?}"
+    );
+
+    let mvp = wat::parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#).unwrap();
+    assert_eq!(engine.detect_features(&mvp)?, WasmFeatures::empty());
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use k23vm::{Engine, Linker, Store, Trap, Val};
+
+#[test_log::test]
+fn main() {
+    let str = r#"
+    (module
+        (memory 1)
+        (func (export "div") (param i32 i32) (result i32)
+            (i32.div_s (local.get 0) (local.get 1))
+        )
+        (func (export "load") (param i32) (result i32)
+            (i32.load (local.get 0))
+        )
+        (func (export "unreachable")
+            (unreachable)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str).unwrap();
+
+    let call = |store: &mut Store<()>, name: &str, params: &[Val]| -> Option<Trap> {
+        let func = instance.get_func(store, name).unwrap();
+        let mut results = [Val::I32(0)];
+        let len = func.ty(store).as_wasm_func_type().results.len();
+        // Safety: the parameters match the signatures of the functions above
+        let res = unsafe { func.call_unchecked(store, params, &mut results[..len]) };
+        res.err().and_then(|err| err.trap_code())
+    };
+
+    assert_eq!(call(&mut store, "div", &[Val::I32(1), Val::I32(1)]), None);
+    assert_eq!(
+        call(&mut store, "div", &[Val::I32(1), Val::I32(0)]),
+        Some(Trap::IntegerDivisionByZero)
+    );
+    assert_eq!(
+        call(&mut store, "div", &[Val::I32(i32::MIN), Val::I32(-1)]),
+        Some(Trap::IntegerOverflow)
+    );
+    assert_eq!(
+        call(&mut store, "load", &[Val::I32(0x10000)]),
+        Some(Trap::MemoryOutOfBounds)
+    );
+    assert_eq!(
+        call(&mut store, "unreachable", &[]),
+        Some(Trap::UnreachableCodeReached)
+    );
+}
+
+#[test_log::test]
+fn unreachable() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    // the trap is raised a few frames deep, the code must survive unwinding to the host
+    let module = common::compile(
+        &engine,
+        r#"
+        (module
+            (func $inner (unreachable))
+            (func $outer (call $inner))
+            (func (export "main") (call $outer))
+        )"#,
+    )
+    .unwrap();
+    let instance = common::instantiate(&mut store, &linker, &module).unwrap();
+
+    let main = instance.get_func(&mut store, "main").unwrap();
+    let err = main.call(&mut store, &[], &mut []).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::UnreachableCodeReached), "{err}");
+    assert!(err.to_string().contains("unreachable"), "{err}");
+}
+
+#[test]
+fn display() {
+    // the messages match the ones used by the spec test suite
+    assert_eq!(
+        Trap::MemoryOutOfBounds.to_string(),
+        "out of bounds memory access"
+    );
+    assert_eq!(
+        Trap::IntegerDivisionByZero.to_string(),
+        "integer divide by zero"
+    );
+    assert_eq!(
+        Trap::BadSignature.to_string(),
+        "indirect call type mismatch"
+    );
+    assert_eq!(
+        Trap::IndirectCallToNull.to_string(),
+        "uninitialized element"
+    );
+    assert_eq!(Trap::UnreachableCodeReached.to_string(), "unreachable");
+    assert_eq!(Trap::StackOverflow.to_string(), "call stack exhausted");
+}
@@ -0,0 +1,77 @@
+//! Installing a trap handler is global to the process, so this lives in its own test binary.
+
+mod common;
+
+use k23vm::{
+    handle_fault, set_trap_handler, Engine, Error, Func, Linker, Store, Trap, TrapHandler,
+    WasmBacktraceDetails,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The program counter our fake fault is raised at.
+const FAULT_PC: usize = 0x1000;
+/// The address our fake fault accessed.
+const FAULT_ADDR: usize = 0x2000;
+
+static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+/// Recognizes a single program counter instead of consulting the compiled code.
+struct FakeTrapHandler;
+
+impl TrapHandler for FakeTrapHandler {
+    fn ensure_registered(&self) {}
+
+    fn lookup_trap(&self, pc: usize, faulting_addr: Option<usize>) -> Option<Trap> {
+        LOOKUPS.fetch_add(1, Ordering::Relaxed);
+        (pc == FAULT_PC && faulting_addr == Some(FAULT_ADDR)).then_some(Trap::MemoryOutOfBounds)
+    }
+}
+
+#[test_log::test]
+fn custom_handler() -> Result<(), Error> {
+    set_trap_handler(FakeTrapHandler);
+
+    let str = r#"
+    (module
+        (import "env" "fault" (func $fault))
+        (func (export "run")
+            (call $fault)
+        )
+    )"#;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    // the fake fault has no frames to walk
+    store.set_wasm_backtrace_details(WasmBacktraceDetails::Disable);
+
+    // stands in for the fault handler of an embedder, which would forward the faulting state
+    let fault = Func::wrap(&mut store, || {
+        // the trapping frame's stack gets copied, so both frame and stack pointer have to point
+        // to readable memory
+        let frame = [0usize; 2];
+        let fp = frame.as_ptr() as usize;
+        // Safety: there is no fault to resume, faults the handler doesn't recognize return and
+        // recognized ones unwind to the call into WebAssembly
+        unsafe {
+            assert!(!handle_fault(FAULT_PC + 4, fp, [fp; 32], None));
+            handle_fault(FAULT_PC, fp, [fp; 32], Some(FAULT_ADDR));
+        }
+    })?;
+    linker.define("env", "fault", fault)?;
+
+    let module = common::compile(&engine, str)?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    // without WebAssembly on the stack there is nothing to unwind to
+    // Safety: see above
+    assert!(!unsafe { handle_fault(FAULT_PC, 0, [0; 32], Some(FAULT_ADDR)) });
+
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    let err = run.call(&mut store, ()).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds));
+    assert_eq!(err.faulting_addr(), Some(FAULT_ADDR));
+    assert_eq!(LOOKUPS.load(Ordering::Relaxed), 2);
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use k23vm::{Engine, Error, Func, Linker, Store, WasmValType};
+use wasmparser::{FuncType, ValType};
+
+#[test_log::test]
+fn identical_signatures_share_type_index() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module_a = common::compile(
+        &engine,
+        r#"(module
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1))
+            )
+            (func (export "neg") (param i64) (result i64)
+                (i64.sub (i64.const 0) (local.get 0))
+            )
+        )"#,
+    )?;
+    // declares the types in a different order so the module-local indices differ
+    let module_b = common::compile(
+        &engine,
+        r#"(module
+            (type (func))
+            (func (export "sub") (param i32 i32) (result i32)
+                (i32.sub (local.get 0) (local.get 1))
+            )
+        )"#,
+    )?;
+
+    let instance_a = common::instantiate(&mut store, &linker, &module_a)?;
+    let instance_b = common::instantiate(&mut store, &linker, &module_b)?;
+
+    let add = instance_a.get_func(&mut store, "add").unwrap().ty(&store);
+    let neg = instance_a.get_func(&mut store, "neg").unwrap().ty(&store);
+    let sub = instance_b.get_func(&mut store, "sub").unwrap().ty(&store);
+
+    assert_eq!(add.type_index(), sub.type_index());
+    assert_eq!(add, sub);
+    assert_ne!(add.type_index(), neg.type_index());
+
+    // types registered by the embedder are deduplicated against module types as well
+    let registered =
+        engine.register_type(&FuncType::new([ValType::I32, ValType::I32], [ValType::I32]));
+    assert_eq!(registered.type_index(), add.type_index());
+    let registered = engine.register_type(&FuncType::new([ValType::I64], [ValType::I64]));
+    assert_eq!(registered.type_index(), neg.type_index());
+
+    Ok(())
+}
+
+#[test_log::test]
+fn func_ty() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(
+        &engine,
+        r#"(module
+            (func $fib (export "fib") (param i32) (result i32)
+                (if (result i32) (i32.le_s (local.get 0) (i32.const 1))
+                    (then (local.get 0))
+                    (else
+                        (i32.add
+                            (call $fib (i32.sub (local.get 0) (i32.const 1)))
+                            (call $fib (i32.sub (local.get 0) (i32.const 2)))
+                        )
+                    )
+                )
+            )
+        )"#,
+    )?;
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+
+    let fib = instance.get_func(&mut store, "fib").unwrap().ty(&store);
+    assert!(fib.params().eq([&WasmValType::I32]));
+    assert!(fib.results().eq([&WasmValType::I32]));
+
+    let host = Func::wrap(&mut store, |_: i64, b: f32| f64::from(b))?.ty(&store);
+    assert!(host.params().eq([&WasmValType::I64, &WasmValType::F32]));
+    assert!(host.results().eq([&WasmValType::F64]));
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use k23vm::{Engine, Error, Trap, TypedFunc};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func $fib (export "fib") (param $n i32) (result i32)
+            (if (result i32) (i32.lt_s (local.get $n) (i32.const 2))
+                (then (local.get $n))
+                (else
+                    (i32.add
+                        (call $fib (i32.sub (local.get $n) (i32.const 1)))
+                        (call $fib (i32.sub (local.get $n) (i32.const 2)))
+                    )
+                )
+            )
+        )
+        (func (export "divmod") (param i64 i64) (result i64 i64)
+            (i64.div_u (local.get 0) (local.get 1))
+            (i64.rem_u (local.get 0) (local.get 1))
+        )
+        (global (export "global") i32 (i32.const 0))
+    )"#;
+
+    let engine = Engine::default();
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let fib: TypedFunc<i32, i32> = instance.get_typed_func(&mut store, "fib")?;
+    assert_eq!(fib.call(&mut store, 10)?, 55);
+    assert_eq!(fib.call(&mut store, 20)?, 6765);
+
+    // multiple parameters and results are passed as tuples
+    let divmod = instance.get_typed_func::<(i64, i64), (i64, i64)>(&mut store, "divmod")?;
+    assert_eq!(divmod.call(&mut store, (17, 5))?, (3, 2));
+
+    // traps are reported as errors
+    let err = divmod.call(&mut store, (1, 0)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::IntegerDivisionByZero));
+
+    // signature mismatches and missing exports are distinct errors
+    assert!(matches!(
+        instance.get_typed_func::<i64, i32>(&mut store, "fib"),
+        Err(Error::FuncTypeMismatch { .. })
+    ));
+    assert!(matches!(
+        instance.get_typed_func::<i32, ()>(&mut store, "fib"),
+        Err(Error::FuncTypeMismatch { .. })
+    ));
+    assert!(matches!(
+        instance.get_typed_func::<i32, i32>(&mut store, "fibonacci"),
+        Err(Error::MissingExport { .. })
+    ));
+    assert!(matches!(
+        instance.get_typed_func::<(), i32>(&mut store, "global"),
+        Err(Error::MissingExport { .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use k23vm::{Engine, Error, ExternType, Linker, Store};
+
+#[test_log::test]
+fn main() -> Result<(), Error> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let linker = Linker::new(&engine);
+
+    let module = common::compile(
+        &engine,
+        r#"
+        (module
+            (func (export "nothing"))
+            (func (export "add") (param i32 i32) (result i64)
+                (i64.extend_i32_s (i32.add (local.get 0) (local.get 1)))
+            )
+            (func (export "pair") (param f32) (result i32 f64)
+                (i32.const 0)
+                (f64.const 0)
+            )
+            (func $f (export "get_ref") (result funcref)
+                (ref.func $f)
+            )
+            (table (export "table") 1 10 funcref)
+            (memory (export "memory") 1)
+            (global (export "counter") (mut i64) (i64.const 0))
+        )"#,
+    )?;
+
+    let exports = module
+        .export_types()
+        .map(|(name, ty)| (name, ty.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        [
+            ("nothing", "(func () -> ())".to_string()),
+            ("add", "(func (i32, i32) -> i64)".to_string()),
+            ("pair", "(func (f32) -> (i32, f64))".to_string()),
+            ("get_ref", "(func () -> funcref)".to_string()),
+            ("table", "(table 1 10 funcref)".to_string()),
+            ("memory", "(memory 1)".to_string()),
+            ("counter", "(global (mut i64))".to_string()),
+        ]
+    );
+
+    // the types of instantiated functions print the same
+    let instance = common::instantiate(&mut store, &linker, &module)?;
+    let add = instance.get_func(&mut store, "add").unwrap();
+    assert_eq!(add.ty(&store).to_string(), "(i32, i32) -> i64");
+    let get_ref = instance.get_func(&mut store, "get_ref").unwrap();
+    assert_eq!(get_ref.ty(&store).to_string(), "() -> funcref");
+
+    Ok(())
+}
+
+#[test_log::test]
+fn imports() -> Result<(), Error> {
+    let engine = Engine::default();
+
+    let module = common::compile(
+        &engine,
+        r#"
+        (module
+            (import "env" "log" (func (param i32)))
+            (import "env" "memory" (memory 1 2))
+            (import "env" "base" (global i32))
+        )"#,
+    )?;
+
+    let imports = module.import_types().collect::<Vec<_>>();
+    assert_eq!(imports.len(), 3);
+    assert!(matches!(
+        imports[0],
+        ("env", "log", ExternType::Func(ref ty)) if ty.params().len() == 1
+    ));
+    let imports = imports
+        .into_iter()
+        .map(|(_, name, ty)| (name, ty.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            ("log", "(func (i32) -> ())".to_string()),
+            ("memory", "(memory 1 2)".to_string()),
+            ("base", "(global i32)".to_string()),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use k23vm::{Config, Engine, Error, Trap, Val};
+use wasmparser::WasmFeatures;
+
+#[test_log::test]
+fn lane_order() {
+    let val = Val::v128_from_i32x4([1, -2, 3, -4]);
+    assert_eq!(val.unwrap_v128(), 0xffff_fffc_0000_0003_ffff_fffe_0000_0001);
+    assert_eq!(val.as_i32x4(), Some([1, -2, 3, -4]));
+    assert_eq!(val.as_i64x2(), Some([-0x1_ffff_ffff, -0x3_ffff_fffd]));
+    assert_eq!(
+        val.as_i8x16(),
+        Some([1, 0, 0, 0, -2, -1, -1, -1, 3, 0, 0, 0, -4, -1, -1, -1])
+    );
+
+    let val = Val::v128_from_i64x2([i64::MIN, 0x0102_0304_0506_0708]);
+    assert_eq!(val.as_i64x2(), Some([i64::MIN, 0x0102_0304_0506_0708]));
+    assert_eq!(
+        val.as_i16x8(),
+        Some([0, 0, 0, i16::MIN, 0x0708, 0x0506, 0x0304, 0x0102])
+    );
+    assert_eq!(
+        Val::v128_from_i8x16(val.as_i8x16().unwrap()).v128(),
+        val.v128()
+    );
+
+    assert_eq!(Val::I32(0).as_i32x4(), None);
+}
+
+#[test_log::test]
+fn lanes_match_wasm() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (func (export "add") (param v128 v128) (result v128)
+            (i32x4.add (local.get 0) (local.get 1))
+        )
+        (func (export "extract") (param v128) (result i32)
+            (i8x16.extract_lane_s 5 (local.get 0))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::SIMD;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let add = instance.get_typed_func::<(u128, u128), u128>(&mut store, "add")?;
+    let extract = instance.get_typed_func::<u128, i32>(&mut store, "extract")?;
+
+    let a = Val::v128_from_i32x4([1, 2, 3, i32::MAX]);
+    let b = Val::v128_from_i32x4([10, -20, 30, 1]);
+    let sum = Val::V128(add.call(&mut store, (a.unwrap_v128(), b.unwrap_v128()))?);
+    assert_eq!(sum.as_i32x4(), Some([11, -18, 33, i32::MIN]));
+
+    let bytes = Val::v128_from_i8x16([0, 1, 2, 3, 4, -5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+    assert_eq!(extract.call(&mut store, bytes.unwrap_v128())?, -5);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn memory_round_trip() -> Result<(), Error> {
+    let str = r#"
+    (module
+        (memory 1)
+        (func (export "store") (param i32 v128)
+            (v128.store offset=16 (local.get 0) (local.get 1))
+        )
+        (func (export "load") (param i32) (result v128)
+            (v128.load offset=16 (local.get 0))
+        )
+        ;; the alignment hint is only a hint, it never causes a trap
+        (func (export "store_add") (param i32 v128 v128)
+            (v128.store align=16 (local.get 0) (i32x4.add (local.get 1) (local.get 2)))
+        )
+        (func (export "load8_splat") (param i32) (result v128)
+            (v128.load8_splat (local.get 0))
+        )
+        (func (export "load16x4_s") (param i32) (result v128)
+            (v128.load16x4_s (local.get 0))
+        )
+    )"#;
+
+    let features = WasmFeatures::default() | WasmFeatures::SIMD;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let (mut store, instance) = common::setup(&engine, str)?;
+
+    let v128_store = instance.get_typed_func::<(i32, u128), ()>(&mut store, "store")?;
+    let v128_load = instance.get_typed_func::<i32, u128>(&mut store, "load")?;
+    let store_add = instance.get_typed_func::<(i32, u128, u128), ()>(&mut store, "store_add")?;
+    let load8_splat = instance.get_typed_func::<i32, u128>(&mut store, "load8_splat")?;
+    let load16x4_s = instance.get_typed_func::<i32, u128>(&mut store, "load16x4_s")?;
+
+    let val = 0x0f0e_0d0c_0b0a_0908_0706_0504_0302_0100;
+    v128_store.call(&mut store, (0, val))?;
+    assert_eq!(v128_load.call(&mut store, 0)?, val);
+    assert_eq!(
+        load8_splat.call(&mut store, 21)?,
+        0x0505_0505_0505_0505_0505_0505_0505_0505
+    );
+
+    // misaligned accesses
+    v128_store.call(&mut store, (3, val))?;
+    assert_eq!(v128_load.call(&mut store, 3)?, val);
+    assert_eq!(v128_load.call(&mut store, 4)?, val >> 8);
+
+    let a = Val::v128_from_i32x4([1, 2, 3, 4]);
+    let b = Val::v128_from_i32x4([-1, -2, -3, i16::MIN.into()]);
+    store_add.call(&mut store, (101, a.unwrap_v128(), b.unwrap_v128()))?;
+    let loaded = Val::V128(v128_load.call(&mut store, 85)?);
+    assert_eq!(loaded.as_i32x4(), Some([0, 0, 0, i32::from(i16::MIN) + 4]));
+    let extended = Val::V128(load16x4_s.call(&mut store, 113)?);
+    assert_eq!(extended.as_i32x4(), Some([-32764, -1, 0, 0]));
+
+    // the last 16 bytes of the memory are accessible, one byte further is out of bounds
+    v128_store.call(&mut store, (0xffe0, val))?;
+    assert_eq!(v128_load.call(&mut store, 0xffe0)?, val);
+    let err = v128_store.call(&mut store, (0xffe1, val)).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+    let err = v128_load.call(&mut store, 0xffe1).unwrap_err();
+    assert_eq!(err.trap_code(), Some(Trap::MemoryOutOfBounds), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use k23vm::{Config, Engine, Error, Module};
+use wasmparser::{Validator, WasmFeatures};
+
+#[test_log::test]
+fn reports_all_unsupported_features() {
+    let str = r#"
+    (module
+        (func $exceptions
+            (block $handler
+                (try_table (catch_all $handler))
+            )
+        )
+        (func $legacy_exceptions
+            (try (do) (catch_all))
+        )
+    )"#;
+
+    let features =
+        WasmFeatures::default() | WasmFeatures::EXCEPTIONS | WasmFeatures::LEGACY_EXCEPTIONS;
+    let engine = Engine::new(Config::new().wasm_features(features));
+    let mut validator = Validator::new_with_features(features);
+
+    let bytes = wat::parse_str(str).unwrap();
+    let errors = Module::validate_and_report(&engine, &mut validator, &bytes);
+
+    let messages: Vec<_> = errors
+        .iter()
+        .map(|err| match err {
+            Error::Unsupported(msg) => msg.as_str(),
+            err => panic!("unexpected error {err}"),
+        })
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "Exception Handling Proposal",
+            "Legacy Exception Handling Proposal"
+        ]
+    );
+}
+
+#[test_log::test]
+fn reports_nothing_for_supported_module() {
+    let engine = Engine::default();
+    let mut validator = Validator::new();
+
+    let bytes = wat::parse_str(r#"(module (func (result i32) (i32.const 42)))"#).unwrap();
+    let errors = Module::validate_and_report(&engine, &mut validator, &bytes);
+    assert!(errors.is_empty());
+}
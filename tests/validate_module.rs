@@ -0,0 +1,26 @@
+use k23vm::{Engine, Module};
+use wasmparser::Validator;
+
+#[test_log::test]
+fn main() {
+    let engine = Engine::default();
+
+    let valid = wat::parse_str(
+        r#"(module (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))
+        ))"#,
+    )
+    .unwrap();
+    engine.validate_module(&valid).unwrap();
+
+    // a truncated module header
+    let malformed = b"\0asm\x01\0";
+    // a function whose body doesn't match its declared result type
+    let invalid = wat::parse_str(r#"(module (func (result i32) (i64.const 0)))"#).unwrap();
+
+    for bytes in [&malformed[..], &invalid] {
+        let err = engine.validate_module(bytes).unwrap_err();
+        let expected = Module::from_bytes(&engine, &mut Validator::new(), bytes).unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+}
@@ -0,0 +1,49 @@
+use k23vm::{Config, Engine, Error, Module};
+use wasmparser::{Validator, WasmFeatures};
+
+const SIMD: &str = r#"
+    (module
+        (func (export "splat") (param i32) (result v128)
+            (i32x4.splat (local.get 0))
+        )
+    )"#;
+
+#[test_log::test]
+fn rejects_disabled_feature() {
+    let engine = Engine::new(Config::new().wasm_features(WasmFeatures::WASM1));
+
+    let bytes = wat::parse_str(SIMD).unwrap();
+    assert!(engine.validate_module(&bytes).is_err());
+
+    // even if the validator itself would allow SIMD
+    let err = Module::from_bytes(&engine, &mut Validator::new(), &bytes).unwrap_err();
+    assert!(matches!(err, Error::InvalidWebAssembly { .. }), "{err}");
+}
+
+#[test_log::test]
+fn accepts_enabled_feature() {
+    let engine = Engine::default();
+
+    let bytes = wat::parse_str(SIMD).unwrap();
+    engine.validate_module(&bytes).unwrap();
+    Module::from_bytes(&engine, &mut Validator::new(), &bytes).unwrap();
+}
+
+#[test_log::test]
+fn rejects_disabled_required_feature() {
+    let engine = Engine::default();
+
+    // a module that doesn't use threads, but declares it requires them
+    let bytes = wat::parse_str(
+        r#"(module
+            (@custom "target_features" "\01\2b\07atomics")
+        )"#,
+    )
+    .unwrap();
+
+    let err = Module::from_bytes(&engine, &mut Validator::new(), &bytes).unwrap_err();
+    match err {
+        Error::DisabledFeatures { features } => assert_eq!(features, "THREADS"),
+        err => panic!("unexpected error {err}"),
+    }
+}
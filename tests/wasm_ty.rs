@@ -0,0 +1,115 @@
+mod common;
+
+use k23vm::{Engine, Error, Func, Instance, Store, WasmTy};
+use std::fmt::Debug;
+
+fn setup() -> Result<(Store<()>, Instance), Error> {
+    let str = r#"
+    (module
+        (func (export "i32") (param i32) (result i32) (local.get 0))
+        (func (export "i64") (param i64) (result i64) (local.get 0))
+        (func (export "f32") (param f32) (result f32) (local.get 0))
+        (func (export "f64") (param f64) (result f64) (local.get 0))
+        (func (export "v128") (param v128) (result v128) (local.get 0))
+        (func (export "funcref") (param funcref) (result funcref) (local.get 0))
+        (func (export "answer") (result i32) (i32.const 42))
+    )"#;
+
+    common::setup(&Engine::default(), str)
+}
+
+/// Passes each value through the WebAssembly identity function `name` and a host identity
+/// function, checking it comes back unchanged.
+fn roundtrip<W>(
+    store: &mut Store<()>,
+    instance: Instance,
+    name: &str,
+    vals: &[W],
+) -> Result<(), Error>
+where
+    W: WasmTy + Copy + PartialEq + Debug,
+{
+    let wasm = instance.get_typed_func::<W, W>(store, name)?;
+    let host = Func::wrap(store, |val: W| val)?.typed::<W, W>(store)?;
+
+    for val in vals {
+        assert_eq!(wasm.call(store, *val)?, *val, "{name} through wasm");
+        assert_eq!(host.call(store, *val)?, *val, "{name} through the host");
+    }
+
+    Ok(())
+}
+
+#[test_log::test]
+fn numbers() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    let store = &mut store;
+
+    roundtrip(store, instance, "i32", &[0, 1, -1, i32::MIN, i32::MAX])?;
+    roundtrip(store, instance, "i32", &[0u32, u32::MAX])?;
+    roundtrip(store, instance, "i64", &[0, 1, -1, i64::MIN, i64::MAX])?;
+    roundtrip(store, instance, "i64", &[0u64, u64::MAX])?;
+    roundtrip(
+        store,
+        instance,
+        "f32",
+        &[0.0, -0.0, 1.5, f32::MIN, f32::MAX, f32::INFINITY],
+    )?;
+    roundtrip(
+        store,
+        instance,
+        "f64",
+        &[0.0, -0.0, 1.5, f64::MIN, f64::MAX, f64::NEG_INFINITY],
+    )?;
+    roundtrip(
+        store,
+        instance,
+        "v128",
+        &[0u128, u128::MAX, 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210],
+    )?;
+
+    // NaNs don't compare equal, so compare their bits instead
+    let f32_id = instance.get_typed_func::<f32, f32>(store, "f32")?;
+    let nan = f32::from_bits(0x7fa0_0123);
+    assert_eq!(f32_id.call(store, nan)?.to_bits(), nan.to_bits());
+    let f64_id = instance.get_typed_func::<f64, f64>(store, "f64")?;
+    let nan = f64::from_bits(0x7ff4_0000_0000_0123);
+    assert_eq!(f64_id.call(store, nan)?.to_bits(), nan.to_bits());
+
+    Ok(())
+}
+
+#[test_log::test]
+fn func_refs() -> Result<(), Error> {
+    let (mut store, instance) = setup()?;
+    let store = &mut store;
+
+    let answer = instance.get_func(store, "answer").unwrap();
+    let call_answer = |store: &mut Store<()>, func: Func| -> Result<i32, Error> {
+        func.typed::<(), i32>(store)?.call(store, ())
+    };
+
+    let funcref = instance.get_typed_func::<Option<Func>, Option<Func>>(store, "funcref")?;
+    assert!(funcref.call(store, None)?.is_none());
+    let func = funcref.call(store, Some(answer))?.unwrap();
+    assert_eq!(call_answer(store, func)?, 42);
+
+    let host_funcref = Func::wrap(store, |func: Option<Func>| func)?;
+    let host_funcref = host_funcref.typed::<Option<Func>, Option<Func>>(store)?;
+    assert!(host_funcref.call(store, None)?.is_none());
+    let func = host_funcref.call(store, Some(answer))?.unwrap();
+    assert_eq!(call_answer(store, func)?, 42);
+
+    let host_ref_func = Func::wrap(store, |func: Func| func)?;
+    let host_ref_func = host_ref_func.typed::<Func, Func>(store)?;
+    let func = host_ref_func.call(store, answer)?;
+    assert_eq!(call_answer(store, func)?, 42);
+
+    // nullable and non-nullable references are different types
+    assert!(matches!(
+        instance.get_typed_func::<Func, Func>(store, "funcref"),
+        Err(Error::FuncTypeMismatch { .. })
+    ));
+
+    Ok(())
+}
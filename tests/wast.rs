@@ -1,11 +1,14 @@
 use anyhow::{anyhow, bail, Context};
 use k23vm::{
-    ConstExprEvaluator, Engine, Extern, Instance, InstanceAllocator, Linker, Module,
-    PlaceholderAllocatorDontUse, Store, Val,
+    Config, ConstExprEvaluator, Engine, Extern, Instance, InstanceAllocator, Linker, Memory,
+    Module, PlaceholderAllocatorDontUse, Ref, Store, Table, Trap, Val,
 };
 use std::fmt::{Display, LowerHex};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use wasmparser::{MemoryType, RefType, TableType};
 use wast::core::{EncodeOptions, GenerateDwarf, NanPattern, V128Pattern, WastArgCore, WastRetCore};
 use wast::parser::ParseBuffer;
 use wast::token::{F32, F64};
@@ -197,11 +200,51 @@ impl<T> Outcome<T> {
     }
 }
 
+/// How often the epoch of the engine advances, this is the granularity of the timeout.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Returns how long a single invocation may run before it's interrupted, read from the
+/// `WAST_TIMEOUT_SECS` environment variable.
+fn wast_timeout() -> anyhow::Result<Duration> {
+    match std::env::var("WAST_TIMEOUT_SECS") {
+        Ok(secs) => Ok(Duration::from_secs(
+            secs.parse().context("invalid WAST_TIMEOUT_SECS")?,
+        )),
+        Err(_) => Ok(Duration::from_secs(60)),
+    }
+}
+
+/// Advances the epoch of an engine every [`EPOCH_TICK`] until dropped.
+struct EpochTicker(Arc<AtomicBool>);
+
+impl EpochTicker {
+    fn spawn(engine: &Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (engine, stop2) = (engine.clone(), stop.clone());
+        std::thread::spawn(move || {
+            while !stop2.load(Ordering::Relaxed) {
+                std::thread::sleep(EPOCH_TICK);
+                engine.increment_epoch();
+            }
+        });
+        Self(stop)
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
 pub struct WastContext {
     engine: Engine,
-    store: Store,
+    /// Interrupts invocations that hang instead of blocking the test forever.
+    timeout: Duration,
+    _ticker: EpochTicker,
+    store: Store<()>,
     linker: Linker,
-    alloc: &'static dyn InstanceAllocator,
+    alloc: Arc<dyn InstanceAllocator>,
     const_eval: ConstExprEvaluator,
     validator: wasmparser::Validator,
     current: Option<Instance>,
@@ -209,14 +252,16 @@ pub struct WastContext {
 
 impl WastContext {
     fn new_default() -> anyhow::Result<Self> {
-        let engine = Engine::default();
-        let ctx = WastContext {
-            store: Store::new(&engine),
+        let engine = Engine::new(Config::new().epoch_interruption(true));
+        let mut ctx = WastContext {
+            store: Store::new(&engine, ()),
             linker: Linker::new(&engine),
+            timeout: wast_timeout()?,
+            _ticker: EpochTicker::spawn(&engine),
+            validator: wasmparser::Validator::new_with_features(engine.features()),
             engine,
-            alloc: &PlaceholderAllocatorDontUse,
+            alloc: Arc::new(PlaceholderAllocatorDontUse),
             const_eval: ConstExprEvaluator::default(),
-            validator: wasmparser::Validator::default(),
             current: None,
         };
         // ctx.linker
@@ -304,29 +349,25 @@ impl WastContext {
         //     Global::new(ty, Value::F64(f64::from_bits(0x4084_d4cc_cccc_cccd))),
         // )?;
 
-        // let ty = TableType {
-        //     element_type: RefType::FUNCREF,
-        //     table64: false,
-        //     initial: 10,
-        //     maximum: Some(20),
-        //     shared: false,
-        // };
-        // ctx.linker.define(
-        //     ctx.store,
-        //     "spectest",
-        //     "table",
-        //     Table::new(ty, Ref::Func(None)),
-        // )?;
+        let ty = TableType {
+            element_type: RefType::FUNCREF,
+            table64: false,
+            initial: 10,
+            maximum: Some(20),
+            shared: false,
+        };
+        let table = Table::new(&mut ctx.store, ctx.alloc.clone(), ty, Ref::Func(None))?;
+        ctx.linker.define("spectest", "table", table)?;
 
-        // let ty = MemoryType {
-        //     memory64: false,
-        //     shared: false,
-        //     initial: 1,
-        //     maximum: Some(2),
-        //     page_size_log2: None,
-        // };
-        // ctx.linker
-        //     .define(&mut ctx.store, "spectest", "memory", Memory::new(ty))?;
+        let ty = MemoryType {
+            memory64: false,
+            shared: false,
+            initial: 1,
+            maximum: Some(2),
+            page_size_log2: None,
+        };
+        let memory = Memory::new(&mut ctx.store, ctx.alloc.clone(), ty)?;
+        ctx.linker.define("spectest", "memory", memory)?;
 
         Ok(ctx)
     }
@@ -522,17 +563,31 @@ impl WastContext {
             })
             .collect::<anyhow::Result<Vec<_>>>()?;
 
-        let ty = func.ty(&self.store);
-        let ty = ty.as_wasm_func_type();
-        let mut results = vec![Val::I32(0); ty.results.len()];
+        let mut results = vec![Val::I32(0); func.ty(&self.store).results().len()];
 
-        // TODO replace with safe
-        match unsafe { func.call_unchecked(&mut self.store, &values, &mut results) } {
+        self.start_timeout();
+        match func.call(&mut self.store, &values, &mut results) {
             Ok(()) => Ok(Outcome::Ok(results)),
-            Err(e) => Ok(Outcome::Trap(e.into())),
+            Err(e) => self.check_timeout(e),
         }
     }
 
+    /// Sets the epoch deadline of the store so the next call is interrupted after the timeout.
+    fn start_timeout(&mut self) {
+        let ticks = self.timeout.as_millis() / EPOCH_TICK.as_millis();
+        self.store
+            .set_epoch_deadline(u64::try_from(ticks).unwrap_or(u64::MAX));
+    }
+
+    /// Turns an interrupt into a timeout error, so a hanging test fails instead of treating the
+    /// interrupt as the expected trap.
+    fn check_timeout<T>(&self, err: k23vm::Error) -> anyhow::Result<Outcome<T>> {
+        if err.trap_code() == Some(Trap::Interrupt) {
+            bail!("timed out after {:?}", self.timeout);
+        }
+        Ok(Outcome::Trap(err.into()))
+    }
+
     fn perform_execute(&mut self, exec: WastExecute<'_>) -> anyhow::Result<Outcome> {
         match exec {
             WastExecute::Invoke(invoke) => self.perform_invoke(invoke),
@@ -570,15 +625,26 @@ impl WastContext {
             Outcome::Ok(values) => bail!("expected trap, got {:?}", values),
             Outcome::Trap(t) => t,
         };
+
+        // Compare the messages of trap codes when we have them, the spec messages are sometimes more
+        // specific than what our trap handling can shepherd out (e.g. `bulk.wast` expects
+        // "uninitialized element 2").
+        let actual = trap
+            .downcast_ref::<k23vm::Error>()
+            .and_then(k23vm::Error::trap_code);
+        if let Some(actual) = actual {
+            if expected.starts_with(&actual.to_string())
+                || spec_trap_aliases(actual)
+                    .iter()
+                    .any(|alias| expected.starts_with(alias))
+            {
+                return Ok(());
+            }
+            bail!("expected '{}', got '{}'", expected, actual)
+        }
+
         let actual = format!("{trap:?}");
-        if actual.contains(expected)
-            // `bulk-memory-operations/bulk.wast` checks for a message that
-            // specifies which element is uninitialized, but our trap_handling don't
-            // shepherd that information out.
-            || (expected.contains("uninitialized element 2") && actual.contains("uninitialized element"))
-            // function references call_ref
-            || (expected.contains("null function") && (actual.contains("uninitialized element") || actual.contains("null reference")))
-        {
+        if actual.contains(expected) {
             return Ok(());
         }
         bail!("expected '{}', got '{}'", expected, actual)
@@ -591,17 +657,17 @@ impl WastContext {
             module,
         )?);
 
-        Ok(
-            match self.linker.instantiate(
-                &mut self.store,
-                self.alloc,
-                &mut self.const_eval,
-                &module,
-            ) {
-                Ok(i) => Outcome::Ok(i),
-                Err(e) => Outcome::Trap(e.into()),
-            },
-        )
+        // instantiation runs the start function
+        self.start_timeout();
+        match self.linker.instantiate(
+            &mut self.store,
+            self.alloc.clone(),
+            &mut self.const_eval,
+            &module,
+        ) {
+            Ok(i) => Ok(Outcome::Ok(i)),
+            Err(e) => self.check_timeout(e),
+        }
     }
 
     /// Get the value of an exported global from an instance.
@@ -611,7 +677,7 @@ impl WastContext {
             .into_global()
             .ok_or_else(|| anyhow!("no global named `{field}`"))?;
 
-        Ok(Outcome::Ok(vec![global.get(&self.store)]))
+        Ok(Outcome::Ok(vec![global.get(&mut self.store)]))
     }
 
     fn get_export(&mut self, module: Option<&str>, name: &str) -> anyhow::Result<Extern> {
@@ -633,6 +699,18 @@ impl WastContext {
     }
 }
 
+/// Returns the messages the spec test suite uses for `trap` in addition to its display message.
+fn spec_trap_aliases(trap: Trap) -> &'static [&'static str] {
+    match trap {
+        Trap::TableOutOfBounds => &["undefined element"],
+        // `call_indirect` of a null table entry
+        Trap::IndirectCallToNull => &["null function"],
+        // `call_ref`, `struct.get` etc. of null references name the kind of reference
+        Trap::NullReference => &["null"],
+        _ => &[],
+    }
+}
+
 fn wast_arg_to_val(arg: &WastArgCore) -> anyhow::Result<Val> {
     match arg {
         WastArgCore::I32(v) => Ok(Val::I32(*v)),
@@ -653,7 +731,7 @@ fn wast_arg_to_val(arg: &WastArgCore) -> anyhow::Result<Val> {
     }
 }
 
-pub fn match_val(store: &Store, actual: &Val, expected: &WastRetCore) -> anyhow::Result<()> {
+pub fn match_val(store: &Store<()>, actual: &Val, expected: &WastRetCore) -> anyhow::Result<()> {
     match (actual, expected) {
         (_, WastRetCore::Either(expected)) => {
             for expected in expected {
@@ -876,24 +954,7 @@ pub fn match_f64(actual: u64, expected: &NanPattern<F64>) -> anyhow::Result<()>
 fn match_v128(actual: u128, expected: &V128Pattern) -> anyhow::Result<()> {
     match expected {
         V128Pattern::I8x16(expected) => {
-            let actual = [
-                extract_lane_as_i8(actual, 0),
-                extract_lane_as_i8(actual, 1),
-                extract_lane_as_i8(actual, 2),
-                extract_lane_as_i8(actual, 3),
-                extract_lane_as_i8(actual, 4),
-                extract_lane_as_i8(actual, 5),
-                extract_lane_as_i8(actual, 6),
-                extract_lane_as_i8(actual, 7),
-                extract_lane_as_i8(actual, 8),
-                extract_lane_as_i8(actual, 9),
-                extract_lane_as_i8(actual, 10),
-                extract_lane_as_i8(actual, 11),
-                extract_lane_as_i8(actual, 12),
-                extract_lane_as_i8(actual, 13),
-                extract_lane_as_i8(actual, 14),
-                extract_lane_as_i8(actual, 15),
-            ];
+            let actual = Val::V128(actual).as_i8x16().unwrap();
             if actual == *expected {
                 return Ok(());
             }
@@ -908,16 +969,7 @@ fn match_v128(actual: u128, expected: &V128Pattern) -> anyhow::Result<()> {
             )
         }
         V128Pattern::I16x8(expected) => {
-            let actual = [
-                extract_lane_as_i16(actual, 0),
-                extract_lane_as_i16(actual, 1),
-                extract_lane_as_i16(actual, 2),
-                extract_lane_as_i16(actual, 3),
-                extract_lane_as_i16(actual, 4),
-                extract_lane_as_i16(actual, 5),
-                extract_lane_as_i16(actual, 6),
-                extract_lane_as_i16(actual, 7),
-            ];
+            let actual = Val::V128(actual).as_i16x8().unwrap();
             if actual == *expected {
                 return Ok(());
             }
@@ -932,12 +984,7 @@ fn match_v128(actual: u128, expected: &V128Pattern) -> anyhow::Result<()> {
             )
         }
         V128Pattern::I32x4(expected) => {
-            let actual = [
-                extract_lane_as_i32(actual, 0),
-                extract_lane_as_i32(actual, 1),
-                extract_lane_as_i32(actual, 2),
-                extract_lane_as_i32(actual, 3),
-            ];
+            let actual = Val::V128(actual).as_i32x4().unwrap();
             if actual == *expected {
                 return Ok(());
             }
@@ -952,10 +999,7 @@ fn match_v128(actual: u128, expected: &V128Pattern) -> anyhow::Result<()> {
             )
         }
         V128Pattern::I64x2(expected) => {
-            let actual = [
-                extract_lane_as_i64(actual, 0),
-                extract_lane_as_i64(actual, 1),
-            ];
+            let actual = Val::V128(actual).as_i64x2().unwrap();
             if actual == *expected {
                 return Ok(());
             }
@@ -970,34 +1014,20 @@ fn match_v128(actual: u128, expected: &V128Pattern) -> anyhow::Result<()> {
             )
         }
         V128Pattern::F32x4(expected) => {
+            let actual = Val::V128(actual).as_i32x4().unwrap();
             for (i, expected) in expected.iter().enumerate() {
-                let a = extract_lane_as_i32(actual, i) as u32;
+                let a = actual[i] as u32;
                 match_f32(a, expected).with_context(|| format!("difference in lane {i}"))?;
             }
             Ok(())
         }
         V128Pattern::F64x2(expected) => {
+            let actual = Val::V128(actual).as_i64x2().unwrap();
             for (i, expected) in expected.iter().enumerate() {
-                let a = extract_lane_as_i64(actual, i) as u64;
+                let a = actual[i] as u64;
                 match_f64(a, expected).with_context(|| format!("difference in lane {i}"))?;
             }
             Ok(())
         }
     }
 }
-
-fn extract_lane_as_i8(bytes: u128, lane: usize) -> i8 {
-    (bytes >> (lane * 8)) as i8
-}
-
-fn extract_lane_as_i16(bytes: u128, lane: usize) -> i16 {
-    (bytes >> (lane * 16)) as i16
-}
-
-fn extract_lane_as_i32(bytes: u128, lane: usize) -> i32 {
-    (bytes >> (lane * 32)) as i32
-}
-
-fn extract_lane_as_i64(bytes: u128, lane: usize) -> i64 {
-    (bytes >> (lane * 64)) as i64
-}